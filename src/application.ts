@@ -19,7 +19,55 @@ const FFI_CACHE: any = {};
 
 class Solution {
   constructor(public queenRows: Uint32Array,
-              public score: number) {}
+              public score: number,
+              public cancelled: boolean = false) {}
+}
+
+// The status code of a solve cancelled by its callback, which comes back
+// negative like the rest of them.
+const CANCELLED_STATUS: number = -4;
+
+// A step of a solve, as `TraceEvent` in the library serializes. Which of the
+// optional fields are there depends on the kind.
+interface TraceEvent {
+  kind: string;
+  step: number;
+  score: number;
+  queen_rows?: number[];
+  column?: number;
+  row?: number;
+  columns?: number[];
+  from?: number;
+  to?: number;
+}
+
+// Applies `event` to `queens`, the queen rows after the previous step, the
+// same way `TraceEvent::apply` does.
+function applyTraceEvent(queens: number[], event: TraceEvent) {
+  switch (event.kind) {
+    case "state":
+    case "generation":
+      queens.splice(0, queens.length, ...event.queen_rows);
+      break;
+    case "placement":
+      queens.push(event.row);
+      break;
+    case "backtrack":
+      queens.pop();
+      break;
+    case "swap":
+      let [a, b] = event.columns;
+      [queens[a], queens[b]] = [queens[b], queens[a]];
+      break;
+    case "move":
+      queens[event.column] = event.to;
+      break;
+  }
+}
+
+class Verification {
+  constructor(public conflicts: number,
+              public valid: boolean) {}
 }
 
 class AlgorithmConfig {
@@ -30,33 +78,127 @@ class AlgorithmConfig {
 class ASMInterface {
   constructor() {}
 
+  // A negative seed means a random one.
   solve(n: number,
         name: string,
-        stepCallback?: (state: Uint32Array, score: number) => void
+        stepCallback?: (event: TraceEvent) => boolean | void,
+        seed: number = -1,
         ...args: number[]) : Uint32Array {
     name = "solve_n_queens_" + name;
     if (!FFI_CACHE[name]) {
-      let arg_kinds = ['number', 'number'];
+      let arg_kinds = ['number', 'number', 'number', 'number'];
       for (let arg of args)
         arg_kinds.push('number');
       FFI_CACHE[name]=
         Module.cwrap(name, 'number', arg_kinds);
     }
 
-    let asmCallback = 0;
-    if (stepCallback) {
-      asmCallback = addFunction(function(ptr, len, score) {
-        let state = new Uint32Array(len);
-        for (let i = 0; i < len; ++i)
-          state[i] = Module.getValue(ptr + i * 4, 'i32');
-        stepCallback(state, score);
-      });
+    let asmCallback = this.eventCallback(stepCallback);
+
+    let mem = Module._malloc((n + 1) * 4);
+
+    let solutionScore =
+      FFI_CACHE[name](n, mem, asmCallback, seed, ...args);
+
+    let resultLen = Module.getValue(mem, 'i32');
+    let rows = new Uint32Array(resultLen);
+    for (var i = 0; i < resultLen; ++i)
+      rows[i] = Module.getValue(mem + (i + 1) * 4, 'i32');
+
+    if (asmCallback)
+      removeFunction(asmCallback);
+    Module._free(mem);
+
+    if (solutionScore == CANCELLED_STATUS)
+      return this.cancelledSolution(n, rows);
+    return new Solution(rows, solutionScore);
+  }
+
+  // Wraps `stepCallback`, if any, so that it gets each step as a
+  // `TraceEvent`. Returning true from it cancels the solve.
+  eventCallback(stepCallback?: (event: TraceEvent) => boolean | void) : number {
+    if (!stepCallback)
+      return 0;
+    return addFunction(function(ptr, len) {
+      let event = JSON.parse(Module.UTF8ToString(ptr, len));
+      return stepCallback(event) ? 1 : 0;
+    });
+  }
+
+  // Scores the best queens found by a cancelled solve, which aren't scored
+  // by the solver.
+  cancelledSolution(n: number, rows: Uint32Array) : Solution {
+    let verification = this.verify(n, rows);
+    return new Solution(rows, verification ? verification.conflicts : 0, true);
+  }
+
+  // Zero means no limit. Solves that hit a limit return no queens.
+  setResourceLimits(maxIterations: number,
+                    maxMilliseconds: number,
+                    maxMemory: number) {
+    Module.ccall("set_resource_limits", null,
+                 ['number', 'number', 'number'],
+                 [maxIterations, maxMilliseconds, maxMemory]);
+  }
+
+  // Makes the step callbacks get at most one step of every `every`, and at
+  // most one step per `milliseconds`, plus the last one. The steps of the
+  // events then count the ones the callback gets. Zero means no limit.
+  setReportRate(every: number, milliseconds: number) {
+    Module.ccall("set_report_rate", null, ['number', 'number'],
+                 [every, milliseconds]);
+  }
+
+  // Checks the queens at `positions`, as indices in the coordinate
+  // convention, in any columns.
+  // Returns null if two queens are on the same square, or one is off the
+  // board.
+  verify(n: number, positions: Uint32Array) : Verification {
+    if (!FFI_CACHE.verify) {
+      FFI_CACHE.verify =
+        Module.cwrap("verify_n_queens", 'number', ['number', 'number', 'number']);
+    }
+
+    let mem = Module._malloc(Math.max(positions.length, 1) * 4);
+    for (let i = 0; i < positions.length; ++i)
+      Module.setValue(mem + i * 4, positions[i], 'i32');
+
+    let result = FFI_CACHE.verify(mem, positions.length, n);
+    Module._free(mem);
+
+    // Status codes count down from the largest integer, so they come back
+    // negative.
+    if (result < 0)
+      return null;
+    return new Verification(result >> 1, (result & 1) == 1);
+  }
+
+  // The origin is 0 for the top left corner, 1 for the top right, 2 for
+  // the bottom left and 3 for the bottom right. The order is 0 for
+  // `x + y * n` and 1 for `y + x * n`.
+  setCoordinateConvention(origin: number, order: number) : boolean {
+    return Module.ccall("set_coordinate_convention", 'number',
+                        ['number', 'number'], [origin, order]) == 0;
+  }
+
+  // A negative seed means a random one.
+  solvePreset(n: number,
+              name: string,
+              preset: string,
+              stepCallback?: (event: TraceEvent) => boolean | void,
+              seed: number = -1) : Solution {
+    if (!FFI_CACHE.preset) {
+      FFI_CACHE.preset =
+        Module.cwrap("solve_n_queens_preset", 'number',
+                     ['number', 'number', 'number', 'string', 'string', 'number']);
     }
 
+    let asmCallback = this.eventCallback(stepCallback);
+
     let mem = Module._malloc((n + 1) * 4);
 
     let solutionScore =
-      FFI_CACHE[name](n, mem, asmCallback, ...args);
+      FFI_CACHE.preset(n, mem, asmCallback, name, preset, seed);
 
     let resultLen = Module.getValue(mem, 'i32');
     let rows = new Uint32Array(resultLen);
@@ -67,6 +209,8 @@ class ASMInterface {
       removeFunction(asmCallback);
     Module._free(mem);
 
+    if (solutionScore == CANCELLED_STATUS)
+      return this.cancelledSolution(n, rows);
     return new Solution(rows, solutionScore);
   }
 }
@@ -96,15 +240,26 @@ class Application {
               public geneticCrossoverProbability: HTMLInputElement,
               public geneticMutationProbability: HTMLInputElement,
               public geneticGenerationCount: HTMLInputElement,
-              public runButton: HTMLElement) {
+              public runButton: HTMLElement,
+              public seedChooser: HTMLInputElement,
+              public seedUsed: HTMLElement,
+              public replayButton: HTMLElement) {
     this.asmInterface = new ASMInterface();
   }
 
+  // The seed of the last run, to replay it.
+  lastSeed: number = -1;
+
   run() {
     this.runButton.addEventListener('click', e => {
       this.runWithCurrentState();
     })
 
+    this.replayButton.addEventListener('click', e => {
+      if (this.lastSeed >= 0)
+        this.runWithCurrentState(this.lastSeed);
+    })
+
     this.algorithmChooser.addEventListener('change', e => {
       this.refreshInterface();
     })
@@ -162,8 +317,15 @@ class Application {
     return new AlgorithmConfig(name, args);
   }
 
-  async runWithCurrentState() {
+  // Runs with `seed`, or the one chosen if not given, or a random one if
+  // none is chosen either.
+  async runWithCurrentState(seed?: number) {
     const count = this.numberChooser.valueAsNumber;
+    if (seed === undefined) {
+      seed = this.seedChooser.valueAsNumber;
+      if (!(seed >= 0))
+        seed = Math.floor(Math.random() * 0xffffffff);
+    }
     this.grid.classList.add('no-solution');
     this.grid.innerHTML = "";
     this.scoreBoard.innerHTML = "";
@@ -191,6 +353,8 @@ class Application {
     this.grid.appendChild(fragment);
 
     let animationEnabled = this.animationEnabled.checked;
+    this.lastSeed = seed;
+    this.seedUsed.innerHTML = String(seed);
 
     // FIXME(emilio): This gets all the steps in memory just to avoid using
     // an iterator pattern from Rust.
@@ -200,14 +364,16 @@ class Application {
     let steps = new Array<Solution>();
 
     let stepCount = 0;
+    let currentQueens = new Array<number>();
 
-    this.asmInterface.solve(count, algorithmConfig.name, function(queens, score) {
+    this.asmInterface.solve(count, algorithmConfig.name, function(event) {
+      applyTraceEvent(currentQueens, event);
       if (!animationEnabled) {
         steps.splice(0, steps.length);
         ++stepCount;
       }
-      steps.push(new Solution(queens, score));
-    }, ...algorithmConfig.extra_args);
+      steps.push(new Solution(Uint32Array.from(currentQueens), event.score));
+    }, seed, ...algorithmConfig.extra_args);
 
     let latestQueens = null;
     for (step of steps) {
@@ -0,0 +1,109 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Repeated attempts of a stochastic strategy, as an iterator of their
+//! solutions, each from a fresh seed:
+//!
+//! ```
+//! use local_search_algorithms::prelude::*;
+//!
+//! let best = Attempts::<HillClimbing, _>::new(12, Some(3), |seed| HillClimbingConfig {
+//!     restarts: 0,
+//!     seed: Some(seed),
+//!     ..HillClimbingConfig::default()
+//! })
+//! .take(10)
+//! .min_by_key(|solution| solution.score())
+//! .unwrap();
+//! assert_eq!(best.queen_rows().len(), 12);
+//! ```
+
+use std::marker::PhantomData;
+use *;
+
+/// Runs a whole search of `S` on each call to `next`, never running out.
+///
+/// The seeds of the attempts are derived from a master seed, so that the
+/// same attempts can be run again, and the buffers of each search are
+/// reused by the next one.
+pub struct Attempts<S, F> {
+    /// The state of the generator of the seeds of the attempts.
+    seeds: u64,
+    context: Option<SolverContext>,
+    make_config: F,
+    strategy: PhantomData<S>,
+}
+
+impl<S, F> Attempts<S, F>
+where
+    S: NQueensStrategy,
+    F: FnMut(u64) -> S::Config,
+{
+    /// Creates attempts on boards of `dimension` queens, configured by
+    /// `make_config` from the seed of each one, which it should use.
+    ///
+    /// The seeds are derived from `seed`, or from a random one if unset.
+    pub fn new(dimension: usize, seed: Option<u64>, make_config: F) -> Self {
+        Attempts {
+            seeds: seed.unwrap_or_else(|| ComponentRngs::from_entropy().master_seed),
+            context: Some(SolverContext::new(dimension)),
+            make_config,
+            strategy: PhantomData,
+        }
+    }
+}
+
+impl<S, F> Iterator for Attempts<S, F>
+where
+    S: NQueensStrategy,
+    F: FnMut(u64) -> S::Config,
+{
+    type Item = Solution;
+
+    fn next(&mut self) -> Option<Solution> {
+        let config = (self.make_config)(split_mix_64(&mut self.seeds));
+        let context = self.context.take().unwrap();
+        let (solution, context) = S::new_in_context(context, config).solve_in_context(|_, _| {});
+        self.context = Some(context);
+        Some(solution)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeats_attempts_from_the_master_seed() {
+        let attempts = |seed| {
+            Attempts::<MinConflicts, _>::new(16, Some(seed), |seed| MinConflictsConfig {
+                max_steps: 20,
+                restarts: 0,
+                seed: Some(seed),
+                ..MinConflictsConfig::default()
+            })
+            .take(5)
+            .map(|solution| solution.statistics().master_seed)
+            .collect::<Vec<_>>()
+        };
+
+        let seeds = attempts(1);
+        assert_eq!(seeds, attempts(1));
+        assert_ne!(seeds, attempts(2));
+        assert!(seeds.windows(2).all(|w| w[0] != w[1]));
+    }
+}
@@ -0,0 +1,560 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! A command line front-end to the strategies, like:
+//!
+//! ```console
+//! $ nqueens solve --algorithm simulated-annealing -n 20 --temperature 100 --cooling 0.01
+//! ```
+
+extern crate clap;
+extern crate local_search_algorithms;
+extern crate serde_json;
+
+use clap::{Args, Parser, Subcommand};
+use local_search_algorithms::presets::Preset;
+use local_search_algorithms::solvers::constraint_propagation::{ColumnOrder, SymmetryBreaking};
+use local_search_algorithms::solvers::genetic_algorithm::{RouletteSelection, TournamentSelection};
+use local_search_algorithms::solvers::hybrid::Finisher;
+use local_search_algorithms::solvers::simulated_annealing::GeometricCooling;
+use local_search_algorithms::*;
+use std::fs;
+use std::io;
+use std::process;
+use std::time::Duration;
+
+#[global_allocator]
+static ALLOCATOR: resources::CountingAllocator = resources::CountingAllocator;
+
+#[derive(Parser)]
+#[command(
+    name = "nqueens",
+    about = "Solves the n-queens problem with local search"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
+enum Command {
+    /// Solves a board and prints it.
+    Solve(SolveArgs),
+    /// Lists the strategies and their parameters.
+    List,
+}
+
+#[derive(Args)]
+struct SolveArgs {
+    /// The strategy to use, like `simulated-annealing`, or `auto` to pick
+    /// one, and its preset, from the board, the time limit and `--exact`.
+    /// See `list`.
+    #[arg(short, long, default_value = "hill-climbing")]
+    algorithm: String,
+    /// The number of queens.
+    #[arg(short)]
+    n: usize,
+    /// The configuration the options below are applied on top of: `fast`,
+    /// `thorough` or `teaching-demo`.
+    #[arg(long, default_value = "fast")]
+    preset: String,
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Give up after this many milliseconds.
+    #[arg(long)]
+    max_time_ms: Option<u64>,
+    /// Carry simulated annealing or the genetic algorithm on from this
+    /// file, if it exists, and save where they stopped to it if they run
+    /// out of time. The file is removed once the run it holds is over.
+    #[arg(long)]
+    checkpoint: Option<String>,
+    /// Only let `auto` pick strategies that are sure to find a solution.
+    #[arg(long)]
+    exact: bool,
+    /// Also draw the board to this file, as SVG.
+    #[arg(long)]
+    svg: Option<String>,
+    /// Print the result as JSON instead.
+    #[arg(long)]
+    json: bool,
+    /// Also print which queens can hit each other, as a graph in the
+    /// `dot` format or an `adjacency` list.
+    #[arg(long)]
+    conflict_graph: Option<String>,
+    /// Print the score to stderr every this many steps, and once the search
+    /// ends.
+    #[arg(long)]
+    report_every: Option<usize>,
+    /// Print the score to stderr at most once per this many milliseconds,
+    /// and once the search ends.
+    #[arg(long)]
+    report_interval_ms: Option<u64>,
+
+    /// Place each queen of constraint propagation on the column with the
+    /// fewest free rows.
+    #[arg(long)]
+    most_constrained_first: bool,
+    /// Only search the placements of constraint propagation with the first
+    /// queen on the top half of its column.
+    #[arg(long)]
+    break_symmetry: bool,
+    /// Restarts, for the strategies that support them.
+    #[arg(long)]
+    restarts: Option<usize>,
+    /// The starting temperature of simulated annealing.
+    #[arg(long)]
+    temperature: Option<f32>,
+    /// The geometric cooling factor of simulated annealing.
+    #[arg(long)]
+    cooling: Option<f32>,
+    /// The states kept by local beam search.
+    #[arg(long)]
+    states: Option<usize>,
+    /// The width of the constructive beam search.
+    #[arg(long)]
+    beam_width: Option<usize>,
+    /// The deviations allowed by limited discrepancy search.
+    #[arg(long)]
+    max_discrepancies: Option<usize>,
+    /// The population size of the genetic algorithm.
+    #[arg(long)]
+    generation_size: Option<usize>,
+    /// The generations to run, for the genetic algorithm and cuckoo search.
+    #[arg(long)]
+    generations: Option<usize>,
+    /// The fraction of the population kept as is by the genetic algorithm.
+    #[arg(long)]
+    elitism: Option<f32>,
+    /// The fraction of the rest of the population that the offspring of
+    /// the genetic algorithm replace each generation.
+    #[arg(long)]
+    generation_gap: Option<f32>,
+    #[arg(long)]
+    crossover_probability: Option<f32>,
+    #[arg(long)]
+    mutation_probability: Option<f32>,
+    /// Use tournaments of this many individuals to select the parents of
+    /// the genetic algorithm, or roulette selection if zero.
+    #[arg(long)]
+    tournament_size: Option<usize>,
+    /// The steps of min-conflicts and tabu search.
+    #[arg(long)]
+    max_steps: Option<usize>,
+    /// The tabu tenure of tabu search.
+    #[arg(long)]
+    tenure: Option<usize>,
+    /// The columns solved together by the decomposition.
+    #[arg(long)]
+    block_size: Option<usize>,
+    /// The populations of the island model.
+    #[arg(long)]
+    islands: Option<usize>,
+    /// The generations between migrations of the island model.
+    #[arg(long)]
+    migration_interval: Option<usize>,
+    /// The individuals of each island that migrate.
+    #[arg(long)]
+    migration_size: Option<usize>,
+    /// Evolve each island on its own thread.
+    #[arg(long)]
+    threads: bool,
+    /// The queens the hybrid places by backtracking.
+    #[arg(long)]
+    placed_queens: Option<usize>,
+    /// The local search that finishes the board of the hybrid:
+    /// `hill-climbing` or `simulated-annealing`.
+    #[arg(long)]
+    finisher: Option<String>,
+}
+
+fn run<T: NQueensStrategy>(args: &SolveArgs, config: T::Config) -> Result<Solution, Error> {
+    for warning in T::lint(args.n, &config) {
+        eprintln!("warning: {}", warning);
+    }
+    report(args, T::try_new(args.n, config)?)
+}
+
+/// Solves with `solver`, printing the score as often as `--report-every`
+/// and `--report-interval-ms` ask, if at all.
+fn report<T: NQueensStrategy>(args: &SolveArgs, solver: T) -> Result<Solution, Error> {
+    if args.report_every.is_none() && args.report_interval_ms.is_none() {
+        return solver.try_solve_with_callback(|_, _| {});
+    }
+    let report = ReportConfig {
+        report_every: args.report_every.unwrap_or(0),
+        report_interval: Duration::from_millis(args.report_interval_ms.unwrap_or(0)),
+    };
+    let mut callback = ThrottledCallback::new(report, |_: &[usize], score| {
+        eprintln!("nqueens: score {}", score)
+    });
+    let solution = solver.try_solve_with_callback(|queens, score| callback.step(queens, score))?;
+    callback.finish(&solution);
+    Ok(solution)
+}
+
+/// Loads the checkpoint saved to `--checkpoint`, if any, which must be of
+/// a run of `strategy` on the same board.
+fn load_checkpoint(args: &SolveArgs, strategy: &StrategyInfo) -> Option<Checkpoint> {
+    let path = args.checkpoint.as_ref()?;
+    if strategy.name != info::SIMULATED_ANNEALING.name
+        && strategy.name != info::GENETIC_ALGORITHM.name
+    {
+        fail("only simulated annealing and the genetic algorithm can be checkpointed");
+    }
+
+    let json = match fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return None,
+        Err(e) => fail(&format!("couldn't read checkpoint {:?}: {}", path, e)),
+    };
+    let checkpoint: Checkpoint = serde_json::from_str(&json)
+        .unwrap_or_else(|e| fail(&format!("invalid checkpoint {:?}: {}", path, e)));
+    if checkpoint.strategy() != strategy.name {
+        fail(&format!(
+            "checkpoint {:?} is of {}, not {}",
+            path,
+            checkpoint.strategy().replace('_', "-"),
+            strategy.name.replace('_', "-")
+        ));
+    }
+    if checkpoint.dimension != args.n {
+        fail(&format!(
+            "checkpoint {:?} is of {} queens, not {}",
+            path, checkpoint.dimension, args.n
+        ));
+    }
+    Some(checkpoint)
+}
+
+/// Saves where the run stopped to `--checkpoint`, or removes the checkpoint
+/// it carried on from once it's over. A file the run didn't resume from is
+/// left alone unless there's a new checkpoint to save.
+fn save_checkpoint(args: &SolveArgs, solution: &Solution, resumed: bool) {
+    let path = match args.checkpoint {
+        Some(ref path) => path,
+        None => return,
+    };
+    match solution.checkpoint() {
+        Some(checkpoint) => serde_json::to_string(checkpoint)
+            .map_err(io::Error::from)
+            .and_then(|json| fs::write(path, json))
+            .unwrap_or_else(|e| fail(&format!("couldn't save checkpoint {:?}: {}", path, e))),
+        None if resumed => fs::remove_file(path)
+            .unwrap_or_else(|e| fail(&format!("couldn't remove checkpoint {:?}: {}", path, e))),
+        None => {}
+    }
+}
+
+fn solve(
+    args: &SolveArgs,
+    strategy: &StrategyInfo,
+    preset: Preset,
+    checkpoint: Option<Checkpoint>,
+) -> Result<Solution, Error> {
+    let n = args.n;
+    let budget = SolveBudget {
+        max_wall_time: args.max_time_ms.map(Duration::from_millis),
+        ..SolveBudget::default()
+    };
+    let seed = args.seed;
+
+    match strategy.name {
+        "constraint_propagation" => {
+            let mut config = preset.constraint_propagation(n);
+            config.restarts = args.restarts.unwrap_or(config.restarts);
+            if args.most_constrained_first {
+                config.column_order = ColumnOrder::MostConstrained;
+            }
+            if args.break_symmetry {
+                config.symmetry_breaking = SymmetryBreaking::Reflection;
+            }
+            run::<ConstraintPropagation>(
+                args,
+                ConstraintPropagationConfig {
+                    seed,
+                    budget,
+                    ..config
+                },
+            )
+        }
+        "constructive_beam_search" => {
+            let mut config = preset.constructive_beam_search(n);
+            config.beam_width = args.beam_width.unwrap_or(config.beam_width);
+            run::<ConstructiveBeamSearch>(
+                args,
+                ConstructiveBeamSearchConfig {
+                    seed,
+                    budget,
+                    ..config
+                },
+            )
+        }
+        "limited_discrepancy_search" => {
+            let mut config = preset.limited_discrepancy_search(n);
+            config.max_discrepancies = args.max_discrepancies.unwrap_or(config.max_discrepancies);
+            run::<LimitedDiscrepancySearch>(
+                args,
+                LimitedDiscrepancySearchConfig { budget, ..config },
+            )
+        }
+        "hill_climbing" => {
+            let mut config = preset.hill_climbing(n);
+            config.restarts = args.restarts.unwrap_or(config.restarts);
+            run::<HillClimbing>(
+                args,
+                HillClimbingConfig {
+                    seed,
+                    budget,
+                    ..config
+                },
+            )
+        }
+        "simulated_annealing" => {
+            let mut config = preset.simulated_annealing(n);
+            config.restarts = args.restarts.unwrap_or(config.restarts);
+            if let Some(temperature) = args.temperature {
+                config.starting_temperature = temperature;
+                config.initial_acceptance = None;
+            }
+            if let Some(factor) = args.cooling {
+                config.cooling_schedule = Box::new(GeometricCooling { factor });
+            }
+            let config = SimulatedAnnealingConfig {
+                seed,
+                budget,
+                ..config
+            };
+            match checkpoint {
+                Some(checkpoint) => report(args, SimulatedAnnealing::resume(checkpoint, config)?),
+                None => run::<SimulatedAnnealing>(args, config),
+            }
+        }
+        "local_beam_search" => {
+            let mut config = preset.local_beam_search(n);
+            config.state_count = args.states.unwrap_or(config.state_count);
+            run::<LocalBeamSearch>(
+                args,
+                LocalBeamSearchConfig {
+                    seed,
+                    budget,
+                    ..config
+                },
+            )
+        }
+        "genetic" => {
+            let mut config = preset.genetic_algorithm(n);
+            config.generation_size = args.generation_size.unwrap_or(config.generation_size);
+            config.generation_count = args.generations.unwrap_or(config.generation_count);
+            config.elitism = args.elitism.unwrap_or(config.elitism);
+            config.generation_gap = args.generation_gap.unwrap_or(config.generation_gap);
+            config.crossover_probability = args
+                .crossover_probability
+                .unwrap_or(config.crossover_probability);
+            config.mutation_probability = args
+                .mutation_probability
+                .unwrap_or(config.mutation_probability);
+            match args.tournament_size {
+                None => {}
+                Some(0) => config.selection = Box::new(RouletteSelection),
+                Some(size) => config.selection = Box::new(TournamentSelection { size }),
+            }
+            let config = GeneticAlgorithmConfig {
+                seed,
+                budget,
+                ..config
+            };
+            match checkpoint {
+                Some(checkpoint) => report(args, GeneticAlgorithm::resume(checkpoint, config)?),
+                None => run::<GeneticAlgorithm>(args, config),
+            }
+        }
+        "pbil" => run::<Pbil>(
+            args,
+            PbilConfig {
+                seed,
+                budget,
+                ..preset.pbil(n)
+            },
+        ),
+        "cuckoo_search" => {
+            let mut config = preset.cuckoo_search(n);
+            config.generation_count = args.generations.unwrap_or(config.generation_count);
+            run::<CuckooSearch>(
+                args,
+                CuckooSearchConfig {
+                    seed,
+                    budget,
+                    ..config
+                },
+            )
+        }
+        "min_conflicts" => {
+            let mut config = preset.min_conflicts(n);
+            config.restarts = args.restarts.unwrap_or(config.restarts);
+            config.max_steps = args.max_steps.unwrap_or(config.max_steps);
+            run::<MinConflicts>(
+                args,
+                MinConflictsConfig {
+                    seed,
+                    budget,
+                    ..config
+                },
+            )
+        }
+        "tabu_search" => {
+            let mut config = preset.tabu_search(n);
+            config.tenure = args.tenure.unwrap_or(config.tenure);
+            config.max_steps = args.max_steps.unwrap_or(config.max_steps);
+            run::<TabuSearch>(
+                args,
+                TabuSearchConfig {
+                    seed,
+                    budget,
+                    ..config
+                },
+            )
+        }
+        "decomposition" => {
+            let mut config = preset.decomposition(n);
+            config.block_size = args.block_size.unwrap_or(config.block_size);
+            run::<Decomposition>(
+                args,
+                DecompositionConfig {
+                    seed,
+                    budget,
+                    ..config
+                },
+            )
+        }
+        "island_model" => {
+            let mut config = preset.island_model(n);
+            config.islands = args.islands.unwrap_or(config.islands);
+            config.migration_interval =
+                args.migration_interval.unwrap_or(config.migration_interval);
+            config.migration_size = args.migration_size.unwrap_or(config.migration_size);
+            config.threads |= args.threads;
+            run::<IslandModel>(
+                args,
+                IslandModelConfig {
+                    seed,
+                    budget,
+                    ..config
+                },
+            )
+        }
+        "hybrid" => {
+            let mut config = preset.hybrid(n);
+            config.placed_queens = args.placed_queens.unwrap_or(config.placed_queens);
+            if let Some(ref finisher) = args.finisher {
+                config.finisher = match &*finisher.replace('-', "_") {
+                    "hill_climbing" => Finisher::HillClimbing(preset.hill_climbing(n)),
+                    "simulated_annealing" => {
+                        Finisher::SimulatedAnnealing(preset.simulated_annealing(n))
+                    }
+                    _ => return Err(Error::InvalidConfig("unknown finisher")),
+                };
+            }
+            run::<Hybrid>(
+                args,
+                HybridConfig {
+                    seed,
+                    budget,
+                    ..config
+                },
+            )
+        }
+        _ => Err(Error::InvalidConfig("unknown strategy")),
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("nqueens: {}", message);
+    process::exit(2)
+}
+
+fn main() {
+    let args = match Cli::parse().command {
+        Command::Solve(args) => args,
+        Command::List => {
+            for strategy in info::STRATEGIES {
+                let parameters = strategy
+                    .parameters
+                    .iter()
+                    .map(|p| p.name)
+                    .collect::<Vec<_>>();
+                println!(
+                    "{}: {}",
+                    strategy.name.replace('_', "-"),
+                    parameters.join(", ")
+                );
+            }
+            return;
+        }
+    };
+
+    let (strategy, preset) = if args.algorithm == "auto" {
+        let budget = SolveBudget {
+            max_wall_time: args.max_time_ms.map(Duration::from_millis),
+            ..SolveBudget::default()
+        };
+        let selection = selector::select(args.n, &budget, args.exact);
+        eprintln!("nqueens: picked {}", selection);
+        (selection.strategy, selection.preset)
+    } else {
+        let strategy = info::strategy(&args.algorithm.replace('-', "_"))
+            .unwrap_or_else(|| fail(&format!("unknown algorithm {:?}", args.algorithm)));
+        let preset = Preset::from_name(&args.preset)
+            .unwrap_or_else(|| fail(&format!("unknown preset {:?}", args.preset)));
+        (strategy, preset)
+    };
+    let conflict_graph = args
+        .conflict_graph
+        .as_ref()
+        .map(|format| match &format[..] {
+            "dot" => ConflictGraph::to_dot as fn(&ConflictGraph) -> String,
+            "adjacency" => ConflictGraph::to_adjacency_list,
+            _ => fail(&format!("unknown conflict graph format {:?}", format)),
+        });
+    let checkpoint = load_checkpoint(&args, strategy);
+    let resumed = checkpoint.is_some();
+    let solution =
+        solve(&args, strategy, preset, checkpoint).unwrap_or_else(|e| fail(&e.to_string()));
+    save_checkpoint(&args, &solution, resumed);
+
+    if let Some(ref path) = args.svg {
+        fs::write(path, solution.to_svg(&SvgOptions::default()))
+            .unwrap_or_else(|e| fail(&format!("couldn't write {:?}: {}", path, e)));
+    }
+
+    if args.json {
+        println!("{}", solution.to_json());
+        return;
+    }
+
+    let statistics = solution.statistics();
+    print!("{}", solution);
+    println!("Score: {}", solution.score());
+    println!("Iterations: {}", statistics.iterations);
+    println!("{}", summary::summarize(&solution, statistics));
+    if let Some(resources) = summary::describe_resources(&statistics.resources) {
+        println!("{}", resources);
+    }
+    if let Some(format) = conflict_graph {
+        print!("{}", format(&solution.conflict_graph()));
+    }
+}
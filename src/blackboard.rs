@@ -0,0 +1,309 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! A blackboard of the best state found by searches running at the same
+//! time, like parallel restarts, the members of a portfolio or islands, so
+//! that they can carry on from each other's discoveries:
+//!
+//! ```
+//! use local_search_algorithms::prelude::*;
+//! use std::thread;
+//!
+//! let blackboard = Blackboard::new();
+//! let workers = (0..4)
+//!     .map(|worker| {
+//!         let mut client = blackboard.client();
+//!         thread::spawn(move || {
+//!             for attempt in 0..3 {
+//!                 let config = MinConflictsConfig {
+//!                     max_steps: 20,
+//!                     restarts: 0,
+//!                     seed: Some(worker * 10 + attempt),
+//!                     ..MinConflictsConfig::default()
+//!                 };
+//!                 // Go on from what another worker found, if it's better.
+//!                 let search = match client.sync() {
+//!                     Some((queen_rows, _)) => MinConflicts::from_placement(queen_rows, config),
+//!                     None => MinConflicts::new(40, config),
+//!                 };
+//!                 search.solve_with_callback(|queens, score| client.offer(queens, score));
+//!             }
+//!             client.sync();
+//!         })
+//!     })
+//!     .collect::<Vec<_>>();
+//! for worker in workers {
+//!     worker.join().unwrap();
+//! }
+//! assert!(blackboard.best().is_some());
+//! ```
+//!
+//! Searches don't touch the blackboard on each step. Each keeps its own best
+//! state in a `BlackboardClient`, and only publishes it, and picks up what
+//! the others published, when it syncs, like between restarts. Publishing
+//! an improvement starts a new epoch of the blackboard, so telling whether
+//! there's anything new to pick up is a single atomic load.
+//!
+//! What a search picks up then depends on how far the others got, and so on
+//! how the threads are scheduled. A blackboard made with
+//! `Blackboard::lockstep` instead has its searches sync in rounds, so that
+//! seeded runs are reproducible.
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::{Barrier, Mutex};
+use *;
+
+/// The best state published by any of the searches sharing it. Clones
+/// share the same blackboard.
+#[derive(Clone, Debug, Default)]
+pub struct Blackboard {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    /// How many improvements were published.
+    epoch: AtomicUsize,
+    best: Mutex<Option<(Vec<usize>, usize)>>,
+    /// Where the clients wait for each other on each sync, if they sync in
+    /// lockstep.
+    lockstep: Option<Barrier>,
+}
+
+impl Blackboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a blackboard for exactly `clients` clients that sync in
+    /// lockstep: each sync waits until every client has published, and
+    /// they all pick up the best of what was published, ties going to the
+    /// smallest queen rows. What each search sees is then the same however
+    /// the threads are scheduled, so a run with seeded searches ends the
+    /// same way every time.
+    ///
+    /// Every client must sync the same number of times, or the others wait
+    /// for it forever.
+    pub fn lockstep(clients: usize) -> Self {
+        Blackboard {
+            shared: Arc::new(Shared {
+                lockstep: Some(Barrier::new(clients)),
+                ..Shared::default()
+            }),
+        }
+    }
+
+    /// The number of improvements published so far.
+    pub fn epoch(&self) -> usize {
+        self.shared.epoch.load(Ordering::Acquire)
+    }
+
+    /// The best state published so far, and its score.
+    pub fn best(&self) -> Option<(Vec<usize>, usize)> {
+        self.shared.best.lock().unwrap().clone()
+    }
+
+    /// Publishes `queen_rows` with `score`, and returns whether it improved
+    /// on the best state, in which case it replaces it.
+    pub fn publish(&self, queen_rows: &[usize], score: usize) -> bool {
+        let mut best = self.shared.best.lock().unwrap();
+        let lockstep = self.shared.lockstep.is_some();
+        let worse = match *best {
+            Some((ref best, best_score)) => {
+                best_score < score
+                    || (best_score == score && (!lockstep || &best[..] <= queen_rows))
+            }
+            None => false,
+        };
+        if worse {
+            return false;
+        }
+        *best = Some((queen_rows.to_vec(), score));
+        self.shared.epoch.fetch_add(1, Ordering::Release);
+        true
+    }
+
+    /// Returns a client for a search to share its states through.
+    pub fn client(&self) -> BlackboardClient {
+        BlackboardClient {
+            blackboard: self.clone(),
+            epoch: 0,
+            pending: None,
+            best_score: None,
+        }
+    }
+}
+
+/// The side of the blackboard of a single search.
+#[derive(Clone, Debug)]
+pub struct BlackboardClient {
+    blackboard: Blackboard,
+    /// The epoch of the blackboard when it was last synced.
+    epoch: usize,
+    /// The best state offered since the last sync, if it's the best one
+    /// known here.
+    pending: Option<(Vec<usize>, usize)>,
+    /// The score of the best state known here, whether offered or picked
+    /// up from the blackboard.
+    best_score: Option<usize>,
+}
+
+impl BlackboardClient {
+    pub fn blackboard(&self) -> &Blackboard {
+        &self.blackboard
+    }
+
+    /// Keeps `queen_rows` to publish on the next sync, if it's the best
+    /// state known here, without touching the blackboard, so that it can
+    /// be called on each step.
+    pub fn offer(&mut self, queen_rows: &[usize], score: usize) {
+        if self.best_score.is_some_and(|best| best <= score) {
+            return;
+        }
+        self.best_score = Some(score);
+        match self.pending {
+            Some((ref mut pending, ref mut pending_score)) => {
+                pending.clear();
+                pending.extend_from_slice(queen_rows);
+                *pending_score = score;
+            }
+            None => self.pending = Some((queen_rows.to_vec(), score)),
+        }
+    }
+
+    /// Publishes the best state offered since the last sync, and returns
+    /// the best one on the blackboard if it's better than any known here,
+    /// which means that another search published it.
+    ///
+    /// On a blackboard in lockstep, this waits for the other clients to
+    /// sync too.
+    pub fn sync(&mut self) -> Option<(Vec<usize>, usize)> {
+        if let Some((queen_rows, score)) = self.pending.take() {
+            self.blackboard.publish(&queen_rows, score);
+        }
+
+        let shared = self.blackboard.shared.clone();
+        let lockstep = shared.lockstep.as_ref();
+        // Nothing is picked up until everyone published, and nothing is
+        // published again until everyone picked up.
+        if let Some(barrier) = lockstep {
+            barrier.wait();
+        }
+        let best = self.pick_up();
+        if let Some(barrier) = lockstep {
+            barrier.wait();
+        }
+        best
+    }
+
+    /// Returns the best state on the blackboard if it's newer and better
+    /// than any known here.
+    fn pick_up(&mut self) -> Option<(Vec<usize>, usize)> {
+        let epoch = self.blackboard.epoch();
+        if epoch == self.epoch {
+            return None;
+        }
+        self.epoch = epoch;
+        let (queen_rows, score) = self.blackboard.best()?;
+        if self.best_score.is_some_and(|best| best <= score) {
+            return None;
+        }
+        self.best_score = Some(score);
+        Some((queen_rows, score))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shares_the_best_state_on_sync() {
+        let blackboard = Blackboard::new();
+        let mut first = blackboard.client();
+        let mut second = blackboard.client();
+
+        first.offer(&[0, 1, 2, 3], 6);
+        first.offer(&[1, 0, 2, 3], 4);
+        first.offer(&[0, 1, 2, 3], 6);
+        assert_eq!(blackboard.epoch(), 0);
+        assert_eq!(first.sync(), None);
+        assert_eq!(blackboard.epoch(), 1);
+        assert_eq!(blackboard.best(), Some((vec![1, 0, 2, 3], 4)));
+
+        assert_eq!(second.sync(), Some((vec![1, 0, 2, 3], 4)));
+        assert_eq!(second.sync(), None);
+        second.offer(&[1, 3, 0, 2], 0);
+        assert_eq!(second.sync(), None);
+        assert_eq!(first.sync(), Some((vec![1, 3, 0, 2], 0)));
+
+        assert!(!blackboard.publish(&[0, 1, 2, 3], 6));
+        assert_eq!(blackboard.epoch(), 2);
+    }
+
+    #[test]
+    fn lockstep_runs_dont_depend_on_scheduling() {
+        use std::thread;
+        use std::time::Duration;
+
+        // One of the searches lags behind on each run, which changes what
+        // the others pick up unless they wait for it.
+        let run = |slow: u64| {
+            let blackboard = Blackboard::lockstep(4);
+            let workers = (0..4)
+                .map(|worker| {
+                    let mut client = blackboard.client();
+                    thread::spawn(move || {
+                        let mut picked_up = vec![];
+                        for attempt in 0..4 {
+                            if worker == slow {
+                                thread::sleep(Duration::from_millis(5));
+                            }
+                            let config = MinConflictsConfig {
+                                max_steps: 5,
+                                restarts: 0,
+                                seed: Some(worker * 10 + attempt),
+                                ..MinConflictsConfig::default()
+                            };
+                            let search = match client.sync() {
+                                Some((queen_rows, score)) => {
+                                    picked_up.push(score);
+                                    MinConflicts::from_placement(queen_rows, config)
+                                }
+                                None => MinConflicts::new(30, config),
+                            };
+                            search.solve_with_callback(|queens, score| client.offer(queens, score));
+                        }
+                        client.sync();
+                        picked_up
+                    })
+                })
+                .collect::<Vec<_>>();
+            let picked_up = workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .collect::<Vec<_>>();
+            (blackboard.best(), picked_up)
+        };
+
+        let first = run(0);
+        assert!(first.0.is_some());
+        for slow in 1..4 {
+            assert_eq!(run(slow), first);
+        }
+    }
+}
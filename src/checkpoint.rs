@@ -0,0 +1,142 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Checkpoints of the searches that run out of budget before they're over,
+//! or taken with `snapshot` between the steps of one, to carry them on
+//! later, for runs too long to do at once.
+//!
+//! With the `serde` feature, checkpoints can be saved and restored, so that
+//! a run split in chunks of bounded wall time survives the process it runs
+//! in:
+//!
+//! ```
+//! use local_search_algorithms::prelude::*;
+//!
+//! let chunk = || SimulatedAnnealingConfig {
+//!     budget: SolveBudget {
+//!         max_iterations: Some(50),
+//!         ..SolveBudget::default()
+//!     },
+//!     ..Preset::Fast.simulated_annealing(30)
+//! };
+//!
+//! let mut solution = SimulatedAnnealing::new(30, chunk()).solve();
+//! while let Some(checkpoint) = solution.checkpoint().cloned() {
+//!     solution = SimulatedAnnealing::resume(checkpoint, chunk()).unwrap().solve();
+//! }
+//! assert!(solution.statistics().iterations > 50);
+//! ```
+
+use *;
+
+/// The state a search was stopped at, along with its random streams and
+/// counters.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Checkpoint {
+    pub dimension: usize,
+    pub rngs: ComponentRngs,
+    pub effort: SearchEffort,
+    /// The iterations run so far, across all the resumed runs.
+    pub iterations: usize,
+    /// The restarts so far, or the reseeds of the genetic algorithm.
+    pub restarts: usize,
+    /// The best placement seen so far, and its score.
+    pub best: Option<(Vec<usize>, usize)>,
+    pub state: SearchState,
+}
+
+/// What each strategy needs to carry on from a checkpoint.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SearchState {
+    SimulatedAnnealing {
+        /// The placement the annealing was at.
+        queen_rows: Vec<usize>,
+        starting_temperature: f32,
+        cooling_start: f32,
+        temperature: f32,
+        /// Iterations since the annealing last (re)started or was
+        /// reheated.
+        iteration: usize,
+        /// Iterations since the annealing last took a move.
+        stagnant_iterations: usize,
+        reheats: usize,
+        /// How many more times the current annealing can be reheated.
+        reheats_left: usize,
+    },
+    GeneticAlgorithm {
+        /// The generation to evolve next.
+        population: Vec<Vec<usize>>,
+        generation: usize,
+        /// The generations left of the run that was stopped.
+        pending_generations: usize,
+        /// The generations since the best score last improved, which
+        /// reseeding and bringing back the hall of fame go by.
+        stagnant_generations: usize,
+        /// The mutation probability, as adapted to the diversity so far.
+        mutation_probability: f32,
+        /// The best distinct individuals seen, best first, and their
+        /// scores.
+        hall_of_fame: Vec<(Vec<usize>, usize)>,
+    },
+}
+
+impl Checkpoint {
+    /// Returns the name of the strategy the checkpoint is of, as in its
+    /// `StrategyInfo`.
+    pub fn strategy(&self) -> &'static str {
+        match self.state {
+            SearchState::SimulatedAnnealing { .. } => info::SIMULATED_ANNEALING.name,
+            SearchState::GeneticAlgorithm { .. } => info::GENETIC_ALGORITHM.name,
+        }
+    }
+
+    /// Checks that every placement in the checkpoint has a queen on one of
+    /// the `dimension` rows of each of its `dimension` columns, as they may
+    /// come from a file.
+    pub fn validate(&self) -> Result<(), Error> {
+        let dimension = self.dimension;
+        let is_placement = |queen_rows: &[usize]| {
+            queen_rows.len() == dimension && queen_rows.iter().all(|&row| row < dimension)
+        };
+        let valid = match self.state {
+            SearchState::SimulatedAnnealing { ref queen_rows, .. } => is_placement(queen_rows),
+            SearchState::GeneticAlgorithm {
+                ref population,
+                ref hall_of_fame,
+                ..
+            } => {
+                population.iter().all(|queen_rows| is_placement(queen_rows))
+                    && hall_of_fame
+                        .iter()
+                        .all(|(queen_rows, _)| is_placement(queen_rows))
+            }
+        };
+        if !valid
+            || !self
+                .best
+                .as_ref()
+                .is_none_or(|(queen_rows, _)| is_placement(queen_rows))
+        {
+            return Err(Error::InvalidConfig(
+                "the checkpoint has placements of another board size",
+            ));
+        }
+        Ok(())
+    }
+}
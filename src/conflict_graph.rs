@@ -0,0 +1,216 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The conflicts of a placement as a graph, where the queens are the nodes,
+//! and each pair of queens that can hit each other is an edge.
+//!
+//! The queens on the same line all hit each other, so each line with more
+//! than one queen is a clique of the graph, and a near-solution is usually
+//! a handful of small ones.
+
+use *;
+
+/// The line two queens share.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ConflictLine {
+    Row,
+    /// The diagonal going up to the right.
+    Diagonal,
+    /// The diagonal going down to the right.
+    AntiDiagonal,
+}
+
+impl ConflictLine {
+    fn name(self) -> &'static str {
+        match self {
+            ConflictLine::Row => "row",
+            ConflictLine::Diagonal => "diagonal",
+            ConflictLine::AntiDiagonal => "anti-diagonal",
+        }
+    }
+}
+
+/// A set of queens on the same line, which all hit each other.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConflictClique {
+    pub line: ConflictLine,
+    /// The columns of the queens, in order.
+    pub columns: Vec<usize>,
+}
+
+/// A pair of queens that can hit each other, by column.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConflictEdge {
+    pub columns: (usize, usize),
+    pub line: ConflictLine,
+}
+
+/// The conflict graph of a placement of one queen per column.
+#[derive(Clone, Debug)]
+pub struct ConflictGraph {
+    queen_rows: Vec<usize>,
+    cliques: Vec<ConflictClique>,
+}
+
+impl ConflictGraph {
+    pub fn new(queen_rows: &[usize]) -> Self {
+        let mut lines = HashMap::new();
+        for (column, &row) in queen_rows.iter().enumerate() {
+            // Offset so that the diagonals of every board are non-negative.
+            let diagonal = column + queen_rows.len() - row;
+            for &line in &[
+                (ConflictLine::Row, row),
+                (ConflictLine::Diagonal, diagonal),
+                (ConflictLine::AntiDiagonal, column + row),
+            ] {
+                lines.entry(line).or_insert_with(Vec::new).push(column);
+            }
+        }
+
+        let mut cliques = lines
+            .into_iter()
+            .filter(|(_, columns)| columns.len() > 1)
+            .map(|((line, _), columns)| ConflictClique { line, columns })
+            .collect::<Vec<_>>();
+        cliques.sort_by(|a, b| a.columns.cmp(&b.columns));
+        ConflictGraph {
+            queen_rows: queen_rows.to_vec(),
+            cliques,
+        }
+    }
+
+    /// Returns the queens on each line with more than one, ordered by
+    /// their columns.
+    pub fn cliques(&self) -> &[ConflictClique] {
+        &self.cliques
+    }
+
+    /// Returns every pair of queens that can hit each other, ordered by
+    /// their columns. Queens that share more than a line are joined by an
+    /// edge for each.
+    pub fn edges(&self) -> Vec<ConflictEdge> {
+        let mut edges = vec![];
+        for clique in &self.cliques {
+            for (i, &one) in clique.columns.iter().enumerate() {
+                for &other in &clique.columns[i + 1..] {
+                    edges.push(ConflictEdge {
+                        columns: (one, other),
+                        line: clique.line,
+                    });
+                }
+            }
+        }
+        edges.sort_by_key(|edge| edge.columns);
+        edges
+    }
+
+    /// Returns the columns of the queens that can hit the queen of each
+    /// column, in order.
+    pub fn adjacency(&self) -> Vec<Vec<usize>> {
+        let mut adjacency = vec![vec![]; self.queen_rows.len()];
+        for edge in self.edges() {
+            let (one, other) = edge.columns;
+            adjacency[one].push(other);
+            adjacency[other].push(one);
+        }
+        for neighbors in &mut adjacency {
+            neighbors.sort();
+            neighbors.dedup();
+        }
+        adjacency
+    }
+
+    /// Returns the adjacency list of the graph, as a line per queen with
+    /// its column, a colon, and the columns of the queens it can hit.
+    pub fn to_adjacency_list(&self) -> String {
+        use std::fmt::Write;
+
+        let mut list = String::new();
+        for (column, neighbors) in self.adjacency().iter().enumerate() {
+            let _ = write!(list, "{}:", column);
+            for neighbor in neighbors {
+                let _ = write!(list, " {}", neighbor);
+            }
+            list.push('\n');
+        }
+        list
+    }
+
+    /// Returns the graph in the DOT format, with the queens labeled by
+    /// their square, and the edges by the line they share.
+    pub fn to_dot(&self) -> String {
+        use std::fmt::Write;
+
+        let mut dot = String::from("graph conflicts {\n");
+        for (column, row) in self.queen_rows.iter().enumerate() {
+            let _ = writeln!(dot, "  {} [label=\"({}, {})\"];", column, column, row);
+        }
+        for edge in self.edges() {
+            let _ = writeln!(
+                dot,
+                "  {} -- {} [label=\"{}\"];",
+                edge.columns.0,
+                edge.columns.1,
+                edge.line.name()
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joins_the_queens_that_can_hit_each_other() {
+        // Queens 0, 1 and 2 are on a diagonal, 1 and 3 on a row, and 2 and
+        // 3 on an anti-diagonal.
+        let graph = ConflictGraph::new(&[0, 1, 2, 1]);
+        assert_eq!(
+            graph.cliques(),
+            &[
+                ConflictClique {
+                    line: ConflictLine::Diagonal,
+                    columns: vec![0, 1, 2],
+                },
+                ConflictClique {
+                    line: ConflictLine::Row,
+                    columns: vec![1, 3],
+                },
+                ConflictClique {
+                    line: ConflictLine::AntiDiagonal,
+                    columns: vec![2, 3],
+                },
+            ]
+        );
+        assert_eq!(
+            graph.edges().len(),
+            attacking_pairs(&[(0, 0), (1, 1), (2, 2), (3, 1)])
+        );
+        assert_eq!(
+            graph.to_adjacency_list(),
+            "0: 1 2\n1: 0 2 3\n2: 0 1 3\n3: 1 2\n"
+        );
+        assert!(graph.to_dot().contains("  1 -- 3 [label=\"row\"];"));
+
+        let solution = ConflictGraph::new(&[1, 3, 0, 2]);
+        assert!(solution.cliques().is_empty());
+        assert!(solution.edges().is_empty());
+    }
+}
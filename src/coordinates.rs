@@ -0,0 +1,196 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Coordinate conventions, to talk about squares the way the caller does.
+//!
+//! Internally, the queen of each column is at `queen_rows[column]`, with
+//! column zero on the left and row zero on top, which is how a `Solution`
+//! renders. A `CoordinateConvention` says which corner of the board the
+//! caller counts columns and rows from, and how it flattens a square into
+//! a single index.
+
+/// The corner of the board that column and row zero are at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// How a `(column, row)` pair is flattened into a single index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Order {
+    /// `column + row * n`, which is what the library has always used.
+    RowMajor,
+    /// `row + column * n`.
+    ColumnMajor,
+}
+
+/// The way a caller numbers the squares of a board. The default is the
+/// internal one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CoordinateConvention {
+    pub origin: Corner,
+    pub order: Order,
+}
+
+impl Default for CoordinateConvention {
+    fn default() -> Self {
+        CoordinateConvention {
+            origin: Corner::TopLeft,
+            order: Order::RowMajor,
+        }
+    }
+}
+
+impl CoordinateConvention {
+    fn flips_columns(self) -> bool {
+        self.origin == Corner::TopRight || self.origin == Corner::BottomRight
+    }
+
+    fn flips_rows(self) -> bool {
+        self.origin == Corner::BottomLeft || self.origin == Corner::BottomRight
+    }
+
+    /// Returns the `(column, row)` in this convention of the internal
+    /// square `(column, row)` of a board of `n` queens.
+    pub fn from_internal(self, n: usize, (column, row): (usize, usize)) -> (usize, usize) {
+        let column = if self.flips_columns() {
+            n - 1 - column
+        } else {
+            column
+        };
+        let row = if self.flips_rows() { n - 1 - row } else { row };
+        (column, row)
+    }
+
+    /// Returns the internal `(column, row)` of the square `(column, row)`
+    /// in this convention, on a board of `n` queens.
+    pub fn to_internal(self, n: usize, square: (usize, usize)) -> (usize, usize) {
+        // Flipping is its own inverse.
+        self.from_internal(n, square)
+    }
+
+    /// Returns the index in this convention of the internal square
+    /// `(column, row)` of a board of `n` queens.
+    pub fn index(self, n: usize, square: (usize, usize)) -> usize {
+        let (column, row) = self.from_internal(n, square);
+        match self.order {
+            Order::RowMajor => column + row * n,
+            Order::ColumnMajor => row + column * n,
+        }
+    }
+
+    /// Returns the internal `(column, row)` of the square at `index` in
+    /// this convention, on a board of `n` queens.
+    pub fn square(self, n: usize, index: usize) -> (usize, usize) {
+        let square = match self.order {
+            Order::RowMajor => (index % n, index / n),
+            Order::ColumnMajor => (index / n, index % n),
+        };
+        self.to_internal(n, square)
+    }
+
+    /// Returns the index in `other` of the square at `index` in this
+    /// convention, on a board of `n` queens.
+    pub fn convert(self, n: usize, index: usize, other: CoordinateConvention) -> usize {
+        other.index(n, self.square(n, index))
+    }
+
+    /// Returns the rows of the queens of the internal `queen_rows` in this
+    /// convention, by column in this convention.
+    ///
+    /// If the placement is partial, that's the rows of the columns placed,
+    /// so the first of them isn't necessarily the one of column zero.
+    pub fn queen_rows(self, n: usize, queen_rows: &[usize]) -> Vec<usize> {
+        let mut rows = queen_rows
+            .iter()
+            .enumerate()
+            .map(|(column, &row)| self.from_internal(n, (column, row)).1)
+            .collect::<Vec<_>>();
+        if self.flips_columns() {
+            rows.reverse();
+        }
+        rows
+    }
+
+    /// Wraps the `callback` of a strategy solving a board of `n` queens,
+    /// so that it gets the queen rows in this convention.
+    pub fn callback<F>(self, n: usize, mut callback: F) -> impl FnMut(&[usize], usize)
+    where
+        F: FnMut(&[usize], usize),
+    {
+        move |queen_rows, score| {
+            if self == CoordinateConvention::default() {
+                callback(queen_rows, score)
+            } else {
+                callback(&self.queen_rows(n, queen_rows), score)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Solution;
+
+    #[test]
+    fn converts_between_conventions() {
+        let internal = CoordinateConvention::default();
+        let chess = CoordinateConvention {
+            origin: Corner::BottomLeft,
+            order: Order::ColumnMajor,
+        };
+
+        // The top right corner of a 4x4 board.
+        assert_eq!(internal.index(4, (3, 0)), 3);
+        assert_eq!(chess.from_internal(4, (3, 0)), (3, 3));
+        assert_eq!(chess.index(4, (3, 0)), 15);
+        assert_eq!(internal.convert(4, 3, chess), 15);
+
+        for &origin in &[
+            Corner::TopLeft,
+            Corner::TopRight,
+            Corner::BottomLeft,
+            Corner::BottomRight,
+        ] {
+            for &order in &[Order::RowMajor, Order::ColumnMajor] {
+                let convention = CoordinateConvention { origin, order };
+                for index in 0..25 {
+                    let square = convention.square(5, index);
+                    assert_eq!(convention.index(5, square), index);
+                }
+            }
+        }
+
+        let mirrored = CoordinateConvention {
+            origin: Corner::BottomRight,
+            order: Order::RowMajor,
+        };
+        assert_eq!(mirrored.queen_rows(4, &[1, 3, 0, 2]), vec![1, 3, 0, 2]);
+        assert_eq!(mirrored.queen_rows(4, &[0, 2]), vec![1, 3]);
+
+        let solution = Solution::new(vec![1, 3, 0, 2], 0);
+        assert_eq!(solution.indices(chess), vec![2, 4, 11, 13]);
+        assert_eq!(
+            solution.render(chess),
+            "3 . . Q .\n2 Q . . .\n1 . . . Q\n0 . Q . .\n  0 1 2 3\n"
+        );
+    }
+}
@@ -0,0 +1,158 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Traces of the steps of a search as CSV, a row per state the search
+//! moved to, to plot how it converges with other tools:
+//!
+//! ```
+//! use local_search_algorithms::prelude::*;
+//!
+//! let mut trace = CsvTraceRecorder::new(Vec::new(), HillClimbing::info()).unwrap();
+//! HillClimbing::new(8, HillClimbingConfig::default())
+//!     .solve_with_callback(|queens, score| trace.record(queens, score));
+//! let csv = String::from_utf8(trace.finish().unwrap()).unwrap();
+//! assert!(csv.starts_with("iteration,score,temperature,generation,positions\n"));
+//! ```
+//!
+//! The positions are the row of the queen of each column, separated by
+//! spaces. The temperature is only there for the steps recorded with
+//! `record_with_temperature`, and the generation for the population-based
+//! strategies.
+
+use std::io::{self, Write};
+use *;
+
+/// Writes each step it's given as a row of CSV, with the `record` methods
+/// as the callback of a search.
+pub struct CsvTraceRecorder<W> {
+    writer: W,
+    population_based: bool,
+    rows: usize,
+    /// The first error writing a row, since the callbacks can't fail, to
+    /// return it from `finish`.
+    error: Option<io::Error>,
+}
+
+impl<W: Write> CsvTraceRecorder<W> {
+    /// Creates a recorder of the steps of `strategy` into `writer`, and
+    /// writes the header row.
+    pub fn new(mut writer: W, strategy: &StrategyInfo) -> io::Result<Self> {
+        writeln!(writer, "iteration,score,temperature,generation,positions")?;
+        Ok(CsvTraceRecorder {
+            writer,
+            population_based: strategy.population_based,
+            rows: 0,
+            error: None,
+        })
+    }
+
+    /// Writes a step with `queen_rows` and `score`.
+    pub fn record(&mut self, queen_rows: &[usize], score: usize) {
+        self.write_row(queen_rows, score, None);
+    }
+
+    /// Writes a step with `queen_rows` and `score` taken at `temperature`,
+    /// as given by `SimulatedAnnealing::solve_with_temperatures`.
+    pub fn record_with_temperature(
+        &mut self,
+        queen_rows: &[usize],
+        score: usize,
+        temperature: f32,
+    ) {
+        self.write_row(queen_rows, score, Some(temperature));
+    }
+
+    /// The number of steps written so far.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Flushes the writer and returns it, or the first error writing to it.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_row(&mut self, queen_rows: &[usize], score: usize, temperature: Option<f32>) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let iteration = self.rows;
+        self.rows += 1;
+        let temperature = temperature.map_or(String::new(), |t| t.to_string());
+        let generation = if self.population_based {
+            iteration.to_string()
+        } else {
+            String::new()
+        };
+        let positions = queen_rows
+            .iter()
+            .map(|row| row.to_string())
+            .collect::<Vec<_>>();
+        if let Err(error) = writeln!(
+            self.writer,
+            "{},{},{},{},{}",
+            iteration,
+            score,
+            temperature,
+            generation,
+            positions.join(" ")
+        ) {
+            self.error = Some(error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_a_row_per_step() {
+        let config = SimulatedAnnealingConfig {
+            seed: Some(5),
+            ..SimulatedAnnealingConfig::default()
+        };
+        let mut trace = CsvTraceRecorder::new(Vec::new(), SimulatedAnnealing::info()).unwrap();
+        let mut steps = 0;
+        SimulatedAnnealing::new(10, config).solve_with_temperatures(
+            |queens, score, temperature| {
+                trace.record_with_temperature(queens, score, temperature);
+                steps += 1;
+            },
+        );
+        assert_eq!(trace.rows(), steps);
+
+        let csv = String::from_utf8(trace.finish().unwrap()).unwrap();
+        let rows = csv.lines().skip(1).collect::<Vec<_>>();
+        assert_eq!(rows.len(), steps);
+        let cells = rows[0].split(',').collect::<Vec<_>>();
+        assert_eq!(cells[0], "0");
+        assert!(cells[2].parse::<f32>().unwrap() > 0.);
+        assert_eq!(cells[3], "");
+        assert_eq!(cells[4].split(' ').count(), 10);
+
+        let mut trace = CsvTraceRecorder::new(Vec::new(), GeneticAlgorithm::info()).unwrap();
+        trace.record(&[1, 3, 0, 2], 0);
+        let csv = String::from_utf8(trace.finish().unwrap()).unwrap();
+        assert_eq!(csv.lines().nth(1), Some("0,0,,0,1 3 0 2"));
+    }
+}
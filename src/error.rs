@@ -0,0 +1,76 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The errors returned by the library, and the warnings about
+//! configurations that work, but probably not as intended.
+
+use std::error;
+use std::fmt;
+
+/// Everything that can go wrong setting up or running a solve.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// A configuration parameter is out of range, with a description of
+    /// which one.
+    InvalidConfig(&'static str),
+    /// The strategy can't work with boards of this size.
+    UnsupportedDimension(usize),
+    /// The operating system couldn't provide a seed.
+    RngUnavailable,
+    /// The solve was cancelled before it finished.
+    Cancelled,
+    /// The solve ran out of time or evaluations before it finished.
+    BudgetExhausted,
+    /// There's no placement without attacks for this board size.
+    Infeasible(usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::InvalidConfig(reason) => write!(f, "invalid configuration: {}", reason),
+            Error::UnsupportedDimension(n) => write!(f, "unsupported board size: {}", n),
+            Error::RngUnavailable => write!(f, "couldn't seed the random number generators"),
+            Error::Cancelled => write!(f, "the solve was cancelled"),
+            Error::BudgetExhausted => write!(f, "the solve ran out of budget"),
+            Error::Infeasible(n) => write!(f, "no solution exists for a board of size {}", n),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+/// Something suspicious about a valid configuration, like a parameter that
+/// makes the strategy degenerate into a simpler one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigWarning {
+    /// The parameter at fault, as in `StrategyInfo::parameters`.
+    pub parameter: &'static str,
+    pub message: String,
+}
+
+impl ConfigWarning {
+    pub fn new(parameter: &'static str, message: String) -> Self {
+        ConfigWarning { parameter, message }
+    }
+}
+
+impl fmt::Display for ConfigWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.parameter, self.message)
+    }
+}
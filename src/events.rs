@@ -0,0 +1,273 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The steps of every strategy as a single kind of event, so that
+//! frontends can follow any solve the same way, without knowing how each
+//! strategy reports its steps.
+//!
+//! With the `serde` feature, events serialize into objects tagged by their
+//! `kind`, like `{"kind":"swap","step":3,"columns":[0,5],"score":2}` in
+//! JSON, which is how the C entry points pass them to the frontend.
+
+use *;
+
+/// A step of a solve, as the change it made to the queens of the previous
+/// one.
+///
+/// The steps are counted from zero, and the columns and rows are those of
+/// the queen rows given to the encoder.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum TraceEvent {
+    /// All the queens, when the step isn't any of the other events.
+    State {
+        step: usize,
+        queen_rows: Vec<usize>,
+        score: usize,
+    },
+    /// A queen was placed on the column after the last one.
+    Placement {
+        step: usize,
+        column: usize,
+        row: usize,
+        score: usize,
+    },
+    /// The queen on the last column was taken off the board.
+    Backtrack {
+        step: usize,
+        column: usize,
+        row: usize,
+        score: usize,
+    },
+    /// The queens on two columns swapped rows.
+    Swap {
+        step: usize,
+        columns: [usize; 2],
+        score: usize,
+    },
+    /// The queen on a column moved to another row.
+    Move {
+        step: usize,
+        column: usize,
+        from: usize,
+        to: usize,
+        score: usize,
+    },
+    /// The best individual of a generation of a population-based strategy.
+    Generation {
+        step: usize,
+        queen_rows: Vec<usize>,
+        score: usize,
+    },
+}
+
+impl TraceEvent {
+    pub fn step(&self) -> usize {
+        match *self {
+            TraceEvent::State { step, .. }
+            | TraceEvent::Placement { step, .. }
+            | TraceEvent::Backtrack { step, .. }
+            | TraceEvent::Swap { step, .. }
+            | TraceEvent::Move { step, .. }
+            | TraceEvent::Generation { step, .. } => step,
+        }
+    }
+
+    pub fn score(&self) -> usize {
+        match *self {
+            TraceEvent::State { score, .. }
+            | TraceEvent::Placement { score, .. }
+            | TraceEvent::Backtrack { score, .. }
+            | TraceEvent::Swap { score, .. }
+            | TraceEvent::Move { score, .. }
+            | TraceEvent::Generation { score, .. } => score,
+        }
+    }
+
+    /// Applies the event to `queen_rows`, the queens after the previous
+    /// step.
+    pub fn apply(&self, queen_rows: &mut Vec<usize>) {
+        match *self {
+            TraceEvent::State {
+                queen_rows: ref rows,
+                ..
+            }
+            | TraceEvent::Generation {
+                queen_rows: ref rows,
+                ..
+            } => {
+                queen_rows.clear();
+                queen_rows.extend_from_slice(rows);
+            }
+            TraceEvent::Placement { row, .. } => queen_rows.push(row),
+            TraceEvent::Backtrack { .. } => {
+                queen_rows.pop();
+            }
+            TraceEvent::Swap { columns, .. } => queen_rows.swap(columns[0], columns[1]),
+            TraceEvent::Move { column, to, .. } => queen_rows[column] = to,
+        }
+    }
+}
+
+/// Turns the steps reported to the callback of a strategy into events, by
+/// comparing each of them with the one before.
+#[derive(Clone, Debug, Default)]
+pub struct TraceEventEncoder {
+    previous: Vec<usize>,
+    steps: usize,
+    population_based: bool,
+}
+
+impl TraceEventEncoder {
+    /// Creates an encoder for the steps of `strategy`.
+    pub fn new(strategy: &StrategyInfo) -> Self {
+        TraceEventEncoder {
+            population_based: strategy.population_based,
+            ..TraceEventEncoder::default()
+        }
+    }
+
+    /// Returns the event of a step with `queen_rows` and `score`.
+    pub fn encode(&mut self, queen_rows: &[usize], score: usize) -> TraceEvent {
+        let step = self.steps;
+        self.steps += 1;
+
+        let previous = &self.previous;
+        let event = if self.population_based {
+            TraceEvent::Generation {
+                step,
+                queen_rows: queen_rows.to_vec(),
+                score,
+            }
+        } else if queen_rows.len() == previous.len() + 1 && queen_rows.starts_with(previous) {
+            TraceEvent::Placement {
+                step,
+                column: previous.len(),
+                row: queen_rows[previous.len()],
+                score,
+            }
+        } else if queen_rows.len() + 1 == previous.len() && previous.starts_with(queen_rows) {
+            TraceEvent::Backtrack {
+                step,
+                column: queen_rows.len(),
+                row: previous[queen_rows.len()],
+                score,
+            }
+        } else {
+            let changed = if queen_rows.len() == previous.len() {
+                (0..queen_rows.len())
+                    .filter(|&column| queen_rows[column] != previous[column])
+                    .take(3)
+                    .collect::<Vec<_>>()
+            } else {
+                vec![]
+            };
+            match changed[..] {
+                [column] => TraceEvent::Move {
+                    step,
+                    column,
+                    from: previous[column],
+                    to: queen_rows[column],
+                    score,
+                },
+                [a, b] if queen_rows[a] == previous[b] && queen_rows[b] == previous[a] => {
+                    TraceEvent::Swap {
+                        step,
+                        columns: [a, b],
+                        score,
+                    }
+                }
+                _ => TraceEvent::State {
+                    step,
+                    queen_rows: queen_rows.to_vec(),
+                    score,
+                },
+            }
+        };
+
+        self.previous.clear();
+        self.previous.extend_from_slice(queen_rows);
+        event
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_steps_as_the_changes_they_made() {
+        let mut encoder = TraceEventEncoder::new(HillClimbing::info());
+        let steps: &[&[usize]] = &[&[], &[1], &[1, 3], &[1], &[1, 2, 0], &[2, 1, 0], &[2, 3, 0]];
+        let events = steps
+            .iter()
+            .map(|rows| encoder.encode(rows, 0))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            events[1],
+            TraceEvent::Placement {
+                step: 1,
+                column: 0,
+                row: 1,
+                score: 0
+            }
+        );
+        assert_eq!(
+            events[3],
+            TraceEvent::Backtrack {
+                step: 3,
+                column: 1,
+                row: 3,
+                score: 0
+            }
+        );
+        assert!(matches!(events[4], TraceEvent::State { .. }));
+        assert_eq!(
+            events[5],
+            TraceEvent::Swap {
+                step: 5,
+                columns: [0, 1],
+                score: 0
+            }
+        );
+        assert_eq!(
+            events[6],
+            TraceEvent::Move {
+                step: 6,
+                column: 1,
+                from: 1,
+                to: 3,
+                score: 0
+            }
+        );
+
+        // Replaying the events gives back every step.
+        let mut queen_rows = vec![];
+        for (event, rows) in events.iter().zip(steps) {
+            event.apply(&mut queen_rows);
+            assert_eq!(queen_rows, *rows);
+        }
+
+        let mut encoder = TraceEventEncoder::new(GeneticAlgorithm::info());
+        assert!(matches!(
+            encoder.encode(&[0, 2], 1),
+            TraceEvent::Generation { .. }
+        ));
+    }
+}
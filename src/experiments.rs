@@ -0,0 +1,379 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Running strategies many times with different seeds, and telling
+//! whether one of them is significantly faster than another, rather than
+//! just comparing means.
+//!
+//! The comparisons are non-parametric, since run times of randomized
+//! searches tend to be heavy-tailed: a Mann-Whitney U test of whether the
+//! runs of one strategy tend to take less than those of the other, and a
+//! bootstrap confidence interval for the difference of the means.
+//!
+//! Runs that don't solve the board are censored rather than compared by
+//! what they cost until they gave up: they count as `UNSOLVED_PENALTY`
+//! times the largest cost of any run, like the PAR10 score of solver
+//! competitions, so that they rank behind every solved run. How many runs
+//! solved the board is reported separately.
+
+use presets::Preset;
+use *;
+
+/// How a single run went.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunRecord {
+    pub seed: u64,
+    pub solved: bool,
+    pub iterations: usize,
+    pub seconds: f64,
+}
+
+/// What to compare runs by.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Metric {
+    Iterations,
+    Seconds,
+}
+
+impl RunRecord {
+    pub fn value(&self, metric: Metric) -> f64 {
+        match metric {
+            Metric::Iterations => self.iterations as f64,
+            Metric::Seconds => self.seconds,
+        }
+    }
+}
+
+/// How many times the largest cost of any run an unsolved run counts as.
+pub const UNSOLVED_PENALTY: f64 = 10.;
+
+/// Returns the `metric` of each of `records`, where the runs that didn't
+/// solve the board count as `UNSOLVED_PENALTY` times `cutoff`, which should
+/// be at least the `metric` of every run.
+pub fn metric_values(records: &[RunRecord], metric: Metric, cutoff: f64) -> Vec<f64> {
+    records
+        .iter()
+        .map(|record| {
+            if record.solved {
+                record.value(metric)
+            } else {
+                UNSOLVED_PENALTY * cutoff
+            }
+        })
+        .collect()
+}
+
+/// Returns the fraction of `records` that solved the board.
+pub fn success_rate(records: &[RunRecord]) -> f64 {
+    records.iter().filter(|record| record.solved).count() as f64 / records.len() as f64
+}
+
+/// Runs strategies with the configuration of a preset on the same board
+/// and seeds, so that their runs can be compared.
+pub struct Experiment {
+    pub preset: Preset,
+    pub dimension: usize,
+    pub runs: usize,
+    /// The seed of the first run, which the others follow.
+    pub first_seed: u64,
+    /// Limits on each run.
+    pub budget: SolveBudget,
+}
+
+impl Experiment {
+    /// Runs `strategy`, by name, as many times as configured.
+    pub fn run(&self, strategy: &str) -> Result<Vec<RunRecord>, Error> {
+        (0..self.runs as u64)
+            .map(|run| {
+                let seed = self.first_seed.wrapping_add(run);
+                let solution = self.preset.solve(
+                    strategy,
+                    self.dimension,
+                    Some(seed),
+                    self.budget.clone(),
+                    |_, _| {},
+                )?;
+                Ok(RunRecord {
+                    seed,
+                    solved: solution.status() == SolveStatus::Solved,
+                    iterations: solution.statistics.iterations,
+                    seconds: solution.statistics.elapsed.as_secs_f64(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// The result of a Mann-Whitney U test.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MannWhitney {
+    /// The number of pairs in which the value of the first sample is
+    /// larger, counting ties as halves.
+    pub u: f64,
+    /// The normal approximation of `u`, with continuity correction.
+    pub z: f64,
+    /// The two-sided probability of a `u` at least as extreme if both
+    /// samples came from the same distribution.
+    pub p_value: f64,
+}
+
+/// Returns the standard normal cumulative distribution at `x`, with an
+/// absolute error of at most about 1.5e-7.
+fn normal_cdf(x: f64) -> f64 {
+    // Abramowitz and Stegun, formula 7.1.26.
+    let t = 1. / (1. + 0.327_591_1 * x.abs() / 2f64.sqrt());
+    let polynomial = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let erf = 1. - polynomial * (-x * x / 2.).exp();
+    if x >= 0. {
+        (1. + erf) / 2.
+    } else {
+        (1. - erf) / 2.
+    }
+}
+
+/// Tests whether the values of `first` and `second` tend to differ.
+pub fn mann_whitney(first: &[f64], second: &[f64]) -> MannWhitney {
+    let (n1, n2) = (first.len() as f64, second.len() as f64);
+    let mut values = first
+        .iter()
+        .map(|&value| (value, true))
+        .chain(second.iter().map(|&value| (value, false)))
+        .collect::<Vec<_>>();
+    values.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    // Ranks from one, with ties getting the mean of their ranks.
+    let mut first_ranks = 0.;
+    let mut ties = 0.;
+    let mut start = 0;
+    while start < values.len() {
+        let end = start
+            + values[start..]
+                .iter()
+                .take_while(|&&(value, _)| value == values[start].0)
+                .count();
+        let rank = (start + end + 1) as f64 / 2.;
+        let tied = (end - start) as f64;
+        ties += tied * tied * tied - tied;
+        first_ranks += rank * values[start..end].iter().filter(|v| v.1).count() as f64;
+        start = end;
+    }
+
+    let u = first_ranks - n1 * (n1 + 1.) / 2.;
+    let n = n1 + n2;
+    let mean = n1 * n2 / 2.;
+    let variance = if n > 1. {
+        n1 * n2 / 12. * ((n + 1.) - ties / (n * (n - 1.)))
+    } else {
+        0.
+    };
+    if variance <= 0. {
+        return MannWhitney {
+            u,
+            z: 0.,
+            p_value: 1.,
+        };
+    }
+
+    let z = ((u - mean).abs() - 0.5).max(0.) * (u - mean).signum() / variance.sqrt();
+    MannWhitney {
+        u,
+        z,
+        p_value: (2. * (1. - normal_cdf(z.abs()))).min(1.),
+    }
+}
+
+/// A confidence interval.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Interval {
+    pub low: f64,
+    pub high: f64,
+}
+
+impl Interval {
+    pub fn contains(&self, value: f64) -> bool {
+        self.low <= value && value <= self.high
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Returns a percentile bootstrap interval with the given `confidence` for
+/// the mean of `first` minus the mean of `second`, from `resamples`
+/// resamples drawn from `seed`.
+///
+/// Panics if either sample is empty.
+pub fn bootstrap_mean_difference(
+    first: &[f64],
+    second: &[f64],
+    resamples: usize,
+    confidence: f64,
+    seed: u64,
+) -> Interval {
+    use rand::Rng;
+
+    assert!(!first.is_empty() && !second.is_empty());
+    let mut rng = stream_rng(seed, RngStream::Selection);
+    let mut resample = |values: &[f64]| {
+        (0..values.len())
+            .map(|_| values[rng.gen_range(0, values.len())])
+            .collect::<Vec<_>>()
+    };
+    let mut differences = (0..resamples.max(1))
+        .map(|_| mean(&resample(first)) - mean(&resample(second)))
+        .collect::<Vec<_>>();
+    differences.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let tail = (1. - confidence) / 2.;
+    let at = |fraction: f64| {
+        let index = (fraction * (differences.len() - 1) as f64).round() as usize;
+        differences[index]
+    };
+    Interval {
+        low: at(tail),
+        high: at(1. - tail),
+    }
+}
+
+/// Which of two strategies is faster.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Verdict {
+    FirstFaster,
+    SecondFaster,
+    NoSignificantDifference,
+}
+
+/// How the runs of two strategies compare.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comparison {
+    /// The fractions of the runs of each strategy that solved the board.
+    pub first_success_rate: f64,
+    pub second_success_rate: f64,
+    /// The mean costs of the runs of each strategy, with unsolved runs
+    /// penalized.
+    pub first_mean: f64,
+    pub second_mean: f64,
+    pub test: MannWhitney,
+    /// The 95% bootstrap interval of the difference of the means.
+    pub mean_difference: Interval,
+    pub verdict: Verdict,
+}
+
+/// Compares the runs of two strategies by `metric`, where one is deemed
+/// faster if the Mann-Whitney test rejects that they're alike at the
+/// `significance` level. Unsolved runs are penalized, with the largest cost
+/// of any run of either strategy as the cutoff.
+///
+/// Panics if either sample is empty.
+pub fn compare(
+    first: &[RunRecord],
+    second: &[RunRecord],
+    metric: Metric,
+    significance: f64,
+) -> Comparison {
+    const RESAMPLES: usize = 2000;
+
+    let cutoff = first
+        .iter()
+        .chain(second)
+        .map(|record| record.value(metric))
+        .fold(0., f64::max);
+    let first_costs = metric_values(first, metric, cutoff);
+    let second_costs = metric_values(second, metric, cutoff);
+    let test = mann_whitney(&first_costs, &second_costs);
+    let verdict = if test.p_value >= significance {
+        Verdict::NoSignificantDifference
+    } else if test.z < 0. {
+        Verdict::FirstFaster
+    } else {
+        Verdict::SecondFaster
+    };
+    Comparison {
+        first_success_rate: success_rate(first),
+        second_success_rate: success_rate(second),
+        first_mean: mean(&first_costs),
+        second_mean: mean(&second_costs),
+        test,
+        mean_difference: bootstrap_mean_difference(&first_costs, &second_costs, RESAMPLES, 0.95, 0),
+        verdict,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_faster_strategies_apart() {
+        let test = mann_whitney(&[1., 2., 3., 4., 5.], &[6., 7., 8., 9., 10.]);
+        assert_eq!(test.u, 0.);
+        // As scipy.stats.mannwhitneyu with the asymptotic method.
+        assert!((test.p_value - 0.0122).abs() < 1e-3, "{:?}", test);
+
+        let ties = mann_whitney(&[1., 1., 2.], &[1., 2., 2.]);
+        assert!(ties.p_value > 0.3);
+
+        let records = |iterations: &[usize], solved: bool| {
+            iterations
+                .iter()
+                .map(|&iterations| RunRecord {
+                    seed: 0,
+                    solved,
+                    iterations,
+                    seconds: 0.,
+                })
+                .collect::<Vec<_>>()
+        };
+        let fast = records(&[1, 2, 3, 4, 5], true);
+        let slow = records(&[6, 7, 8, 9, 10], true);
+        let comparison = compare(&fast, &slow, Metric::Iterations, 0.05);
+        assert_eq!(comparison.verdict, Verdict::FirstFaster);
+        assert!(!comparison.mean_difference.contains(0.));
+
+        // Giving up early isn't faster than solving.
+        let failed = records(&[1, 1, 2, 2, 3], false);
+        let comparison = compare(&slow, &failed, Metric::Iterations, 0.05);
+        assert_eq!(comparison.verdict, Verdict::FirstFaster);
+        assert_eq!(
+            (
+                comparison.first_success_rate,
+                comparison.second_success_rate
+            ),
+            (1., 0.)
+        );
+        assert_eq!(comparison.second_mean, 100.);
+
+        let experiment = Experiment {
+            preset: Preset::Fast,
+            dimension: 8,
+            runs: 3,
+            first_seed: 1,
+            budget: SolveBudget::default(),
+        };
+        let runs = experiment.run("min_conflicts").unwrap();
+        assert_eq!(runs.len(), 3);
+        // The same seeds give the same runs.
+        let again = experiment.run("min_conflicts").unwrap();
+        for (run, other) in runs.iter().zip(&again) {
+            assert_eq!((run.seed, run.iterations), (other.seed, other.iterations));
+        }
+    }
+}
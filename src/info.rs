@@ -0,0 +1,815 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Descriptions of the strategies and their parameters, so that frontends
+//! can build configuration forms without hardcoding them.
+
+/// The values a parameter can take, and the one used by default.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParameterKind {
+    /// A whole number, at least `min`, and at most `max` if present.
+    Integer {
+        min: usize,
+        max: Option<usize>,
+        default: usize,
+    },
+    /// A real number, at least `min`, and at most `max` if present.
+    Real {
+        min: f32,
+        max: Option<f32>,
+        default: f32,
+    },
+    Flag {
+        default: bool,
+    },
+    /// One of `options`, by index.
+    Choice {
+        options: &'static [&'static str],
+        default: usize,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParameterInfo {
+    /// The name of the configuration field.
+    pub name: &'static str,
+    pub description: &'static str,
+    pub kind: ParameterKind,
+    /// Whether the parameter can be left unset, in which case it's unset
+    /// by default.
+    pub optional: bool,
+}
+
+/// What a strategy is and how it can be configured.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StrategyInfo {
+    /// The name used to refer to the strategy, like in the
+    /// `solve_n_queens_*` functions.
+    pub name: &'static str,
+    pub display_name: &'static str,
+    /// Whether the strategy is guaranteed to find a solution if there's
+    /// one.
+    pub exact: bool,
+    /// Whether the strategy makes random choices with its default
+    /// parameters, so that runs can differ.
+    pub stochastic: bool,
+    /// Whether the strategy evolves a population, reporting the best
+    /// member of each generation as a step.
+    pub population_based: bool,
+    /// Whether the strategy can start from a given placement.
+    pub supports_warm_start: bool,
+    /// Whether a running solve of the strategy can be cancelled.
+    pub supports_cancellation: bool,
+    /// Whether a search of the strategy can be driven a step at a time,
+    /// as by `Preset::stepper`.
+    pub supports_stepping: bool,
+    /// The scalar parameters of the configuration. Parameters that take
+    /// structured values are described by their most common form.
+    pub parameters: &'static [ParameterInfo],
+}
+
+impl StrategyInfo {
+    /// Returns the parameter called `name`, if any.
+    pub fn parameter(&self, name: &str) -> Option<&'static ParameterInfo> {
+        self.parameters.iter().find(|p| p.name == name)
+    }
+}
+
+const fn integer(
+    name: &'static str,
+    description: &'static str,
+    min: usize,
+    default: usize,
+) -> ParameterInfo {
+    ParameterInfo {
+        name,
+        description,
+        kind: ParameterKind::Integer {
+            min,
+            max: None,
+            default,
+        },
+        optional: false,
+    }
+}
+
+const fn probability(name: &'static str, description: &'static str, default: f32) -> ParameterInfo {
+    ParameterInfo {
+        name,
+        description,
+        kind: ParameterKind::Real {
+            min: 0.,
+            max: Some(1.),
+            default,
+        },
+        optional: false,
+    }
+}
+
+const fn tie_breaking(description: &'static str, default: usize) -> ParameterInfo {
+    ParameterInfo {
+        name: "tie_breaking",
+        description,
+        kind: ParameterKind::Choice {
+            options: &[
+                "keep_incumbent",
+                "random",
+                "least_recently_moved",
+                "most_conflicted",
+            ],
+            default,
+        },
+        optional: false,
+    }
+}
+
+const fn flag(name: &'static str, description: &'static str) -> ParameterInfo {
+    ParameterInfo {
+        name,
+        description,
+        kind: ParameterKind::Flag { default: false },
+        optional: false,
+    }
+}
+
+pub const OBJECTIVE: ParameterInfo = ParameterInfo {
+    name: "objective",
+    description: "What to minimize.",
+    kind: ParameterKind::Choice {
+        options: &["attacking_pairs", "conflicted_queens"],
+        default: 0,
+    },
+    optional: false,
+};
+
+pub const MOVE_SELECTION: ParameterInfo = ParameterInfo {
+    name: "move_selection",
+    description: "How to choose the next move to try.",
+    kind: ParameterKind::Choice {
+        options: &["random_swap", "heavy_tailed", "novelty_biased"],
+        default: 0,
+    },
+    optional: false,
+};
+
+pub const MAX_EVALUATIONS: ParameterInfo = ParameterInfo {
+    name: "max_evaluations",
+    description: "Stop once this many placements have been scored.",
+    kind: ParameterKind::Integer {
+        min: 1,
+        max: None,
+        default: 1,
+    },
+    optional: true,
+};
+
+pub const SEED: ParameterInfo = ParameterInfo {
+    name: "seed",
+    description: "The seed of the random streams, to reproduce a run.",
+    kind: ParameterKind::Integer {
+        min: 0,
+        max: None,
+        default: 0,
+    },
+    optional: true,
+};
+
+pub const RESTARTS: ParameterInfo = integer(
+    "restarts",
+    "How many times the search can be restarted.",
+    0,
+    0,
+);
+
+pub const EXHAUST_RESTARTS: ParameterInfo = flag(
+    "exhaust_restarts",
+    "Whether to keep restarting after a solution is found.",
+);
+
+pub const SNAPSHOT_INTERVAL: ParameterInfo = integer(
+    "snapshot_interval",
+    "Keep a snapshot of the population every this many iterations, if non-zero.",
+    0,
+    0,
+);
+
+pub static CONSTRAINT_PROPAGATION: StrategyInfo = StrategyInfo {
+    name: "constraint_propagation",
+    display_name: "Constraint Propagation",
+    exact: true,
+    stochastic: false,
+    population_based: false,
+    supports_warm_start: false,
+    supports_cancellation: true,
+    supports_stepping: false,
+    parameters: &[
+        flag(
+            "randomize_rows",
+            "Whether to try the rows of each column in a random order.",
+        ),
+        ParameterInfo {
+            name: "column_order",
+            description: "Which column gets the next queen.",
+            kind: ParameterKind::Choice {
+                options: &["left_to_right", "most_constrained"],
+                default: 0,
+            },
+            optional: false,
+        },
+        ParameterInfo {
+            name: "symmetry_breaking",
+            description: "Which solutions to skip for being symmetric to others.",
+            kind: ParameterKind::Choice {
+                options: &["none", "reflection", "fundamental"],
+                default: 0,
+            },
+            optional: false,
+        },
+        RESTARTS,
+        integer(
+            "restart_backtracks",
+            "How many backtracks to allow before the first restart.",
+            0,
+            0,
+        ),
+        integer(
+            "max_nogood_size",
+            "Remember failed partial placements of up to this many queens.",
+            0,
+            0,
+        ),
+        SEED,
+    ],
+};
+
+pub static CONSTRUCTIVE_BEAM_SEARCH: StrategyInfo = StrategyInfo {
+    name: "constructive_beam_search",
+    display_name: "Constructive Beam Search",
+    exact: false,
+    stochastic: true,
+    population_based: false,
+    supports_warm_start: false,
+    supports_cancellation: true,
+    supports_stepping: false,
+    parameters: &[
+        integer(
+            "beam_width",
+            "How many partial placements to keep at each depth.",
+            1,
+            32,
+        ),
+        flag(
+            "prune_conflicts",
+            "Whether to drop partial placements with attacks altogether.",
+        ),
+        MAX_EVALUATIONS,
+        SEED,
+    ],
+};
+
+pub static LIMITED_DISCREPANCY_SEARCH: StrategyInfo = StrategyInfo {
+    name: "limited_discrepancy_search",
+    display_name: "Limited Discrepancy Search",
+    exact: true,
+    stochastic: false,
+    population_based: false,
+    supports_warm_start: false,
+    supports_cancellation: true,
+    supports_stepping: false,
+    parameters: &[integer(
+        "max_discrepancies",
+        "The most deviations from the heuristic allowed.",
+        0,
+        8,
+    )],
+};
+
+pub static HILL_CLIMBING: StrategyInfo = StrategyInfo {
+    name: "hill_climbing",
+    display_name: "Hill Climbing",
+    exact: false,
+    stochastic: true,
+    population_based: false,
+    supports_warm_start: true,
+    supports_cancellation: true,
+    supports_stepping: true,
+    parameters: &[
+        RESTARTS,
+        EXHAUST_RESTARTS,
+        MOVE_SELECTION,
+        probability(
+            "random_walk_probability",
+            "Probability of accepting a move that doesn't improve the score.",
+            0.,
+        ),
+        tie_breaking("Whether to take moves that leave the score unchanged.", 0),
+        OBJECTIVE,
+        MAX_EVALUATIONS,
+        SEED,
+    ],
+};
+
+pub static SIMULATED_ANNEALING: StrategyInfo = StrategyInfo {
+    name: "simulated_annealing",
+    display_name: "Simulated Annealing",
+    exact: false,
+    stochastic: true,
+    population_based: false,
+    supports_warm_start: true,
+    supports_cancellation: true,
+    supports_stepping: true,
+    parameters: &[
+        ParameterInfo {
+            name: "starting_temperature",
+            description: "The temperature the search starts at.",
+            kind: ParameterKind::Real {
+                min: 0.,
+                max: None,
+                default: 10.,
+            },
+            optional: false,
+        },
+        probability(
+            "cooling_schedule",
+            "The factor of the geometric cooling schedule.",
+            0.01,
+        ),
+        RESTARTS,
+        EXHAUST_RESTARTS,
+        ParameterInfo {
+            name: "restart_policy",
+            description: "Where reheats and restarts go on from.",
+            kind: ParameterKind::Choice {
+                options: &["fresh", "from_best"],
+                default: 0,
+            },
+            optional: false,
+        },
+        MOVE_SELECTION,
+        ParameterInfo {
+            optional: true,
+            ..tie_breaking("Whether to take moves that leave the score unchanged.", 0)
+        },
+        ParameterInfo {
+            optional: true,
+            ..probability(
+                "initial_acceptance",
+                "Calibrate the starting temperature so worsening moves are accepted this often.",
+                0.5,
+            )
+        },
+        OBJECTIVE,
+        ParameterInfo {
+            name: "neighborhood",
+            description: "The moves the search makes.",
+            kind: ParameterKind::Choice {
+                options: &["swaps", "single_queen_moves"],
+                default: 0,
+            },
+            optional: false,
+        },
+        MAX_EVALUATIONS,
+        SEED,
+    ],
+};
+
+pub static LOCAL_BEAM_SEARCH: StrategyInfo = StrategyInfo {
+    name: "local_beam_search",
+    display_name: "Local Beam Search",
+    exact: false,
+    stochastic: true,
+    population_based: true,
+    supports_warm_start: false,
+    supports_cancellation: true,
+    supports_stepping: false,
+    parameters: &[
+        integer("state_count", "How many states to keep in the beam.", 1, 8),
+        ParameterInfo {
+            name: "schedule",
+            description: "How the width of the beam changes over time.",
+            kind: ParameterKind::Choice {
+                options: &["fixed", "shrinking", "widen_on_stagnation"],
+                default: 0,
+            },
+            optional: false,
+        },
+        integer(
+            "min_distance",
+            "Prefer successors that differ in at least this many queens.",
+            0,
+            0,
+        ),
+        OBJECTIVE,
+        SNAPSHOT_INTERVAL,
+        MAX_EVALUATIONS,
+        SEED,
+    ],
+};
+
+pub static GENETIC_ALGORITHM: StrategyInfo = StrategyInfo {
+    name: "genetic",
+    display_name: "Genetic",
+    exact: false,
+    stochastic: true,
+    population_based: true,
+    supports_warm_start: false,
+    supports_cancellation: true,
+    supports_stepping: true,
+    parameters: &[
+        integer(
+            "generation_size",
+            "How many individuals each generation has.",
+            1,
+            50,
+        ),
+        probability(
+            "elitism",
+            "The fraction of the best individuals that survive.",
+            0.1,
+        ),
+        probability(
+            "generation_gap",
+            "The fraction of the rest that offspring replace each generation, worst first.",
+            1.,
+        ),
+        ParameterInfo {
+            name: "selection",
+            description: "How the parents are picked.",
+            kind: ParameterKind::Choice {
+                options: &[
+                    "roulette",
+                    "rank",
+                    "tournament",
+                    "stochastic_universal_sampling",
+                ],
+                default: 2,
+            },
+            optional: false,
+        },
+        integer(
+            "tournament_size",
+            "How many individuals take part in each tournament.",
+            1,
+            3,
+        ),
+        ParameterInfo {
+            name: "crossover",
+            description: "How two parents are combined.",
+            kind: ParameterKind::Choice {
+                options: &["prefix_swap", "partially_mapped", "ordered"],
+                default: 1,
+            },
+            optional: false,
+        },
+        probability(
+            "crossover_probability",
+            "The probability of crossing over two parents.",
+            0.8,
+        ),
+        probability(
+            "mutation_probability",
+            "The probability of mutating a child.",
+            0.1,
+        ),
+        integer("generation_count", "How many generations to run.", 0, 200),
+        probability(
+            "immigrant_fraction",
+            "The fraction of each generation replaced by random individuals.",
+            0.,
+        ),
+        probability(
+            "min_diversity",
+            "Stop if the fraction of distinct individuals falls below this.",
+            0.,
+        ),
+        integer(
+            "max_stagnant_generations",
+            "Stop if the best score doesn't improve for this many generations, if non-zero.",
+            0,
+            0,
+        ),
+        flag(
+            "track_genealogy",
+            "Whether to record where each individual came from.",
+        ),
+        flag(
+            "track_diversity",
+            "Whether to report how diverse each generation is.",
+        ),
+        OBJECTIVE,
+        SNAPSHOT_INTERVAL,
+        MAX_EVALUATIONS,
+        SEED,
+    ],
+};
+
+pub static PBIL: StrategyInfo = StrategyInfo {
+    name: "pbil",
+    display_name: "Population-Based Incremental Learning",
+    exact: false,
+    stochastic: true,
+    population_based: true,
+    supports_warm_start: false,
+    supports_cancellation: true,
+    supports_stepping: false,
+    parameters: &[
+        integer(
+            "samples",
+            "How many placements to sample on each iteration.",
+            1,
+            50,
+        ),
+        probability(
+            "learning_rate",
+            "How much the distribution moves towards the best sample.",
+            0.1,
+        ),
+        probability(
+            "mutation_probability",
+            "The probability of mutating each entry of the distribution.",
+            0.02,
+        ),
+        probability(
+            "mutation_shift",
+            "How much a mutation moves an entry towards a random value.",
+            0.05,
+        ),
+        integer("iterations", "How many iterations to run.", 0, 200),
+        OBJECTIVE,
+        MAX_EVALUATIONS,
+        SEED,
+    ],
+};
+
+pub static CUCKOO_SEARCH: StrategyInfo = StrategyInfo {
+    name: "cuckoo_search",
+    display_name: "Cuckoo Search",
+    exact: false,
+    stochastic: true,
+    population_based: true,
+    supports_warm_start: false,
+    supports_cancellation: true,
+    supports_stepping: false,
+    parameters: &[
+        integer("nests", "How many placements to keep.", 1, 15),
+        probability(
+            "discovery_probability",
+            "The fraction of the worst nests abandoned on each generation.",
+            0.25,
+        ),
+        ParameterInfo {
+            name: "levy_exponent",
+            description: "The exponent of the distribution of Lévy flight lengths.",
+            kind: ParameterKind::Real {
+                min: 0.,
+                max: None,
+                default: 1.5,
+            },
+            optional: false,
+        },
+        integer("generation_count", "How many generations to run.", 0, 800),
+        OBJECTIVE,
+        MAX_EVALUATIONS,
+        SEED,
+    ],
+};
+
+pub static MIN_CONFLICTS: StrategyInfo = StrategyInfo {
+    name: "min_conflicts",
+    display_name: "Min-Conflicts",
+    exact: false,
+    stochastic: true,
+    population_based: false,
+    supports_warm_start: true,
+    supports_cancellation: true,
+    supports_stepping: true,
+    parameters: &[
+        integer(
+            "max_steps",
+            "How many queens to move before giving up on a placement.",
+            1,
+            10_000,
+        ),
+        RESTARTS,
+        probability(
+            "random_walk_probability",
+            "Probability of moving a queen to a random row instead of the best one.",
+            0.02,
+        ),
+        tie_breaking("How to choose among the best rows for a queen.", 1),
+        SEED,
+    ],
+};
+
+pub static TABU_SEARCH: StrategyInfo = StrategyInfo {
+    name: "tabu_search",
+    display_name: "Tabu Search",
+    exact: false,
+    stochastic: true,
+    population_based: false,
+    supports_warm_start: false,
+    supports_cancellation: true,
+    supports_stepping: true,
+    parameters: &[
+        integer(
+            "tenure",
+            "For how many steps the queens of a swap can't be moved again.",
+            0,
+            7,
+        ),
+        integer(
+            "max_steps",
+            "How many swaps to make before giving up.",
+            1,
+            5_000,
+        ),
+        tie_breaking("How to choose among the best moves that aren't tabu.", 1),
+        SEED,
+    ],
+};
+
+pub static DECOMPOSITION: StrategyInfo = StrategyInfo {
+    name: "decomposition",
+    display_name: "Column Decomposition",
+    exact: false,
+    stochastic: true,
+    population_based: false,
+    supports_warm_start: false,
+    supports_cancellation: true,
+    supports_stepping: false,
+    parameters: &[
+        integer(
+            "block_size",
+            "How many columns are solved together before repairing the whole board.",
+            1,
+            64,
+        ),
+        SEED,
+    ],
+};
+
+pub static ISLAND_MODEL: StrategyInfo = StrategyInfo {
+    name: "island_model",
+    display_name: "Island Model Genetic Algorithm",
+    exact: false,
+    stochastic: true,
+    population_based: true,
+    supports_warm_start: false,
+    supports_cancellation: true,
+    supports_stepping: false,
+    parameters: &[
+        integer(
+            "islands",
+            "How many populations evolve independently.",
+            1,
+            4,
+        ),
+        integer(
+            "migration_interval",
+            "How many generations pass between migrations.",
+            1,
+            25,
+        ),
+        integer(
+            "migration_size",
+            "How many of the best individuals of each island migrate.",
+            0,
+            2,
+        ),
+        ParameterInfo {
+            name: "topology",
+            description: "Where the migrants of each island go.",
+            kind: ParameterKind::Choice {
+                options: &["ring", "random"],
+                default: 0,
+            },
+            optional: false,
+        },
+        integer("epochs", "How many migration intervals to run for.", 1, 40),
+        flag("threads", "Evolves each island on its own thread."),
+        SEED,
+    ],
+};
+
+pub static HYBRID: StrategyInfo = StrategyInfo {
+    name: "hybrid",
+    display_name: "Constraint Propagation and Local Search",
+    exact: false,
+    stochastic: true,
+    population_based: false,
+    supports_warm_start: false,
+    supports_cancellation: true,
+    supports_stepping: false,
+    parameters: &[
+        integer(
+            "placed_queens",
+            "How many queens to place by backtracking before the local search.",
+            0,
+            8,
+        ),
+        ParameterInfo {
+            name: "finisher",
+            description: "The local search that finishes the board.",
+            kind: ParameterKind::Choice {
+                options: &["hill_climbing", "simulated_annealing"],
+                default: 0,
+            },
+            optional: false,
+        },
+        SEED,
+    ],
+};
+
+/// All the strategies, in the order frontends should list them.
+pub static STRATEGIES: &[&StrategyInfo] = &[
+    &CONSTRAINT_PROPAGATION,
+    &CONSTRUCTIVE_BEAM_SEARCH,
+    &LIMITED_DISCREPANCY_SEARCH,
+    &HILL_CLIMBING,
+    &SIMULATED_ANNEALING,
+    &LOCAL_BEAM_SEARCH,
+    &GENETIC_ALGORITHM,
+    &PBIL,
+    &CUCKOO_SEARCH,
+    &MIN_CONFLICTS,
+    &TABU_SEARCH,
+    &DECOMPOSITION,
+    &ISLAND_MODEL,
+    &HYBRID,
+];
+
+/// Returns the strategy called `name`, if any.
+pub fn strategy(name: &str) -> Option<&'static StrategyInfo> {
+    STRATEGIES.iter().cloned().find(|s| s.name == name)
+}
+
+/// (De)serializes the strategy of some statistics by its name, which is
+/// enough to find it again among the built-in ones.
+#[cfg(feature = "serde")]
+pub(crate) mod by_name {
+    use super::StrategyInfo;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S>(
+        strategy: &Option<&'static StrategyInfo>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        strategy.map(|s| s.name).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<&'static StrategyInfo>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<String>::deserialize(deserializer)? {
+            None => Ok(None),
+            Some(name) => match super::strategy(&name) {
+                Some(strategy) => Ok(Some(strategy)),
+                None => Err(D::Error::custom(format!("unknown strategy `{}`", name))),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_are_in_range() {
+        for strategy in STRATEGIES {
+            assert_eq!(super::strategy(strategy.name), Some(*strategy));
+            for parameter in strategy.parameters {
+                let in_range = match parameter.kind {
+                    ParameterKind::Integer { min, max, default } => {
+                        default >= min && max.is_none_or(|max| default <= max)
+                    }
+                    ParameterKind::Real { min, max, default } => {
+                        default >= min && max.is_none_or(|max| default <= max)
+                    }
+                    ParameterKind::Flag { .. } => true,
+                    ParameterKind::Choice { options, default } => default < options.len(),
+                };
+                assert!(in_range, "{}.{}", strategy.name, parameter.name);
+            }
+        }
+    }
+}
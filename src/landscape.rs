@@ -0,0 +1,168 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Sampling the scores around a placement, to tell how steep the landscape
+//! is at that point.
+
+use solvers::simulated_annealing::Neighborhood;
+use *;
+
+/// The distribution of the scores of all the neighbors of a placement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LandscapeSample {
+    /// The score of the placement itself.
+    pub score: usize,
+    /// How many neighbors the placement has.
+    pub neighbors: usize,
+    /// The best score among the neighbors, or the placement's own if it
+    /// has none.
+    pub min: usize,
+    /// The worst score among the neighbors, or the placement's own if it
+    /// has none.
+    pub max: usize,
+    pub mean: f32,
+    /// The fraction of the neighbors that score better than the placement.
+    pub improving_fraction: f32,
+    /// The fraction of the neighbors that score the same as the placement.
+    pub sideways_fraction: f32,
+}
+
+/// Adds the basic neighborhoods that `neighborhood` may draw moves from,
+/// at a placement with score `score`, to `basics`.
+fn basic_neighborhoods(neighborhood: &Neighborhood, score: usize, basics: &mut Vec<Neighborhood>) {
+    match *neighborhood {
+        Neighborhood::Swaps | Neighborhood::SingleQueenMoves => {
+            if !basics.contains(neighborhood) {
+                basics.push(neighborhood.clone());
+            }
+        }
+        Neighborhood::Union(ref parts) => {
+            for (_, part) in parts {
+                basic_neighborhoods(part, score, basics);
+            }
+        }
+        Neighborhood::Phases(ref phases) => {
+            for (_, phase) in phases {
+                basic_neighborhoods(phase, score, basics);
+            }
+        }
+        Neighborhood::WhileScoreAbove {
+            threshold,
+            ref above,
+            ref otherwise,
+        } => {
+            let chosen = if score > threshold { above } else { otherwise };
+            basic_neighborhoods(chosen, score, basics);
+        }
+    }
+}
+
+/// Scores every neighbor of `queen_rows` in `neighborhood` by `objective`,
+/// and summarizes the result.
+///
+/// The neighbors in a combination of neighborhoods are those of any of the
+/// neighborhoods it may draw moves from at that placement.
+pub fn sample_landscape(
+    queen_rows: &[usize],
+    neighborhood: Neighborhood,
+    objective: Objective,
+) -> LandscapeSample {
+    let size = queen_rows.len();
+    let mut state = GenericChallengeState {
+        size,
+        queen_rows: queen_rows.to_vec(),
+    };
+    let score = state.evaluate(objective);
+
+    let mut basics = vec![];
+    basic_neighborhoods(&neighborhood, score, &mut basics);
+
+    let mut scores = vec![];
+    for basic in basics {
+        match basic {
+            Neighborhood::Swaps => {
+                for i in 0..size {
+                    for j in (i + 1)..size {
+                        state.queen_rows.swap(i, j);
+                        scores.push(state.evaluate(objective));
+                        state.queen_rows.swap(i, j);
+                    }
+                }
+            }
+            Neighborhood::SingleQueenMoves => {
+                for column in 0..size {
+                    let from = state.queen_rows[column];
+                    for row in (0..size).filter(|&row| row != from) {
+                        state.queen_rows[column] = row;
+                        scores.push(state.evaluate(objective));
+                    }
+                    state.queen_rows[column] = from;
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    if scores.is_empty() {
+        return LandscapeSample {
+            score,
+            neighbors: 0,
+            min: score,
+            max: score,
+            mean: score as f32,
+            improving_fraction: 0.,
+            sideways_fraction: 0.,
+        };
+    }
+
+    let neighbors = scores.len();
+    let fraction = |count: usize| count as f32 / neighbors as f32;
+    LandscapeSample {
+        score,
+        neighbors,
+        min: *scores.iter().min().unwrap(),
+        max: *scores.iter().max().unwrap(),
+        mean: scores.iter().sum::<usize>() as f32 / neighbors as f32,
+        improving_fraction: fraction(scores.iter().filter(|&&s| s < score).count()),
+        sideways_fraction: fraction(scores.iter().filter(|&&s| s == score).count()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_every_neighbor() {
+        let solution = [0, 4, 7, 5, 2, 6, 1, 3];
+        let swaps = sample_landscape(&solution, Neighborhood::Swaps, Objective::AttackingPairs);
+        assert_eq!(swaps.score, 0);
+        assert_eq!(swaps.neighbors, 8 * 7 / 2);
+        assert_eq!(swaps.improving_fraction, 0.);
+        assert!(swaps.min <= swaps.max && swaps.mean <= swaps.max as f32);
+
+        let diagonal = [0, 1, 2, 3];
+        let moves = sample_landscape(
+            &diagonal,
+            Neighborhood::SingleQueenMoves,
+            Objective::AttackingPairs,
+        );
+        assert_eq!(moves.score, 6);
+        assert_eq!(moves.neighbors, 4 * 3);
+        assert!(moves.improving_fraction > 0. && moves.min < 6);
+    }
+}
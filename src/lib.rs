@@ -0,0 +1,2479 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Local search (and a few exact) strategies to solve the n-queens problem.
+//!
+//! Every strategy lives in its own module under [`solvers`], and implements
+//! [`NQueensStrategy`]. The strategies and their configurations are also
+//! re-exported from the crate root for convenience, and along with the rest
+//! of the common types from [`prelude`].
+//!
+//! With the `serde` feature, solutions and the configurations of the
+//! strategies can be serialized and deserialized, to describe experiments in
+//! files and keep their results. The parts of a configuration that are trait
+//! objects or closures, like the cooling schedule of simulated annealing or
+//! the cancellation flag of a budget, are left out, and take their default
+//! values when deserializing, as do any other fields that are missing.
+
+#[cfg(feature = "wasm")]
+extern crate js_sys;
+extern crate rand;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "wasm")]
+extern crate wasm_bindgen;
+
+use resources::ResourceSnapshot;
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub mod attempts;
+pub mod blackboard;
+pub mod checkpoint;
+pub mod conflict_graph;
+pub mod coordinates;
+pub mod csv_trace;
+pub mod error;
+pub mod events;
+pub mod experiments;
+pub mod info;
+pub mod landscape;
+pub mod neighborhood_op;
+pub mod portfolio;
+pub mod prelude;
+pub mod presets;
+pub mod problem;
+pub mod resources;
+pub mod scheduler;
+pub mod selector;
+pub mod solution_set;
+pub mod solvers;
+pub mod stepper;
+pub mod summary;
+pub mod svg;
+pub mod timing;
+pub mod tuning;
+pub mod validation;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use attempts::Attempts;
+pub use blackboard::{Blackboard, BlackboardClient};
+pub use checkpoint::Checkpoint;
+pub use conflict_graph::ConflictGraph;
+pub use coordinates::{CoordinateConvention, Corner, Order};
+pub use csv_trace::CsvTraceRecorder;
+pub use error::{ConfigWarning, Error};
+pub use events::{TraceEvent, TraceEventEncoder};
+pub use info::StrategyInfo;
+pub use neighborhood_op::{
+    verify_neighborhood, MoveQueenToRow, NeighborhoodOp, QueenMove, ShiftQueen, SwapQueens,
+};
+pub use resources::ResourceUsage;
+pub use solvers::constraint_propagation::{ConstraintPropagation, ConstraintPropagationConfig};
+pub use solvers::constructive_beam_search::{ConstructiveBeamSearch, ConstructiveBeamSearchConfig};
+pub use solvers::cuckoo_search::{CuckooSearch, CuckooSearchConfig};
+pub use solvers::decomposition::{Decomposition, DecompositionConfig};
+pub use solvers::genetic_algorithm::{GeneticAlgorithm, GeneticAlgorithmConfig};
+pub use solvers::hill_climbing::{HillClimbing, HillClimbingConfig};
+pub use solvers::hybrid::{Hybrid, HybridConfig};
+pub use solvers::island_model::{IslandModel, IslandModelConfig};
+pub use solvers::limited_discrepancy_search::{
+    LimitedDiscrepancySearch, LimitedDiscrepancySearchConfig,
+};
+pub use solvers::local_beam_search::{LocalBeamSearch, LocalBeamSearchConfig};
+pub use solvers::min_conflicts::{MinConflicts, MinConflictsConfig};
+pub use solvers::pbil::{Pbil, PbilConfig};
+pub use solvers::simulated_annealing::{SimulatedAnnealing, SimulatedAnnealingConfig};
+pub use solvers::tabu_search::{TabuSearch, TabuSearchConfig};
+pub use stepper::{StepResult, Stepper, Steps};
+pub use svg::SvgOptions;
+pub use timing::TimingHistogram;
+
+pub enum PositionError {
+    /// A queen is already there.
+    Match,
+    /// Queen in the same column.
+    Column,
+    /// Queen in the same row.
+    Row,
+    /// Queen in the same diagonal.
+    Diagonal,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Solution {
+    queen_rows: Box<[usize]>,
+    /// The size of the board, which is more than the queens placed for the
+    /// exact strategies that didn't complete a placement.
+    dimension: usize,
+    score: usize,
+    /// The best distinct placements found during the search, best first, if
+    /// they were requested.
+    alternatives: Vec<Solution>,
+    /// All the solutions found across restarts, for the strategies that
+    /// support them.
+    archive: SolutionArchive,
+    /// Where the solution came from, for the genetic algorithm if requested.
+    genealogy: Option<solvers::genetic_algorithm::Genealogy>,
+    /// The steps recorded during the search, if requested.
+    trace: Option<TraceRecorder>,
+    /// The state the search ended on and its score, for the strategies that
+    /// can end on a state other than the best one they visited, when that
+    /// happens.
+    final_state: Option<(Box<[usize]>, usize)>,
+    /// Why the search stopped.
+    status: SolveStatus,
+    /// The population at regular intervals, for the population-based
+    /// strategies that were asked to take snapshots.
+    snapshots: Vec<PopulationSnapshot>,
+    /// How diverse the population was on each generation, for the
+    /// population-based strategies that were asked to track it.
+    diversity: Vec<DiversityReport>,
+    /// Where to carry the search on from, for the strategies that support
+    /// it, if the budget stopped it before it was over.
+    checkpoint: Option<Checkpoint>,
+    statistics: Statistics,
+}
+
+/// The whole population of a population-based strategy at some point of the
+/// search.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PopulationSnapshot {
+    /// The generation or iteration the snapshot was taken at.
+    pub generation: usize,
+    /// Each member of the population and its score, best first.
+    pub members: Vec<(Box<[usize]>, usize)>,
+}
+
+impl PopulationSnapshot {
+    fn new<'a, I>(generation: usize, members: I) -> Self
+    where
+        I: Iterator<Item = (&'a [usize], usize)>,
+    {
+        let mut members = members
+            .map(|(queen_rows, score)| (queen_rows.to_vec().into_boxed_slice(), score))
+            .collect::<Vec<_>>();
+        members.sort_by_key(|&(_, score)| score);
+        PopulationSnapshot {
+            generation,
+            members,
+        }
+    }
+
+    /// Returns whether a snapshot should be taken at `generation` given an
+    /// interval, where zero means never.
+    fn is_due(generation: usize, interval: usize) -> bool {
+        interval != 0 && generation.is_multiple_of(interval)
+    }
+
+    pub fn diversity(&self) -> DiversityReport {
+        let members = self.members.iter().map(|(queen_rows, _)| &queen_rows[..]);
+        DiversityReport::new(self.generation, members)
+    }
+}
+
+/// How spread out the population of a population-based strategy is at some
+/// point of the search, to tell when it has converged.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DiversityReport {
+    /// The generation or iteration the report is about.
+    pub generation: usize,
+    /// The average number of columns in which two members have their queens
+    /// on different rows, over every pair of members.
+    pub mean_distance: f64,
+    /// How many members are different to each other.
+    pub distinct: usize,
+    /// The entropy of the row of the queen of each column across the
+    /// population, in bits. Zero means every member agrees on the column.
+    pub column_entropy: Vec<f64>,
+}
+
+impl DiversityReport {
+    pub fn new<'a, I>(generation: usize, members: I) -> Self
+    where
+        I: IntoIterator<Item = &'a [usize]>,
+    {
+        let members = members.into_iter().collect::<Vec<_>>();
+        let columns = members.iter().map(|m| m.len()).max().unwrap_or(0);
+        let count = members.len() as f64;
+        let pairs = count * (count - 1.) / 2.;
+
+        let mut mean_distance = 0.;
+        let mut column_entropy = Vec::with_capacity(columns);
+        for column in 0..columns {
+            let mut rows = HashMap::new();
+            for member in &members {
+                *rows.entry(member.get(column)).or_insert(0usize) += 1;
+            }
+
+            // Two members differ unless they have the same row.
+            let same = rows.values().map(|&c| c * (c - 1) / 2).sum::<usize>();
+            if pairs > 0. {
+                mean_distance += (pairs - same as f64) / pairs;
+            }
+            let entropy = rows
+                .values()
+                .map(|&c| {
+                    let p = c as f64 / count;
+                    -p * p.log2()
+                })
+                .sum::<f64>();
+            column_entropy.push(entropy);
+        }
+
+        DiversityReport {
+            generation,
+            mean_distance,
+            distinct: members.iter().collect::<HashSet<_>>().len(),
+            column_entropy,
+        }
+    }
+
+    /// The average entropy of the columns, in bits.
+    pub fn mean_entropy(&self) -> f64 {
+        if self.column_entropy.is_empty() {
+            return 0.;
+        }
+        self.column_entropy.iter().sum::<f64>() / self.column_entropy.len() as f64
+    }
+}
+
+/// How much work a search took, in units that are comparable across
+/// strategies. Counters that don't apply to a strategy are left at zero.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SearchEffort {
+    /// Queens placed by the exact solvers.
+    pub nodes_expanded: usize,
+    /// Queens removed by the exact solvers after running out of options.
+    pub backtracks: usize,
+    /// Complete placements scored, or partial ones in the case of the
+    /// constructive beam search.
+    pub evaluations: usize,
+    /// Moves accepted by the local search strategies, or states kept in the
+    /// beam in the case of local beam search.
+    pub accepted_moves: usize,
+    /// Generations computed by the genetic algorithm, however much of the
+    /// population each replaces, or iterations of local beam search.
+    pub generations: usize,
+}
+
+/// Limits that apply to a whole solve, on top of the ones of each strategy.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct SolveBudget {
+    /// How long the whole solve may take, counted from its first iteration.
+    pub max_wall_time: Option<Duration>,
+    /// The most iterations of the main loop of the strategy, like moves
+    /// tried, generations, or queens placed.
+    pub max_iterations: Option<usize>,
+    /// Stops the search as soon as it's set, from any thread.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub cancellation: Option<Arc<AtomicBool>>,
+    /// Whether to time each iteration into `Statistics::step_times`, which
+    /// isn't free for the strategies with very cheap iterations.
+    pub time_steps: bool,
+}
+
+/// How a solve ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SolveStatus {
+    /// The placement returned is a solution.
+    Solved,
+    /// The strategy gave up on its own before finding a solution.
+    Unsolved,
+    /// The search ran out of time or iterations.
+    Timeout,
+    Cancelled,
+}
+
+/// Keeps track of a `SolveBudget` during a solve.
+pub struct BudgetTracker {
+    budget: SolveBudget,
+    /// When the first iteration started.
+    start: Option<Instant>,
+    /// The resources used by the process when the first iteration started.
+    resources: Option<ResourceSnapshot>,
+    iterations: usize,
+    /// Why the search was stopped, if it was.
+    stopped: Option<SolveStatus>,
+    /// When the current iteration started, if timing them.
+    step_start: Option<Instant>,
+    step_times: TimingHistogram,
+}
+
+impl BudgetTracker {
+    pub fn new(budget: SolveBudget) -> Self {
+        BudgetTracker {
+            budget,
+            start: None,
+            resources: None,
+            iterations: 0,
+            stopped: None,
+            step_start: None,
+            step_times: TimingHistogram::default(),
+        }
+    }
+
+    /// Returns whether the search must stop before running its next
+    /// iteration, counting the iteration otherwise.
+    pub fn should_stop(&mut self) -> bool {
+        if self.stopped.is_some() {
+            return true;
+        }
+
+        if self.resources.is_none() {
+            self.resources = Some(ResourceSnapshot::take());
+        }
+        let start = *self.start.get_or_insert_with(Instant::now);
+        if self.budget.time_steps {
+            let now = Instant::now();
+            if let Some(step_start) = self.step_start {
+                self.step_times.record(now - step_start);
+            }
+            self.step_start = Some(now);
+        }
+
+        if self
+            .budget
+            .cancellation
+            .as_ref()
+            .is_some_and(|cancelled| cancelled.load(Ordering::Relaxed))
+        {
+            self.stopped = Some(SolveStatus::Cancelled);
+        } else if self
+            .budget
+            .max_iterations
+            .is_some_and(|max| self.iterations >= max)
+            || self
+                .budget
+                .max_wall_time
+                .is_some_and(|max| start.elapsed() >= max)
+        {
+            self.stopped = Some(SolveStatus::Timeout);
+        } else {
+            self.iterations += 1;
+        }
+
+        self.stopped.is_some()
+    }
+
+    /// Returns the budget left for a search nested in this one, which can
+    /// run for the rest of the time and be cancelled along with it.
+    pub fn remaining(&self) -> SolveBudget {
+        let elapsed = self
+            .start
+            .map_or(Duration::from_secs(0), |start| start.elapsed());
+        SolveBudget {
+            max_wall_time: self
+                .budget
+                .max_wall_time
+                .map(|max| max.saturating_sub(elapsed)),
+            max_iterations: None,
+            cancellation: self.budget.cancellation.clone(),
+            time_steps: false,
+        }
+    }
+
+    /// Returns how many iterations were counted so far.
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// Returns whether the search was stopped.
+    pub fn stopped(&self) -> bool {
+        self.stopped.is_some()
+    }
+
+    /// Returns the status of a solve that ended with a solution if
+    /// `solved`.
+    pub fn status(&self, solved: bool) -> SolveStatus {
+        match self.stopped {
+            _ if solved => SolveStatus::Solved,
+            Some(status) => status,
+            None => SolveStatus::Unsolved,
+        }
+    }
+
+    /// Records how the search of `strategy` ended in `solution`, which is a
+    /// solution if `solved`.
+    pub fn finish(&self, strategy: &'static StrategyInfo, solution: &mut Solution, solved: bool) {
+        solution.status = self.status(solved);
+        solution.statistics.strategy = Some(strategy);
+        solution.statistics.iterations = self.iterations;
+        solution.statistics.elapsed = self
+            .start
+            .map_or(Duration::from_secs(0), |start| start.elapsed());
+        solution.statistics.resources = self
+            .resources
+            .as_ref()
+            .map_or_else(ResourceUsage::default, ResourceSnapshot::usage);
+
+        solution.statistics.step_times.clone_from(&self.step_times);
+        // The last iteration didn't get to check whether to stop.
+        if let (None, Some(step_start)) = (self.stopped, self.step_start) {
+            solution.statistics.step_times.record(step_start.elapsed());
+        }
+    }
+}
+
+impl SearchEffort {
+    /// Returns whether the search has scored at least `max_evaluations`
+    /// placements, if there's such a limit.
+    pub fn exhausts(&self, max_evaluations: Option<usize>) -> bool {
+        max_evaluations.is_some_and(|max| self.evaluations >= max)
+    }
+}
+
+/// Information about how the search went.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Statistics {
+    pub effort: SearchEffort,
+    /// The master seed the random streams of the search were derived from.
+    pub master_seed: u64,
+    /// How many times the search was restarted.
+    pub restarts: usize,
+    /// How many nogoods were recorded, for the strategies that learn them.
+    pub nogoods: usize,
+    /// How many times the annealing was reheated, for the strategies that
+    /// anneal.
+    pub reheats: usize,
+    /// The strategy that ran the search, if it's one of the built-in ones.
+    #[cfg_attr(feature = "serde", serde(with = "info::by_name"))]
+    pub strategy: Option<&'static StrategyInfo>,
+    /// How many iterations of its main loop the strategy ran, in the same
+    /// units as `SolveBudget::max_iterations`.
+    pub iterations: usize,
+    /// How long the search took, from its first iteration.
+    pub elapsed: Duration,
+    /// How long each iteration took, if requested with
+    /// `SolveBudget::time_steps`.
+    pub step_times: TimingHistogram,
+    /// The processor time and memory the search used, from its first
+    /// iteration.
+    pub resources: ResourceUsage,
+}
+
+impl Solution {
+    /// Records `queen_rows` as the state the search ended on, unless it's
+    /// the one returned.
+    fn set_final_state(&mut self, queen_rows: &[usize], score: usize) {
+        if *queen_rows != *self.queen_rows {
+            self.final_state = Some((queen_rows.to_vec().into_boxed_slice(), score));
+        }
+    }
+
+    pub fn new(queen_rows: Vec<usize>, score: usize) -> Self {
+        let dimension = queen_rows.len();
+        Self::partial(queen_rows, score, dimension)
+    }
+
+    /// Creates a solution with queens in only the first columns of a board
+    /// of `dimension` queens.
+    pub fn partial(queen_rows: Vec<usize>, score: usize, dimension: usize) -> Self {
+        debug_assert!(queen_rows.len() <= dimension);
+        Solution {
+            queen_rows: queen_rows.into_boxed_slice(),
+            dimension,
+            score,
+            alternatives: vec![],
+            archive: SolutionArchive::default(),
+            genealogy: None,
+            trace: None,
+            final_state: None,
+            status: SolveStatus::Unsolved,
+            snapshots: vec![],
+            diversity: vec![],
+            checkpoint: None,
+            statistics: Statistics::default(),
+        }
+    }
+
+    /// The row of the queen in each column.
+    pub fn queen_rows(&self) -> &[usize] {
+        &self.queen_rows
+    }
+
+    /// The score of the solution, where zero means no queen is attacked.
+    pub fn score(&self) -> usize {
+        self.score
+    }
+
+    pub fn status(&self) -> SolveStatus {
+        self.status
+    }
+
+    pub fn statistics(&self) -> &Statistics {
+        &self.statistics
+    }
+
+    /// How diverse the population was on each generation, if tracked.
+    pub fn diversity(&self) -> &[DiversityReport] {
+        &self.diversity
+    }
+
+    /// The size of the board.
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// The steps recorded during the search, if it was traced.
+    pub fn trace(&self) -> Option<&TraceRecorder> {
+        self.trace.as_ref()
+    }
+
+    /// Where to carry the search on from, if the budget stopped it before
+    /// it was over, and the strategy supports resuming.
+    pub fn checkpoint(&self) -> Option<&Checkpoint> {
+        self.checkpoint.as_ref()
+    }
+
+    /// The best distinct placements found during the search, best first, if
+    /// the strategy was asked to keep them.
+    pub fn alternatives(&self) -> &[Solution] {
+        &self.alternatives
+    }
+
+    /// The population at regular intervals, if the strategy was asked to
+    /// take snapshots.
+    pub fn snapshots(&self) -> &[PopulationSnapshot] {
+        &self.snapshots
+    }
+
+    /// All the distinct solutions found across restarts, for the strategies
+    /// that collect them.
+    pub fn archive(&self) -> &SolutionArchive {
+        &self.archive
+    }
+
+    /// Where the solution came from, if the genetic algorithm was asked to
+    /// track it.
+    pub fn genealogy(&self) -> Option<&solvers::genetic_algorithm::Genealogy> {
+        self.genealogy.as_ref()
+    }
+
+    /// The state the search ended on and its score, if it isn't the one
+    /// returned.
+    pub fn final_state(&self) -> Option<(&[usize], usize)> {
+        self.final_state
+            .as_ref()
+            .map(|&(ref queen_rows, score)| (&queen_rows[..], score))
+    }
+
+    /// The `(column, row)` of each queen placed, by column.
+    pub fn positions<'a>(&'a self) -> impl Iterator<Item = (usize, usize)> + 'a {
+        self.queen_rows.iter().cloned().enumerate()
+    }
+
+    /// The `(column, row)` of each queen placed in `convention`, by internal
+    /// column.
+    pub fn positions_in(&self, convention: CoordinateConvention) -> Vec<(usize, usize)> {
+        self.positions()
+            .map(|square| convention.from_internal(self.dimension, square))
+            .collect()
+    }
+
+    /// The index of the square of each queen placed in `convention`, by
+    /// internal column.
+    pub fn indices(&self, convention: CoordinateConvention) -> Vec<usize> {
+        self.positions()
+            .map(|square| convention.index(self.dimension, square))
+            .collect()
+    }
+
+    /// Renders the board like `Display`, with the columns and rows numbered
+    /// as in `convention` along the edges closest to its origin.
+    pub fn render(&self, convention: CoordinateConvention) -> String {
+        let n = self.dimension;
+        let width = n.saturating_sub(1).to_string().len();
+        let pad = |text: &str| format!("{:>width$}", text, width = width);
+        let line = |label: String, squares: Vec<String>| match convention.origin {
+            Corner::TopLeft | Corner::BottomLeft => format!("{} {}", label, squares.join(" ")),
+            Corner::TopRight | Corner::BottomRight => format!("{} {}", squares.join(" "), label),
+        };
+
+        let labels = (0..n)
+            .map(|column| pad(&convention.from_internal(n, (column, 0)).0.to_string()))
+            .collect();
+        let labels = line(pad(""), labels).trim_end().to_owned();
+        let mut lines = self
+            .to_board()
+            .iter()
+            .enumerate()
+            .map(|(row, squares)| {
+                let label = convention.from_internal(n, (0, row)).1;
+                let squares = squares
+                    .iter()
+                    .map(|&queen| pad(if queen { "Q" } else { "." }))
+                    .collect();
+                line(pad(&label.to_string()), squares)
+            })
+            .collect::<Vec<_>>();
+        match convention.origin {
+            Corner::TopLeft | Corner::TopRight => lines.insert(0, labels),
+            Corner::BottomLeft | Corner::BottomRight => lines.push(labels),
+        }
+
+        let mut rendered = lines.join("\n");
+        rendered.push('\n');
+        rendered
+    }
+
+    /// Returns the number of queens that can hit each queen, by column, to
+    /// highlight the ones under attack.
+    pub fn attack_counts(&self) -> Vec<usize> {
+        GenericChallengeState {
+            size: self.dimension,
+            queen_rows: self.queen_rows.to_vec(),
+        }
+        .attack_counts()
+    }
+
+    /// Returns the graph of the queens that can hit each other.
+    pub fn conflict_graph(&self) -> ConflictGraph {
+        ConflictGraph::new(&self.queen_rows)
+    }
+
+    /// Returns whether every column has a queen, and none of them attack
+    /// each other, regardless of the objective the score was computed with.
+    pub fn is_valid(&self) -> bool {
+        let n = self.dimension;
+        if self.queen_rows.len() != n || self.queen_rows.iter().any(|&row| row >= n) {
+            return false;
+        }
+
+        let lines = 2 * n.max(1) - 1;
+        let mut rows = vec![false; n];
+        let mut diagonals = vec![false; lines];
+        let mut anti_diagonals = vec![false; lines];
+        self.positions().all(|(column, row)| {
+            let seen = [
+                &mut rows[row],
+                &mut diagonals[column + n - 1 - row],
+                &mut anti_diagonals[column + row],
+            ];
+            let attacked = seen.iter().any(|seen| **seen);
+            for seen in seen {
+                *seen = true;
+            }
+            !attacked
+        })
+    }
+
+    /// Returns the board as a grid of rows, where `true` means there's a
+    /// queen.
+    pub fn to_board(&self) -> Vec<Vec<bool>> {
+        let mut board = vec![vec![false; self.dimension]; self.dimension];
+        for (column, row) in self.positions() {
+            board[row][column] = true;
+        }
+        board
+    }
+
+    /// Returns a standalone SVG drawing of the board, as `options` says.
+    pub fn to_svg(&self, options: &SvgOptions) -> String {
+        svg::render(self, options)
+    }
+
+    /// Returns the result of the search as a JSON object, for tools that
+    /// would rather not parse the board, like:
+    ///
+    /// ```json
+    /// {"n":4,"positions":[1,3,0,2],"score":0,"iterations":9,"algorithm":"hill_climbing",
+    ///  "solved":true,"elapsed_ms":0.02,"seed":42}
+    /// ```
+    ///
+    /// The positions are the row of the queen of each column placed, and the
+    /// algorithm is the name of the strategy in its `StrategyInfo`, or null
+    /// if it isn't one of the built-in ones.
+    pub fn to_json(&self) -> String {
+        let positions = self
+            .queen_rows
+            .iter()
+            .map(|row| row.to_string())
+            .collect::<Vec<_>>();
+        let algorithm = match self.statistics.strategy {
+            Some(strategy) => format!("\"{}\"", strategy.name),
+            None => "null".to_owned(),
+        };
+        format!(
+            "{{\"n\":{},\"positions\":[{}],\"score\":{},\"iterations\":{},\"algorithm\":{},\
+             \"solved\":{},\"elapsed_ms\":{},\"seed\":{}}}",
+            self.dimension,
+            positions.join(","),
+            self.score,
+            self.statistics.iterations,
+            algorithm,
+            self.status == SolveStatus::Solved,
+            self.statistics.elapsed.as_secs_f64() * 1000.,
+            self.statistics.master_seed,
+        )
+    }
+}
+
+/// Renders the board with a `Q` for each queen and a `.` for each empty
+/// cell, a row per line.
+impl fmt::Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.to_board() {
+            let line = row
+                .iter()
+                .map(|&queen| if queen { "Q" } else { "." })
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// The stochastic components of the strategies, each of which draws from its
+/// own random stream, so that changing how much randomness one of them uses
+/// doesn't change what the others see.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RngStream {
+    /// Generating fresh placements, initially or on restarts.
+    Initializer,
+    /// Choosing the next move to try.
+    Neighborhood,
+    /// Deciding whether to accept a move.
+    Acceptance,
+    Mutation,
+    Crossover,
+    /// Choosing members of a population, like parents or nests.
+    Selection,
+    /// Ordering the rows to try in the exact solvers.
+    ValueOrdering,
+}
+
+/// The generator behind each random stream: the xorshift generator of
+/// `rand`, with the same output, but a state that can be saved and
+/// restored.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StreamRng {
+    state: [u32; 4],
+}
+
+impl StreamRng {
+    /// Returns the state of the generator, which `from_seed` restores.
+    pub fn state(&self) -> [u32; 4] {
+        self.state
+    }
+}
+
+impl rand::Rng for StreamRng {
+    fn next_u32(&mut self) -> u32 {
+        let [x, y, z, w] = self.state;
+        let t = x ^ (x << 11);
+        let next = w ^ (w >> 19) ^ (t ^ (t >> 8));
+        self.state = [y, z, w, next];
+        next
+    }
+}
+
+impl rand::SeedableRng<[u32; 4]> for StreamRng {
+    /// Panics if `seed` is all zeros.
+    fn reseed(&mut self, seed: [u32; 4]) {
+        *self = Self::from_seed(seed);
+    }
+
+    /// Panics if `seed` is all zeros, which would make the generator
+    /// output zeros forever.
+    fn from_seed(seed: [u32; 4]) -> Self {
+        assert!(seed != [0; 4], "xorshift can't be seeded with all zeros");
+        StreamRng { state: seed }
+    }
+}
+
+/// Returns the next output of the SplitMix64 generator with state `state`,
+/// which is good at turning similar seeds into unrelated ones.
+fn split_mix_64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Returns the random stream `stream` derived from `master_seed`.
+pub fn stream_rng(master_seed: u64, stream: RngStream) -> StreamRng {
+    use rand::SeedableRng;
+
+    let mut state = master_seed ^ split_mix_64(&mut (stream as u64));
+    let mut seed = [0u32; 4];
+    for pair in seed.chunks_mut(2) {
+        let bits = split_mix_64(&mut state);
+        pair[0] = bits as u32;
+        pair[1] = (bits >> 32) as u32;
+    }
+    // An all-zero seed would make xorshift output zeros forever.
+    if seed == [0; 4] {
+        seed[0] = 1;
+    }
+    StreamRng::from_seed(seed)
+}
+
+/// The random streams of a search, all derived from the same master seed.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ComponentRngs {
+    pub master_seed: u64,
+    pub initializer: StreamRng,
+    pub neighborhood: StreamRng,
+    pub acceptance: StreamRng,
+    pub mutation: StreamRng,
+    pub crossover: StreamRng,
+    pub selection: StreamRng,
+    pub value_ordering: StreamRng,
+}
+
+impl ComponentRngs {
+    pub fn new(master_seed: u64) -> Self {
+        ComponentRngs {
+            master_seed,
+            initializer: stream_rng(master_seed, RngStream::Initializer),
+            neighborhood: stream_rng(master_seed, RngStream::Neighborhood),
+            acceptance: stream_rng(master_seed, RngStream::Acceptance),
+            mutation: stream_rng(master_seed, RngStream::Mutation),
+            crossover: stream_rng(master_seed, RngStream::Crossover),
+            selection: stream_rng(master_seed, RngStream::Selection),
+            value_ordering: stream_rng(master_seed, RngStream::ValueOrdering),
+        }
+    }
+
+    /// Derives the streams from a master seed drawn from the operating
+    /// system.
+    pub fn try_from_entropy() -> Result<Self, Error> {
+        use rand::Rng;
+
+        match rand::OsRng::new() {
+            Ok(mut rng) => Ok(Self::new(rng.next_u64())),
+            Err(..) => Err(Error::RngUnavailable),
+        }
+    }
+
+    /// Derives the streams from `seed`, or from a random one if unset.
+    pub fn for_seed(seed: Option<u64>) -> Self {
+        match seed {
+            Some(seed) => Self::new(seed),
+            None => Self::from_entropy(),
+        }
+    }
+
+    /// Like `try_from_entropy`, but panics if there's no seed available.
+    pub fn from_entropy() -> Self {
+        Self::try_from_entropy().expect("couldn't seed the random number generators")
+    }
+}
+
+/// Fails with `Error::InvalidConfig(what)` unless `value` is a probability.
+fn check_probability(value: f32, what: &'static str) -> Result<(), Error> {
+    if (0.0..=1.0).contains(&value) {
+        Ok(())
+    } else {
+        Err(Error::InvalidConfig(what))
+    }
+}
+
+/// Returns a hash identifying the placement of the queens in a board.
+pub fn board_hash(queen_rows: &[usize]) -> u64 {
+    use std::collections::hash_map::DefaultHasher;
+
+    let mut hasher = DefaultHasher::new();
+    queen_rows.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Keeps the `k` best distinct complete placements it's been offered.
+pub struct BestPlacements {
+    k: usize,
+    dimension: usize,
+    hashes: HashSet<u64>,
+    best: Vec<Solution>,
+}
+
+impl BestPlacements {
+    pub fn new(k: usize, dimension: usize) -> Self {
+        BestPlacements {
+            k,
+            dimension,
+            hashes: HashSet::with_capacity(k),
+            best: Vec::with_capacity(k),
+        }
+    }
+
+    /// Records the placement if it's among the `k` best seen so far. Partial
+    /// placements and placements already recorded are ignored.
+    pub fn offer(&mut self, queen_rows: &[usize], score: usize) {
+        if self.k == 0 || queen_rows.len() != self.dimension {
+            return;
+        }
+
+        if self.best.len() == self.k && self.best[self.k - 1].score <= score {
+            return;
+        }
+
+        if !self.hashes.insert(board_hash(queen_rows)) {
+            return;
+        }
+
+        let index = self
+            .best
+            .iter()
+            .position(|s| s.score > score)
+            .unwrap_or(self.best.len());
+        self.best
+            .insert(index, Solution::new(queen_rows.to_vec(), score));
+
+        if self.best.len() > self.k {
+            let evicted = self.best.pop().unwrap();
+            self.hashes.remove(&board_hash(&evicted.queen_rows));
+        }
+    }
+
+    /// Returns how many placements can be recorded.
+    pub fn capacity(&self) -> usize {
+        self.k
+    }
+
+    /// Returns the placements recorded so far, best first.
+    pub fn solutions(&self) -> &[Solution] {
+        &self.best
+    }
+
+    /// Returns the placements recorded, best first.
+    pub fn into_solutions(self) -> Vec<Solution> {
+        self.best
+    }
+}
+
+/// Returns the smallest (lexicographically) of the placements equivalent to
+/// `queen_rows` under rotations and reflections of the board.
+///
+/// Only placements with a queen in each row can be rotated without leaving
+/// the one-queen-per-column representation, which is the case for all
+/// solutions.
+pub fn canonical_placement(queen_rows: &[usize]) -> Vec<usize> {
+    let n = queen_rows.len();
+
+    let mut transposed = vec![0; n];
+    for (x, &y) in queen_rows.iter().enumerate() {
+        transposed[y] = x;
+    }
+
+    let mut canonical = queen_rows.to_vec();
+    for base in &[queen_rows.to_vec(), transposed] {
+        let mirrored = base.iter().rev().cloned().collect::<Vec<_>>();
+        for candidate in &[base, &mirrored] {
+            let flipped = candidate.iter().map(|y| n - 1 - y).collect::<Vec<_>>();
+            for candidate in &[candidate.to_vec(), flipped] {
+                if *candidate < canonical {
+                    canonical = candidate.clone();
+                }
+            }
+        }
+    }
+
+    canonical
+}
+
+/// Counts the pairs of queens at `positions`, as `(column, row)`, that can
+/// hit each other, without assuming a queen per column.
+///
+/// Each pair counts once per line the queens share, so two queens on the
+/// same square count four times.
+pub fn attacking_pairs(positions: &[(usize, usize)]) -> usize {
+    let mut lines = HashMap::new();
+    for &(column, row) in positions {
+        let diagonal = column as i64 - row as i64;
+        let anti_diagonal = (column + row) as i64;
+        for &line in &[
+            (0, column as i64),
+            (1, row as i64),
+            (2, diagonal),
+            (3, anti_diagonal),
+        ] {
+            *lines.entry(line).or_insert(0) += 1;
+        }
+    }
+    lines
+        .values()
+        .map(|&queens: &usize| queens * (queens - 1) / 2)
+        .sum()
+}
+
+/// A collection of solutions, deduplicated up to the symmetries of the
+/// board.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SolutionArchive {
+    canonical: HashSet<Vec<usize>>,
+    solutions: Vec<Box<[usize]>>,
+}
+
+impl SolutionArchive {
+    /// Adds a solution to the archive, returning false if it, or a symmetric
+    /// one, was already there.
+    pub fn insert(&mut self, queen_rows: &[usize]) -> bool {
+        if !self.canonical.insert(canonical_placement(queen_rows)) {
+            return false;
+        }
+        self.solutions.push(queen_rows.to_vec().into_boxed_slice());
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.solutions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.solutions.is_empty()
+    }
+
+    /// The archived solutions, in the order they were found.
+    pub fn solutions(&self) -> &[Box<[usize]>] {
+        &self.solutions
+    }
+}
+
+/// A problem-solving strategy for the n-queens problem.
+pub trait NQueensStrategy: Sized {
+    /// Extra parameters that may be given to the challenge to configure the
+    /// solution.
+    type Config;
+
+    /// Creates a new solvable instance of this challenge.
+    fn new(dimension: usize, config: Self::Config) -> Self;
+
+    /// Checks that `config` makes sense for boards of size `dimension`.
+    fn validate(_dimension: usize, _config: &Self::Config) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Returns warnings about the parts of `config` that are valid, but
+    /// probably not what was intended for boards of size `dimension`, to
+    /// show before starting a run.
+    fn lint(_dimension: usize, _config: &Self::Config) -> Vec<ConfigWarning> {
+        vec![]
+    }
+
+    /// Like `new`, but validating the configuration first.
+    fn try_new(dimension: usize, config: Self::Config) -> Result<Self, Error> {
+        Self::validate(dimension, &config)?;
+        Ok(Self::new(dimension, config))
+    }
+
+    /// Like `new`, but reusing the buffers in `context`, which must come
+    /// from a previous solve of a board of the same size, or be new.
+    fn new_in_context(context: SolverContext, config: Self::Config) -> Self {
+        Self::new(context.dimension(), config)
+    }
+
+    /// Describes the strategy and its configuration.
+    fn info() -> &'static StrategyInfo;
+
+    /// Returns the size of the board this instance is solving.
+    fn dimension(&self) -> usize;
+
+    /// Solves the challenge for returning a vector with `n` positions,
+    /// representing the column at which the queen is positioned for each index.
+    fn solve(self) -> Solution {
+        self.solve_with_callback(|_, _| {})
+    }
+
+    /// Solves a board of `dimension` queens with the default configuration,
+    /// for when the parameters don't matter much.
+    fn solve_default(dimension: usize) -> Solution
+    where
+        Self::Config: Default,
+    {
+        Self::new(dimension, Self::Config::default()).solve()
+    }
+
+    /// Like `solve_with_callback`, but fails upfront instead of searching
+    /// when the board is known not to have any solution.
+    ///
+    /// Otherwise the solution is returned even if it has attacks, as the
+    /// local search strategies may give up before finding one.
+    fn try_solve_with_callback<F>(self, callback: F) -> Result<Solution, Error>
+    where
+        F: FnMut(&[usize], usize),
+    {
+        let dimension = self.dimension();
+        if validation::known_solution_count(dimension) == Some(0) {
+            return Err(Error::Infeasible(dimension));
+        }
+        Ok(self.solve_with_callback(callback))
+    }
+
+    /// Solves the challenge for returning a vector with `n` positions,
+    /// representing the column at which the queen is positioned for each index,
+    /// and additionally runs `callback` on each step the positions changed,
+    /// with the queen positions and the current score so far.
+    fn solve_with_callback<F>(self, callback: F) -> Solution
+    where
+        F: FnMut(&[usize], usize);
+
+    /// Like `solve_with_callback`, but also gives back the buffers used
+    /// during the search, to pass to `new_in_context`.
+    fn solve_in_context<F>(self, callback: F) -> (Solution, SolverContext)
+    where
+        F: FnMut(&[usize], usize),
+    {
+        let dimension = self.dimension();
+        (
+            self.solve_with_callback(callback),
+            SolverContext::new(dimension),
+        )
+    }
+
+    /// Like `solve_with_callback`, but only forwards to `callback` as often
+    /// as `report` allows, with whatever the state is at that point. If the
+    /// last step isn't forwarded, where the search ended is, as in
+    /// `ThrottledCallback::finish`.
+    fn solve_with_throttled_callback<F>(self, report: ReportConfig, callback: F) -> Solution
+    where
+        F: FnMut(&[usize], usize),
+    {
+        let mut callback = ThrottledCallback::new(report, callback);
+        let solution = self.solve_with_callback(|queens, score| callback.step(queens, score));
+        callback.finish(&solution);
+        solution
+    }
+
+    /// Like `solve_with_callback`, but additionally records the steps as
+    /// `level` allows, in the solution's trace.
+    fn solve_with_trace<F>(self, level: TraceLevel, mut callback: F) -> Solution
+    where
+        F: FnMut(&[usize], usize),
+    {
+        let mut recorder = TraceRecorder::new(level);
+        let mut solution = self.solve_with_callback(|queens, score| {
+            recorder.record(queens, score);
+            callback(queens, score)
+        });
+        solution.trace = Some(recorder);
+        solution
+    }
+
+    /// Like `solve_with_callback`, but additionally keeps the `k` best
+    /// distinct placements encountered during the run, which are returned in
+    /// the solution's `alternatives`.
+    fn solve_keeping_best<F>(self, k: usize, mut callback: F) -> Solution
+    where
+        F: FnMut(&[usize], usize),
+    {
+        let mut best = BestPlacements::new(k, self.dimension());
+        let mut solution = self.solve_with_callback(|queens, score| {
+            best.offer(queens, score);
+            callback(queens, score)
+        });
+        best.offer(&solution.queen_rows, solution.score);
+        solution.alternatives = best.into_solutions();
+        solution
+    }
+}
+
+/// Which steps of a search a `TraceRecorder` keeps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TraceLevel {
+    /// Keep nothing.
+    Off,
+    /// Keep only the steps that improve on the best score so far.
+    BestImprovementsOnly,
+    /// Keep one step out of every `k`. Zero and one mean every step.
+    SampledEveryK(usize),
+    /// Keep every step.
+    Full,
+    /// Keep no steps, only the scores summarized in at most this many
+    /// `ScoreBin`s of equal width, which grows as the search goes on. Less
+    /// than two means two.
+    Binned(usize),
+}
+
+/// A step of the search kept by a `TraceRecorder`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TraceEntry {
+    /// The index of the step, counting from zero.
+    pub step: usize,
+    pub score: usize,
+    pub queen_rows: Box<[usize]>,
+}
+
+/// The scores of a run of consecutive steps, to plot long searches without
+/// keeping every step.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScoreBin {
+    /// The index of the first step in the bin.
+    pub first_step: usize,
+    /// How many steps the bin covers.
+    pub steps: usize,
+    pub min: usize,
+    pub max: usize,
+    pub mean: f64,
+}
+
+impl ScoreBin {
+    fn new(first_step: usize, score: usize) -> Self {
+        ScoreBin {
+            first_step,
+            steps: 1,
+            min: score,
+            max: score,
+            mean: score as f64,
+        }
+    }
+
+    fn add(&mut self, score: usize) {
+        self.steps += 1;
+        self.min = cmp::min(self.min, score);
+        self.max = cmp::max(self.max, score);
+        self.mean += (score as f64 - self.mean) / self.steps as f64;
+    }
+
+    /// Merges `next`, which covers the steps right after these, into this
+    /// bin.
+    fn merge(&mut self, next: &ScoreBin) {
+        let steps = self.steps + next.steps;
+        self.min = cmp::min(self.min, next.min);
+        self.max = cmp::max(self.max, next.max);
+        self.mean = (self.mean * self.steps as f64 + next.mean * next.steps as f64) / steps as f64;
+        self.steps = steps;
+    }
+}
+
+/// Summarizes `convergence`, as returned by `TraceRecorder::convergence`,
+/// in at most `points` bins with the same number of entries, give or take
+/// one.
+///
+/// The steps of each bin go up to the first step of the next one, so that
+/// bins of sampled traces cover the steps that weren't kept too.
+pub fn bin_convergence(convergence: &[(usize, usize)], points: usize) -> Vec<ScoreBin> {
+    let points = cmp::min(points, convergence.len());
+    let mut bins: Vec<ScoreBin> = Vec::with_capacity(points);
+    for i in 0..points {
+        let start = i * convergence.len() / points;
+        let end = (i + 1) * convergence.len() / points;
+        let scores = convergence[start..end].iter().map(|&(_, score)| score);
+        let step = convergence[start].0;
+        let bin = ScoreBin {
+            first_step: step,
+            steps: end - start,
+            min: scores.clone().min().unwrap(),
+            max: scores.clone().max().unwrap(),
+            mean: scores.sum::<usize>() as f64 / (end - start) as f64,
+        };
+        if let Some(previous) = bins.last_mut() {
+            previous.steps = step - previous.first_step;
+        }
+        bins.push(bin);
+    }
+    if let Some(last) = bins.last_mut() {
+        last.steps = convergence[convergence.len() - 1].0 + 1 - last.first_step;
+    }
+    bins
+}
+
+/// Records the steps of a search, keeping as many of them as its
+/// `TraceLevel` allows.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TraceRecorder {
+    level: TraceLevel,
+    steps: usize,
+    best_score: Option<usize>,
+    entries: Vec<TraceEntry>,
+    bins: Vec<ScoreBin>,
+    /// How many steps each bin covers with `TraceLevel::Binned`.
+    bin_width: usize,
+}
+
+impl TraceRecorder {
+    pub fn new(level: TraceLevel) -> Self {
+        TraceRecorder {
+            level,
+            steps: 0,
+            best_score: None,
+            entries: vec![],
+            bins: vec![],
+            bin_width: 1,
+        }
+    }
+
+    /// Accounts for a new step, keeping it if the trace level allows.
+    pub fn record(&mut self, queen_rows: &[usize], score: usize) {
+        let step = self.steps;
+        self.steps += 1;
+
+        let improves = self.best_score.is_none_or(|best| score < best);
+        if improves {
+            self.best_score = Some(score);
+        }
+
+        let keep = match self.level {
+            TraceLevel::Off => false,
+            TraceLevel::BestImprovementsOnly => improves,
+            TraceLevel::SampledEveryK(k) => k <= 1 || step.is_multiple_of(k),
+            TraceLevel::Full => true,
+            TraceLevel::Binned(points) => {
+                self.bin(step, score, cmp::max(points, 2));
+                false
+            }
+        };
+
+        if keep {
+            self.entries.push(TraceEntry {
+                step,
+                score,
+                queen_rows: queen_rows.to_vec().into_boxed_slice(),
+            });
+        }
+    }
+
+    /// The number of steps accounted for, whether they were kept or not.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// The steps kept, in order.
+    pub fn entries(&self) -> &[TraceEntry] {
+        &self.entries
+    }
+
+    /// The convergence history, as pairs of step and score.
+    pub fn convergence(&self) -> Vec<(usize, usize)> {
+        self.entries.iter().map(|e| (e.step, e.score)).collect()
+    }
+
+    /// The scores of every step, binned, with `TraceLevel::Binned`.
+    pub fn bins(&self) -> &[ScoreBin] {
+        &self.bins
+    }
+
+    /// Returns the scores in at most `points` bins, either merging the bins
+    /// of a binned trace or binning the steps kept otherwise.
+    pub fn binned(&self, points: usize) -> Vec<ScoreBin> {
+        if self.bins.is_empty() {
+            return bin_convergence(&self.convergence(), points);
+        }
+        let points = cmp::max(points, 1);
+        let per_bin = self.bins.len().div_ceil(points);
+        self.bins
+            .chunks(per_bin)
+            .map(|chunk| {
+                let mut bin = chunk[0];
+                for next in &chunk[1..] {
+                    bin.merge(next);
+                }
+                bin
+            })
+            .collect()
+    }
+
+    fn bin(&mut self, step: usize, score: usize, points: usize) {
+        if !step.is_multiple_of(self.bin_width) {
+            self.bins.last_mut().unwrap().add(score);
+            return;
+        }
+        if self.bins.len() == points {
+            // Halve the resolution by merging each pair of bins, which
+            // keeps them all the same width.
+            let bins = mem::take(&mut self.bins);
+            for pair in bins.chunks(2) {
+                let mut bin = pair[0];
+                if let Some(next) = pair.get(1) {
+                    bin.merge(next);
+                }
+                self.bins.push(bin);
+            }
+            self.bin_width *= 2;
+            if !step.is_multiple_of(self.bin_width) {
+                self.bins.last_mut().unwrap().add(score);
+                return;
+            }
+        }
+        self.bins.push(ScoreBin::new(step, score));
+    }
+}
+
+/// How often a strategy should report its progress to the callback.
+///
+/// Both limits apply at the same time. The default reports every step.
+#[derive(Clone, Debug, Default)]
+pub struct ReportConfig {
+    /// Report at most once every `report_every` steps. Zero and one mean
+    /// every step.
+    pub report_every: usize,
+    /// Report at most once per `report_interval` of wall-clock time. Zero
+    /// means no limit.
+    pub report_interval: Duration,
+}
+
+/// Keeps track of when the last report happened, in order to honor a
+/// `ReportConfig`.
+struct ReportThrottle {
+    config: ReportConfig,
+    steps: usize,
+    last_report: Option<Instant>,
+}
+
+impl ReportThrottle {
+    fn new(config: ReportConfig) -> Self {
+        ReportThrottle {
+            config,
+            steps: 0,
+            last_report: None,
+        }
+    }
+
+    /// Accounts for a new step, and returns whether it should be reported.
+    fn should_report(&mut self) -> bool {
+        let step = self.steps;
+        self.steps += 1;
+
+        if self.config.report_every > 1 && !step.is_multiple_of(self.config.report_every) {
+            return false;
+        }
+
+        if self.config.report_interval > Duration::from_secs(0) {
+            let now = Instant::now();
+            if let Some(last) = self.last_report {
+                if now.duration_since(last) < self.config.report_interval {
+                    return false;
+                }
+            }
+            self.last_report = Some(now);
+        }
+
+        true
+    }
+}
+
+/// Forwards the steps of a solve to a callback only as often as a
+/// `ReportConfig` allows, for front-ends that can't keep up with every step.
+pub struct ThrottledCallback<F> {
+    throttle: ReportThrottle,
+    callback: F,
+    /// Whether there were steps after the last one forwarded.
+    skipped: bool,
+}
+
+impl<F: FnMut(&[usize], usize)> ThrottledCallback<F> {
+    pub fn new(report: ReportConfig, callback: F) -> Self {
+        ThrottledCallback {
+            throttle: ReportThrottle::new(report),
+            callback,
+            skipped: false,
+        }
+    }
+
+    /// Accounts for a step of the solve, forwarding it if it's time to.
+    pub fn step(&mut self, queens: &[usize], score: usize) {
+        self.skipped = !self.throttle.should_report();
+        if !self.skipped {
+            (self.callback)(queens, score)
+        }
+    }
+
+    /// Forwards where the search ended once the solve is over, if the last
+    /// step wasn't: the final state of `solution` if it has one, or else
+    /// the solution itself. Nothing is kept of the steps skipped meanwhile.
+    pub fn finish(mut self, solution: &Solution) {
+        if self.skipped {
+            let (queens, score) = solution
+                .final_state()
+                .unwrap_or((solution.queen_rows(), solution.score()));
+            (self.callback)(queens, score)
+        }
+    }
+}
+
+/// What the swap-based strategies minimize. Both objectives are zero exactly
+/// on solutions, but shape the landscape differently.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Objective {
+    /// The number of pairs of queens that can hit each other.
+    AttackingPairs,
+    /// The number of queens that can hit at least another queen.
+    ConflictedQueens,
+}
+
+/// A generic data with most of the state needed for common algorithms to be
+/// solved.
+///
+/// This would be a base class in other OOP languages. Instead, we use
+/// composition in Rust.
+#[derive(Clone, Debug)]
+pub struct GenericChallengeState {
+    size: usize,
+    queen_rows: Vec<usize>,
+}
+
+impl GenericChallengeState {
+    pub fn new<R>(size: usize, rng: &mut R) -> Self
+    where
+        R: rand::Rng,
+    {
+        let mut positions_pending = (0..size).collect::<Vec<_>>();
+
+        let mut queen_rows = vec![0; size];
+
+        // Distribute the initial positions randomly.
+        while !positions_pending.is_empty() {
+            let chosen = rng.next_u32() as usize % positions_pending.len();
+
+            let position = positions_pending.remove(chosen);
+            queen_rows[positions_pending.len()] = position;
+        }
+
+        Self { size, queen_rows }
+    }
+
+    pub fn unpositioned(size: usize) -> Self {
+        Self {
+            size,
+            queen_rows: vec![],
+        }
+    }
+
+    pub fn random_queen_index<R>(&mut self, rng: &mut R) -> usize
+    where
+        R: rand::Rng,
+    {
+        rng.next_u32() as usize % self.queen_rows.len()
+    }
+
+    /// Returns two queens at random from the current ones, guaranteed to be
+    /// different.
+    pub fn get_two_random_queens<R>(&mut self, rng: &mut R) -> (usize, usize)
+    where
+        R: rand::Rng,
+    {
+        debug_assert!(self.queen_rows.len() > 1);
+
+        let queen_1 = self.random_queen_index(rng);
+        let mut queen_2 = self.random_queen_index(rng);
+        while queen_1 == queen_2 {
+            queen_2 = self.random_queen_index(rng);
+        }
+
+        (queen_1, queen_2)
+    }
+
+    /// Returns true if a queen positioned at `one` could be hit by a queen
+    /// positioned at `other`.
+    fn can_position(&self, p1: (usize, usize), p2: (usize, usize)) -> Result<(), PositionError> {
+        let (x1, y1) = p1;
+        let (x2, y2) = p2;
+
+        if x1 == x2 && y1 == y2 {
+            return Err(PositionError::Match);
+        }
+
+        if x1 == x2 {
+            return Err(PositionError::Column);
+        }
+
+        if y1 == y2 {
+            return Err(PositionError::Row);
+        }
+
+        let x_difference = (x1 as isize - x2 as isize).abs();
+        let y_difference = (y1 as isize - y2 as isize).abs();
+
+        if x_difference == y_difference {
+            return Err(PositionError::Diagonal);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of pairs of queens that can hit each other.
+    ///
+    /// Two queens in different columns can hit each other exactly when they
+    /// share a row or diagonal, so this counts the queens on each line
+    /// instead of checking every pair.
+    fn score(&self) -> usize {
+        ConflictCounters::new(self).attacking_pairs()
+    }
+
+    /// Returns the score of the current state according to `objective`.
+    fn evaluate(&self, objective: Objective) -> usize {
+        Self::evaluate_rows(self.size, &self.queen_rows, objective)
+    }
+
+    /// Like `evaluate`, for the rows of the queens of each column of a
+    /// board of `size`, so that they don't need to be copied into a state
+    /// first.
+    fn evaluate_rows(size: usize, queen_rows: &[usize], objective: Objective) -> usize {
+        let counters = ConflictCounters::for_rows(size, queen_rows);
+        match objective {
+            Objective::AttackingPairs => counters.attacking_pairs(),
+            Objective::ConflictedQueens => queen_rows
+                .iter()
+                .enumerate()
+                .filter(|&(column, &row)| counters.is_conflicted(column, row))
+                .count(),
+        }
+    }
+
+    /// Returns the number of queens that can hit each queen, by column.
+    pub fn attack_counts(&self) -> Vec<usize> {
+        ConflictCounters::new(self).attack_counts(&self.queen_rows)
+    }
+
+    fn queen_can_be_positioned_at(&self, pos: (usize, usize)) -> bool {
+        for (x, &y) in self.queen_rows.iter().enumerate() {
+            if self.can_position(pos, (x, y)).is_err() {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// How the swap-based strategies choose the next move to try.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MoveSelection {
+    /// Swap two random queens.
+    RandomSwap,
+    /// Apply a random number of random swaps at once, where the probability
+    /// of applying at least `k` swaps is `k^-exponent`, so that most moves are
+    /// single swaps but large jumps happen every now and then.
+    HeavyTailed { exponent: f32 },
+    /// Sample `candidates` random swaps and try the one that leads to the
+    /// least visited state so far.
+    NoveltyBiased { candidates: usize },
+}
+
+/// Picks moves according to a `MoveSelection`, keeping track of the visited
+/// states if needed.
+pub struct MoveSelector {
+    selection: MoveSelection,
+    /// The number of times each state has been visited, keyed by its board
+    /// hash.
+    visits: HashMap<u64, usize>,
+}
+
+impl MoveSelector {
+    pub fn new(selection: MoveSelection) -> Self {
+        MoveSelector {
+            selection,
+            visits: HashMap::new(),
+        }
+    }
+
+    /// Picks a move and applies it to `state`, returning the swaps applied,
+    /// in order.
+    pub fn apply_next<R>(
+        &mut self,
+        state: &mut GenericChallengeState,
+        rng: &mut R,
+    ) -> Vec<(usize, usize)>
+    where
+        R: rand::Rng,
+    {
+        let swaps = match self.selection {
+            MoveSelection::RandomSwap => vec![state.get_two_random_queens(rng)],
+            MoveSelection::HeavyTailed { exponent } => {
+                // Inverse transform sampling of a Pareto distribution.
+                let u = 1. - rng.next_f32();
+                let count = u.powf(-1. / exponent) as usize;
+                return Self::apply_random_swaps(state, rng, count);
+            }
+            MoveSelection::NoveltyBiased { candidates } => {
+                let mut best = None;
+                for _ in 0..candidates.max(1) {
+                    let (one, other) = state.get_two_random_queens(rng);
+                    state.queen_rows.swap(one, other);
+                    let visits = self.visit_count(&state.queen_rows);
+                    state.queen_rows.swap(one, other);
+
+                    if best.is_none_or(|(_, best_visits)| visits < best_visits) {
+                        best = Some(((one, other), visits));
+                    }
+                }
+                vec![best.unwrap().0]
+            }
+        };
+
+        for &(one, other) in &swaps {
+            state.queen_rows.swap(one, other);
+        }
+
+        swaps
+    }
+
+    /// Applies `count` random swaps to `state`, clamped so that there's at
+    /// least one and no more than queens, and returns them in order.
+    pub fn apply_random_swaps<R>(
+        state: &mut GenericChallengeState,
+        rng: &mut R,
+        count: usize,
+    ) -> Vec<(usize, usize)>
+    where
+        R: rand::Rng,
+    {
+        let swaps = (0..count.clamp(1, state.size))
+            .map(|_| state.get_two_random_queens(rng))
+            .collect::<Vec<_>>();
+        for &(one, other) in &swaps {
+            state.queen_rows.swap(one, other);
+        }
+        swaps
+    }
+
+    /// Undoes a move previously returned by `apply_next`.
+    pub fn undo(state: &mut GenericChallengeState, swaps: &[(usize, usize)]) {
+        for &(one, other) in swaps.iter().rev() {
+            state.queen_rows.swap(one, other);
+        }
+    }
+
+    /// Records that the search has moved to the state `queen_rows`.
+    pub fn record_visit(&mut self, queen_rows: &[usize]) {
+        if let MoveSelection::NoveltyBiased { .. } = self.selection {
+            *self.visits.entry(board_hash(queen_rows)).or_insert(0) += 1;
+        }
+    }
+
+    fn visit_count(&self, queen_rows: &[usize]) -> usize {
+        self.visits
+            .get(&board_hash(queen_rows))
+            .cloned()
+            .unwrap_or(0)
+    }
+}
+
+/// The number of queens on each row and diagonal of a board, which allows
+/// computing in constant time how the number of attacking pairs changes when
+/// moving a queen.
+pub struct ConflictCounters {
+    size: usize,
+    rows: Vec<usize>,
+    diagonals: Vec<usize>,
+    anti_diagonals: Vec<usize>,
+}
+
+impl ConflictCounters {
+    pub fn new(state: &GenericChallengeState) -> Self {
+        Self::for_rows(state.size, &state.queen_rows)
+    }
+
+    /// Like `new`, for the rows of the queens of each column of a board of
+    /// `size`.
+    fn for_rows(size: usize, queen_rows: &[usize]) -> Self {
+        let lines = 2 * size.max(1) - 1;
+        let mut counters = ConflictCounters {
+            size,
+            rows: vec![0; size],
+            diagonals: vec![0; lines],
+            anti_diagonals: vec![0; lines],
+        };
+        for (column, &row) in queen_rows.iter().enumerate() {
+            counters.add(column, row);
+        }
+        counters
+    }
+
+    /// Recounts the queens of `state`, reusing the buffers if possible.
+    pub fn reset(&mut self, state: &GenericChallengeState) {
+        self.recount(state.size, &state.queen_rows)
+    }
+
+    /// Like `reset`, for the rows of the queens of each column of a board
+    /// of `size`.
+    fn recount(&mut self, size: usize, queen_rows: &[usize]) {
+        let lines = 2 * size.max(1) - 1;
+        self.size = size;
+        self.rows.clear();
+        self.rows.resize(size, 0);
+        self.diagonals.clear();
+        self.diagonals.resize(lines, 0);
+        self.anti_diagonals.clear();
+        self.anti_diagonals.resize(lines, 0);
+        for (column, &row) in queen_rows.iter().enumerate() {
+            self.add(column, row);
+        }
+    }
+
+    fn add(&mut self, column: usize, row: usize) {
+        self.rows[row] += 1;
+        self.diagonals[column + row] += 1;
+        self.anti_diagonals[column + self.size - 1 - row] += 1;
+    }
+
+    fn remove(&mut self, column: usize, row: usize) {
+        self.rows[row] -= 1;
+        self.diagonals[column + row] -= 1;
+        self.anti_diagonals[column + self.size - 1 - row] -= 1;
+    }
+
+    /// Returns how the number of attacking pairs changes when moving the
+    /// queen at `column` from `from` to `to`.
+    pub fn move_delta(&self, column: usize, from: usize, to: usize) -> isize {
+        if from == to {
+            return 0;
+        }
+
+        // Two different squares of a column share no row nor diagonal, so
+        // the lines the queen leaves and enters are all different.
+        let lost = self.rows[from]
+            + self.diagonals[column + from]
+            + self.anti_diagonals[column + self.size - 1 - from]
+            - 3;
+        let gained = self.rows[to]
+            + self.diagonals[column + to]
+            + self.anti_diagonals[column + self.size - 1 - to];
+        gained as isize - lost as isize
+    }
+
+    /// Accounts for moving the queen at `column` from `from` to `to`.
+    pub fn move_queen(&mut self, column: usize, from: usize, to: usize) {
+        self.remove(column, from);
+        self.add(column, to);
+    }
+
+    /// Returns the number of pairs of queens sharing a line, which is the
+    /// number of attacking pairs.
+    pub fn attacking_pairs(&self) -> usize {
+        let pairs = |&count: &usize| count * count.saturating_sub(1) / 2;
+        self.rows.iter().map(pairs).sum::<usize>()
+            + self.diagonals.iter().map(pairs).sum::<usize>()
+            + self.anti_diagonals.iter().map(pairs).sum::<usize>()
+    }
+
+    /// Returns the number of queens that can hit the queen at `column` and
+    /// `row`, which is as cheap to keep up to date as the counters are.
+    pub fn attacks(&self, column: usize, row: usize) -> usize {
+        self.rows[row]
+            + self.diagonals[column + row]
+            + self.anti_diagonals[column + self.size - 1 - row]
+            - 3
+    }
+
+    /// Returns the number of queens that can hit each of the queens of
+    /// `queen_rows`, which the counters must be up to date with.
+    pub fn attack_counts(&self, queen_rows: &[usize]) -> Vec<usize> {
+        queen_rows
+            .iter()
+            .enumerate()
+            .map(|(column, &row)| self.attacks(column, row))
+            .collect()
+    }
+
+    /// Returns the columns of the queens of `queen_rows` that share a line
+    /// with another one, which the counters must be up to date with.
+    pub fn conflicted_queens(&self, queen_rows: &[usize]) -> Vec<usize> {
+        (0..queen_rows.len())
+            .filter(|&column| self.is_conflicted(column, queen_rows[column]))
+            .collect()
+    }
+
+    /// Returns whether the queen at `column` and `row` shares a line with
+    /// another one.
+    pub fn is_conflicted(&self, column: usize, row: usize) -> bool {
+        self.rows[row] > 1
+            || self.diagonals[column + row] > 1
+            || self.anti_diagonals[column + self.size - 1 - row] > 1
+    }
+
+    /// Returns how the number of attacking pairs changes when swapping the
+    /// rows of the queens at columns `one` and `other` of `queen_rows`,
+    /// which the counters must be up to date with.
+    pub fn score_delta_for_swap(&self, queen_rows: &[usize], one: usize, other: usize) -> isize {
+        self.swap_delta(one, queen_rows[one], other, queen_rows[other])
+    }
+
+    /// Like `score_delta_for_swap`, given the rows of both queens before
+    /// the swap.
+    fn swap_delta(&self, one: usize, row_one: usize, other: usize, row_other: usize) -> isize {
+        if one == other || row_one == row_other {
+            return 0;
+        }
+
+        // Both rows keep a queen, so only the diagonals change.
+        let diagonal = |column: usize, row: usize| column + row;
+        let anti_diagonal = |column: usize, row: usize| column + self.size - 1 - row;
+        let changes = |line: &dyn Fn(usize, usize) -> usize| {
+            [
+                (line(one, row_one), -1),
+                (line(other, row_other), -1),
+                (line(one, row_other), 1),
+                (line(other, row_one), 1),
+            ]
+        };
+        Self::lines_delta(&self.diagonals, &changes(&diagonal))
+            + Self::lines_delta(&self.anti_diagonals, &changes(&anti_diagonal))
+    }
+
+    /// Returns how the pairs of queens sharing a line change when adding
+    /// the given number of queens to each line, which may be repeated.
+    fn lines_delta(counts: &[usize], changes: &[(usize, isize); 4]) -> isize {
+        let pairs = |count: isize| count * (count - 1) / 2;
+        let mut delta = 0;
+        for (i, &(line, _)) in changes.iter().enumerate() {
+            if changes[..i].iter().any(|&(seen, _)| seen == line) {
+                continue;
+            }
+            let change = changes
+                .iter()
+                .filter(|&&(other, _)| other == line)
+                .map(|&(_, change)| change)
+                .sum::<isize>();
+            let count = counts[line] as isize;
+            delta += pairs(count + change) - pairs(count);
+        }
+        delta
+    }
+
+    /// Returns the number of attacking pairs of `state` right after
+    /// applying `swaps` to it, given its score before, which the counters
+    /// must be up to date with. Only single swaps are scored incrementally.
+    pub fn score_after_swaps(
+        &self,
+        state: &GenericChallengeState,
+        swaps: &[(usize, usize)],
+        score: usize,
+    ) -> usize {
+        match *swaps {
+            [(one, other)] => {
+                let rows = &state.queen_rows;
+                (score as isize + self.swap_delta(one, rows[other], other, rows[one])) as usize
+            }
+            _ => state.score(),
+        }
+    }
+
+    /// Returns how the number of attacking pairs changes with `applied`,
+    /// which the counters must be up to date with the placement before.
+    ///
+    /// The counters follow the move along the way, and are restored
+    /// before returning.
+    pub fn score_delta_for_move(&mut self, applied: &QueenMove) -> isize {
+        let mut delta = 0;
+        for &(column, from, to) in applied.relocations() {
+            delta += self.move_delta(column, from, to);
+            self.move_queen(column, from, to);
+        }
+        for &(column, from, to) in applied.relocations().iter().rev() {
+            self.move_queen(column, to, from);
+        }
+        delta
+    }
+
+    /// Accounts for `applied` having been applied.
+    pub fn record_move(&mut self, applied: &QueenMove) {
+        for &(column, from, to) in applied.relocations() {
+            self.move_queen(column, from, to);
+        }
+    }
+
+    /// Accounts for `swaps` having been applied to `state`.
+    pub fn record_swaps(&mut self, state: &GenericChallengeState, swaps: &[(usize, usize)]) {
+        match *swaps {
+            [(one, other)] => {
+                let rows = &state.queen_rows;
+                self.move_queen(one, rows[other], rows[one]);
+                self.move_queen(other, rows[one], rows[other]);
+            }
+            _ => self.reset(state),
+        }
+    }
+}
+
+/// Buffers that strategies can reuse across solves of boards of the same
+/// size, so that solving many of them doesn't allocate over and over.
+///
+/// Get one back from `NQueensStrategy::solve_in_context`, and pass it to
+/// `NQueensStrategy::new_in_context` for the next solve.
+pub struct SolverContext {
+    dimension: usize,
+    /// Spare placements, for successor pools and populations.
+    states: Vec<GenericChallengeState>,
+    /// Spare row and diagonal counters.
+    counters: Option<ConflictCounters>,
+}
+
+impl SolverContext {
+    pub fn new(dimension: usize) -> Self {
+        SolverContext {
+            dimension,
+            states: vec![],
+            counters: None,
+        }
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Keeps `states` around to be reused.
+    fn recycle<I>(&mut self, states: I)
+    where
+        I: IntoIterator<Item = GenericChallengeState>,
+    {
+        self.states.extend(states)
+    }
+}
+
+/// How to choose between the current state and a move that leads to a state
+/// with the same score, or between moves that lead to the same best score.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TieBreaking {
+    /// Always keep the current state, or the first of the moves found.
+    KeepIncumbent,
+    /// Pick one of them at random.
+    Random,
+    /// Take the move if it doesn't touch any queen moved in the last `size`
+    /// accepted moves, or the move whose queens moved the longest ago.
+    LeastRecentlyMoved,
+    /// Take the move if it moves one of the queens with the most conflicts,
+    /// or the move of the most conflicted queens.
+    MostConflicted,
+}
+
+/// Applies a `TieBreaking` policy, keeping track of when each queen last
+/// moved if needed.
+pub struct TieBreaker {
+    policy: TieBreaking,
+    /// The number of accepted moves so far.
+    step: usize,
+    /// The step each queen last moved at, if any.
+    last_moved: Vec<Option<usize>>,
+}
+
+impl TieBreaker {
+    pub fn new(policy: TieBreaking, size: usize) -> Self {
+        TieBreaker {
+            policy,
+            step: 0,
+            last_moved: vec![None; size],
+        }
+    }
+
+    /// Records that the search accepted `applied`.
+    pub fn record_move(&mut self, applied: &QueenMove) {
+        self.record_moved(applied.columns());
+    }
+
+    /// Records that the search accepted a move of the queens at `columns`.
+    pub fn record_moved<I>(&mut self, columns: I)
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        self.step += 1;
+        for column in columns {
+            self.last_moved[column] = Some(self.step);
+        }
+    }
+
+    /// Returns whether to take `applied`, which has already been applied to
+    /// `state` and leads to a state with the same score as before, where
+    /// `counters`, if any, are up to date with the state before the move.
+    pub fn prefers_move<R>(
+        &self,
+        state: &mut GenericChallengeState,
+        applied: &QueenMove,
+        counters: Option<&ConflictCounters>,
+        rng: &mut R,
+    ) -> bool
+    where
+        R: rand::Rng,
+    {
+        let mut moved = applied.columns();
+        match self.policy {
+            TieBreaking::KeepIncumbent => false,
+            TieBreaking::Random => rng.next_f32() < 0.5,
+            TieBreaking::LeastRecentlyMoved => moved.all(|queen| {
+                self.last_moved[queen].is_none_or(|step| self.step - step >= self.last_moved.len())
+            }),
+            TieBreaking::MostConflicted => {
+                // Look at the conflicts before the move.
+                applied.undo(state);
+                let attacks = match counters {
+                    Some(counters) => counters.attack_counts(&state.queen_rows),
+                    None => state.attack_counts(),
+                };
+                let most = attacks.iter().max();
+                let prefers = moved.any(|queen| Some(&attacks[queen]) == most);
+                applied.apply(state);
+                prefers
+            }
+        }
+    }
+
+    /// Returns the index of the move to take out of `count` candidates,
+    /// moves from `queen_rows` that all lead to the same score, where
+    /// `moved` returns the columns of the queens each of them moves, and
+    /// `counters` are up to date with `queen_rows`. The candidates the
+    /// policy can't tell apart are picked from at random.
+    pub fn choose<F, I, R>(
+        &self,
+        count: usize,
+        moved: F,
+        queen_rows: &[usize],
+        counters: &ConflictCounters,
+        rng: &mut R,
+    ) -> usize
+    where
+        F: Fn(usize) -> I,
+        I: Iterator<Item = usize>,
+        R: rand::Rng,
+    {
+        // Higher is better.
+        let rank = |candidate: usize| match self.policy {
+            TieBreaking::LeastRecentlyMoved => {
+                let last_moved = moved(candidate)
+                    .filter_map(|queen| self.last_moved[queen])
+                    .max();
+                usize::MAX - last_moved.unwrap_or(0)
+            }
+            _ => moved(candidate)
+                .map(|queen| counters.attacks(queen, queen_rows[queen]))
+                .max()
+                .unwrap_or(0),
+        };
+
+        match self.policy {
+            _ if count == 1 => 0,
+            TieBreaking::KeepIncumbent => 0,
+            TieBreaking::Random => rng.gen_range(0, count),
+            TieBreaking::LeastRecentlyMoved | TieBreaking::MostConflicted => {
+                let ranks = (0..count).map(rank).collect::<Vec<_>>();
+                let best = ranks.iter().cloned().max().unwrap_or(0);
+                let best = (0..count).filter(|&i| ranks[i] == best).collect::<Vec<_>>();
+                best[rng.gen_range(0, best.len())]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts the attacking pairs of `state` by checking every pair.
+    fn attacking_pairs(state: &GenericChallengeState) -> usize {
+        let rows = &state.queen_rows;
+        (0..rows.len())
+            .map(|i| {
+                ((i + 1)..rows.len())
+                    .filter(|&j| state.can_position((i, rows[i]), (j, rows[j])).is_err())
+                    .count()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn counters_score_swaps_incrementally() {
+        use rand::Rng;
+
+        let mut rng = stream_rng(42, RngStream::Initializer);
+        for size in 1..12 {
+            // Not necessarily permutations, to have several queens per row.
+            let rows = (0..size).map(|_| rng.gen_range(0, size)).collect();
+            let mut state = GenericChallengeState {
+                size,
+                queen_rows: rows,
+            };
+            let score = attacking_pairs(&state);
+            assert_eq!(state.score(), score);
+
+            let counters = ConflictCounters::new(&state);
+            for one in 0..size {
+                for other in 0..size {
+                    let delta = counters.score_delta_for_swap(&state.queen_rows, one, other);
+                    state.queen_rows.swap(one, other);
+                    assert_eq!(score as isize + delta, attacking_pairs(&state) as isize);
+                    state.queen_rows.swap(one, other);
+                }
+            }
+
+            // The attacks on each queen stay right as queens move.
+            let mut counters = counters;
+            for _ in 0..size {
+                let column = rng.gen_range(0, size);
+                let to = rng.gen_range(0, size);
+                counters.move_queen(column, state.queen_rows[column], to);
+                state.queen_rows[column] = to;
+
+                let rows = &state.queen_rows;
+                let attacks = (0..size)
+                    .map(|i| {
+                        (0..size)
+                            .filter(|&j| {
+                                i != j && state.can_position((i, rows[i]), (j, rows[j])).is_err()
+                            })
+                            .count()
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(counters.attack_counts(rows), attacks);
+                assert_eq!(state.attack_counts(), attacks);
+                let conflicted = (0..size).filter(|&i| attacks[i] > 0).collect::<Vec<_>>();
+                assert_eq!(counters.conflicted_queens(rows), conflicted);
+            }
+        }
+    }
+
+    #[test]
+    fn solutions_render_and_validate() {
+        let solution = Solution::new(vec![1, 3, 0, 2], 0);
+        assert!(solution.is_valid());
+        assert_eq!(solution.dimension(), 4);
+        assert_eq!(
+            solution.positions().collect::<Vec<_>>(),
+            vec![(0, 1), (1, 3), (2, 0), (3, 2)]
+        );
+        assert_eq!(solution.to_string(), ". . Q .\nQ . . .\n. . . Q\n. Q . .\n");
+
+        assert!(!Solution::new(vec![0, 1, 3, 2], 0).is_valid());
+        let partial = Solution::partial(vec![1, 3], 0, 4);
+        assert!(!partial.is_valid());
+        assert_eq!(partial.to_string().lines().count(), 4);
+        assert_eq!(
+            partial.to_json(),
+            "{\"n\":4,\"positions\":[1,3],\"score\":0,\"iterations\":0,\"algorithm\":null,\
+             \"solved\":false,\"elapsed_ms\":0,\"seed\":0}"
+        );
+    }
+
+    #[test]
+    fn solves_with_the_defaults() {
+        fn check<T: NQueensStrategy>(n: usize) -> Solution
+        where
+            T::Config: Default,
+        {
+            assert!(T::validate(n, &T::Config::default()).is_ok());
+            let solution = T::solve_default(n);
+            assert_eq!(solution.dimension(), n, "{}", T::info().name);
+            solution
+        }
+
+        // The complete strategies must find a solution; the rest may stop
+        // at a local optimum.
+        assert!(check::<ConstraintPropagation>(12).is_valid());
+        assert!(check::<ConstructiveBeamSearch>(12).is_valid());
+        assert!(check::<LimitedDiscrepancySearch>(12).is_valid());
+        for n in 4..=20 {
+            assert!(check::<ConstructiveBeamSearch>(n).is_valid(), "{}", n);
+        }
+
+        check::<CuckooSearch>(12);
+        check::<Decomposition>(12);
+        check::<GeneticAlgorithm>(12);
+        check::<HillClimbing>(12);
+        check::<Hybrid>(12);
+        check::<IslandModel>(12);
+        check::<LocalBeamSearch>(12);
+        check::<MinConflicts>(12);
+        check::<Pbil>(12);
+        check::<SimulatedAnnealing>(12);
+        check::<TabuSearch>(12);
+
+        assert!(HillClimbing::solve_default(8).is_valid());
+        assert!(MinConflicts::solve_default(64).is_valid());
+    }
+
+    #[test]
+    fn reports_population_diversity() {
+        let members: &[&[usize]] = &[&[0, 1, 2], &[0, 1, 2], &[0, 2, 1], &[1, 0, 2]];
+        let report = DiversityReport::new(4, members.iter().cloned());
+        assert_eq!(report.generation, 4);
+        assert_eq!(report.distinct, 3);
+        assert!((report.mean_distance - 11. / 6.).abs() < 1e-9);
+        assert!((report.column_entropy[1] - 1.5).abs() < 1e-9);
+
+        let converged = DiversityReport::new(0, vec![&[3, 1, 0, 2][..]; 5]);
+        assert_eq!(converged.distinct, 1);
+        assert_eq!(converged.mean_distance, 0.);
+        assert_eq!(converged.mean_entropy(), 0.);
+    }
+
+    #[test]
+    fn tie_breakers_choose_among_moves() {
+        let mut rng = stream_rng(5, RngStream::Selection);
+        let mut state = GenericChallengeState {
+            size: 6,
+            queen_rows: vec![0, 0, 3, 1, 5, 2],
+        };
+        let counters = ConflictCounters::new(&state);
+        let attacks = counters.attack_counts(&state.queen_rows);
+        let most = (0..6).max_by_key(|&column| attacks[column]).unwrap();
+        let least = (0..6).min_by_key(|&column| attacks[column]).unwrap();
+        assert!(attacks[least] < attacks[most]);
+        let candidates = [[least], [most]];
+        let moved = |i: usize| candidates[i].iter().cloned();
+
+        let breaker = |policy| TieBreaker::new(policy, 6);
+        let rows = &state.queen_rows[..];
+        assert_eq!(
+            breaker(TieBreaking::KeepIncumbent).choose(2, moved, rows, &counters, &mut rng),
+            0
+        );
+        assert_eq!(
+            breaker(TieBreaking::MostConflicted).choose(2, moved, rows, &counters, &mut rng),
+            1
+        );
+
+        let mut recent = breaker(TieBreaking::LeastRecentlyMoved);
+        recent.record_moved(Some(0));
+        recent.record_moved(Some(1));
+        let candidates = [1, 0, 2];
+        let moved = |i: usize| Some(candidates[i]).into_iter();
+        assert_eq!(recent.choose(3, moved, rows, &counters, &mut rng), 2);
+        assert_eq!(recent.choose(2, moved, rows, &counters, &mut rng), 1);
+
+        // The counters give the same answer as counting the attacks anew.
+        let conflicted = breaker(TieBreaking::MostConflicted);
+        for column in 0..6 {
+            let from = state.queen_rows[column];
+            let applied = QueenMove::new(vec![(column, from, (from + 1) % 6)]);
+            applied.apply(&mut state);
+            assert_eq!(
+                conflicted.prefers_move(&mut state, &applied, Some(&counters), &mut rng),
+                conflicted.prefers_move(&mut state, &applied, None, &mut rng)
+            );
+            applied.undo(&mut state);
+        }
+    }
+
+    #[test]
+    fn streams_are_independent() {
+        use rand::Rng;
+
+        let draw = |rng: &mut StreamRng| (0..10).map(|_| rng.next_u32()).collect::<Vec<_>>();
+        let mut quiet = ComponentRngs::new(9);
+        let mut busy = ComponentRngs::new(9);
+        for _ in 0..1000 {
+            busy.neighborhood.next_u32();
+        }
+
+        assert_ne!(draw(&mut quiet.neighborhood), draw(&mut busy.neighborhood));
+        assert_eq!(draw(&mut quiet.acceptance), draw(&mut busy.acceptance));
+        assert_eq!(draw(&mut quiet.mutation), draw(&mut busy.mutation));
+    }
+
+    #[test]
+    fn counts_attacking_pairs_anywhere() {
+        assert_eq!(super::attacking_pairs(&[]), 0);
+        assert_eq!(super::attacking_pairs(&[(0, 1), (1, 3), (2, 0), (3, 2)]), 0);
+        // Two in a column, and the second of them on a diagonal with a
+        // third.
+        assert_eq!(super::attacking_pairs(&[(0, 0), (0, 2), (1, 3)]), 2);
+        assert_eq!(super::attacking_pairs(&[(2, 2), (2, 2)]), 4);
+
+        let solution = HillClimbing::solve_default(8);
+        let positions = solution.positions().collect::<Vec<_>>();
+        assert_eq!(super::attacking_pairs(&positions), solution.score());
+    }
+
+    #[test]
+    fn bins_long_traces() {
+        let score = |step: usize| 1000 - step / 3;
+        let mut binned = TraceRecorder::new(TraceLevel::Binned(10));
+        let mut full = TraceRecorder::new(TraceLevel::Full);
+        for step in 0..1000 {
+            binned.record(&[], score(step));
+            full.record(&[], score(step));
+        }
+
+        let bins = binned.bins();
+        assert!(binned.entries().is_empty());
+        assert!(bins.len() > 5 && bins.len() <= 10);
+        assert_eq!(bins.iter().map(|b| b.steps).sum::<usize>(), 1000);
+        for (bin, next) in bins.iter().zip(&bins[1..]) {
+            assert_eq!(bin.first_step + bin.steps, next.first_step);
+        }
+        let last = bins.last().unwrap();
+        assert_eq!(bins[0].max, 1000);
+        assert_eq!(last.min, score(999));
+        assert!(bins
+            .iter()
+            .all(|b| b.min as f64 <= b.mean && b.mean <= b.max as f64));
+
+        let merged = binned.binned(3);
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged.iter().map(|b| b.steps).sum::<usize>(), 1000);
+
+        let bins = full.binned(4);
+        assert_eq!(bins.len(), 4);
+        assert_eq!(
+            bins[1],
+            ScoreBin {
+                first_step: 250,
+                steps: 250,
+                min: score(499),
+                max: score(250),
+                mean: (250..500).map(|step| score(step) as f64).sum::<f64>() / 250.,
+            }
+        );
+    }
+
+    #[test]
+    fn throttled_callback_reports_every_n_steps() {
+        let config = || ConstraintPropagationConfig {
+            seed: Some(7),
+            ..Default::default()
+        };
+        let mut steps = 0usize;
+        ConstraintPropagation::new(8, config()).solve_with_callback(|_, _| steps += 1);
+
+        let mut reports = 0;
+        let mut last = vec![];
+        let report = ReportConfig {
+            report_every: 10,
+            ..Default::default()
+        };
+        let solution = ConstraintPropagation::new(8, config()).solve_with_throttled_callback(
+            report,
+            |queens, _| {
+                reports += 1;
+                last = queens.to_vec();
+            },
+        );
+
+        // Every tenth step, and where the search ended if the last step
+        // isn't among them.
+        let last_reported = (steps - 1).is_multiple_of(10);
+        assert_eq!(reports, steps.div_ceil(10) + !last_reported as usize);
+        assert_eq!(last, solution.queen_rows());
+    }
+}
@@ -15,768 +15,603 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-extern crate rand;
-
-pub enum PositionError {
-    /// A queen is already there.
-    Match,
-    /// Queen in the same column.
-    Column,
-    /// Queen in the same row.
-    Row,
-    /// Queen in the same diagonal.
-    Diagonal,
+//! The C entry points used by the web frontend, as a thin layer over the
+//! library.
+
+extern crate local_search_algorithms;
+extern crate serde_json;
+
+use local_search_algorithms::resources::CountingAllocator;
+use local_search_algorithms::solvers::*;
+use local_search_algorithms::*;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+/// The caps on every solve, as set by `set_resource_limits`, where zero
+/// means no cap.
+static MAX_ITERATIONS: AtomicUsize = AtomicUsize::new(0);
+static MAX_MILLISECONDS: AtomicUsize = AtomicUsize::new(0);
+static MAX_MEMORY: AtomicUsize = AtomicUsize::new(0);
+
+/// How often the callbacks get the steps of a solve, as set by
+/// `set_report_rate`.
+static REPORT_EVERY: AtomicUsize = AtomicUsize::new(0);
+static REPORT_MILLISECONDS: AtomicUsize = AtomicUsize::new(0);
+
+/// The coordinate convention of the queens going to and from the frontend,
+/// as set by `set_coordinate_convention`.
+static COORDINATE_ORIGIN: AtomicUsize = AtomicUsize::new(0);
+static COORDINATE_ORDER: AtomicUsize = AtomicUsize::new(0);
+
+/// Set to cancel the current solve, when it goes over the memory cap or its
+/// callback asks to.
+static CANCELLATION: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+/// Set when the callback of the current solve asked to cancel it.
+static CANCELLED_BY_CALLBACK: AtomicBool = AtomicBool::new(false);
+
+/// Keeps count of the memory in use, to enforce the memory cap.
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Caps the iterations, the milliseconds, and the bytes of memory that any
+/// later solve can take, so that parameters coming from user input can't
+/// wedge the page. Zero means no cap.
+///
+/// A solve that reaches a cap stores no queens, and returns the status code
+/// of `Error::BudgetExhausted`.
+#[no_mangle]
+pub fn set_resource_limits(max_iterations: usize, max_milliseconds: usize, max_memory: usize) {
+    MAX_ITERATIONS.store(max_iterations, Ordering::Relaxed);
+    MAX_MILLISECONDS.store(max_milliseconds, Ordering::Relaxed);
+    MAX_MEMORY.store(max_memory, Ordering::Relaxed);
 }
 
-pub struct Solution {
-    queen_rows: Box<[usize]>,
-    score: usize,
+/// Makes the callback of any later solve get at most one step of every
+/// `every`, and at most one step per `milliseconds`, as in `ReportConfig`,
+/// plus where the search ended. Zero means no limit, which is the default.
+///
+/// The steps of the events count the ones the callback gets, and it can
+/// only cancel the solve on those.
+#[no_mangle]
+pub fn set_report_rate(every: usize, milliseconds: usize) {
+    REPORT_EVERY.store(every, Ordering::Relaxed);
+    REPORT_MILLISECONDS.store(milliseconds, Ordering::Relaxed);
 }
 
-impl Solution {
-    pub fn new(queen_rows: Vec<usize>, score: usize) -> Self {
-        Solution {
-            queen_rows: queen_rows.into_boxed_slice(),
-            score: score,
-        }
+/// Returns the report rate set by `set_report_rate`.
+fn report_config() -> ReportConfig {
+    ReportConfig {
+        report_every: REPORT_EVERY.load(Ordering::Relaxed),
+        report_interval: Duration::from_millis(REPORT_MILLISECONDS.load(Ordering::Relaxed) as u64),
     }
 }
 
-/// A problem-solving strategy for the n-queens problem.
-pub trait NQueensStrategy: Sized {
-    /// Extra parameters that may be given to the challenge to configure the
-    /// solution.
-    type Config;
-
-    /// Creates a new solvable instance of this challenge.
-    fn new(dimension: usize, config: Self::Config) -> Self;
-
-    /// Solves the challenge for returning a vector with `n` positions,
-    /// representing the column at which the queen is positioned for each index.
-    fn solve(self) -> Solution {
-        self.solve_with_callback(|_, _| {})
+/// Sets how the squares of the queens going to and from the frontend are
+/// numbered from then on, in the solutions, the callbacks and the boards
+/// to verify.
+///
+/// `origin` is the corner of column and row zero: 0 for the top left, 1 for
+/// the top right, 2 for the bottom left and 3 for the bottom right. `order`
+/// is 0 for `x + y * n`, and 1 for `y + x * n`. The default is zero for
+/// both.
+///
+/// Returns zero, or the status code of `Error::InvalidConfig` if either is
+/// unknown.
+#[no_mangle]
+pub fn set_coordinate_convention(origin: usize, order: usize) -> usize {
+    if origin > 3 || order > 1 {
+        return status_code(&Error::InvalidConfig("unknown coordinate convention"));
     }
-
-    /// Solves the challenge for returning a vector with `n` positions,
-    /// representing the column at which the queen is positioned for each index,
-    /// and additionally runs `callback` on each step the positions changed,
-    /// with the queen positions and the current score so far.
-    fn solve_with_callback<F>(self, callback: F) -> Solution
-    where
-        F: FnMut(&[usize], usize);
+    COORDINATE_ORIGIN.store(origin, Ordering::Relaxed);
+    COORDINATE_ORDER.store(order, Ordering::Relaxed);
+    0
 }
 
-pub mod constraint_propagation {
-    use super::*;
-
-    /// A constraint-propagation solution to the n-queens challenge.
-    pub struct ConstraintPropagation {
-        base: GenericChallengeState,
+/// Returns the coordinate convention set by `set_coordinate_convention`.
+fn convention() -> CoordinateConvention {
+    CoordinateConvention {
+        origin: match COORDINATE_ORIGIN.load(Ordering::Relaxed) {
+            0 => Corner::TopLeft,
+            1 => Corner::TopRight,
+            2 => Corner::BottomLeft,
+            _ => Corner::BottomRight,
+        },
+        order: match COORDINATE_ORDER.load(Ordering::Relaxed) {
+            0 => Order::RowMajor,
+            _ => Order::ColumnMajor,
+        },
     }
+}
 
-    impl ConstraintPropagation {
-        /// Tries to position the next queen at row `row`, or any of the
-        /// following columns.
-        fn position_next_queen_from_row(&self, mut row: usize) -> Result<usize, ()> {
-            while row < self.base.size {
-                if self
-                    .base
-                    .queen_can_be_positioned_at((self.base.queen_rows.len(), row))
-                {
-                    return Ok(row);
-                }
-                row += 1;
-            }
+/// Returns the budget of a solve about to start, within the caps.
+fn capped_budget() -> SolveBudget {
+    let cap = |limit: &AtomicUsize| match limit.load(Ordering::Relaxed) {
+        0 => None,
+        max => Some(max),
+    };
 
-            Err(())
-        }
+    let cancellation = CANCELLATION.get_or_init(Default::default);
+    cancellation.store(false, Ordering::Relaxed);
+    CANCELLED_BY_CALLBACK.store(false, Ordering::Relaxed);
+    match cap(&MAX_MEMORY) {
+        Some(max) => CountingAllocator::limit_memory(max, cancellation),
+        None => CountingAllocator::remove_memory_limit(),
     }
 
-    impl NQueensStrategy for ConstraintPropagation {
-        /// No configuration needed.
-        type Config = ();
-
-        fn new(size: usize, _: ()) -> Self {
-            ConstraintPropagation {
-                base: GenericChallengeState::unpositioned(size),
-            }
-        }
-
-        fn solve_with_callback<F>(mut self, mut callback: F) -> Solution
-        where
-            F: FnMut(&[usize], usize),
-        {
-            let mut start_search_at = 0;
-            while self.base.queen_rows.len() != self.base.size {
-                match self.position_next_queen_from_row(start_search_at) {
-                    Ok(pos) => {
-                        self.base.queen_rows.push(pos);
-                        callback(&self.base.queen_rows, 0);
-                        start_search_at = 0;
-                    }
-                    Err(()) => {
-                        match self.base.queen_rows.pop() {
-                            Some(row) => {
-                                callback(&self.base.queen_rows, 0);
-                                start_search_at = row + 1;
-                            }
-                            // Not a single solution.
-                            None => break,
-                        }
-                    }
-                }
-            }
-
-            let score = self.base.score();
-            Solution::new(self.base.queen_rows, score)
-        }
+    SolveBudget {
+        max_wall_time: cap(&MAX_MILLISECONDS).map(|ms| Duration::from_millis(ms as u64)),
+        max_iterations: cap(&MAX_ITERATIONS),
+        cancellation: Some(cancellation.clone()),
+        time_steps: false,
     }
+}
 
-    #[cfg(test)]
-    mod tests {
-        use super::*;
-
-        const DIM: usize = 8;
-        fn pos(x: usize, y: usize) -> (usize, usize) {
-            (x, y)
-        }
-
-        #[test]
-        fn are_reachable_test() {
-            let challenge = ConstraintPropagation::new(DIM, ());
-
-            assert!(challenge.can_position(pos(0, 0), pos(0, 0)).is_err());
-            assert!(challenge.can_position(pos(0, 1), pos(0, 0)).is_err());
-            assert!(challenge.can_position(pos(1, 0), pos(0, 0)).is_err());
-            assert!(challenge.can_position(pos(1, 1), pos(5, 5)).is_err());
-            assert!(challenge.can_position(pos(3, 2), pos(2, 3)).is_err());
-        }
-
-        #[test]
-        fn finds_eight_queens_solution() {
-            let challenge = ConstraintPropagation::new(DIM, ());
-            assert_eq!(challenge.solve().score, 0);
+/// Returns the status code reported for `error`, counting down from
+/// `usize::MAX` so that they can't be confused with scores.
+pub fn status_code(error: &Error) -> usize {
+    usize::MAX
+        - match *error {
+            Error::InvalidConfig(..) => 0,
+            Error::UnsupportedDimension(..) => 1,
+            Error::RngUnavailable => 2,
+            Error::Cancelled => 3,
+            Error::BudgetExhausted => 4,
+            Error::Infeasible(..) => 5,
         }
+}
 
-        #[test]
-        fn finds_twelve_queens_solution() {
-            let challenge = ConstraintPropagation::new(12, ());
-            assert_eq!(challenge.solve().score, 0);
+/// Forwards the steps of a solve of `n` queens with `strategy` to
+/// `callback`, if any, at the report rate, as trace events with the queen
+/// rows in the coordinate convention, and cancels the solve if the callback
+/// returns non-zero.
+fn forward(
+    n: usize,
+    strategy: &StrategyInfo,
+    callback: Option<JSCallback>,
+) -> ThrottledCallback<impl FnMut(&[usize], usize)> {
+    let mut encoder = TraceEventEncoder::new(strategy);
+    let callback = convention().callback(n, move |queens, score| {
+        let cb = match callback {
+            Some(cb) => cb,
+            None => return,
+        };
+        let event = serde_json::to_string(&encoder.encode(queens, score)).unwrap();
+        if cb(event.as_ptr(), event.len()) != 0 {
+            CANCELLED_BY_CALLBACK.store(true, Ordering::Relaxed);
+            if let Some(cancellation) = CANCELLATION.get() {
+                cancellation.store(true, Ordering::Relaxed);
+            }
         }
+    });
+    ThrottledCallback::new(report_config(), callback)
+}
 
-        #[test]
-        fn finds_fifteen_queens_solution() {
-            let challenge = ConstraintPropagation::new(15, ());
-            assert_eq!(challenge.solve().score, 0);
-        }
+/// Returns the seed to run with for `seed` as given by the frontend, where
+/// anything but a non-negative integer means a random one.
+fn replay_seed(seed: f64) -> Option<u64> {
+    if seed >= 0. && seed.fract() == 0. {
+        Some(seed as u64)
+    } else {
+        None
     }
 }
 
-/// A generic data with most of the state needed for common algorithms to be
-/// solved.
-///
-/// This would be a base class in other OOP languages. Instead, we use
-/// composition in Rust.
-#[derive(Clone, Debug)]
-pub struct GenericChallengeState {
-    size: usize,
-    queen_rows: Vec<usize>,
+/// Solves with `T`, storing the queens in `result_storage` and returning the
+/// score, or storing no queens and returning the status code of the error.
+pub fn solve<T: NQueensStrategy>(
+    n: usize,
+    result_storage: *mut usize,
+    callback: Option<JSCallback>,
+    config: T::Config,
+) -> usize {
+    let result = check_dimension(n).and_then(|()| {
+        let mut callback = forward(n, T::info(), callback);
+        let solution = T::try_new(n, config)?
+            .try_solve_with_callback(|queens, score| callback.step(queens, score))?;
+        callback.finish(&solution);
+        Ok(solution)
+    });
+    store(n, result_storage, result)
 }
 
-impl GenericChallengeState {
-    pub fn new<R>(size: usize, rng: &mut R) -> Self
-    where
-        R: rand::Rng,
-    {
-        let mut positions_pending = (0..size).into_iter().collect::<Vec<_>>();
-
-        let mut queen_rows = vec![0; size];
-
-        // Distribute the initial positions randomly.
-        while !positions_pending.is_empty() {
-            let chosen = rng.next_u32() as usize % positions_pending.len();
-
-            let position = positions_pending.remove(chosen);
-            queen_rows[positions_pending.len()] = position;
-        }
-
-        Self {
-            size: size,
-            queen_rows: queen_rows,
-        }
-    }
-
-    pub fn unpositioned(size: usize) -> Self {
-        Self {
-            size: size,
-            queen_rows: vec![],
-        }
+/// The positions are stored as indices of the squares, which must fit.
+fn check_dimension(n: usize) -> Result<(), Error> {
+    match n.checked_mul(n) {
+        Some(_) => Ok(()),
+        None => Err(Error::UnsupportedDimension(n)),
     }
+}
 
-    pub fn random_queen_index<R>(&mut self, rng: &mut R) -> usize
-    where
-        R: rand::Rng,
-    {
-        rng.next_u32() as usize % self.queen_rows.len()
-    }
-
-    /// Returns two queens at random from the current ones, guaranteed to be
-    /// different.
-    pub fn get_two_random_queens<R>(&mut self, rng: &mut R) -> (usize, usize)
-    where
-        R: rand::Rng,
-    {
-        debug_assert!(self.queen_rows.len() > 1);
-
-        let queen_1 = self.random_queen_index(rng);
-        let mut queen_2 = self.random_queen_index(rng);
-        while queen_1 == queen_2 {
-            queen_2 = self.random_queen_index(rng);
-        }
-
-        (queen_1, queen_2)
-    }
-
-    /// Returns true if a queen positioned at `one` could be hit by a queen
-    /// positioned at `other`.
-    fn can_position(&self, p1: (usize, usize), p2: (usize, usize)) -> Result<(), PositionError> {
-        let (x1, y1) = p1;
-        let (x2, y2) = p2;
-
-        if x1 == x2 && y1 == y2 {
-            return Err(PositionError::Match);
-        }
+/// Stores the queens of `result` in `result_storage` and returns the score,
+/// or stores no queens and returns the status code of the error, or of
+/// `Error::BudgetExhausted` if the solve was stopped by the caps.
+///
+/// If the callback cancelled the solve, the best queens found so far are
+/// stored anyway, and the status code of `Error::Cancelled` is returned.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+fn store(n: usize, result_storage: *mut usize, result: Result<Solution, Error>) -> usize {
+    use std::slice;
 
-        if x1 == x2 {
-            return Err(PositionError::Column);
-        }
+    CountingAllocator::remove_memory_limit();
+    let cancelled = CANCELLED_BY_CALLBACK.swap(false, Ordering::Relaxed);
+    let result = result.and_then(|solution| match solution.status() {
+        SolveStatus::Cancelled if cancelled => Ok(solution),
+        SolveStatus::Timeout | SolveStatus::Cancelled => Err(Error::BudgetExhausted),
+        _ => Ok(solution),
+    });
 
-        if y1 == y2 {
-            return Err(PositionError::Row);
+    let solution = match result {
+        Ok(solution) => solution,
+        Err(error) => {
+            unsafe { *result_storage = 0 };
+            return status_code(&error);
         }
+    };
 
-        let x_difference = (x1 as isize - x2 as isize).abs();
-        let y_difference = (y1 as isize - y2 as isize).abs();
-
-        if x_difference == y_difference {
-            return Err(PositionError::Diagonal);
-        }
+    let storage = unsafe { slice::from_raw_parts_mut(result_storage, n + 1) };
+    storage[0] = solution.queen_rows().len();
 
-        Ok(())
+    // Both are in the coordinate convention, but the callback gets the row
+    // of the queen in each column, while this is the index of the square of
+    // each queen.
+    for (i, index) in solution.indices(convention()).into_iter().enumerate() {
+        storage[i + 1] = index;
     }
 
-    fn can_hit(&self, p1: (usize, usize), p2: (usize, usize)) -> bool {
-        self.can_position(p1, p2).is_err()
+    match solution.status() {
+        SolveStatus::Cancelled => status_code(&Error::Cancelled),
+        _ => solution.score(),
     }
+}
 
-    /// Returns the number of pairs of queens that can hit each other.
-    fn score(&self) -> usize {
-        let rows = &self.queen_rows;
-
-        let mut score = 0;
+/// Called on each step with a `TraceEvent` as UTF-8 JSON, of `len` bytes.
+/// Returning non-zero cancels the solve.
+pub type JSCallback = extern "C" fn(event: *const u8, len: usize) -> usize;
 
-        for i in 0..rows.len() {
-            for j in (i + 1)..rows.len() {
-                if self.can_hit((i, rows[i]), (j, rows[j])) {
-                    score += 1;
-                }
-            }
+/// Registers every strategy exposed to the frontend, in one place each.
+///
+/// Each entry defines the `solve_n_queens_*` export of the strategy, taking
+/// the given arguments after the common ones, and the name it goes by in
+/// `solve_n_queens_preset`.
+///
+/// The seed is one of the common arguments, given like the one of
+/// `solve_n_queens_preset`, and the configuration gets it as an
+/// `Option<u64>`. The strategies that make no random choices ignore it.
+macro_rules! strategies {
+    ($(
+        $name:ident: $strategy:ty {
+            export $export:ident(
+                $n:ident,
+                $seed:ident
+                $(, $arg:ident: $arg_ty:ty)*
+            ) => $config:expr,
         }
-
-        score
-    }
-
-    fn queen_can_be_positioned_at(&self, pos: (usize, usize)) -> bool {
-        for (x, &y) in self.queen_rows.iter().enumerate() {
-            if self.can_position(pos, (x, y)).is_err() {
-                return false;
+    )*) => {
+        $(
+            #[no_mangle]
+            #[allow(clippy::too_many_arguments)]
+            pub fn $export(
+                $n: usize,
+                result_storage: *mut usize,
+                cb: Option<JSCallback>,
+                $seed: f64,
+                $($arg: $arg_ty),*
+            ) -> usize {
+                let $seed = replay_seed($seed);
+                solve::<$strategy>($n, result_storage, cb, $config)
+            }
+        )*
+
+        /// Returns the library name of the strategy the frontend calls
+        /// `name`, if any.
+        fn strategy_name(name: &str) -> Option<&'static str> {
+            match name {
+                $(stringify!($name) => Some(<$strategy as NQueensStrategy>::info().name),)*
+                _ => None,
             }
         }
-
-        true
-    }
+    };
 }
 
-pub mod hill_climbing {
-    use super::*;
-
-    pub struct HillClimbing {
-        base: GenericChallengeState,
-        rng: rand::OsRng,
+strategies! {
+    constraint_propagation: ConstraintPropagation {
+        export solve_n_queens_constraint_propagation(n, seed) => ConstraintPropagationConfig {
+            randomize_rows: false,
+            column_order: constraint_propagation::ColumnOrder::LeftToRight,
+            symmetry_breaking: constraint_propagation::SymmetryBreaking::None,
+            restarts: 0,
+            restart_backtracks: 0,
+            max_nogood_size: 0,
+            seed,
+            budget: capped_budget(),
+        },
     }
 
-    impl NQueensStrategy for HillClimbing {
-        /// No configuration needed.
-        type Config = ();
-
-        fn new(size: usize, _: ()) -> Self {
-            let mut rng = rand::OsRng::new().unwrap();
-            let base = GenericChallengeState::new(size, &mut rng);
-            Self {
-                base: base,
-                rng: rng,
+    constructive_beam_search: ConstructiveBeamSearch {
+        export solve_n_queens_constructive_beam_search(n, seed, beam_width: usize) => {
+            ConstructiveBeamSearchConfig {
+                beam_width,
+                prune_conflicts: true,
+                max_evaluations: None,
+                seed,
+                budget: capped_budget(),
             }
-        }
-
-        fn solve_with_callback<F>(mut self, mut callback: F) -> Solution
-        where
-            F: FnMut(&[usize], usize),
-        {
-            const MAX_ITERATIONS_WITHOUT_IMPROVEMENT: usize = 1000;
-
-            let mut current_score = self.base.score();
-            let mut iterations_without_improvement = 0;
-
-            callback(&self.base.queen_rows, current_score);
-
-            while current_score != 0
-                && iterations_without_improvement <= MAX_ITERATIONS_WITHOUT_IMPROVEMENT
-            {
-                let (queen_1, queen_2) = self.base.get_two_random_queens(&mut self.rng);
-
-                // Swap them, and check score.
-                self.base.queen_rows.swap(queen_1, queen_2);
-
-                let score = self.base.score();
-                if score < current_score {
-                    // Yay, an improvement! Let's leave the stuff as-is :)
-                    iterations_without_improvement = 0;
-                    current_score = score;
-                    callback(&self.base.queen_rows, current_score)
-                } else {
-                    // Didn't improve, let's just get back to where we were.
-                    iterations_without_improvement += 1;
-                    self.base.queen_rows.swap(queen_1, queen_2);
-                }
-            }
-
-            Solution::new(self.base.queen_rows, current_score)
-        }
-    }
-}
-
-pub mod simulated_annealing {
-    use super::*;
-
-    pub struct SimulatedAnnealingConfig {
-        pub starting_temperature: f32,
-        pub cooling_factor: f32,
+        },
     }
 
-    pub struct SimulatedAnnealing {
-        base: GenericChallengeState,
-        rng: rand::OsRng,
-        temperature: f32,
-        cooling_factor: f32,
+    hill_climbing: HillClimbing {
+        export solve_n_queens_hill_climbing(n, seed) => HillClimbingConfig {
+            restarts: 0,
+            exhaust_restarts: false,
+            move_selection: MoveSelection::RandomSwap,
+            operator: None,
+            random_walk_probability: 0.,
+            tie_breaking: TieBreaking::KeepIncumbent,
+            objective: Objective::AttackingPairs,
+            max_evaluations: None,
+            seed,
+            budget: capped_budget(),
+        },
     }
 
-    impl SimulatedAnnealing {
-        fn should_accept(&mut self, old_score: usize, new_score: usize) -> bool {
-            use rand::Rng;
-            debug_assert!(old_score <= new_score);
-            if self.temperature <= 1.0 {
-                return false;
+    limited_discrepancy_search: LimitedDiscrepancySearch {
+        export solve_n_queens_limited_discrepancy_search(n, _seed, max_discrepancies: usize) => {
+            LimitedDiscrepancySearchConfig {
+                max_discrepancies,
+                budget: capped_budget(),
             }
-
-            ((new_score - old_score) as f32 / self.temperature).exp() > self.rng.next_f32()
-        }
+        },
     }
 
-    impl NQueensStrategy for SimulatedAnnealing {
-        type Config = SimulatedAnnealingConfig;
-
-        fn new(size: usize, config: Self::Config) -> Self {
-            let mut rng = rand::OsRng::new().unwrap();
-            let base = GenericChallengeState::new(size, &mut rng);
-            SimulatedAnnealing {
-                base: base,
-                rng: rng,
-                temperature: config.starting_temperature,
-                cooling_factor: config.cooling_factor,
-            }
-        }
-
-        fn solve_with_callback<F>(mut self, mut callback: F) -> Solution
-        where
-            F: FnMut(&[usize], usize),
-        {
-            const MAX_ITERATIONS_WITHOUT_IMPROVEMENT: usize = 1000;
-
-            let mut score = self.base.score();
-            callback(&self.base.queen_rows, score);
-
-            let mut iterations_without_improvement = 0;
-            while score != 0
-                && (self.temperature >= 1.
-                    || iterations_without_improvement <= MAX_ITERATIONS_WITHOUT_IMPROVEMENT)
-            {
-                let (queen_1, queen_2) = self.base.get_two_random_queens(&mut self.rng);
-
-                self.base.queen_rows.swap(queen_1, queen_2);
-
-                let new_score = self.base.score();
-                if new_score < score || self.should_accept(score, new_score) {
-                    score = new_score;
-                    // This is fiddly, but this only really matters when the
-                    // system is already cooled down, so it's fine.
-                    iterations_without_improvement = 0;
-                    callback(&self.base.queen_rows, score);
-                } else {
-                    iterations_without_improvement += 1;
-                    // Back to where we were.
-                    self.base.queen_rows.swap(queen_1, queen_2);
-                }
-
-                // Cool the system down.
-                self.temperature *= 1. - self.cooling_factor;
-            }
-
-            Solution::new(self.base.queen_rows, score)
-        }
+    simulated_annealing: SimulatedAnnealing {
+        export solve_n_queens_simulated_annealing(
+            n,
+            seed,
+            initial_temperature: f32,
+            cooling_factor: f32
+        ) => SimulatedAnnealingConfig {
+            starting_temperature: initial_temperature,
+            cooling_schedule: Box::new(simulated_annealing::GeometricCooling {
+                factor: cooling_factor,
+            }),
+            acceptance: None,
+            reheating: None,
+            restarts: 0,
+            exhaust_restarts: false,
+            restart_policy: simulated_annealing::RestartPolicy::Fresh,
+            move_selection: MoveSelection::RandomSwap,
+            tie_breaking: None,
+            initial_acceptance: None,
+            objective: Objective::AttackingPairs,
+            neighborhood: simulated_annealing::Neighborhood::Swaps,
+            operator: None,
+            max_evaluations: None,
+            seed,
+            budget: capped_budget(),
+        },
     }
-}
-
-pub mod local_beam_search {
-    use super::*;
 
-    pub struct LocalBeamSearchConfig {
-        pub state_count: usize,
+    local_beam_search: LocalBeamSearch {
+        export solve_n_queens_local_beam_search(n, seed, state_count: usize) => LocalBeamSearchConfig {
+            state_count,
+            schedule: local_beam_search::BeamWidthSchedule::Fixed,
+            min_distance: 0,
+            reseeding: None,
+            objective: Objective::AttackingPairs,
+            snapshot_interval: 0,
+            max_evaluations: None,
+            seed,
+            budget: capped_budget(),
+        },
     }
 
-    pub struct LocalBeamSearch {
-        size: usize,
-        state_count: usize,
-        rng: rand::OsRng,
+    genetic: GeneticAlgorithm {
+        export solve_n_queens_genetic(
+            n,
+            seed,
+            generation_size: usize,
+            elitism_percent: f32,
+            crossover_probability: f32,
+            mutation_probability: f32,
+            generation_count: usize
+        ) => GeneticAlgorithmConfig {
+            generation_size,
+            elitism: elitism_percent,
+            generation_gap: 1.,
+            selection: Box::new(genetic_algorithm::RouletteSelection),
+            crossover: genetic_algorithm::Crossover::PrefixSwap,
+            crossover_probability,
+            mutation_probability,
+            adaptive_mutation: None,
+            generation_count,
+            immigrant_fraction: 0.,
+            hall_of_fame: None,
+            reseed: None,
+            min_diversity: 0.,
+            max_stagnant_generations: 0,
+            track_genealogy: false,
+            track_diversity: false,
+            constraints: None,
+            objective: Objective::AttackingPairs,
+            snapshot_interval: 0,
+            max_evaluations: None,
+            seed,
+            budget: capped_budget(),
+        },
     }
 
-    impl NQueensStrategy for LocalBeamSearch {
-        type Config = LocalBeamSearchConfig;
-
-        fn new(size: usize, config: Self::Config) -> Self {
-            Self {
-                size: size,
-                state_count: config.state_count,
-                rng: rand::OsRng::new().unwrap(),
-            }
-        }
-
-        fn solve_with_callback<F>(mut self, mut callback: F) -> Solution
-        where
-            F: FnMut(&[usize], usize),
-        {
-            use std::mem;
-
-            let mut states = Vec::with_capacity(self.state_count);
-            for _ in 0..self.state_count {
-                states.push(GenericChallengeState::new(self.size, &mut self.rng))
+    pbil: Pbil {
+        export solve_n_queens_pbil(n, seed, samples: usize, learning_rate: f32, iterations: usize) => {
+            PbilConfig {
+                samples,
+                learning_rate,
+                iterations,
+                seed,
+                budget: capped_budget(),
+                ..presets::Preset::Fast.pbil(n)
             }
+        },
+    }
 
-            loop {
-                let mut is_first = true;
-
-                // First, see if one of the states if a solution. If so, stop.
-                for state in &states {
-                    let score = state.score();
-
-                    // FIXME(emilio): We only visualize the first state,
-                    // which is... not great.
-                    if is_first || score == 0 {
-                        callback(&state.queen_rows, score);
-                    }
-
-                    if score == 0 {
-                        return Solution::new(state.queen_rows.clone(), 0);
-                    }
-
-                    is_first = false;
-                }
-
-                // Find all the successors to the current states, and push them.
-                let mut successors = Vec::with_capacity(states.len() * self.size);
-
-                for state in &states {
-                    for i in 0..self.size {
-                        for j in i + 1..self.size {
-                            let mut successor = state.clone();
-                            successor.queen_rows.swap(i, j);
-                            successors.push(successor);
-                        }
-                    }
-                }
-
-                // TODO(emilio): This recomputes the score a few times more than
-                // needed, but oh well.
-                successors.sort_by_key(|s| s.score());
-                mem::swap(&mut successors, &mut states);
-                states.truncate(self.state_count);
+    cuckoo_search: CuckooSearch {
+        export solve_n_queens_cuckoo_search(n, seed, nests: usize, generation_count: usize) => {
+            CuckooSearchConfig {
+                nests,
+                generation_count,
+                seed,
+                budget: capped_budget(),
+                ..presets::Preset::Fast.cuckoo_search(n)
             }
-        }
+        },
     }
-}
 
-pub mod genetic_algorithm {
-    use super::*;
-
-    #[derive(Debug)]
-    pub struct GeneticAlgorithmConfig {
-        pub generation_size: usize,
-        pub elitism: f32,
-        pub crossover_probability: f32,
-        pub mutation_probability: f32,
-        pub generation_count: usize,
+    min_conflicts: MinConflicts {
+        export solve_n_queens_min_conflicts(
+            n,
+            seed,
+            max_steps: usize,
+            restarts: usize,
+            random_walk_probability: f32
+        ) => MinConflictsConfig {
+            max_steps,
+            restarts,
+            random_walk_probability,
+            tie_breaking: TieBreaking::Random,
+            seed,
+            budget: capped_budget(),
+        },
     }
 
-    pub struct GeneticAlgorithm {
-        size: usize,
-        rng: rand::OsRng,
-        config: GeneticAlgorithmConfig,
+    decomposition: Decomposition {
+        export solve_n_queens_decomposition(n, seed, block_size: usize) => DecompositionConfig {
+            block_size,
+            seed,
+            budget: capped_budget(),
+            ..DecompositionConfig::default()
+        },
     }
 
-    impl GeneticAlgorithm {
-        fn maybe_mutate(&mut self, state: &mut GenericChallengeState) {
-            use rand::Rng;
-            for _ in 0..self.size {
-                if self.rng.next_f32() < self.config.mutation_probability {
-                    let (one, other) = state.get_two_random_queens(&mut self.rng);
-                    state.queen_rows.swap(one, other);
-                }
-            }
-        }
+    island_model: IslandModel {
+        export solve_n_queens_island_model(
+            n,
+            seed,
+            islands: usize,
+            migration_interval: usize,
+            migration_size: usize
+        ) => IslandModelConfig {
+            islands,
+            migration_interval,
+            migration_size,
+            seed,
+            budget: capped_budget(),
+            ..IslandModelConfig::default()
+        },
     }
 
-    impl NQueensStrategy for GeneticAlgorithm {
-        type Config = GeneticAlgorithmConfig;
-
-        fn new(size: usize, config: Self::Config) -> Self {
-            Self {
-                size: size,
-                rng: rand::OsRng::new().unwrap(),
-                config: config,
-            }
-        }
-
-        fn solve_with_callback<F>(mut self, mut callback: F) -> Solution
-        where
-            F: FnMut(&[usize], usize),
-        {
-            use rand::Rng;
-            use std::{cmp, mem};
-
-            if self.config.generation_size == 0 {
-                return Solution::new(vec![], 0);
-            }
-
-            let mut current_generation = Vec::with_capacity(self.config.generation_size);
-            for _ in 0..self.config.generation_size {
-                current_generation.push(GenericChallengeState::new(self.size, &mut self.rng))
-            }
-
-            let mut pending_generations = self.config.generation_count;
-            while pending_generations > 0 {
-                let mut is_first = true;
-                let mut max_score = 0;
-                let mut scores = Vec::with_capacity(self.config.generation_size);
-
-                current_generation.sort_by_key(|s| s.score());
-                for state in &current_generation {
-                    // TODO(emilio): Same problem as before, need a better way
-                    // to visualize it.
-                    let score = state.score();
-                    if is_first || score == 0 {
-                        callback(&state.queen_rows, score);
-                    }
-
-                    if score == 0 {
-                        return Solution::new(state.queen_rows.clone(), 0);
-                    }
-
-                    max_score = cmp::max(max_score, score);
-                    scores.push(score);
-                    is_first = false;
-                }
-
-                let mut total_inverse_score = 0;
-                for score in &scores {
-                    total_inverse_score += max_score - *score
-                }
-                let mut next_generation = Vec::with_capacity(self.config.generation_size);
-
-                let percent_per_individual = 1.0f32 / current_generation.len() as f32;
-                let mut percent_so_far = 0.0f32;
-                let mut non_elite_generation_start = 0;
-                while percent_so_far < self.config.elitism {
-                    percent_so_far += percent_per_individual;
-                    next_generation.push(current_generation[non_elite_generation_start].clone());
-                    non_elite_generation_start += 1;
-                }
-
-                // Lower score is better, so make a probability of:
-                // (max_score - score / total).
-                for _ in non_elite_generation_start..self.config.generation_size {
-                    let p = self.rng.next_f32();
-                    let mut previous = 0.;
-                    let mut chosen_one = false;
-                    for (i, score) in scores.iter().enumerate().rev() {
-                        let probability = if total_inverse_score == 0 {
-                            previous + percent_per_individual as f32
-                        } else {
-                            previous + (max_score - *score) as f32 / total_inverse_score as f32
-                        };
-                        if p < probability {
-                            next_generation.push(current_generation[i].clone());
-                            chosen_one = true;
-                            break;
-                        }
-                        previous = probability;
-                    }
-
-                    assert!(chosen_one);
-                }
-
-                // Now do the mix.
-                // TODO(emilio): We always leave the last untouched, which is
-                // fishy.
-                for i in non_elite_generation_start..next_generation.len() - 1 {
-                    let crossover = self.rng.next_f32() < self.config.crossover_probability;
-                    if crossover {
-                        let solution_split = self.rng.next_u32() as usize % self.size;
-                        let (left, right) = next_generation.split_at_mut(i + 1);
-                        for j in 0..solution_split {
-                            mem::swap(&mut right[0].queen_rows[j], &mut left[i].queen_rows[j]);
-                        }
-                    }
-                }
-
-                if next_generation.len() - non_elite_generation_start >= 2 {
-                    // Cross-over last with first.
-                    let crossover = self.rng.next_f32() < self.config.crossover_probability;
-                    if crossover {
-                        let solution_split = self.rng.next_u32() as usize % self.size;
-
-                        // Just so the borrow checker is fine.
-                        let (left, right) =
-                            next_generation.split_at_mut(non_elite_generation_start + 1);
-                        let right_index = right.len() - 1;
-                        let left_index = left.len() - 1;
-                        for i in 0..solution_split {
-                            mem::swap(
-                                &mut left[left_index].queen_rows[i],
-                                &mut right[right_index].queen_rows[i],
-                            );
-                        }
-                    }
-                }
-
-                for item in &mut next_generation[non_elite_generation_start..] {
-                    self.maybe_mutate(item);
-                }
-
-                current_generation = next_generation;
-
-                pending_generations -= 1;
-            }
-
-            current_generation.sort_by_key(|s| s.score());
-            let best_solution = current_generation.into_iter().next().unwrap();
-            let score = best_solution.score();
-            return Solution::new(best_solution.queen_rows, score);
-        }
+    hybrid: Hybrid {
+        export solve_n_queens_hybrid(n, seed, placed_queens: usize) => HybridConfig {
+            placed_queens,
+            seed,
+            budget: capped_budget(),
+            ..presets::Preset::Fast.hybrid(n)
+        },
     }
-}
 
-pub fn solve<T: NQueensStrategy>(
-    n: usize,
-    result_storage: *mut usize,
-    callback: Option<JSCallback>,
-    config: T::Config,
-) -> usize {
-    use std::slice;
-
-    let challenge = T::new(n, config);
-    let solution = challenge.solve_with_callback(|queens, score| {
-        if let Some(cb) = callback {
-            cb(queens.as_ptr(), queens.len(), score)
-        }
-    });
-
-    let storage = unsafe { slice::from_raw_parts_mut(result_storage, n + 1) };
-    storage[0] = solution.queen_rows.len();
-
-    // TODO(emilio): This is inconsistent with the data passed to the callback.
-    for (x, y) in solution.queen_rows.into_iter().enumerate() {
-        storage[x + 1] = x + y * n;
+    tabu: TabuSearch {
+        export solve_n_queens_tabu(n, seed, tenure: usize, max_steps: usize) => TabuSearchConfig {
+            tenure,
+            max_steps,
+            tie_breaking: TieBreaking::Random,
+            operator: None,
+            seed,
+            budget: capped_budget(),
+        },
     }
-
-    solution.score
-}
-
-pub type JSCallback = extern "C" fn(positions: *const usize, len: usize, score: usize);
-
-#[no_mangle]
-pub fn solve_n_queens_constraint_propagation(
-    n: usize,
-    result_storage: *mut usize,
-    cb: Option<JSCallback>,
-) -> usize {
-    solve::<constraint_propagation::ConstraintPropagation>(n, result_storage, cb, ())
 }
 
+/// Checks the queens at `positions`, stored as indices in the coordinate
+/// convention like the queens of a solve, on a board of `n` queens, in any
+/// columns.
+///
+/// Returns twice the number of pairs of queens that can hit each other,
+/// plus one if the queens are a solution: `n` queens, none of them
+/// attacked. Returns the status code of `Error::InvalidConfig` if there are
+/// two queens on the same square, or one off the board.
 #[no_mangle]
-pub fn solve_n_queens_hill_climbing(
-    n: usize,
-    result_storage: *mut usize,
-    cb: Option<JSCallback>,
-) -> usize {
-    solve::<hill_climbing::HillClimbing>(n, result_storage, cb, ())
-}
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub fn verify_n_queens(positions: *const usize, len: usize, n: usize) -> usize {
+    use std::collections::HashSet;
+    use std::slice;
 
-#[no_mangle]
-pub fn solve_n_queens_simulated_annealing(
-    n: usize,
-    result_storage: *mut usize,
-    cb: Option<JSCallback>,
-    initial_temperature: f32,
-    cooling_factor: f32,
-) -> usize {
-    let config = simulated_annealing::SimulatedAnnealingConfig {
-        starting_temperature: initial_temperature,
-        cooling_factor: cooling_factor,
+    if let Err(error) = check_dimension(n) {
+        return status_code(&error);
+    }
+    let positions = match len {
+        0 => &[][..],
+        _ => unsafe { slice::from_raw_parts(positions, len) },
     };
-    solve::<simulated_annealing::SimulatedAnnealing>(n, result_storage, cb, config)
-}
 
-#[no_mangle]
-pub fn solve_n_queens_local_beam_search(
-    n: usize,
-    result_storage: *mut usize,
-    cb: Option<JSCallback>,
-    state_count: usize,
-) -> usize {
-    let config = local_beam_search::LocalBeamSearchConfig {
-        state_count: state_count,
-    };
-    solve::<local_beam_search::LocalBeamSearch>(n, result_storage, cb, config)
+    let mut squares = HashSet::new();
+    if positions
+        .iter()
+        .any(|&position| position >= n * n || !squares.insert(position))
+    {
+        return status_code(&Error::InvalidConfig(
+            "queens off the board or on the same square",
+        ));
+    }
+
+    let convention = convention();
+    let positions = positions
+        .iter()
+        .map(|&position| convention.square(n, position))
+        .collect::<Vec<_>>();
+    let conflicts = attacking_pairs(&positions);
+    let valid = conflicts == 0 && len == n;
+    2 * conflicts + valid as usize
 }
 
+/// Solves with the preset named `preset` for the strategy named `algorithm`,
+/// both given as NUL-terminated strings, using the same names as the
+/// `solve_n_queens_*` functions.
+///
+/// If `seed` is a non-negative integer, the run is seeded with it, so that it
+/// can be replayed. Otherwise a random seed is used.
+///
+/// Returns the status code of `Error::InvalidConfig`, and stores no queens,
+/// if either name is unknown.
 #[no_mangle]
-pub fn solve_n_queens_genetic(
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub fn solve_n_queens_preset(
     n: usize,
     result_storage: *mut usize,
     cb: Option<JSCallback>,
-    generation_size: usize,
-    elitism_percent: f32,
-    crossover_probability: f32,
-    mutation_probability: f32,
-    generation_count: usize,
+    algorithm: *const std::os::raw::c_char,
+    preset: *const std::os::raw::c_char,
+    seed: f64,
 ) -> usize {
-    let config = genetic_algorithm::GeneticAlgorithmConfig {
-        generation_size: generation_size,
-        elitism: elitism_percent,
-        crossover_probability: crossover_probability,
-        mutation_probability: mutation_probability,
-        generation_count: generation_count,
+    use std::ffi::CStr;
+
+    let algorithm = unsafe { CStr::from_ptr(algorithm) }.to_str().ok();
+    let preset = unsafe { CStr::from_ptr(preset) }
+        .to_str()
+        .ok()
+        .and_then(presets::Preset::from_name);
+
+    let seed = replay_seed(seed);
+    let result = match (algorithm.and_then(strategy_name), preset) {
+        (Some(strategy), Some(preset)) => check_dimension(n).and_then(|()| {
+            let mut callback = forward(n, info::strategy(strategy).unwrap(), cb);
+            let solution = preset.solve(strategy, n, seed, capped_budget(), |queens, score| {
+                callback.step(queens, score)
+            })?;
+            callback.finish(&solution);
+            Ok(solution)
+        }),
+        _ => Err(Error::InvalidConfig("unknown strategy or preset")),
     };
-    solve::<genetic_algorithm::GeneticAlgorithm>(n, result_storage, cb, config)
+    store(n, result_storage, result)
 }
 
 fn main() {
@@ -0,0 +1,322 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Pluggable kinds of moves for the strategies that move queens around one
+//! move at a time: hill climbing, simulated annealing and tabu search.
+//!
+//! Each of them sets `operator` in its config to draw moves from a
+//! `NeighborhoodOp` instead of its built-in moves.
+
+use std::fmt;
+use *;
+
+/// A move on a placement, as the queens it relocates, in order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct QueenMove {
+    /// The column of each queen moved, and the rows it moves from and to.
+    relocations: Vec<(usize, usize, usize)>,
+}
+
+impl QueenMove {
+    pub fn new(relocations: Vec<(usize, usize, usize)>) -> Self {
+        QueenMove { relocations }
+    }
+
+    /// Returns the move made of `swaps` of the rows of two queens, which
+    /// were just applied to `state`, in order.
+    pub fn from_swaps(state: &mut GenericChallengeState, swaps: &[(usize, usize)]) -> Self {
+        MoveSelector::undo(state, swaps);
+        let mut relocations = Vec::with_capacity(2 * swaps.len());
+        for &(one, other) in swaps {
+            let (row_one, row_other) = (state.queen_rows[one], state.queen_rows[other]);
+            relocations.push((one, row_one, row_other));
+            relocations.push((other, row_other, row_one));
+            state.queen_rows.swap(one, other);
+        }
+        QueenMove { relocations }
+    }
+
+    pub fn relocations(&self) -> &[(usize, usize, usize)] {
+        &self.relocations
+    }
+
+    /// Returns the columns of the queens moved, which may be repeated.
+    pub fn columns<'a>(&'a self) -> impl Iterator<Item = usize> + 'a {
+        self.relocations.iter().map(|&(column, _, _)| column)
+    }
+
+    pub fn apply(&self, state: &mut GenericChallengeState) {
+        for &(column, _, to) in &self.relocations {
+            state.queen_rows[column] = to;
+        }
+    }
+
+    /// Undoes the move on `state`, to which it was just applied.
+    pub fn undo(&self, state: &mut GenericChallengeState) {
+        for &(column, from, _) in self.relocations.iter().rev() {
+            state.queen_rows[column] = from;
+        }
+    }
+}
+
+/// A kind of move, which picks random moves for the stochastic strategies,
+/// and lists all of them for the ones that look at the whole
+/// neighborhood.
+pub trait NeighborhoodOp: fmt::Debug {
+    /// Picks a random move from `state`, applies it, and returns it.
+    fn apply(&self, state: &mut GenericChallengeState, rng: &mut StreamRng) -> QueenMove;
+
+    /// Undoes `applied`, the last move applied to `state`.
+    fn undo(&self, state: &mut GenericChallengeState, applied: &QueenMove) {
+        applied.undo(state)
+    }
+
+    /// Returns every move from `state`, without applying any.
+    fn moves(&self, state: &GenericChallengeState) -> Vec<QueenMove>;
+}
+
+/// Swaps the rows of two queens, which keeps one queen per row.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SwapQueens;
+
+impl NeighborhoodOp for SwapQueens {
+    fn apply(&self, state: &mut GenericChallengeState, rng: &mut StreamRng) -> QueenMove {
+        let swap = state.get_two_random_queens(rng);
+        state.queen_rows.swap(swap.0, swap.1);
+        QueenMove::from_swaps(state, &[swap])
+    }
+
+    fn moves(&self, state: &GenericChallengeState) -> Vec<QueenMove> {
+        let rows = &state.queen_rows;
+        let mut moves = vec![];
+        for one in 0..rows.len() {
+            for other in one + 1..rows.len() {
+                moves.push(QueenMove::new(vec![
+                    (one, rows[one], rows[other]),
+                    (other, rows[other], rows[one]),
+                ]));
+            }
+        }
+        moves
+    }
+}
+
+/// Moves a queen to any other row of its column.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MoveQueenToRow;
+
+impl NeighborhoodOp for MoveQueenToRow {
+    fn apply(&self, state: &mut GenericChallengeState, rng: &mut StreamRng) -> QueenMove {
+        use rand::Rng;
+
+        let column = state.random_queen_index(rng);
+        let from = state.queen_rows[column];
+        let to = (from + rng.gen_range(1, state.size)) % state.size;
+        state.queen_rows[column] = to;
+        QueenMove::new(vec![(column, from, to)])
+    }
+
+    fn moves(&self, state: &GenericChallengeState) -> Vec<QueenMove> {
+        let mut moves = vec![];
+        for (column, &from) in state.queen_rows.iter().enumerate() {
+            for to in (0..state.size).filter(|&to| to != from) {
+                moves.push(QueenMove::new(vec![(column, from, to)]));
+            }
+        }
+        moves
+    }
+}
+
+/// Moves a queen one row up or down, for the smoothest landscape.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ShiftQueen;
+
+impl ShiftQueen {
+    /// Returns the rows next to `row` in a board of `size`.
+    fn targets(row: usize, size: usize) -> Vec<usize> {
+        let mut targets = vec![];
+        if row > 0 {
+            targets.push(row - 1);
+        }
+        if row + 1 < size {
+            targets.push(row + 1);
+        }
+        targets
+    }
+}
+
+impl NeighborhoodOp for ShiftQueen {
+    fn apply(&self, state: &mut GenericChallengeState, rng: &mut StreamRng) -> QueenMove {
+        use rand::Rng;
+
+        let column = state.random_queen_index(rng);
+        let from = state.queen_rows[column];
+        let to = *rng.choose(&Self::targets(from, state.size)).unwrap();
+        state.queen_rows[column] = to;
+        QueenMove::new(vec![(column, from, to)])
+    }
+
+    fn moves(&self, state: &GenericChallengeState) -> Vec<QueenMove> {
+        let mut moves = vec![];
+        for (column, &from) in state.queen_rows.iter().enumerate() {
+            for to in Self::targets(from, state.size) {
+                moves.push(QueenMove::new(vec![(column, from, to)]));
+            }
+        }
+        moves
+    }
+}
+
+/// How a move listed by a `NeighborhoodOp` misbehaves.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NeighborhoodFault {
+    /// A queen isn't on the row the move says it moves from.
+    WrongOrigin,
+    /// The move leaves the placement as it was.
+    NoChange,
+    /// Another move listed before leads to the same placement.
+    Duplicate,
+    /// Undoing the move doesn't give back the placement.
+    BadUndo,
+    /// The change of score computed incrementally isn't the one found by
+    /// scoring the whole placement again.
+    ScoreDelta {
+        incremental: isize,
+        recomputed: isize,
+    },
+}
+
+/// A move that failed `verify_neighborhood`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NeighborhoodMismatch {
+    pub queen_move: QueenMove,
+    pub fault: NeighborhoodFault,
+}
+
+/// Checks every move that `op` lists from `state`: that it moves queens
+/// from where they are, changes the placement, leads somewhere no other
+/// move does, is undone by `op`, and changes the score as much as
+/// `ConflictCounters` says.
+///
+/// Returns how many moves were checked, or the first one that failed. It
+/// scores each neighbor from scratch, so it's meant for tests and for
+/// trying out new kinds of moves, not for searching.
+pub fn verify_neighborhood(
+    op: &dyn NeighborhoodOp,
+    state: &GenericChallengeState,
+) -> Result<usize, NeighborhoodMismatch> {
+    let mut counters = ConflictCounters::new(state);
+    let score = state.score() as isize;
+    let mut neighbors = HashSet::new();
+    let moves = op.moves(state);
+    for queen_move in &moves {
+        let fault = |fault| NeighborhoodMismatch {
+            queen_move: queen_move.clone(),
+            fault,
+        };
+
+        let mut next = state.clone();
+        for &(column, from, to) in queen_move.relocations() {
+            if next.queen_rows[column] != from {
+                return Err(fault(NeighborhoodFault::WrongOrigin));
+            }
+            next.queen_rows[column] = to;
+        }
+        if next.queen_rows == state.queen_rows {
+            return Err(fault(NeighborhoodFault::NoChange));
+        }
+        if !neighbors.insert(next.queen_rows.clone()) {
+            return Err(fault(NeighborhoodFault::Duplicate));
+        }
+
+        let incremental = counters.score_delta_for_move(queen_move);
+        let recomputed = next.score() as isize - score;
+        if incremental != recomputed {
+            return Err(fault(NeighborhoodFault::ScoreDelta {
+                incremental,
+                recomputed,
+            }));
+        }
+
+        op.undo(&mut next, queen_move);
+        if next.queen_rows != state.queen_rows {
+            return Err(fault(NeighborhoodFault::BadUndo));
+        }
+    }
+    Ok(moves.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_are_undone_and_scored_incrementally() {
+        let mut rng = stream_rng(7, RngStream::Neighborhood);
+        let ops: [&dyn NeighborhoodOp; 3] = [&SwapQueens, &MoveQueenToRow, &ShiftQueen];
+        for op in &ops {
+            let mut state = GenericChallengeState::new(10, &mut rng);
+            let mut counters = ConflictCounters::new(&state);
+            for _ in 0..50 {
+                let before = state.queen_rows.clone();
+                let score = state.score();
+                let applied = op.apply(&mut state, &mut rng);
+                assert!(applied.columns().all(|column| column < 10));
+                let delta = counters.score_delta_for_move(&applied);
+                assert_eq!(score as isize + delta, state.score() as isize);
+
+                op.undo(&mut state, &applied);
+                assert_eq!(state.queen_rows, before);
+                applied.apply(&mut state);
+                counters.record_move(&applied);
+            }
+            assert_eq!(counters.attacking_pairs(), state.score());
+
+            for candidate in op.moves(&state) {
+                let mut next = state.clone();
+                candidate.apply(&mut next);
+                assert!(next.queen_rows != state.queen_rows);
+            }
+        }
+    }
+
+    #[test]
+    fn verifies_whole_neighborhoods() {
+        let mut rng = stream_rng(3, RngStream::Neighborhood);
+        let state = GenericChallengeState::new(8, &mut rng);
+        assert_eq!(verify_neighborhood(&SwapQueens, &state), Ok(28));
+        assert_eq!(verify_neighborhood(&MoveQueenToRow, &state), Ok(56));
+        assert!(verify_neighborhood(&ShiftQueen, &state).is_ok());
+
+        // Lists every swap twice.
+        #[derive(Debug)]
+        struct RepeatedSwaps;
+        impl NeighborhoodOp for RepeatedSwaps {
+            fn apply(&self, state: &mut GenericChallengeState, rng: &mut StreamRng) -> QueenMove {
+                SwapQueens.apply(state, rng)
+            }
+
+            fn moves(&self, state: &GenericChallengeState) -> Vec<QueenMove> {
+                let moves = SwapQueens.moves(state);
+                moves.iter().chain(&moves).cloned().collect()
+            }
+        }
+        let mismatch = verify_neighborhood(&RepeatedSwaps, &state).unwrap_err();
+        assert_eq!(mismatch.fault, NeighborhoodFault::Duplicate);
+        assert_eq!(mismatch.queen_move, SwapQueens.moves(&state)[0]);
+    }
+}
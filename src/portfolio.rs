@@ -0,0 +1,373 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! A portfolio that runs the stochastic strategies in rounds of restarts,
+//! splitting the time of each round according to how well each strategy
+//! has done so far on boards of the same size.
+//!
+//! The statistics outlive each solve, and can be saved and restored (with
+//! the `serde` feature), so that a long-running service ends up favoring
+//! whatever works best for the sizes it's asked for.
+
+use presets::Preset;
+use solvers::constructive_beam_search::ConstructiveBeamSearchConfig;
+use solvers::cuckoo_search::CuckooSearchConfig;
+use solvers::genetic_algorithm::GeneticAlgorithmConfig;
+use solvers::hill_climbing::HillClimbingConfig;
+use solvers::local_beam_search::LocalBeamSearchConfig;
+use solvers::min_conflicts::MinConflictsConfig;
+use solvers::pbil::PbilConfig;
+use solvers::simulated_annealing::SimulatedAnnealingConfig;
+use solvers::tabu_search::TabuSearchConfig;
+use *;
+
+/// How a strategy has done on boards of a given size.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StrategyRecord {
+    /// The name of the strategy, as in its `StrategyInfo`.
+    pub strategy: String,
+    pub dimension: usize,
+    pub runs: usize,
+    /// How many of the runs found a solution.
+    pub solved: usize,
+    /// The time spent in all the runs, in seconds.
+    pub seconds: f64,
+}
+
+impl StrategyRecord {
+    /// Returns the solutions found per second, as if the strategy had
+    /// solved one more board in `prior` more time, so that untried
+    /// strategies still get a fair share.
+    pub fn weight(&self, prior: Duration) -> f64 {
+        let prior = prior.as_secs_f64().max(1e-6);
+        (self.solved as f64 + 1.) / (self.seconds + prior)
+    }
+}
+
+/// What a portfolio has learned across solves.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PortfolioStats {
+    pub records: Vec<StrategyRecord>,
+}
+
+impl PortfolioStats {
+    /// Returns the record of `strategy` for boards of `dimension`, if it
+    /// has run on any.
+    pub fn record(&self, strategy: &str, dimension: usize) -> Option<&StrategyRecord> {
+        self.records
+            .iter()
+            .find(|r| r.strategy == strategy && r.dimension == dimension)
+    }
+
+    /// Records a run of `strategy` on a board of `dimension`.
+    pub fn add_run(&mut self, strategy: &str, dimension: usize, elapsed: Duration, solved: bool) {
+        let index = match self
+            .records
+            .iter()
+            .position(|r| r.strategy == strategy && r.dimension == dimension)
+        {
+            Some(index) => index,
+            None => {
+                self.records.push(StrategyRecord {
+                    strategy: strategy.to_owned(),
+                    dimension,
+                    ..StrategyRecord::default()
+                });
+                self.records.len() - 1
+            }
+        };
+
+        let record = &mut self.records[index];
+        record.runs += 1;
+        record.seconds += elapsed.as_secs_f64();
+        if solved {
+            record.solved += 1;
+        }
+    }
+
+    /// Returns the fraction of a round that each of `strategies` should get
+    /// on boards of `dimension`, proportional to their weights.
+    pub fn shares(&self, strategies: &[&str], dimension: usize, prior: Duration) -> Vec<f64> {
+        let weights = strategies
+            .iter()
+            .map(|strategy| match self.record(strategy, dimension) {
+                Some(record) => record.weight(prior),
+                None => StrategyRecord::default().weight(prior),
+            })
+            .collect::<Vec<_>>();
+        let total = weights.iter().sum::<f64>();
+        weights.iter().map(|weight| weight / total).collect()
+    }
+}
+
+type Runner = fn(Preset, usize, SolveBudget, &mut dyn FnMut(&[usize], usize)) -> Solution;
+
+struct Member {
+    info: &'static StrategyInfo,
+    run: Runner,
+}
+
+/// Runs the stochastic strategies with the configurations of a preset, in
+/// rounds of a fixed length, until one of them finds a solution.
+pub struct Portfolio {
+    preset: Preset,
+    /// How long each round takes, split among all the strategies.
+    round: Duration,
+    stats: PortfolioStats,
+    members: Vec<Member>,
+}
+
+impl Portfolio {
+    pub fn new(preset: Preset, round: Duration) -> Self {
+        Self::with_stats(preset, round, PortfolioStats::default())
+    }
+
+    /// Creates a portfolio that keeps learning from `stats`, saved from a
+    /// previous one.
+    pub fn with_stats(preset: Preset, round: Duration, stats: PortfolioStats) -> Self {
+        let members = vec![
+            Member {
+                info: ConstructiveBeamSearch::info(),
+                run: |preset, n, budget, callback| {
+                    let config = ConstructiveBeamSearchConfig {
+                        budget,
+                        ..preset.constructive_beam_search(n)
+                    };
+                    ConstructiveBeamSearch::new(n, config).solve_with_callback(callback)
+                },
+            },
+            Member {
+                info: HillClimbing::info(),
+                run: |preset, n, budget, callback| {
+                    let config = HillClimbingConfig {
+                        budget,
+                        ..preset.hill_climbing(n)
+                    };
+                    HillClimbing::new(n, config).solve_with_callback(callback)
+                },
+            },
+            Member {
+                info: SimulatedAnnealing::info(),
+                run: |preset, n, budget, callback| {
+                    let config = SimulatedAnnealingConfig {
+                        budget,
+                        ..preset.simulated_annealing(n)
+                    };
+                    SimulatedAnnealing::new(n, config).solve_with_callback(callback)
+                },
+            },
+            Member {
+                info: LocalBeamSearch::info(),
+                run: |preset, n, budget, callback| {
+                    let config = LocalBeamSearchConfig {
+                        budget,
+                        ..preset.local_beam_search(n)
+                    };
+                    LocalBeamSearch::new(n, config).solve_with_callback(callback)
+                },
+            },
+            Member {
+                info: GeneticAlgorithm::info(),
+                run: |preset, n, budget, callback| {
+                    let config = GeneticAlgorithmConfig {
+                        budget,
+                        ..preset.genetic_algorithm(n)
+                    };
+                    GeneticAlgorithm::new(n, config).solve_with_callback(callback)
+                },
+            },
+            Member {
+                info: Pbil::info(),
+                run: |preset, n, budget, callback| {
+                    let config = PbilConfig {
+                        budget,
+                        ..preset.pbil(n)
+                    };
+                    Pbil::new(n, config).solve_with_callback(callback)
+                },
+            },
+            Member {
+                info: CuckooSearch::info(),
+                run: |preset, n, budget, callback| {
+                    let config = CuckooSearchConfig {
+                        budget,
+                        ..preset.cuckoo_search(n)
+                    };
+                    CuckooSearch::new(n, config).solve_with_callback(callback)
+                },
+            },
+            Member {
+                info: MinConflicts::info(),
+                run: |preset, n, budget, callback| {
+                    let config = MinConflictsConfig {
+                        budget,
+                        ..preset.min_conflicts(n)
+                    };
+                    MinConflicts::new(n, config).solve_with_callback(callback)
+                },
+            },
+            Member {
+                info: TabuSearch::info(),
+                run: |preset, n, budget, callback| {
+                    let config = TabuSearchConfig {
+                        budget,
+                        ..preset.tabu_search(n)
+                    };
+                    TabuSearch::new(n, config).solve_with_callback(callback)
+                },
+            },
+        ];
+
+        Portfolio {
+            preset,
+            round,
+            stats,
+            members,
+        }
+    }
+
+    /// Returns what the portfolio has learned so far, to save it.
+    pub fn stats(&self) -> &PortfolioStats {
+        &self.stats
+    }
+
+    pub fn solve(&mut self, n: usize, budget: SolveBudget) -> Result<Solution, Error> {
+        self.solve_with_callback(n, budget, |_, _| {})
+    }
+
+    /// Solves a board of `n` queens within `budget`, where each iteration is
+    /// a round. Returns the first solution found, or the best placement if
+    /// the budget runs out first.
+    pub fn solve_with_callback<F>(
+        &mut self,
+        n: usize,
+        budget: SolveBudget,
+        mut callback: F,
+    ) -> Result<Solution, Error>
+    where
+        F: FnMut(&[usize], usize),
+    {
+        if validation::known_solution_count(n) == Some(0) {
+            return Err(Error::Infeasible(n));
+        }
+
+        let start = Instant::now();
+        let mut tracker = BudgetTracker::new(budget.clone());
+        let mut best: Option<Solution> = None;
+        'rounds: while !tracker.should_stop() {
+            let names = self
+                .members
+                .iter()
+                .map(|member| member.info.name)
+                .collect::<Vec<_>>();
+            let shares = self.stats.shares(&names, n, self.round);
+
+            for (member, share) in self.members.iter().zip(shares) {
+                let mut slice = self.round.mul_f64(share);
+                if let Some(max) = budget.max_wall_time {
+                    slice = std::cmp::min(slice, max.saturating_sub(start.elapsed()));
+                }
+                if slice == Duration::from_secs(0) {
+                    continue;
+                }
+
+                let member_budget = SolveBudget {
+                    max_wall_time: Some(slice),
+                    max_iterations: None,
+                    cancellation: budget.cancellation.clone(),
+                    time_steps: budget.time_steps,
+                };
+                let run_start = Instant::now();
+                let solution = (member.run)(self.preset, n, member_budget, &mut callback);
+                let solved = solution.status == SolveStatus::Solved;
+                self.stats
+                    .add_run(member.info.name, n, run_start.elapsed(), solved);
+
+                // A partial placement with no attacks isn't better than a
+                // complete one with some.
+                let rank = |s: &Solution| (s.queen_rows.len() != n, s.score);
+                if solved || best.as_ref().is_none_or(|b| rank(&solution) < rank(b)) {
+                    best = Some(solution);
+                }
+                if solved {
+                    break 'rounds;
+                }
+                // The tracker notices on the next round.
+                if is_cancelled(&budget) {
+                    continue 'rounds;
+                }
+            }
+        }
+
+        let mut solution = best.unwrap_or_else(|| Solution::partial(vec![], 0, n));
+        if solution.status != SolveStatus::Solved {
+            solution.status = tracker.status(false);
+        }
+        Ok(solution)
+    }
+}
+
+fn is_cancelled(budget: &SolveBudget) -> bool {
+    budget
+        .cancellation
+        .as_ref()
+        .is_some_and(|cancelled| cancelled.load(Ordering::Relaxed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn favors_the_strategies_that_solve_more() {
+        let mut stats = PortfolioStats::default();
+        let second = Duration::from_secs(1);
+        for _ in 0..10 {
+            stats.add_run("good", 16, second / 10, true);
+            stats.add_run("bad", 16, second, false);
+        }
+
+        let shares = stats.shares(&["good", "bad", "new"], 16, second);
+        assert!(shares[0] > shares[2] && shares[2] > shares[1]);
+        assert!((shares.iter().sum::<f64>() - 1.).abs() < 1e-9);
+
+        // Other sizes are learned separately.
+        let shares = stats.shares(&["good", "bad"], 32, second);
+        assert_eq!(shares[0], shares[1]);
+    }
+
+    #[test]
+    fn solves_and_records_runs() {
+        let mut portfolio = Portfolio::new(Preset::Fast, Duration::from_millis(50));
+        let budget = SolveBudget {
+            max_wall_time: Some(Duration::from_secs(30)),
+            ..SolveBudget::default()
+        };
+        let solution = portfolio.solve(8, budget).unwrap();
+        assert_eq!(solution.status(), SolveStatus::Solved);
+        assert_eq!(solution.queen_rows().len(), 8);
+        assert_eq!(solution.score(), 0);
+        assert!(portfolio.stats().records.iter().any(|r| r.solved == 1));
+
+        assert_eq!(
+            portfolio.solve(3, SolveBudget::default()).err(),
+            Some(Error::Infeasible(3))
+        );
+    }
+}
@@ -0,0 +1,59 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The types most uses of the crate need, to import them all at once:
+//!
+//! ```
+//! use local_search_algorithms::prelude::*;
+//!
+//! let solution = HillClimbing::new(8, HillClimbingConfig::default()).solve();
+//! assert_eq!(solution.queen_rows().len(), 8);
+//! ```
+
+pub use attempts::Attempts;
+pub use blackboard::{Blackboard, BlackboardClient};
+pub use checkpoint::Checkpoint;
+pub use conflict_graph::ConflictGraph;
+pub use csv_trace::CsvTraceRecorder;
+pub use error::{ConfigWarning, Error};
+pub use events::TraceEvent;
+pub use info::StrategyInfo;
+pub use presets::Preset;
+pub use selector::{select, Selection};
+pub use solvers::constraint_propagation::{ColumnOrder, SymmetryBreaking};
+pub use solvers::genetic_algorithm::{
+    Crossover, RankSelection, RouletteSelection, StochasticUniversalSampling, TournamentSelection,
+};
+pub use solvers::hybrid::Finisher;
+pub use solvers::simulated_annealing::{FastCooling, GeometricCooling};
+pub use stepper::{StepResult, Stepper, Steps};
+pub use svg::SvgOptions;
+pub use validation::known_solution_count;
+pub use {attacking_pairs, canonical_placement};
+pub use {
+    ConstraintPropagation, ConstructiveBeamSearch, CuckooSearch, Decomposition, GeneticAlgorithm,
+    HillClimbing, Hybrid, IslandModel, LimitedDiscrepancySearch, LocalBeamSearch, MinConflicts,
+    Pbil, SimulatedAnnealing, TabuSearch,
+};
+pub use {
+    ConstraintPropagationConfig, ConstructiveBeamSearchConfig, CuckooSearchConfig,
+    DecompositionConfig, GeneticAlgorithmConfig, HillClimbingConfig, HybridConfig,
+    IslandModelConfig, LimitedDiscrepancySearchConfig, LocalBeamSearchConfig, MinConflictsConfig,
+    PbilConfig, SimulatedAnnealingConfig, TabuSearchConfig,
+};
+pub use {CoordinateConvention, Corner, Order};
+pub use {NQueensStrategy, Solution, SolveBudget, SolveStatus, Statistics};
@@ -0,0 +1,991 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Named configurations for each strategy, so that frontends don't need to
+//! know good parameter values for each of them.
+
+use solvers::constraint_propagation::{ColumnOrder, ConstraintPropagationConfig, SymmetryBreaking};
+use solvers::constructive_beam_search::ConstructiveBeamSearchConfig;
+use solvers::cuckoo_search::CuckooSearchConfig;
+use solvers::decomposition::DecompositionConfig;
+use solvers::genetic_algorithm::{
+    AdaptiveMutation, Crossover, GeneticAlgorithmConfig, TournamentSelection,
+};
+use solvers::hill_climbing::HillClimbingConfig;
+use solvers::hybrid::{Finisher, HybridConfig};
+use solvers::island_model::{IslandModelConfig, MigrationTopology};
+use solvers::limited_discrepancy_search::LimitedDiscrepancySearchConfig;
+use solvers::local_beam_search::{BeamWidthSchedule, LocalBeamSearchConfig};
+use solvers::min_conflicts::MinConflictsConfig;
+use solvers::pbil::PbilConfig;
+use solvers::simulated_annealing::{
+    GeometricCooling, Neighborhood, RestartPolicy, SimulatedAnnealingConfig,
+};
+use solvers::tabu_search::TabuSearchConfig;
+use std::sync::Arc;
+use *;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Preset {
+    /// Finds a solution quickly most of the time, without insisting much
+    /// if it doesn't.
+    Fast,
+    /// Spends more effort in order to solve bigger boards reliably.
+    Thorough,
+    /// Small, slow and noise-free runs whose steps are easy to follow in
+    /// an animation.
+    TeachingDemo,
+}
+
+impl Preset {
+    /// Returns the preset with the given name, if any.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "fast" => Preset::Fast,
+            "thorough" => Preset::Thorough,
+            "teaching-demo" => Preset::TeachingDemo,
+            _ => return None,
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::Fast => "fast",
+            Preset::Thorough => "thorough",
+            Preset::TeachingDemo => "teaching-demo",
+        }
+    }
+
+    pub fn constraint_propagation(self, n: usize) -> ConstraintPropagationConfig {
+        match self {
+            Preset::Fast => ConstraintPropagationConfig {
+                randomize_rows: true,
+                column_order: ColumnOrder::MostConstrained,
+                symmetry_breaking: SymmetryBreaking::None,
+                restarts: 10,
+                restart_backtracks: 4 * n,
+                max_nogood_size: 0,
+                seed: None,
+                budget: SolveBudget::default(),
+            },
+            Preset::Thorough => ConstraintPropagationConfig {
+                randomize_rows: true,
+                column_order: ColumnOrder::LeftToRight,
+                symmetry_breaking: SymmetryBreaking::Reflection,
+                restarts: 30,
+                restart_backtracks: 4 * n,
+                max_nogood_size: 3,
+                seed: None,
+                budget: SolveBudget::default(),
+            },
+            Preset::TeachingDemo => ConstraintPropagationConfig {
+                randomize_rows: false,
+                column_order: ColumnOrder::LeftToRight,
+                symmetry_breaking: SymmetryBreaking::None,
+                restarts: 0,
+                restart_backtracks: 0,
+                max_nogood_size: 0,
+                seed: None,
+                budget: SolveBudget::default(),
+            },
+        }
+    }
+
+    pub fn constructive_beam_search(self, n: usize) -> ConstructiveBeamSearchConfig {
+        let (beam_width, prune_conflicts) = match self {
+            Preset::Fast => (2 * n, true),
+            Preset::Thorough => (16 * n, true),
+            Preset::TeachingDemo => (3, false),
+        };
+        ConstructiveBeamSearchConfig {
+            beam_width: beam_width.max(1),
+            prune_conflicts,
+            max_evaluations: None,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+
+    pub fn hill_climbing(self, n: usize) -> HillClimbingConfig {
+        let (restarts, random_walk_probability, tie_breaking) = match self {
+            Preset::Fast => (n, 0.05, TieBreaking::Random),
+            Preset::Thorough => (10 * n, 0.1, TieBreaking::LeastRecentlyMoved),
+            Preset::TeachingDemo => (0, 0., TieBreaking::KeepIncumbent),
+        };
+        HillClimbingConfig {
+            restarts,
+            exhaust_restarts: false,
+            move_selection: MoveSelection::RandomSwap,
+            operator: None,
+            random_walk_probability,
+            tie_breaking,
+            objective: Objective::AttackingPairs,
+            max_evaluations: None,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+
+    pub fn simulated_annealing(self, n: usize) -> SimulatedAnnealingConfig {
+        let (cooling_factor, restarts, neighborhood) = match self {
+            Preset::Fast => (0.01, 2, Neighborhood::Swaps),
+            Preset::Thorough => (0.001, 10, Neighborhood::SingleQueenMoves),
+            Preset::TeachingDemo => (0.05, 0, Neighborhood::Swaps),
+        };
+        SimulatedAnnealingConfig {
+            starting_temperature: n as f32,
+            cooling_schedule: Box::new(GeometricCooling {
+                factor: cooling_factor,
+            }),
+            acceptance: None,
+            reheating: None,
+            restarts,
+            exhaust_restarts: false,
+            restart_policy: RestartPolicy::Fresh,
+            move_selection: MoveSelection::RandomSwap,
+            tie_breaking: None,
+            initial_acceptance: match self {
+                Preset::TeachingDemo => None,
+                _ => Some(0.5),
+            },
+            objective: Objective::AttackingPairs,
+            neighborhood,
+            operator: None,
+            max_evaluations: None,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+
+    pub fn limited_discrepancy_search(self, n: usize) -> LimitedDiscrepancySearchConfig {
+        LimitedDiscrepancySearchConfig {
+            max_discrepancies: match self {
+                Preset::Fast => 3,
+                // Enough to make the search exact.
+                Preset::Thorough => n,
+                Preset::TeachingDemo => 1,
+            },
+            budget: SolveBudget::default(),
+        }
+    }
+
+    pub fn local_beam_search(self, n: usize) -> LocalBeamSearchConfig {
+        match self {
+            Preset::Fast => LocalBeamSearchConfig {
+                state_count: 8,
+                schedule: BeamWidthSchedule::Fixed,
+                min_distance: 0,
+                reseeding: None,
+                objective: Objective::AttackingPairs,
+                snapshot_interval: 0,
+                max_evaluations: None,
+                seed: None,
+                budget: SolveBudget::default(),
+            },
+            Preset::Thorough => LocalBeamSearchConfig {
+                state_count: 2 * n.max(8),
+                schedule: BeamWidthSchedule::WidenOnStagnation {
+                    max_state_count: 8 * n.max(8),
+                    factor: 1.5,
+                },
+                min_distance: 2,
+                reseeding: Some(solvers::local_beam_search::BeamReseeding {
+                    stagnant_iterations: 20,
+                    keep: 2,
+                    mutation_swaps: 2,
+                }),
+                objective: Objective::AttackingPairs,
+                snapshot_interval: 0,
+                max_evaluations: None,
+                seed: None,
+                budget: SolveBudget::default(),
+            },
+            Preset::TeachingDemo => LocalBeamSearchConfig {
+                state_count: 3,
+                schedule: BeamWidthSchedule::Fixed,
+                min_distance: 0,
+                reseeding: None,
+                objective: Objective::AttackingPairs,
+                snapshot_interval: 0,
+                max_evaluations: None,
+                seed: None,
+                budget: SolveBudget::default(),
+            },
+        }
+    }
+
+    pub fn genetic_algorithm(self, n: usize) -> GeneticAlgorithmConfig {
+        let (generation_size, generation_count, immigrant_fraction) = match self {
+            Preset::Fast => (5 * n.max(10), 200, 0.),
+            Preset::Thorough => (20 * n.max(10), 2000, 0.05),
+            Preset::TeachingDemo => (10, 100, 0.),
+        };
+        GeneticAlgorithmConfig {
+            generation_size,
+            elitism: 0.1,
+            generation_gap: 1.,
+            selection: Box::new(TournamentSelection { size: 3 }),
+            crossover: Crossover::PartiallyMapped,
+            crossover_probability: 0.8,
+            mutation_probability: 0.1,
+            // Long runs tend to converge early on larger boards.
+            adaptive_mutation: match self {
+                Preset::Thorough => Some(AdaptiveMutation {
+                    min_diversity: 0.1,
+                    step: 0.02,
+                    max_probability: 0.3,
+                }),
+                _ => None,
+            },
+            generation_count,
+            immigrant_fraction,
+            hall_of_fame: None,
+            reseed: None,
+            min_diversity: 0.,
+            max_stagnant_generations: 0,
+            track_genealogy: false,
+            track_diversity: false,
+            constraints: None,
+            objective: Objective::AttackingPairs,
+            snapshot_interval: 0,
+            max_evaluations: None,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+
+    pub fn pbil(self, n: usize) -> PbilConfig {
+        let (samples, iterations) = match self {
+            Preset::Fast => (5 * n.max(10), 200),
+            Preset::Thorough => (20 * n.max(10), 2000),
+            Preset::TeachingDemo => (10, 100),
+        };
+        PbilConfig {
+            samples,
+            learning_rate: 0.1,
+            mutation_probability: 0.02,
+            mutation_shift: 0.05,
+            iterations,
+            objective: Objective::AttackingPairs,
+            max_evaluations: None,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+
+    pub fn cuckoo_search(self, n: usize) -> CuckooSearchConfig {
+        let (nests, generation_count) = match self {
+            Preset::Fast => (15, 100 * n),
+            Preset::Thorough => (25, 1000 * n),
+            Preset::TeachingDemo => (5, 100),
+        };
+        CuckooSearchConfig {
+            nests,
+            discovery_probability: 0.25,
+            levy_exponent: 1.5,
+            generation_count,
+            objective: Objective::AttackingPairs,
+            max_evaluations: None,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+
+    pub fn min_conflicts(self, n: usize) -> MinConflictsConfig {
+        let (max_steps, restarts, random_walk_probability) = match self {
+            Preset::Fast => (50 * n.max(10), 5, 0.02),
+            Preset::Thorough => (500 * n.max(10), 20, 0.05),
+            Preset::TeachingDemo => (10 * n.max(10), 0, 0.),
+        };
+        MinConflictsConfig {
+            max_steps,
+            restarts,
+            random_walk_probability,
+            tie_breaking: TieBreaking::Random,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+
+    pub fn tabu_search(self, n: usize) -> TabuSearchConfig {
+        let (tenure, max_steps) = match self {
+            Preset::Fast => (n / 4, 20 * n.max(10)),
+            Preset::Thorough => (n / 3, 200 * n.max(10)),
+            Preset::TeachingDemo => (2, 5 * n.max(10)),
+        };
+        TabuSearchConfig {
+            tenure,
+            max_steps,
+            tie_breaking: TieBreaking::Random,
+            operator: None,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+
+    pub fn decomposition(self, n: usize) -> DecompositionConfig {
+        let block_size = match self {
+            Preset::Fast => 64,
+            Preset::Thorough => 256,
+            Preset::TeachingDemo => 4,
+        };
+        let mut repair = self.min_conflicts(n);
+        repair.restarts = 0;
+        DecompositionConfig {
+            block_size,
+            blocks: self.min_conflicts(block_size),
+            repair,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+
+    pub fn island_model(self, n: usize) -> IslandModelConfig {
+        let (islands, migration_interval, epochs) = match self {
+            Preset::Fast => (4, 20, 10),
+            Preset::Thorough => (4, 50, 40),
+            Preset::TeachingDemo => (2, 10, 10),
+        };
+        // The islands split the population of the genetic algorithm, and
+        // threads aren't available everywhere, e.g. on the web.
+        IslandModelConfig {
+            island: Arc::new(move |n| {
+                let config = self.genetic_algorithm(n);
+                GeneticAlgorithmConfig {
+                    generation_size: cmp::max(config.generation_size / islands, 2),
+                    ..config
+                }
+            }),
+            islands,
+            migration_interval,
+            migration_size: if n < 4 { 0 } else { 1 },
+            topology: MigrationTopology::Ring,
+            epochs,
+            threads: false,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+
+    pub fn hybrid(self, n: usize) -> HybridConfig {
+        // Half the board still leaves the local search room to move.
+        let finisher = match self {
+            Preset::Thorough => Finisher::SimulatedAnnealing(self.simulated_annealing(n)),
+            _ => Finisher::HillClimbing(self.hill_climbing(n)),
+        };
+        HybridConfig {
+            placed_queens: n / 2,
+            finisher,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+
+    /// Solves a board of `n` queens with the strategy called `strategy`, as
+    /// in its `StrategyInfo`, configured by this preset with `seed` and
+    /// `budget`.
+    ///
+    /// The seed is ignored by the deterministic strategies.
+    pub fn solve<F>(
+        self,
+        strategy: &str,
+        n: usize,
+        seed: Option<u64>,
+        budget: SolveBudget,
+        callback: F,
+    ) -> Result<Solution, Error>
+    where
+        F: FnMut(&[usize], usize),
+    {
+        fn run<T, F>(n: usize, config: T::Config, callback: F) -> Result<Solution, Error>
+        where
+            T: NQueensStrategy,
+            F: FnMut(&[usize], usize),
+        {
+            T::try_new(n, config)?.try_solve_with_callback(callback)
+        }
+
+        match strategy {
+            "constraint_propagation" => run::<ConstraintPropagation, _>(
+                n,
+                ConstraintPropagationConfig {
+                    seed,
+                    budget,
+                    ..self.constraint_propagation(n)
+                },
+                callback,
+            ),
+            "constructive_beam_search" => run::<ConstructiveBeamSearch, _>(
+                n,
+                ConstructiveBeamSearchConfig {
+                    seed,
+                    budget,
+                    ..self.constructive_beam_search(n)
+                },
+                callback,
+            ),
+            "limited_discrepancy_search" => run::<LimitedDiscrepancySearch, _>(
+                n,
+                LimitedDiscrepancySearchConfig {
+                    budget,
+                    ..self.limited_discrepancy_search(n)
+                },
+                callback,
+            ),
+            "hill_climbing" => run::<HillClimbing, _>(
+                n,
+                HillClimbingConfig {
+                    seed,
+                    budget,
+                    ..self.hill_climbing(n)
+                },
+                callback,
+            ),
+            "simulated_annealing" => run::<SimulatedAnnealing, _>(
+                n,
+                SimulatedAnnealingConfig {
+                    seed,
+                    budget,
+                    ..self.simulated_annealing(n)
+                },
+                callback,
+            ),
+            "local_beam_search" => run::<LocalBeamSearch, _>(
+                n,
+                LocalBeamSearchConfig {
+                    seed,
+                    budget,
+                    ..self.local_beam_search(n)
+                },
+                callback,
+            ),
+            "genetic" => run::<GeneticAlgorithm, _>(
+                n,
+                GeneticAlgorithmConfig {
+                    seed,
+                    budget,
+                    ..self.genetic_algorithm(n)
+                },
+                callback,
+            ),
+            "pbil" => run::<Pbil, _>(
+                n,
+                PbilConfig {
+                    seed,
+                    budget,
+                    ..self.pbil(n)
+                },
+                callback,
+            ),
+            "cuckoo_search" => run::<CuckooSearch, _>(
+                n,
+                CuckooSearchConfig {
+                    seed,
+                    budget,
+                    ..self.cuckoo_search(n)
+                },
+                callback,
+            ),
+            "min_conflicts" => run::<MinConflicts, _>(
+                n,
+                MinConflictsConfig {
+                    seed,
+                    budget,
+                    ..self.min_conflicts(n)
+                },
+                callback,
+            ),
+            "tabu_search" => run::<TabuSearch, _>(
+                n,
+                TabuSearchConfig {
+                    seed,
+                    budget,
+                    ..self.tabu_search(n)
+                },
+                callback,
+            ),
+            "decomposition" => run::<Decomposition, _>(
+                n,
+                DecompositionConfig {
+                    seed,
+                    budget,
+                    ..self.decomposition(n)
+                },
+                callback,
+            ),
+            "island_model" => run::<IslandModel, _>(
+                n,
+                IslandModelConfig {
+                    seed,
+                    budget,
+                    ..self.island_model(n)
+                },
+                callback,
+            ),
+            "hybrid" => run::<Hybrid, _>(
+                n,
+                HybridConfig {
+                    seed,
+                    budget,
+                    ..self.hybrid(n)
+                },
+                callback,
+            ),
+            _ => Err(Error::InvalidConfig("unknown strategy")),
+        }
+    }
+
+    /// Returns a search of a board of `n` queens with the strategy called
+    /// `strategy`, configured like `Preset::solve` does, to drive step by
+    /// step.
+    ///
+    /// Fails with `Error::InvalidConfig` for the strategies that can't be
+    /// stepped through, as said by `StrategyInfo::supports_stepping`.
+    pub fn stepper(
+        self,
+        strategy: &str,
+        n: usize,
+        seed: Option<u64>,
+        budget: SolveBudget,
+    ) -> Result<Box<dyn Stepper>, Error> {
+        match info::strategy(strategy) {
+            None => return Err(Error::InvalidConfig("unknown strategy")),
+            Some(info) if !info.supports_stepping => {
+                return Err(Error::InvalidConfig(
+                    "the strategy can't be stepped through",
+                ))
+            }
+            Some(_) => {}
+        }
+        if validation::known_solution_count(n) == Some(0) {
+            return Err(Error::Infeasible(n));
+        }
+        Ok(match strategy {
+            "hill_climbing" => Box::new(HillClimbing::try_new(
+                n,
+                HillClimbingConfig {
+                    seed,
+                    budget,
+                    ..self.hill_climbing(n)
+                },
+            )?),
+            "simulated_annealing" => Box::new(SimulatedAnnealing::try_new(
+                n,
+                SimulatedAnnealingConfig {
+                    seed,
+                    budget,
+                    ..self.simulated_annealing(n)
+                },
+            )?),
+            "genetic" => Box::new(GeneticAlgorithm::try_new(
+                n,
+                GeneticAlgorithmConfig {
+                    seed,
+                    budget,
+                    ..self.genetic_algorithm(n)
+                },
+            )?),
+            "min_conflicts" => Box::new(MinConflicts::try_new(
+                n,
+                MinConflictsConfig {
+                    seed,
+                    budget,
+                    ..self.min_conflicts(n)
+                },
+            )?),
+            "tabu_search" => Box::new(TabuSearch::try_new(
+                n,
+                TabuSearchConfig {
+                    seed,
+                    budget,
+                    ..self.tabu_search(n)
+                },
+            )?),
+            _ => unreachable!("{} can't be stepped through", strategy),
+        })
+    }
+
+    /// Returns the warnings about how this preset configures the strategy
+    /// called `strategy` for boards of `n` queens, as in
+    /// `NQueensStrategy::lint`.
+    pub fn lint(self, strategy: &str, n: usize) -> Result<Vec<ConfigWarning>, Error> {
+        Ok(match strategy {
+            "constraint_propagation" => {
+                ConstraintPropagation::lint(n, &self.constraint_propagation(n))
+            }
+            "constructive_beam_search" => {
+                ConstructiveBeamSearch::lint(n, &self.constructive_beam_search(n))
+            }
+            "limited_discrepancy_search" => {
+                LimitedDiscrepancySearch::lint(n, &self.limited_discrepancy_search(n))
+            }
+            "hill_climbing" => HillClimbing::lint(n, &self.hill_climbing(n)),
+            "simulated_annealing" => SimulatedAnnealing::lint(n, &self.simulated_annealing(n)),
+            "local_beam_search" => LocalBeamSearch::lint(n, &self.local_beam_search(n)),
+            "genetic" => GeneticAlgorithm::lint(n, &self.genetic_algorithm(n)),
+            "pbil" => Pbil::lint(n, &self.pbil(n)),
+            "cuckoo_search" => CuckooSearch::lint(n, &self.cuckoo_search(n)),
+            "min_conflicts" => MinConflicts::lint(n, &self.min_conflicts(n)),
+            "tabu_search" => TabuSearch::lint(n, &self.tabu_search(n)),
+            "decomposition" => Decomposition::lint(n, &self.decomposition(n)),
+            "island_model" => IslandModel::lint(n, &self.island_model(n)),
+            "hybrid" => Hybrid::lint(n, &self.hybrid(n)),
+            _ => return Err(Error::InvalidConfig("unknown strategy")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_presets_by_name() {
+        for &preset in &[Preset::Fast, Preset::Thorough, Preset::TeachingDemo] {
+            assert_eq!(Preset::from_name(preset.name()), Some(preset));
+        }
+        assert_eq!(Preset::from_name("slow"), None);
+
+        let solution = HillClimbing::new(8, Preset::Fast.hill_climbing(8)).solve();
+        assert_eq!(solution.queen_rows.len(), 8);
+    }
+
+    #[test]
+    fn presets_are_valid() {
+        for &preset in &[Preset::Fast, Preset::Thorough, Preset::TeachingDemo] {
+            for n in 1..12 {
+                let cp = preset.constraint_propagation(n);
+                assert_eq!(ConstraintPropagation::validate(n, &cp), Ok(()));
+                let cbs = preset.constructive_beam_search(n);
+                assert_eq!(ConstructiveBeamSearch::validate(n, &cbs), Ok(()));
+                assert_eq!(HillClimbing::validate(n, &preset.hill_climbing(n)), Ok(()));
+                let sa = preset.simulated_annealing(n);
+                assert_eq!(SimulatedAnnealing::validate(n, &sa), Ok(()));
+                let lds = preset.limited_discrepancy_search(n);
+                assert_eq!(LimitedDiscrepancySearch::validate(n, &lds), Ok(()));
+                let lbs = preset.local_beam_search(n);
+                assert_eq!(LocalBeamSearch::validate(n, &lbs), Ok(()));
+                let ga = preset.genetic_algorithm(n);
+                assert_eq!(GeneticAlgorithm::validate(n, &ga), Ok(()));
+                assert_eq!(Pbil::validate(n, &preset.pbil(n)), Ok(()));
+                assert_eq!(CuckooSearch::validate(n, &preset.cuckoo_search(n)), Ok(()));
+                assert_eq!(MinConflicts::validate(n, &preset.min_conflicts(n)), Ok(()));
+                assert_eq!(TabuSearch::validate(n, &preset.tabu_search(n)), Ok(()));
+                let decomposition = preset.decomposition(n);
+                assert_eq!(Decomposition::validate(n, &decomposition), Ok(()));
+                let islands = preset.island_model(n);
+                assert_eq!(IslandModel::validate(n, &islands), Ok(()));
+                assert_eq!(Hybrid::validate(n, &preset.hybrid(n)), Ok(()));
+            }
+        }
+    }
+
+    #[test]
+    fn lints_suspicious_configs() {
+        for &preset in &[Preset::Fast, Preset::Thorough] {
+            for strategy in info::STRATEGIES {
+                assert_eq!(
+                    preset.lint(strategy.name, 8),
+                    Ok(vec![]),
+                    "{}",
+                    strategy.name
+                );
+            }
+        }
+
+        let sa = SimulatedAnnealingConfig {
+            starting_temperature: 10.,
+            cooling_schedule: Box::new(solvers::simulated_annealing::GeometricCooling {
+                factor: 0.5,
+            }),
+            initial_acceptance: None,
+            ..Preset::Fast.simulated_annealing(8)
+        };
+        let warnings = SimulatedAnnealing::lint(8, &sa);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].parameter, "cooling_schedule");
+
+        let cbs = ConstructiveBeamSearchConfig {
+            beam_width: 1,
+            ..Preset::Fast.constructive_beam_search(8)
+        };
+        assert_eq!(
+            ConstructiveBeamSearch::lint(8, &cbs)[0].parameter,
+            "beam_width"
+        );
+        assert!(Preset::Fast.lint("annealing", 8).is_err());
+    }
+
+    #[test]
+    fn seeded_runs_are_reproducible() {
+        fn trajectory<T: NQueensStrategy>(config: T::Config) -> Vec<(Vec<usize>, usize)> {
+            let mut steps = vec![];
+            let solution = T::new(10, config).solve_with_callback(|queens, score| {
+                steps.push((queens.to_vec(), score));
+            });
+            steps.push((solution.queen_rows.to_vec(), solution.score));
+            steps
+        }
+
+        fn check<T: NQueensStrategy, F: Fn() -> T::Config>(config: F) {
+            assert_eq!(trajectory::<T>(config()), trajectory::<T>(config()));
+        }
+
+        let seed = Some(42);
+        let preset = Preset::TeachingDemo;
+        check::<ConstraintPropagation, _>(|| ConstraintPropagationConfig {
+            seed,
+            randomize_rows: true,
+            ..preset.constraint_propagation(10)
+        });
+        check::<ConstructiveBeamSearch, _>(|| ConstructiveBeamSearchConfig {
+            seed,
+            ..preset.constructive_beam_search(10)
+        });
+        check::<HillClimbing, _>(|| HillClimbingConfig {
+            seed,
+            ..preset.hill_climbing(10)
+        });
+        check::<SimulatedAnnealing, _>(|| SimulatedAnnealingConfig {
+            seed,
+            ..preset.simulated_annealing(10)
+        });
+        check::<LocalBeamSearch, _>(|| LocalBeamSearchConfig {
+            seed,
+            ..preset.local_beam_search(10)
+        });
+        check::<GeneticAlgorithm, _>(|| GeneticAlgorithmConfig {
+            seed,
+            ..preset.genetic_algorithm(10)
+        });
+        check::<Pbil, _>(|| PbilConfig {
+            seed,
+            ..preset.pbil(10)
+        });
+        check::<CuckooSearch, _>(|| CuckooSearchConfig {
+            seed,
+            ..preset.cuckoo_search(10)
+        });
+        check::<MinConflicts, _>(|| MinConflictsConfig {
+            seed,
+            ..preset.min_conflicts(10)
+        });
+        check::<TabuSearch, _>(|| TabuSearchConfig {
+            seed,
+            ..preset.tabu_search(10)
+        });
+    }
+
+    #[test]
+    fn rejects_invalid_configs_and_infeasible_boards() {
+        let mut config = Preset::Fast.genetic_algorithm(8);
+        config.mutation_probability = 1.5;
+        assert!(GeneticAlgorithm::try_new(8, config).is_err());
+
+        let beam = LocalBeamSearch::try_new(3, Preset::Fast.local_beam_search(3)).unwrap();
+        let result = beam.try_solve_with_callback(|_, _| {});
+        assert_eq!(result.err(), Some(Error::Infeasible(3)));
+    }
+
+    #[test]
+    fn solves_every_strategy_by_name() {
+        // Local beam search never gives up on its own.
+        let budget = SolveBudget {
+            max_iterations: Some(1000),
+            ..SolveBudget::default()
+        };
+        for strategy in info::STRATEGIES {
+            let solution = Preset::Fast
+                .solve(strategy.name, 6, Some(1), budget.clone(), |_, _| {})
+                .unwrap();
+            assert_eq!(solution.dimension(), 6);
+        }
+        assert!(Preset::Fast
+            .solve("bogo_sort", 6, None, SolveBudget::default(), |_, _| {})
+            .is_err());
+    }
+
+    #[test]
+    fn steps_the_strategies_that_say_so() {
+        for strategy in info::STRATEGIES {
+            let stepper = Preset::Fast.stepper(strategy.name, 6, Some(1), SolveBudget::default());
+            match stepper {
+                Ok(_) => assert!(strategy.supports_stepping, "{}", strategy.name),
+                Err(error) => {
+                    assert!(!strategy.supports_stepping, "{}", strategy.name);
+                    assert_eq!(
+                        error,
+                        Error::InvalidConfig("the strategy can't be stepped through")
+                    );
+                }
+            }
+        }
+        assert!(Preset::Fast
+            .stepper("bogo_sort", 6, None, SolveBudget::default())
+            .is_err());
+    }
+
+    #[test]
+    fn budgets_stop_every_strategy() {
+        use std::sync::atomic::AtomicBool;
+        use std::sync::Arc;
+
+        fn status<T: NQueensStrategy>(config: T::Config) -> SolveStatus {
+            T::new(20, config).solve().status()
+        }
+
+        let cancelled = SolveBudget {
+            cancellation: Some(Arc::new(AtomicBool::new(true))),
+            ..SolveBudget::default()
+        };
+        let budget = || cancelled.clone();
+        let preset = Preset::Thorough;
+        let expected = SolveStatus::Cancelled;
+        assert_eq!(
+            status::<ConstraintPropagation>(ConstraintPropagationConfig {
+                budget: budget(),
+                ..preset.constraint_propagation(20)
+            }),
+            expected
+        );
+        assert_eq!(
+            status::<ConstructiveBeamSearch>(ConstructiveBeamSearchConfig {
+                budget: budget(),
+                ..preset.constructive_beam_search(20)
+            }),
+            expected
+        );
+        assert_eq!(
+            status::<HillClimbing>(HillClimbingConfig {
+                budget: budget(),
+                ..preset.hill_climbing(20)
+            }),
+            expected
+        );
+        assert_eq!(
+            status::<SimulatedAnnealing>(SimulatedAnnealingConfig {
+                budget: budget(),
+                ..preset.simulated_annealing(20)
+            }),
+            expected
+        );
+        assert_eq!(
+            status::<LimitedDiscrepancySearch>(LimitedDiscrepancySearchConfig {
+                budget: budget(),
+                ..preset.limited_discrepancy_search(20)
+            }),
+            expected
+        );
+        assert_eq!(
+            status::<LocalBeamSearch>(LocalBeamSearchConfig {
+                budget: budget(),
+                ..preset.local_beam_search(20)
+            }),
+            expected
+        );
+        assert_eq!(
+            status::<GeneticAlgorithm>(GeneticAlgorithmConfig {
+                budget: budget(),
+                ..preset.genetic_algorithm(20)
+            }),
+            expected
+        );
+        assert_eq!(
+            status::<Pbil>(PbilConfig {
+                budget: budget(),
+                ..preset.pbil(20)
+            }),
+            expected
+        );
+        assert_eq!(
+            status::<CuckooSearch>(CuckooSearchConfig {
+                budget: budget(),
+                ..preset.cuckoo_search(20)
+            }),
+            expected
+        );
+        assert_eq!(
+            status::<MinConflicts>(MinConflictsConfig {
+                budget: budget(),
+                ..preset.min_conflicts(20)
+            }),
+            expected
+        );
+        assert_eq!(
+            status::<TabuSearch>(TabuSearchConfig {
+                budget: budget(),
+                ..preset.tabu_search(20)
+            }),
+            expected
+        );
+        assert_eq!(
+            status::<Decomposition>(DecompositionConfig {
+                budget: budget(),
+                ..preset.decomposition(20)
+            }),
+            expected
+        );
+        assert_eq!(
+            status::<IslandModel>(IslandModelConfig {
+                budget: budget(),
+                ..preset.island_model(20)
+            }),
+            expected
+        );
+        assert_eq!(
+            status::<Hybrid>(HybridConfig {
+                budget: budget(),
+                ..preset.hybrid(20)
+            }),
+            expected
+        );
+
+        let solution = SimulatedAnnealing::new(
+            20,
+            SimulatedAnnealingConfig {
+                budget: SolveBudget {
+                    max_iterations: Some(3),
+                    ..SolveBudget::default()
+                },
+                ..preset.simulated_annealing(20)
+            },
+        )
+        .solve();
+        assert_eq!(solution.status(), SolveStatus::Timeout);
+        assert!(solution.statistics.effort.accepted_moves <= 3);
+        assert_eq!(solution.statistics.step_times.count(), 0);
+
+        let solution = GeneticAlgorithm::new(
+            20,
+            GeneticAlgorithmConfig {
+                generation_count: 10,
+                budget: SolveBudget {
+                    time_steps: true,
+                    ..SolveBudget::default()
+                },
+                ..preset.genetic_algorithm(20)
+            },
+        )
+        .solve();
+        let step_times = &solution.statistics.step_times;
+        assert_eq!(step_times.count(), solution.statistics.iterations);
+        assert!(step_times.max() <= solution.statistics.elapsed);
+
+        let solution = HillClimbing::new(8, preset.hill_climbing(8)).solve();
+        assert_eq!(solution.status(), SolveStatus::Solved);
+    }
+}
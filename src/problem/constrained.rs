@@ -0,0 +1,271 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Problems with soft constraints on top of the hard ones, and the ways the
+//! generic genetic algorithm and simulated annealing can trade breaking the
+//! hard ones against the cost of the soft ones.
+//!
+//! A single fixed penalty weight either lets the search settle on cheap but
+//! infeasible states, or makes it ignore the cost until it's feasible.
+//! Instead, the weight can adapt to how often the search is feasible, or
+//! the comparisons can be made stochastically by either criterion.
+//!
+//! The n-queens strategies in `solvers` have no soft constraints, so this
+//! is only supported by the generic solvers.
+
+use problem::Problem;
+use *;
+
+/// A problem where `Problem::score` counts the hard constraints a state
+/// breaks, and the states that break none are better the lower their cost.
+pub trait SoftConstrainedProblem: Problem {
+    /// Returns the cost of the soft constraints `state` breaks.
+    fn soft_cost(&self, state: &Self::State) -> f64;
+}
+
+impl<P: SoftConstrainedProblem> SoftConstrainedProblem for &P {
+    fn soft_cost(&self, state: &P::State) -> f64 {
+        (**self).soft_cost(state)
+    }
+}
+
+/// How to adapt the penalty weight of the hard constraints, following Bean
+/// and Hadj-Alouane.
+#[derive(Clone, Debug)]
+pub struct PenaltyAdaptation {
+    pub initial_weight: f64,
+    /// What the weight is multiplied by after `window` iterations in a row
+    /// whose best state broke hard constraints.
+    pub increase: f64,
+    /// What the weight is divided by after `window` iterations in a row
+    /// whose best state broke none.
+    pub decrease: f64,
+    pub window: usize,
+}
+
+/// How to weigh the hard constraints against the soft ones.
+#[derive(Clone, Debug)]
+pub enum ConstraintHandling {
+    /// Minimize the cost plus the hard constraints broken times a weight,
+    /// which adapts as the search goes.
+    AdaptivePenalty(PenaltyAdaptation),
+    /// Compare states by cost if both are feasible, or otherwise with
+    /// probability `cost_probability`, and by the hard constraints they
+    /// break the rest of the time, following Runarsson and Yao.
+    StochasticRanking { cost_probability: f32 },
+}
+
+/// The state of a `PenaltyAdaptation` during a search.
+#[derive(Clone, Debug)]
+pub struct AdaptivePenalty {
+    adaptation: PenaltyAdaptation,
+    weight: f64,
+    /// Iterations in a row whose best state was feasible, or infeasible if
+    /// negative.
+    streak: isize,
+}
+
+impl AdaptivePenalty {
+    pub fn new(adaptation: PenaltyAdaptation) -> Self {
+        AdaptivePenalty {
+            weight: adaptation.initial_weight,
+            adaptation,
+            streak: 0,
+        }
+    }
+
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+
+    /// Returns the penalized cost of a state breaking `violations` hard
+    /// constraints with soft cost `cost`.
+    pub fn energy(&self, violations: usize, cost: f64) -> f64 {
+        cost + self.weight * violations as f64
+    }
+
+    /// Records whether the best state of an iteration was feasible,
+    /// adapting the weight after a full window of either.
+    pub fn record(&mut self, feasible: bool) {
+        self.streak = match (feasible, self.streak) {
+            (true, streak) if streak >= 0 => streak + 1,
+            (false, streak) if streak <= 0 => streak - 1,
+            (true, _) => 1,
+            (false, _) => -1,
+        };
+
+        let window = self.adaptation.window.max(1) as isize;
+        if self.streak >= window {
+            self.weight /= self.adaptation.decrease;
+            self.streak = 0;
+        } else if self.streak <= -window {
+            self.weight *= self.adaptation.increase;
+            self.streak = 0;
+        }
+    }
+}
+
+/// Returns whether `(violations, cost)` pairs should be compared by cost,
+/// as stochastic ranking does.
+pub fn compares_by_cost(
+    one: (usize, f64),
+    other: (usize, f64),
+    cost_probability: f32,
+    rng: &mut StreamRng,
+) -> bool {
+    use rand::Rng;
+
+    (one.0 == 0 && other.0 == 0) || rng.next_f32() < cost_probability
+}
+
+/// Returns the indices of `members`, given as `(violations, cost)` pairs,
+/// ranked best first by stochastic ranking: a bubble sort whose comparisons
+/// go by cost with probability `cost_probability`, or always if both are
+/// feasible, and by violations otherwise.
+pub fn stochastic_ranking(
+    members: &[(usize, f64)],
+    cost_probability: f32,
+    rng: &mut StreamRng,
+) -> Vec<usize> {
+    let mut ranking = (0..members.len()).collect::<Vec<_>>();
+    for _ in 0..members.len() {
+        let mut swapped = false;
+        for i in 1..ranking.len() {
+            let (one, other) = (members[ranking[i - 1]], members[ranking[i]]);
+            let out_of_order = if compares_by_cost(one, other, cost_probability, rng) {
+                one.1 > other.1
+            } else {
+                one.0 > other.0
+            };
+            if out_of_order {
+                ranking.swap(i - 1, i);
+                swapped = true;
+            }
+        }
+        if !swapped {
+            break;
+        }
+    }
+    ranking
+}
+
+/// Returns whether `one` is better than `other`, both `(violations, cost)`
+/// pairs: feasible states are better, and then cheaper ones.
+pub fn is_better(one: (usize, f64), other: (usize, f64)) -> bool {
+    one.0 < other.0 || (one.0 == other.0 && one.1 < other.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use problem::{
+        GenericGeneticAlgorithm, GenericGeneticAlgorithmConfig, GenericSimulatedAnnealing,
+        GenericSimulatedAnnealingConfig, WeightedNQueens,
+    };
+    use solvers::constraint_propagation::{ColumnOrder, SymmetryBreaking};
+
+    #[test]
+    fn ranks_and_adapts_penalties() {
+        let members = [(2, 1.), (0, 5.), (0, 3.), (1, 0.)];
+        let mut rng = stream_rng(0, RngStream::Selection);
+        assert_eq!(stochastic_ranking(&members, 0., &mut rng), vec![2, 1, 3, 0]);
+        assert_eq!(stochastic_ranking(&members, 1., &mut rng), vec![3, 0, 2, 1]);
+
+        let mut penalty = AdaptivePenalty::new(PenaltyAdaptation {
+            initial_weight: 4.,
+            increase: 2.,
+            decrease: 4.,
+            window: 2,
+        });
+        penalty.record(false);
+        penalty.record(false);
+        assert_eq!(penalty.weight(), 8.);
+        penalty.record(true);
+        penalty.record(false);
+        assert_eq!(penalty.weight(), 8.);
+        penalty.record(true);
+        penalty.record(true);
+        assert_eq!(penalty.weight(), 2.);
+        assert_eq!(penalty.energy(3, 1.), 7.);
+    }
+
+    #[test]
+    fn finds_cheap_feasible_placements() {
+        // Cheaper towards the top left corner.
+        let n = 8;
+        let costs = (0..n)
+            .map(|column| (0..n).map(|row| (column * row) as f64).collect())
+            .collect::<Vec<Vec<f64>>>();
+        let problem = WeightedNQueens::new(costs);
+
+        let mut cheapest = f64::INFINITY;
+        let config = ConstraintPropagationConfig {
+            randomize_rows: false,
+            column_order: ColumnOrder::LeftToRight,
+            symmetry_breaking: SymmetryBreaking::None,
+            restarts: 0,
+            restart_backtracks: 0,
+            max_nogood_size: 0,
+            seed: None,
+            budget: SolveBudget::default(),
+        };
+        ConstraintPropagation::new(n, config).for_each_solution(|queen_rows| {
+            cheapest = cheapest.min(problem.soft_cost(&queen_rows.to_vec()));
+        });
+
+        let handlings = [
+            ConstraintHandling::AdaptivePenalty(PenaltyAdaptation {
+                initial_weight: 10.,
+                increase: 2.,
+                decrease: 1.5,
+                window: 20,
+            }),
+            ConstraintHandling::StochasticRanking {
+                cost_probability: 0.45,
+            },
+        ];
+        for handling in &handlings {
+            let config = GenericSimulatedAnnealingConfig {
+                starting_temperature: 50.,
+                freezing_temperature: 1.,
+                cooling_schedule: Box::new(solvers::simulated_annealing::GeometricCooling {
+                    factor: 0.0005,
+                }),
+                restarts: 5,
+                seed: Some(1),
+                initial_acceptance: None,
+                ..GenericSimulatedAnnealingConfig::default()
+            };
+            let outcome = GenericSimulatedAnnealing::new(&problem, config)
+                .solve_constrained(handling, |_, _| {});
+            assert_eq!(outcome.status, SolveStatus::Solved);
+            assert!(problem.soft_cost(&outcome.state) <= cheapest * 1.5);
+
+            let config = GenericGeneticAlgorithmConfig {
+                generation_size: 60,
+                crossover_probability: 0.7,
+                mutation_probability: 0.4,
+                generation_count: 500,
+                seed: Some(2),
+                ..GenericGeneticAlgorithmConfig::default()
+            };
+            let outcome = GenericGeneticAlgorithm::new(&problem, config)
+                .solve_constrained(handling, |_, _| {});
+            assert_eq!(problem.score(&outcome.state), outcome.score);
+        }
+    }
+}
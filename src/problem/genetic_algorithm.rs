@@ -0,0 +1,975 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! A genetic algorithm over any problem whose states can be bred.
+//!
+//! The n-queens genetic algorithm in `solvers` is this one over `NQueens`,
+//! breeding placements through a `Breeding` hook with its own crossovers
+//! and swap mutations, which also keeps their genealogy, the snapshots of
+//! the population and how diverse it is.
+
+use problem::constrained::{self, AdaptivePenalty};
+use problem::{ConstraintHandling, EvolvableProblem, Outcome, Problem, SoftConstrainedProblem};
+use solvers::genetic_algorithm::{
+    AdaptiveMutation, HallOfFame, Reseed, SelectionStrategy, TournamentSelection,
+};
+use std::hash::Hash;
+use std::{cmp, mem};
+use *;
+
+pub struct GenericGeneticAlgorithmConfig {
+    pub generation_size: usize,
+    /// The fraction of each generation, the best of it, that passes to the
+    /// next one untouched.
+    pub elitism: f32,
+    /// The fraction of the individuals other than the elite that offspring
+    /// replace each generation, worst first. The rest are carried over as
+    /// they are, and aren't scored again.
+    pub generation_gap: f32,
+    /// How the parents of the offspring are picked.
+    pub selection: Box<dyn SelectionStrategy>,
+    /// The probability of each pair of parents being crossed over, rather
+    /// than passed on as they are.
+    pub crossover_probability: f32,
+    /// How likely each child is to be mutated, as `Breeding::mutate` takes
+    /// it.
+    pub mutation_probability: f32,
+    /// If set, the mutation probability goes up while the population is
+    /// too uniform, and back down to `mutation_probability` once it isn't.
+    pub adaptive_mutation: Option<AdaptiveMutation>,
+    pub generation_count: usize,
+    /// The fraction of each generation that is replaced by fresh random
+    /// individuals, to prevent premature convergence.
+    pub immigrant_fraction: f32,
+    /// If set, the best individuals ever seen are kept outside of the
+    /// population.
+    pub hall_of_fame: Option<HallOfFame>,
+    /// If set, part of the population is replaced by fresh random
+    /// individuals whenever the best score stops improving.
+    pub reseed: Option<Reseed>,
+    /// Stop before `generation_count` if the fraction of distinct
+    /// individuals in a generation falls below this. Zero disables it.
+    pub min_diversity: f32,
+    /// Stop before `generation_count` if the best score hasn't improved
+    /// for this many generations. Zero disables it.
+    pub max_stagnant_generations: usize,
+    /// Stop once this many states have been scored across the whole run,
+    /// if set.
+    pub max_evaluations: Option<usize>,
+    /// The master seed of the random streams, so that runs can be
+    /// reproduced, or a random one if unset.
+    pub seed: Option<u64>,
+    /// Limits on the whole solve, and a way to cancel it.
+    pub budget: SolveBudget,
+}
+
+impl Default for GenericGeneticAlgorithmConfig {
+    fn default() -> Self {
+        GenericGeneticAlgorithmConfig {
+            generation_size: 100,
+            elitism: 0.1,
+            generation_gap: 1.,
+            selection: Box::new(TournamentSelection { size: 3 }),
+            crossover_probability: 0.8,
+            mutation_probability: 0.1,
+            adaptive_mutation: None,
+            generation_count: 1000,
+            immigrant_fraction: 0.,
+            hall_of_fame: None,
+            reseed: None,
+            min_diversity: 0.,
+            max_stagnant_generations: 0,
+            max_evaluations: None,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+}
+
+/// Breeds the individuals of `P`, and keeps track of them.
+pub trait Breeding<P: Problem> {
+    /// What the search keeps along with each individual, like where it
+    /// came from.
+    type Tag: Clone;
+
+    /// Returns the tag of an individual born into `generation` out of
+    /// nothing, like the initial ones and the immigrants.
+    fn newcomer(&mut self, generation: usize) -> Self::Tag;
+
+    /// Fixes up `state`, which is either random or given, if needed.
+    fn repair(&mut self, _problem: &P, _state: &mut P::State, _rng: &mut StreamRng) {}
+
+    /// Replaces the individuals `one` and `other`, with their tags, by two
+    /// children of them born into `generation`.
+    fn cross_over(
+        &mut self,
+        problem: &P,
+        one: (&mut P::State, &mut Self::Tag),
+        other: (&mut P::State, &mut Self::Tag),
+        generation: usize,
+        rng: &mut StreamRng,
+    );
+
+    /// Mutates `state`, more so the higher `probability` is, into
+    /// `generation`, and returns whether it changed.
+    fn mutate(
+        &mut self,
+        problem: &P,
+        state: &mut P::State,
+        tag: &mut Self::Tag,
+        generation: usize,
+        probability: f32,
+        rng: &mut StreamRng,
+    ) -> bool;
+
+    /// Returns how different the individuals of `generation`, best first,
+    /// are from the first one, from zero to one.
+    fn gene_diversity(&self, generation: &[P::State]) -> f32;
+
+    /// Called with each generation once scored, best first, as the score,
+    /// the state and the tag of each individual.
+    fn record_generation(
+        &mut self,
+        _generation: usize,
+        _individuals: &[(usize, P::State, Self::Tag)],
+    ) {
+    }
+
+    /// Returns a copy of `state`, to breed separately.
+    fn copy(&mut self, state: &P::State) -> P::State {
+        state.clone()
+    }
+
+    /// Takes the individuals the search is done with, so that they can be
+    /// reused for the next copies.
+    fn recycle<I>(&mut self, _states: I)
+    where
+        I: IntoIterator<Item = P::State>,
+    {
+    }
+}
+
+/// Breeds through the crossover and the mutation of the problem, tagging
+/// individuals with nothing.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ProblemBreeding;
+
+impl<P: EvolvableProblem> Breeding<P> for ProblemBreeding
+where
+    P::State: PartialEq,
+{
+    type Tag = ();
+
+    fn newcomer(&mut self, _generation: usize) {}
+
+    fn cross_over(
+        &mut self,
+        problem: &P,
+        (one, _): (&mut P::State, &mut ()),
+        (other, _): (&mut P::State, &mut ()),
+        _generation: usize,
+        rng: &mut StreamRng,
+    ) {
+        let first = problem.crossover(one, other, rng);
+        let second = problem.crossover(other, one, rng);
+        *one = first;
+        *other = second;
+    }
+
+    /// Mutates `state` once with `probability`.
+    fn mutate(
+        &mut self,
+        problem: &P,
+        state: &mut P::State,
+        _tag: &mut (),
+        _generation: usize,
+        probability: f32,
+        rng: &mut StreamRng,
+    ) -> bool {
+        use rand::Rng;
+
+        if rng.next_f32() >= probability {
+            return false;
+        }
+        problem.mutate(state, rng);
+        true
+    }
+
+    /// Returns the fraction of the individuals that aren't the first one.
+    fn gene_diversity(&self, generation: &[P::State]) -> f32 {
+        let first = match generation.first() {
+            Some(first) => first,
+            None => return 0.,
+        };
+        let different = generation[1..].iter().filter(|s| *s != first).count();
+        different as f32 / generation.len() as f32
+    }
+}
+
+/// One of the best distinct individuals a run has seen.
+struct Famous<S, T> {
+    state: S,
+    score: usize,
+    /// The tag it was last seen with, unless it comes from a resumed run.
+    tag: Option<T>,
+}
+
+/// Records `state` in `hall_of_fame`, best first, if it's among the
+/// `capacity` best distinct individuals seen so far, like `BestPlacements`
+/// does. If it's there already, it keeps `tag`, if any.
+fn offer<S: Clone + PartialEq, T: Clone>(
+    hall_of_fame: &mut Vec<Famous<S, T>>,
+    capacity: usize,
+    state: &S,
+    score: usize,
+    tag: Option<&T>,
+) {
+    if capacity == 0 {
+        return;
+    }
+    if let Some(famous) = hall_of_fame.iter_mut().find(|f| f.state == *state) {
+        if tag.is_some() {
+            famous.tag = tag.cloned();
+        }
+        return;
+    }
+    if hall_of_fame.len() == capacity && hall_of_fame[capacity - 1].score <= score {
+        return;
+    }
+
+    let index = hall_of_fame
+        .iter()
+        .position(|f| f.score > score)
+        .unwrap_or(hall_of_fame.len());
+    hall_of_fame.insert(
+        index,
+        Famous {
+            state: state.clone(),
+            score,
+            tag: tag.cloned(),
+        },
+    );
+    hall_of_fame.truncate(capacity);
+}
+
+/// How the population is evolving.
+struct Evolution<S, T> {
+    /// The generation to score next, or the last one, best first, once the
+    /// run is over.
+    individuals: Vec<S>,
+    tags: Vec<T>,
+    /// The scores of the individuals carried over unchanged from the
+    /// previous generation.
+    known_scores: Vec<Option<usize>>,
+    hall_of_fame: Vec<Famous<S, T>>,
+    /// The best individual seen so far, and its tag.
+    best: Option<(S, T)>,
+    best_score: usize,
+}
+
+pub struct GenericGeneticAlgorithm<P: Problem, B: Breeding<P> = ProblemBreeding> {
+    problem: P,
+    pub(crate) breeding: B,
+    pub(crate) rngs: ComponentRngs,
+    pub(crate) effort: SearchEffort,
+    pub(crate) budget: BudgetTracker,
+    /// How many times part of the population was reseeded.
+    pub(crate) restarts: usize,
+    /// The first individuals of the initial population, if given, and the
+    /// last generation, best first, once the run is over.
+    pub(crate) population: Vec<P::State>,
+    /// The population being evolved, or `None` before creating it.
+    evolution: Option<Evolution<P::State, B::Tag>>,
+    pub(crate) generation: usize,
+    pub(crate) pending_generations: usize,
+    pub(crate) stagnant_generations: usize,
+    pub(crate) mutation_probability: f32,
+    /// The best individual of the last generation scored, and its score.
+    leader: Option<(P::State, usize)>,
+    /// The best individual the runs this one resumes saw, and its score.
+    pub(crate) resumed_best: Option<(P::State, usize)>,
+    /// The hall of fame of the runs this one resumes.
+    pub(crate) resumed_hall_of_fame: Vec<(P::State, usize)>,
+    /// The tag of the individual returned, once the run is over.
+    pub(crate) winner: Option<B::Tag>,
+    /// The best individual of the last generation, if the one returned is
+    /// from an earlier one.
+    final_state: Option<(P::State, usize)>,
+    pub(crate) config: GenericGeneticAlgorithmConfig,
+}
+
+impl<P: EvolvableProblem> GenericGeneticAlgorithm<P>
+where
+    P::State: PartialEq,
+{
+    pub fn new(problem: P, config: GenericGeneticAlgorithmConfig) -> Self {
+        Self::with_breeding(problem, ProblemBreeding, config)
+    }
+}
+
+impl<P: Problem, B: Breeding<P>> GenericGeneticAlgorithm<P, B> {
+    /// Like `new`, but breeding and tagging individuals through `breeding`.
+    pub fn with_breeding(problem: P, breeding: B, config: GenericGeneticAlgorithmConfig) -> Self {
+        GenericGeneticAlgorithm {
+            problem,
+            breeding,
+            rngs: ComponentRngs::for_seed(config.seed),
+            effort: SearchEffort::default(),
+            budget: BudgetTracker::new(config.budget.clone()),
+            restarts: 0,
+            population: vec![],
+            evolution: None,
+            generation: 0,
+            pending_generations: config.generation_count,
+            stagnant_generations: 0,
+            mutation_probability: config.mutation_probability,
+            leader: None,
+            resumed_best: None,
+            resumed_hall_of_fame: vec![],
+            winner: None,
+            final_state: None,
+            config,
+        }
+    }
+
+    pub fn problem(&self) -> &P {
+        &self.problem
+    }
+
+    /// Returns the best individual of the last generation scored, or the
+    /// first one of the initial population before scoring any.
+    pub fn current(&self) -> Option<(&P::State, usize)> {
+        if let Some((ref state, score)) = self.leader {
+            return Some((state, score));
+        }
+        let first = self.evolution.as_ref()?.individuals.first()?;
+        Some((first, self.problem.score(first)))
+    }
+
+    /// The best individual of the last generation and its score, if the
+    /// one the run returned is from an earlier generation.
+    pub fn final_state(&self) -> Option<&(P::State, usize)> {
+        self.final_state.as_ref()
+    }
+
+    /// Returns what's needed to carry on the search later: the population
+    /// to evolve next, the best individual seen so far and the hall of
+    /// fame.
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn resumable(
+        &self,
+    ) -> (
+        Vec<P::State>,
+        Option<(P::State, usize)>,
+        Vec<(P::State, usize)>,
+    ) {
+        let evolution = match self.evolution {
+            Some(ref evolution) => evolution,
+            None => {
+                return (
+                    self.population.clone(),
+                    self.resumed_best.clone(),
+                    self.resumed_hall_of_fame.clone(),
+                )
+            }
+        };
+        let best = evolution
+            .best
+            .as_ref()
+            .map(|(state, _)| (state.clone(), evolution.best_score));
+        let hall_of_fame = evolution
+            .hall_of_fame
+            .iter()
+            .map(|f| (f.state.clone(), f.score))
+            .collect();
+        (evolution.individuals.clone(), best, hall_of_fame)
+    }
+}
+
+impl<P: Problem, B: Breeding<P>> GenericGeneticAlgorithm<P, B>
+where
+    P::State: Eq + Hash,
+{
+    /// Returns the fraction of the individuals in `generation` that are
+    /// different to each other.
+    fn diversity(generation: &[P::State]) -> f32 {
+        if generation.is_empty() {
+            return 0.;
+        }
+
+        let distinct = generation.iter().collect::<HashSet<_>>();
+        distinct.len() as f32 / generation.len() as f32
+    }
+
+    /// Pairs each individual and its tag with its score, scoring the ones
+    /// whose score isn't known.
+    fn score(
+        &mut self,
+        evolution: &mut Evolution<P::State, B::Tag>,
+    ) -> Vec<(usize, P::State, B::Tag)> {
+        let problem = &self.problem;
+        let effort = &mut self.effort;
+        mem::take(&mut evolution.individuals)
+            .into_iter()
+            .zip(mem::take(&mut evolution.tags))
+            .zip(mem::take(&mut evolution.known_scores))
+            .map(|((state, tag), score)| {
+                let score = score.unwrap_or_else(|| {
+                    effort.evaluations += 1;
+                    problem.score(&state)
+                });
+                (score, state, tag)
+            })
+            .collect()
+    }
+
+    /// Returns a random individual, repaired if needed.
+    fn random_individual(&mut self) -> P::State {
+        let mut state = self.problem.random_state(&mut self.rngs.initializer);
+        self.breeding
+            .repair(&self.problem, &mut state, &mut self.rngs.initializer);
+        state
+    }
+
+    /// Crosses over the individuals at `one` and `other` of `generation`,
+    /// where `one` comes first.
+    fn cross_over(
+        &mut self,
+        generation: &mut [P::State],
+        tags: &mut [B::Tag],
+        one: usize,
+        other: usize,
+    ) {
+        let (left, right) = generation.split_at_mut(other);
+        let (left_tags, right_tags) = tags.split_at_mut(other);
+        self.breeding.cross_over(
+            &self.problem,
+            (&mut left[one], &mut left_tags[one]),
+            (&mut right[0], &mut right_tags[0]),
+            self.generation + 1,
+            &mut self.rngs.crossover,
+        );
+    }
+
+    pub fn solve(self) -> Outcome<P::State> {
+        self.solve_with_callback(|_, _| {})
+    }
+
+    /// Solves the problem, calling `callback` with the best individual of
+    /// each generation and its score.
+    ///
+    /// Panics if `generation_size` is zero.
+    pub fn solve_with_callback<F>(mut self, mut callback: F) -> Outcome<P::State>
+    where
+        F: FnMut(&P::State, usize),
+    {
+        assert!(
+            self.config.generation_size > 0,
+            "generation size must be positive"
+        );
+        loop {
+            if let Some(outcome) = self.advance(&mut callback) {
+                return outcome;
+            }
+        }
+    }
+
+    /// Runs the next iteration of the search, which either creates the
+    /// initial population, or scores a generation and breeds the next one,
+    /// and returns the outcome if the search is over.
+    pub(crate) fn advance<F>(&mut self, callback: &mut F) -> Option<Outcome<P::State>>
+    where
+        F: FnMut(&P::State, usize),
+    {
+        use rand::Rng;
+
+        let mut evolution = match self.evolution.take() {
+            Some(evolution) => evolution,
+            None => {
+                self.evolution = Some(self.start());
+                return None;
+            }
+        };
+
+        if self.pending_generations == 0
+            || self.effort.exhausts(self.config.max_evaluations)
+            || self.budget.should_stop()
+        {
+            return Some(self.conclude(evolution));
+        }
+
+        let generation_size = self.config.generation_size;
+        let hall_of_fame_size = self.config.hall_of_fame.as_ref().map_or(0, |h| h.size);
+        let mut scores = Vec::with_capacity(generation_size);
+
+        let mut sorted = self.score(&mut evolution);
+        sorted.sort_by_key(|&(score, _, _)| score);
+        self.breeding.record_generation(self.generation, &sorted);
+
+        let mut current_generation = Vec::with_capacity(sorted.len());
+        let mut tags = Vec::with_capacity(sorted.len());
+        let mut known_scores = Vec::with_capacity(sorted.len());
+        for (i, (score, state, tag)) in sorted.into_iter().enumerate() {
+            if i < hall_of_fame_size {
+                offer(
+                    &mut evolution.hall_of_fame,
+                    hall_of_fame_size,
+                    &state,
+                    score,
+                    Some(&tag),
+                );
+            }
+
+            // TODO(emilio): Same problem as before, need a better way
+            // to visualize it.
+            if scores.is_empty() || score == 0 {
+                callback(&state, score);
+                match self.leader {
+                    Some((ref mut leader, ref mut leader_score)) => {
+                        leader.clone_from(&state);
+                        *leader_score = score;
+                    }
+                    None => self.leader = Some((state.clone(), score)),
+                }
+            }
+
+            if score == 0 {
+                self.winner = Some(tag);
+                self.population.clear();
+                self.evolution = Some(evolution);
+                return Some(Outcome::new(state, 0, &self.budget, self.rngs.master_seed));
+            }
+
+            scores.push(score);
+            current_generation.push(state);
+            tags.push(tag);
+            known_scores.push(Some(score));
+        }
+
+        // See whether we've converged, and there's no point in
+        // going on.
+        if scores[0] < evolution.best_score {
+            evolution.best_score = scores[0];
+            evolution.best = Some((current_generation[0].clone(), tags[0].clone()));
+            self.stagnant_generations = 0;
+        } else {
+            self.stagnant_generations += 1;
+        }
+
+        let converged = (self.config.max_stagnant_generations != 0
+            && self.stagnant_generations >= self.config.max_stagnant_generations)
+            || Self::diversity(&current_generation) < self.config.min_diversity;
+        if converged {
+            evolution.individuals = current_generation;
+            evolution.tags = tags;
+            evolution.known_scores = known_scores;
+            return Some(self.conclude(evolution));
+        }
+
+        if let Some(ref adaptive) = self.config.adaptive_mutation {
+            self.mutation_probability = if self.breeding.gene_diversity(&current_generation)
+                < adaptive.min_diversity
+            {
+                (self.mutation_probability + adaptive.step).min(adaptive.max_probability)
+            } else {
+                (self.mutation_probability - adaptive.step).max(self.config.mutation_probability)
+            };
+        }
+
+        let generation = self.generation;
+        let mut next_generation = Vec::with_capacity(generation_size);
+        let mut next_tags = Vec::with_capacity(generation_size);
+        let mut next_scores = Vec::with_capacity(generation_size);
+
+        let percent_per_individual = 1.0f32 / current_generation.len() as f32;
+        let mut percent_so_far = 0.0f32;
+        let mut non_elite_generation_start = 0;
+        while percent_so_far < self.config.elitism {
+            percent_so_far += percent_per_individual;
+            next_generation.push(
+                self.breeding
+                    .copy(&current_generation[non_elite_generation_start]),
+            );
+            next_tags.push(tags[non_elite_generation_start].clone());
+            next_scores.push(Some(scores[non_elite_generation_start]));
+            non_elite_generation_start += 1;
+        }
+
+        // Carry the best of the rest over, so that the offspring replace
+        // the worst individuals.
+        let non_elite = generation_size - non_elite_generation_start;
+        let offspring = cmp::min(
+            non_elite,
+            cmp::max(
+                2,
+                (non_elite as f32 * self.config.generation_gap).ceil() as usize,
+            ),
+        );
+        for i in non_elite_generation_start..generation_size - offspring {
+            next_generation.push(self.breeding.copy(&current_generation[i]));
+            next_tags.push(tags[i].clone());
+            next_scores.push(Some(scores[i]));
+        }
+        let offspring_start = next_generation.len();
+
+        let parents = self
+            .config
+            .selection
+            .select(&scores, offspring, &mut self.rngs.selection);
+        for i in parents {
+            next_generation.push(self.breeding.copy(&current_generation[i]));
+            next_tags.push(tags[i].clone());
+            next_scores.push(Some(scores[i]));
+        }
+
+        // Now do the mix.
+        // TODO(emilio): We always leave the last untouched, which is
+        // fishy.
+        for i in offspring_start..next_generation.len() - 1 {
+            let crossover = self.rngs.crossover.next_f32() < self.config.crossover_probability;
+            if crossover {
+                self.cross_over(&mut next_generation, &mut next_tags, i, i + 1);
+                next_scores[i] = None;
+                next_scores[i + 1] = None;
+            }
+        }
+
+        if next_generation.len() - offspring_start >= 2 {
+            // Cross-over last with first.
+            let crossover = self.rngs.crossover.next_f32() < self.config.crossover_probability;
+            if crossover {
+                let last = next_generation.len() - 1;
+                self.cross_over(&mut next_generation, &mut next_tags, offspring_start, last);
+                next_scores[offspring_start] = None;
+                next_scores[last] = None;
+            }
+        }
+
+        for i in offspring_start..next_generation.len() {
+            let mutated = self.breeding.mutate(
+                &self.problem,
+                &mut next_generation[i],
+                &mut next_tags[i],
+                generation + 1,
+                self.mutation_probability,
+                &mut self.rngs.mutation,
+            );
+            if mutated {
+                next_scores[i] = None;
+            }
+        }
+
+        // Let some fresh blood in, never replacing the elite.
+        let immigrants = cmp::min(
+            (generation_size as f32 * self.config.immigrant_fraction) as usize,
+            next_generation.len() - non_elite_generation_start,
+        );
+        let immigrants_start = next_generation.len() - immigrants;
+        for i in immigrants_start..next_generation.len() {
+            let immigrant = self.random_individual();
+            let replaced = mem::replace(&mut next_generation[i], immigrant);
+            self.breeding.recycle(Some(replaced));
+            next_scores[i] = None;
+            next_tags[i] = self.breeding.newcomer(generation + 1);
+        }
+
+        // Start part of the population over if the search is stuck,
+        // also never replacing the elite.
+        let stagnant_generations = self.stagnant_generations;
+        let reseeded = match self.config.reseed {
+            Some(ref reseed)
+                if stagnant_generations != 0
+                    && stagnant_generations.is_multiple_of(reseed.stagnant_generations) =>
+            {
+                let non_elite = next_generation.len() - non_elite_generation_start;
+                (non_elite as f32 * reseed.fraction).round() as usize
+            }
+            _ => 0,
+        };
+        if reseeded != 0 {
+            self.restarts += 1;
+        }
+        for i in next_generation.len() - reseeded..next_generation.len() {
+            let individual = self.random_individual();
+            let replaced = mem::replace(&mut next_generation[i], individual);
+            self.breeding.recycle(Some(replaced));
+            next_scores[i] = None;
+            next_tags[i] = self.breeding.newcomer(generation + 1);
+        }
+
+        // Bring the best individuals back if the search is stuck,
+        // never replacing the elite.
+        let reinject = self.config.hall_of_fame.as_ref().is_some_and(|h| {
+            h.stagnant_generations != 0
+                && stagnant_generations != 0
+                && stagnant_generations.is_multiple_of(h.stagnant_generations)
+        });
+        if reinject {
+            let famous = &evolution.hall_of_fame;
+            let count = cmp::min(
+                famous.len(),
+                next_generation.len() - non_elite_generation_start,
+            );
+            for (i, individual) in famous[..count].iter().enumerate() {
+                let index = non_elite_generation_start + i;
+                next_generation[index].clone_from(&individual.state);
+                next_scores[index] = Some(individual.score);
+                if let Some(ref tag) = individual.tag {
+                    next_tags[index] = tag.clone();
+                }
+            }
+        }
+
+        self.breeding.recycle(current_generation);
+        evolution.individuals = next_generation;
+        evolution.tags = next_tags;
+        evolution.known_scores = next_scores;
+
+        self.generation += 1;
+        self.effort.generations += 1;
+        self.pending_generations -= 1;
+        self.evolution = Some(evolution);
+        None
+    }
+
+    /// Creates the initial population, starting with the given
+    /// individuals, if any.
+    fn start(&mut self) -> Evolution<P::State, B::Tag> {
+        let generation_size = self.config.generation_size;
+
+        // The best individual seen so far, and its tag.
+        let mut best = None;
+        let mut best_score = usize::MAX;
+        if let Some((state, score)) = self.resumed_best.take() {
+            let tag = self.breeding.newcomer(self.generation);
+            best = Some((state, tag));
+            best_score = score;
+        }
+
+        let mut tags = Vec::with_capacity(generation_size);
+        let mut individuals = Vec::with_capacity(generation_size);
+        let mut seeded = mem::take(&mut self.population).into_iter();
+        for _ in 0..generation_size {
+            let individual = match seeded.next() {
+                Some(mut state) => {
+                    self.breeding
+                        .repair(&self.problem, &mut state, &mut self.rngs.initializer);
+                    state
+                }
+                None => self.random_individual(),
+            };
+            individuals.push(individual);
+            tags.push(self.breeding.newcomer(self.generation));
+        }
+
+        let hall_of_fame_size = self.config.hall_of_fame.as_ref().map_or(0, |h| h.size);
+        let mut hall_of_fame = Vec::with_capacity(hall_of_fame_size);
+        for (state, score) in mem::take(&mut self.resumed_hall_of_fame) {
+            offer(&mut hall_of_fame, hall_of_fame_size, &state, score, None);
+        }
+
+        Evolution {
+            individuals,
+            tags,
+            known_scores: vec![None; generation_size],
+            hall_of_fame,
+            best,
+            best_score,
+        }
+    }
+
+    /// Ends a run that didn't find a solution, with the best individual
+    /// it saw.
+    fn conclude(&mut self, mut evolution: Evolution<P::State, B::Tag>) -> Outcome<P::State> {
+        let mut last_generation = self.score(&mut evolution);
+        last_generation.sort_by_key(|&(score, _, _)| score);
+        let population = last_generation
+            .iter()
+            .map(|(_, state, _)| state.clone())
+            .collect::<Vec<_>>();
+        let (score, leader, tag) = last_generation.into_iter().next().unwrap();
+
+        let hall_of_fame_size = self.config.hall_of_fame.as_ref().map_or(0, |h| h.size);
+        offer(
+            &mut evolution.hall_of_fame,
+            hall_of_fame_size,
+            &leader,
+            score,
+            None,
+        );
+        let (state, score) = match evolution.best {
+            Some((ref best, ref best_tag)) if evolution.best_score < score => {
+                self.final_state = Some((leader, score));
+                self.winner = Some(best_tag.clone());
+                (best.clone(), evolution.best_score)
+            }
+            _ => {
+                self.winner = Some(tag);
+                (leader, score)
+            }
+        };
+
+        // Kept to carry on from if the budget stopped the run before it was
+        // over. The best score is the one stagnation is measured against,
+        // so it leaves out the generation that wasn't evolved yet.
+        evolution.individuals = population.clone();
+        self.population = population;
+        self.evolution = Some(evolution);
+        Outcome::new(state, score, &self.budget, self.rngs.master_seed)
+    }
+}
+
+impl<P: EvolvableProblem + SoftConstrainedProblem> GenericGeneticAlgorithm<P>
+where
+    P::State: PartialEq,
+{
+    /// Picks one of `count` members ranked best first, with a probability
+    /// proportional to how many members rank below it, plus one.
+    fn select_by_rank(&mut self, count: usize) -> usize {
+        use rand::Rng;
+
+        let mut pick = self.rngs.selection.gen_range(0, count * (count + 1) / 2);
+        for rank in 0..count {
+            if pick < count - rank {
+                return rank;
+            }
+            pick -= count - rank;
+        }
+        unreachable!()
+    }
+
+    /// Returns the elite of `ranked`, best first, and children of the rest
+    /// of it up to the size of a generation.
+    fn breed(&mut self, ranked: &[P::State], elite: usize) -> Vec<P::State> {
+        use rand::Rng;
+
+        let mut generation = ranked[..elite].to_vec();
+        while generation.len() < self.config.generation_size {
+            let parent = &ranked[self.select_by_rank(ranked.len())];
+            let mut child = if self.rngs.crossover.next_f32() < self.config.crossover_probability {
+                let other = &ranked[self.select_by_rank(ranked.len())];
+                self.problem
+                    .crossover(parent, other, &mut self.rngs.crossover)
+            } else {
+                parent.clone()
+            };
+            if self.rngs.mutation.next_f32() < self.config.mutation_probability {
+                self.problem.mutate(&mut child, &mut self.rngs.mutation);
+            }
+            generation.push(child);
+        }
+        generation
+    }
+
+    /// Returns how many of the best of each generation pass to the next one
+    /// untouched.
+    fn elite(&self) -> usize {
+        let size = self.config.generation_size;
+        cmp::min((self.config.elitism * size as f32).ceil() as usize, size)
+    }
+
+    /// Like `solve_with_callback`, but minimizing the soft cost too, and
+    /// ranking each generation as `handling` says to select parents by
+    /// rank. The search doesn't stop at the first individual that breaks
+    /// no hard constraints, and returns the cheapest of those it sees, if
+    /// any.
+    ///
+    /// Panics if `generation_size` is zero.
+    pub fn solve_constrained<F>(
+        mut self,
+        handling: &ConstraintHandling,
+        mut callback: F,
+    ) -> Outcome<P::State>
+    where
+        F: FnMut(&P::State, usize),
+    {
+        assert!(
+            self.config.generation_size > 0,
+            "generation size must be positive"
+        );
+        let elite = self.elite();
+        let mut penalty = match *handling {
+            ConstraintHandling::AdaptivePenalty(ref adaptation) => {
+                Some(AdaptivePenalty::new(adaptation.clone()))
+            }
+            ConstraintHandling::StochasticRanking { .. } => None,
+        };
+
+        let mut generation = (0..self.config.generation_size)
+            .map(|_| self.problem.random_state(&mut self.rngs.initializer))
+            .collect::<Vec<_>>();
+        let mut best: Option<(P::State, usize, f64)> = None;
+
+        let mut generations_left = self.config.generation_count;
+        while generations_left > 0 && !self.budget.should_stop() {
+            generations_left -= 1;
+
+            let evaluated = generation
+                .iter()
+                .map(|state| (self.problem.score(state), self.problem.soft_cost(state)))
+                .collect::<Vec<_>>();
+            let ranking = match (handling, penalty.as_ref()) {
+                (_, Some(penalty)) => {
+                    let mut ranking = (0..evaluated.len()).collect::<Vec<_>>();
+                    ranking.sort_by(|&one, &other| {
+                        let energy = |i: usize| penalty.energy(evaluated[i].0, evaluated[i].1);
+                        energy(one).total_cmp(&energy(other))
+                    });
+                    ranking
+                }
+                (&ConstraintHandling::StochasticRanking { cost_probability }, None) => {
+                    constrained::stochastic_ranking(
+                        &evaluated,
+                        cost_probability,
+                        &mut self.rngs.selection,
+                    )
+                }
+                (&ConstraintHandling::AdaptivePenalty(..), None) => unreachable!(),
+            };
+
+            for (state, &member) in generation.iter().zip(&evaluated) {
+                if best
+                    .as_ref()
+                    .is_none_or(|best| constrained::is_better(member, (best.1, best.2)))
+                {
+                    best = Some((state.clone(), member.0, member.1));
+                }
+            }
+            let leader = ranking[0];
+            callback(&generation[leader], evaluated[leader].0);
+            if let Some(ref mut penalty) = penalty {
+                penalty.record(evaluated[leader].0 == 0);
+            }
+
+            let ranked = ranking
+                .iter()
+                .map(|&i| generation[i].clone())
+                .collect::<Vec<_>>();
+            generation = self.breed(&ranked, elite);
+        }
+
+        let (state, score) = match best {
+            Some((state, score, _)) => (state, score),
+            None => {
+                let state = generation.swap_remove(0);
+                let score = self.problem.score(&state);
+                (state, score)
+            }
+        };
+        Outcome::new(state, score, &self.budget, self.rngs.master_seed)
+    }
+}
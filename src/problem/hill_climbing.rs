@@ -0,0 +1,310 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Hill climbing over any problem: take neighbors that improve the score,
+//! and some that don't, restarting from a random state when stuck.
+
+use problem::moves::{MoveContext, Moves, WholeStates};
+use problem::{Outcome, Problem};
+use std::mem;
+use *;
+
+pub struct GenericHillClimbingConfig {
+    /// How many times the search can be restarted from a fresh random
+    /// state after getting stuck.
+    pub restarts: usize,
+    /// Whether to keep restarting after a solution is found, in order to
+    /// collect more of them in `Outcome::solutions`.
+    pub exhaust_restarts: bool,
+    /// A climb is stuck once more than this many moves in a row failed to
+    /// improve the score.
+    pub max_iterations_without_improvement: usize,
+    /// Probability of accepting a move that doesn't improve the score
+    /// anyway, WalkSAT-style, to escape local minima.
+    pub random_walk_probability: f32,
+    /// Stop once this many states have been scored across the whole run,
+    /// counting incremental evaluations too, if set.
+    pub max_evaluations: Option<usize>,
+    /// The master seed of the random streams, so that runs can be
+    /// reproduced, or a random one if unset.
+    pub seed: Option<u64>,
+    /// Limits on the whole solve, and a way to cancel it.
+    pub budget: SolveBudget,
+}
+
+impl Default for GenericHillClimbingConfig {
+    fn default() -> Self {
+        GenericHillClimbingConfig {
+            restarts: 100,
+            exhaust_restarts: false,
+            max_iterations_without_improvement: 1000,
+            random_walk_probability: 0.05,
+            max_evaluations: None,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+}
+
+pub struct GenericHillClimbing<P: Problem, M: Moves<P> = WholeStates> {
+    problem: P,
+    moves: M,
+    pub(crate) rngs: ComponentRngs,
+    pub(crate) effort: SearchEffort,
+    pub(crate) budget: BudgetTracker,
+    /// The state the search is at, which climbs start from.
+    pub(crate) state: P::State,
+    score: usize,
+    /// The current climb, or `None` before starting the next one.
+    climb: Option<Climb<P::State>>,
+    restarts_left: usize,
+    /// The best state any climb ended on, and its score.
+    best: Option<(P::State, usize)>,
+    /// The state the last climb ended on, before going back to the best
+    /// one it saw.
+    final_state: Option<(P::State, usize)>,
+    /// The solutions climbs ended on.
+    solutions: Vec<P::State>,
+    config: GenericHillClimbingConfig,
+}
+
+/// How a climb is going.
+struct Climb<S> {
+    iteration: usize,
+    iterations_without_improvement: usize,
+    /// Random walk steps can make things worse, so this is the best state
+    /// seen during the climb, or `None` while it's the current one.
+    best: Option<(S, usize)>,
+    best_score: usize,
+}
+
+impl<P: Problem> GenericHillClimbing<P> {
+    pub fn new(problem: P, config: GenericHillClimbingConfig) -> Self {
+        Self::with_moves(problem, WholeStates, config)
+    }
+}
+
+impl<P: Problem, M: Moves<P>> GenericHillClimbing<P, M> {
+    /// Like `new`, but drawing moves from `moves` rather than taking whole
+    /// neighbor states.
+    pub fn with_moves(problem: P, moves: M, config: GenericHillClimbingConfig) -> Self {
+        let mut rngs = ComponentRngs::for_seed(config.seed);
+        let state = problem.random_state(&mut rngs.initializer);
+        GenericHillClimbing {
+            problem,
+            moves,
+            rngs,
+            effort: SearchEffort::default(),
+            budget: BudgetTracker::new(config.budget.clone()),
+            state,
+            score: 0,
+            climb: None,
+            restarts_left: config.restarts,
+            best: None,
+            final_state: None,
+            solutions: vec![],
+            config,
+        }
+    }
+
+    pub fn problem(&self) -> &P {
+        &self.problem
+    }
+
+    /// Returns the state the search is at, and its score, which is only
+    /// meaningful once climbing.
+    pub fn current(&self) -> (&P::State, usize) {
+        (&self.state, self.score)
+    }
+
+    /// Returns the state the last climb ended on, before going back to the
+    /// best one it saw, once a climb ended.
+    pub fn final_state(&self) -> Option<&(P::State, usize)> {
+        self.final_state.as_ref()
+    }
+
+    /// How many times the search was restarted so far.
+    pub fn restarts(&self) -> usize {
+        self.config.restarts - self.restarts_left
+    }
+
+    pub fn solve(self) -> Outcome<P::State> {
+        self.solve_with_callback(|_, _| {})
+    }
+
+    /// Solves the problem, calling `callback` with each state moved to and
+    /// its score.
+    pub fn solve_with_callback<F>(mut self, mut callback: F) -> Outcome<P::State>
+    where
+        F: FnMut(&P::State, usize),
+    {
+        loop {
+            if let Some(outcome) = self.advance(&mut callback) {
+                return outcome;
+            }
+        }
+    }
+
+    /// Runs the next iteration of the search, which either starts climbing
+    /// from the current state, tries a move, or ends the climb and
+    /// restarts, and returns how the search ended if it's over.
+    pub(crate) fn advance<F>(&mut self, callback: &mut F) -> Option<Outcome<P::State>>
+    where
+        F: FnMut(&P::State, usize),
+    {
+        let mut climb = match self.climb.take() {
+            Some(climb) => climb,
+            None => {
+                self.start_climb(callback);
+                return None;
+            }
+        };
+
+        if self.score != 0
+            && climb.iterations_without_improvement
+                <= self.config.max_iterations_without_improvement
+            && !self.effort.exhausts(self.config.max_evaluations)
+            && !self.budget.should_stop()
+        {
+            self.try_move(&mut climb, callback);
+            self.climb = Some(climb);
+            return None;
+        }
+
+        self.final_state = Some((self.state.clone(), self.score));
+        if let Some((state, score)) = climb.best {
+            if score < self.score {
+                self.state = state;
+                self.score = score;
+            }
+        }
+
+        let score = self.score;
+        if score == 0 {
+            self.solutions.push(self.state.clone());
+        }
+        if self
+            .best
+            .as_ref()
+            .is_none_or(|&(_, best_score)| score < best_score)
+        {
+            self.best = Some((self.state.clone(), score));
+        }
+
+        if self.restarts_left == 0
+            || (score == 0 && !self.config.exhaust_restarts)
+            || self.effort.exhausts(self.config.max_evaluations)
+            || self.budget.stopped()
+        {
+            let (state, score) = self.best.take().unwrap();
+            let mut outcome = Outcome::new(state, score, &self.budget, self.rngs.master_seed);
+            outcome.solutions = mem::take(&mut self.solutions);
+            return Some(outcome);
+        }
+
+        self.restarts_left -= 1;
+        self.state = self.problem.random_state(&mut self.rngs.initializer);
+        None
+    }
+
+    /// Starts climbing from the current state.
+    fn start_climb<F>(&mut self, callback: &mut F)
+    where
+        F: FnMut(&P::State, usize),
+    {
+        self.score = self.problem.score(&self.state);
+        self.effort.evaluations += 1;
+        callback(&self.state, self.score);
+
+        self.moves.reset(&self.problem, &self.state);
+        self.moves.visit(&self.problem, &self.state);
+        self.climb = Some(Climb {
+            iteration: 0,
+            iterations_without_improvement: 0,
+            best: None,
+            best_score: self.score,
+        });
+    }
+
+    /// Tries the next move of `climb`, and takes it if it's good enough.
+    fn try_move<F>(&mut self, climb: &mut Climb<P::State>, callback: &mut F)
+    where
+        F: FnMut(&P::State, usize),
+    {
+        use rand::Rng;
+
+        let current_score = self.score;
+        let context = MoveContext {
+            iteration: climb.iteration,
+            score: current_score,
+            temperature: 0.,
+            starting_temperature: 0.,
+            cooling_schedule: None,
+        };
+        let applied = self.moves.apply(
+            &self.problem,
+            &mut self.state,
+            &context,
+            &mut self.rngs.neighborhood,
+        );
+        climb.iteration += 1;
+
+        let score = self
+            .moves
+            .score_after(&self.problem, &self.state, &applied, current_score);
+        self.effort.evaluations += 1;
+        if score < current_score {
+            climb.iterations_without_improvement = 0;
+            if score < climb.best_score {
+                climb.best_score = score;
+                climb.best = None;
+            }
+        } else if score == current_score
+            && self
+                .moves
+                .breaks_tie(
+                    &self.problem,
+                    &mut self.state,
+                    &applied,
+                    &mut self.rngs.acceptance,
+                )
+                .unwrap_or_else(|| self.rngs.acceptance.next_f32() < 0.5)
+        {
+            // A sideways move along a plateau.
+            climb.iterations_without_improvement += 1;
+        } else if self.rngs.acceptance.next_f32() < self.config.random_walk_probability {
+            // Take a random step regardless of the score.
+            if climb.best.is_none() && current_score == climb.best_score {
+                let mut previous = self.state.clone();
+                self.moves
+                    .undo(&self.problem, &mut previous, applied.clone());
+                climb.best = Some((previous, climb.best_score));
+            }
+            climb.iterations_without_improvement += 1;
+        } else {
+            climb.iterations_without_improvement += 1;
+            self.moves.undo(&self.problem, &mut self.state, applied);
+            return;
+        }
+
+        self.effort.accepted_moves += 1;
+        self.score = score;
+        self.moves.accept(&self.problem, &self.state, &applied);
+        self.moves.visit(&self.problem, &self.state);
+        callback(&self.state, score);
+    }
+}
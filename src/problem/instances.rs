@@ -0,0 +1,178 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Random instances of the variants of n-queens, for benchmarks and tests
+//! that need more than empty boards.
+//!
+//! Each instance is built around a random solution, which is returned along
+//! with it, so that it's known to be solvable.
+
+use problem::{NQueensCompletion, WeightedNQueens};
+use solvers::constraint_propagation::{ColumnOrder, SymmetryBreaking};
+use *;
+
+/// A generated problem, and a solution to it.
+#[derive(Clone, Debug)]
+pub struct Instance<P> {
+    pub problem: P,
+    pub solution: Vec<usize>,
+}
+
+/// Generates random instances from a seed.
+pub struct InstanceGenerator {
+    rng: StreamRng,
+    master_seed: u64,
+}
+
+impl InstanceGenerator {
+    /// Creates a generator whose instances depend only on `seed`, or a
+    /// random one if unset.
+    pub fn new(seed: Option<u64>) -> Self {
+        let rngs = ComponentRngs::for_seed(seed);
+        InstanceGenerator {
+            rng: rngs.initializer,
+            master_seed: rngs.master_seed,
+        }
+    }
+
+    /// Returns the seed to create a generator that makes the same
+    /// instances.
+    pub fn master_seed(&self) -> u64 {
+        self.master_seed
+    }
+
+    /// Returns a random solution for a board of `size`.
+    fn random_solution(&mut self, size: usize) -> Result<Vec<usize>, Error> {
+        use rand::Rng;
+
+        if validation::known_solution_count(size) == Some(0) {
+            return Err(Error::Infeasible(size));
+        }
+        let config = ConstraintPropagationConfig {
+            randomize_rows: true,
+            column_order: ColumnOrder::LeftToRight,
+            symmetry_breaking: SymmetryBreaking::None,
+            restarts: 0,
+            restart_backtracks: 0,
+            max_nogood_size: 0,
+            seed: Some(self.rng.next_u64()),
+            budget: SolveBudget::default(),
+        };
+        Ok(ConstraintPropagation::new(size, config)
+            .solve()
+            .queen_rows()
+            .to_vec())
+    }
+
+    /// Returns `count` distinct columns of a board of `size`, clamped to
+    /// the size.
+    fn random_columns(&mut self, size: usize, count: usize) -> Vec<usize> {
+        use rand::Rng;
+
+        let mut columns = (0..size).collect::<Vec<_>>();
+        self.rng.shuffle(&mut columns);
+        columns.truncate(count);
+        columns
+    }
+
+    /// Returns a board of `size` with `pinned` queens of a random solution
+    /// already placed.
+    pub fn preplaced(
+        &mut self,
+        size: usize,
+        pinned: usize,
+    ) -> Result<Instance<NQueensCompletion>, Error> {
+        let solution = self.random_solution(size)?;
+        let mut problem = NQueensCompletion::new(size);
+        for column in self.random_columns(size, pinned) {
+            problem.pin(column, solution[column]);
+        }
+        Ok(Instance { problem, solution })
+    }
+
+    /// Returns a board of `size` with `blocked` random squares blocked,
+    /// clamped so that every column keeps a square for a queen.
+    pub fn blocked(
+        &mut self,
+        size: usize,
+        blocked: usize,
+    ) -> Result<Instance<NQueensCompletion>, Error> {
+        use rand::Rng;
+
+        let solution = self.random_solution(size)?;
+        let mut free = (0..size)
+            .flat_map(|column| (0..size).map(move |row| (column, row)))
+            .filter(|&(column, row)| solution[column] != row)
+            .collect::<Vec<_>>();
+        self.rng.shuffle(&mut free);
+
+        let mut problem = NQueensCompletion::new(size);
+        for &(column, row) in free.iter().take(blocked) {
+            problem.block(column, row);
+        }
+        Ok(Instance { problem, solution })
+    }
+
+    /// Returns a board of `size` where each square costs between zero and
+    /// one, at random. The solution isn't necessarily the cheapest one.
+    pub fn weighted(&mut self, size: usize) -> Result<Instance<WeightedNQueens>, Error> {
+        use rand::Rng;
+
+        let solution = self.random_solution(size)?;
+        let rng = &mut self.rng;
+        let costs = (0..size)
+            .map(|_| (0..size).map(|_| rng.next_f64()).collect())
+            .collect();
+        Ok(Instance {
+            problem: WeightedNQueens::new(costs),
+            solution,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use problem::Problem;
+
+    #[test]
+    fn generates_solvable_instances() {
+        let mut generator = InstanceGenerator::new(Some(9));
+        let preplaced = generator.preplaced(12, 4).unwrap();
+        assert_eq!(preplaced.problem.score(&preplaced.solution), 0);
+        assert_eq!(
+            (0..12)
+                .filter(|&column| preplaced.problem.pinned(column).is_some())
+                .count(),
+            4
+        );
+
+        let blocked = generator.blocked(12, 50).unwrap();
+        assert_eq!(blocked.problem.score(&blocked.solution), 0);
+        let mut rng = stream_rng(1, RngStream::Neighborhood);
+        let state = blocked.problem.random_state(&mut rng);
+        assert!((0..12).all(|column| !blocked.problem.is_blocked(column, state[column])));
+
+        let weighted = generator.weighted(8).unwrap();
+        assert_eq!(weighted.problem.score(&weighted.solution), 0);
+        assert!(generator.preplaced(3, 1).is_err());
+
+        // The same seed makes the same instances.
+        let mut again = InstanceGenerator::new(Some(9));
+        assert_eq!(again.preplaced(12, 4).unwrap().solution, preplaced.solution);
+    }
+}
@@ -0,0 +1,356 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Local beam search over any problem: keep the best few states among the
+//! neighbors of the current ones, reseeding the beam when it stops
+//! improving.
+//!
+//! The n-queens local beam search in `solvers` is this one over `NQueens`,
+//! scoring the swaps out of the beam incrementally through a `Successors`
+//! hook, which also keeps the beam diverse and takes its snapshots.
+
+use problem::moves::{Successors, WholeStates};
+use problem::{Outcome, Problem};
+use solvers::local_beam_search::{BeamReseeding, BeamWidthSchedule};
+use std::collections::BinaryHeap;
+use std::{cmp, mem};
+use *;
+
+pub struct GenericLocalBeamSearchConfig {
+    /// How many states to keep.
+    pub state_count: usize,
+    pub schedule: BeamWidthSchedule,
+    /// What to do when the beam stops improving, if anything.
+    pub reseeding: Option<BeamReseeding>,
+    /// Give up once the best score hasn't improved for this many
+    /// iterations, if set.
+    pub max_iterations_without_improvement: Option<usize>,
+    /// Stop once this many states have been scored across the whole run,
+    /// counting incremental evaluations too, if set.
+    pub max_evaluations: Option<usize>,
+    /// The master seed of the random streams, so that runs can be
+    /// reproduced, or a random one if unset.
+    pub seed: Option<u64>,
+    /// Limits on the whole solve, and a way to cancel it.
+    pub budget: SolveBudget,
+}
+
+impl Default for GenericLocalBeamSearchConfig {
+    fn default() -> Self {
+        GenericLocalBeamSearchConfig {
+            state_count: 8,
+            schedule: BeamWidthSchedule::Fixed,
+            // Start over from random states when stuck.
+            reseeding: Some(BeamReseeding {
+                stagnant_iterations: 10,
+                keep: 0,
+                mutation_swaps: 0,
+            }),
+            max_iterations_without_improvement: Some(100),
+            max_evaluations: None,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+}
+
+/// A successor found while scoring the moves out of the beam, ordered by
+/// score, and then by the order it was found in.
+struct Candidate<M> {
+    score: usize,
+    /// The index in the beam of the state it moves from.
+    index: usize,
+    /// How many moves out of that state were found before it.
+    order: usize,
+    chosen: M,
+}
+
+impl<M> Candidate<M> {
+    fn key(&self) -> (usize, usize, usize) {
+        (self.score, self.index, self.order)
+    }
+}
+
+impl<M> PartialEq for Candidate<M> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl<M> Eq for Candidate<M> {}
+
+impl<M> PartialOrd for Candidate<M> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<M> Ord for Candidate<M> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+pub struct GenericLocalBeamSearch<P: Problem, S: Successors<P> = WholeStates> {
+    problem: P,
+    pub(crate) successors: S,
+    pub(crate) rngs: ComponentRngs,
+    pub(crate) effort: SearchEffort,
+    pub(crate) budget: BudgetTracker,
+    /// How many times the beam was reseeded.
+    pub(crate) reseeds: usize,
+    config: GenericLocalBeamSearchConfig,
+}
+
+impl<P: Problem> GenericLocalBeamSearch<P> {
+    pub fn new(problem: P, config: GenericLocalBeamSearchConfig) -> Self {
+        Self::with_successors(problem, WholeStates, config)
+    }
+}
+
+impl<P: Problem, S: Successors<P>> GenericLocalBeamSearch<P, S> {
+    /// Like `new`, but scoring and building successors through
+    /// `successors`.
+    pub fn with_successors(
+        problem: P,
+        successors: S,
+        config: GenericLocalBeamSearchConfig,
+    ) -> Self {
+        GenericLocalBeamSearch {
+            problem,
+            successors,
+            rngs: ComponentRngs::for_seed(config.seed),
+            effort: SearchEffort::default(),
+            budget: BudgetTracker::new(config.budget.clone()),
+            reseeds: 0,
+            config,
+        }
+    }
+
+    pub fn problem(&self) -> &P {
+        &self.problem
+    }
+
+    /// Returns the best `count` successors of `states`, whose scores are
+    /// `scores`, best first, with their scores. Only the ones returned are
+    /// built.
+    pub(crate) fn best_successors(
+        &mut self,
+        states: &mut [P::State],
+        scores: &[usize],
+        count: usize,
+    ) -> Vec<(P::State, usize)> {
+        // The worst successor kept is on top, so that ties are broken in the
+        // order the successors are found.
+        let mut heap = BinaryHeap::with_capacity(count + 1);
+        let mut evaluations = 0;
+        for (index, state) in states.iter_mut().enumerate() {
+            let mut order = 0;
+            self.successors
+                .scored_moves(&self.problem, state, scores[index], |chosen, score| {
+                    evaluations += 1;
+                    let candidate = Candidate {
+                        score,
+                        index,
+                        order,
+                        chosen,
+                    };
+                    order += 1;
+                    if heap.len() == count && heap.peek().is_some_and(|worst| candidate >= *worst) {
+                        return;
+                    }
+                    heap.push(candidate);
+                    if heap.len() > count {
+                        heap.pop();
+                    }
+                });
+        }
+        self.effort.evaluations += evaluations;
+
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|candidate| {
+                let successor = self.successors.successor(
+                    &self.problem,
+                    &states[candidate.index],
+                    candidate.chosen,
+                );
+                (successor, candidate.score)
+            })
+            .collect()
+    }
+
+    /// Keeps the best states of the beam, and replaces the rest with
+    /// fresh ones according to `reseeding`.
+    fn reseed(&mut self, states: &mut Vec<P::State>, reseeding: &BeamReseeding) {
+        let width = states.len();
+        let keep = cmp::min(reseeding.keep, width);
+        self.successors.recycle(states.drain(keep..));
+
+        for i in keep..width {
+            let state = if reseeding.mutation_swaps == 0 || keep == 0 {
+                self.problem.random_state(&mut self.rngs.initializer)
+            } else {
+                let mut state = self
+                    .problem
+                    .random_neighbor(&states[i % keep], &mut self.rngs.mutation);
+                for _ in 1..reseeding.mutation_swaps {
+                    let neighbor = self
+                        .problem
+                        .random_neighbor(&state, &mut self.rngs.mutation);
+                    self.successors
+                        .recycle(Some(mem::replace(&mut state, neighbor)));
+                }
+                state
+            };
+            states.push(state);
+        }
+    }
+
+    /// Returns the width the beam should have for the next iteration.
+    pub(crate) fn next_width(&self, width: usize, improved: bool) -> usize {
+        match self.config.schedule {
+            BeamWidthSchedule::Fixed => width,
+            BeamWidthSchedule::Shrinking {
+                min_state_count,
+                factor,
+            } => cmp::max(
+                cmp::max(min_state_count, 1),
+                (width as f32 * factor) as usize,
+            ),
+            BeamWidthSchedule::WidenOnStagnation {
+                max_state_count,
+                factor,
+            } => {
+                if improved {
+                    self.config.state_count
+                } else {
+                    cmp::min(max_state_count, (width as f32 * factor).ceil() as usize)
+                }
+            }
+        }
+    }
+
+    pub fn solve(self) -> Outcome<P::State> {
+        self.solve_with_callback(|_, _| {})
+    }
+
+    /// Solves the problem, calling `callback` with the best state of each
+    /// iteration and its score.
+    ///
+    /// Panics if `state_count` is zero.
+    pub fn solve_with_callback<F>(mut self, mut callback: F) -> Outcome<P::State>
+    where
+        F: FnMut(&P::State, usize),
+    {
+        self.search(&mut callback)
+    }
+
+    /// Searches until finding a solution or giving up, and returns the
+    /// best state found.
+    pub(crate) fn search<F>(&mut self, callback: &mut F) -> Outcome<P::State>
+    where
+        F: FnMut(&P::State, usize),
+    {
+        assert!(self.config.state_count > 0, "state count must be positive");
+
+        let mut width = self.config.state_count;
+        let mut best: Option<(P::State, usize)> = None;
+        let mut best_score = usize::MAX;
+        let mut stagnant_iterations = 0;
+        let mut iterations_since_best = 0;
+
+        let mut states = Vec::with_capacity(width);
+        for _ in 0..width {
+            states.push(self.problem.random_state(&mut self.rngs.initializer));
+        }
+
+        'search: loop {
+            let mut improved = false;
+
+            let scores = states
+                .iter()
+                .map(|state| self.problem.score(state))
+                .collect::<Vec<_>>();
+            self.effort.evaluations += scores.len();
+            self.successors
+                .record_beam(self.effort.generations, &states, &scores);
+
+            // First, see if one of the states is a solution. If so, stop.
+            for (index, (state, &score)) in states.iter().zip(&scores).enumerate() {
+                if score < best_score {
+                    best_score = score;
+                    best = Some((state.clone(), score));
+                    improved = true;
+                }
+
+                // FIXME(emilio): We only visualize the first state,
+                // which is... not great.
+                if index == 0 || score == 0 {
+                    callback(state, score);
+                }
+
+                if score == 0 {
+                    break 'search;
+                }
+            }
+
+            if improved {
+                iterations_since_best = 0;
+            }
+            let gives_up = self
+                .config
+                .max_iterations_without_improvement
+                .is_some_and(|max| iterations_since_best >= max);
+            if self.effort.exhausts(self.config.max_evaluations)
+                || self.budget.should_stop()
+                || gives_up
+            {
+                break;
+            }
+
+            // TODO(emilio): This recomputes the score of the states we
+            // keep on the next iteration, but oh well.
+            width = self.next_width(width, improved);
+            let count = self.successors.candidates(width);
+            let candidates = self.best_successors(&mut states, &scores, count);
+            let selected = self.successors.select(&self.problem, candidates, width);
+            self.successors.recycle(mem::replace(&mut states, selected));
+            self.effort.accepted_moves += states.len();
+            self.effort.generations += 1;
+
+            if improved {
+                stagnant_iterations = 0;
+            } else {
+                stagnant_iterations += 1;
+                iterations_since_best += 1;
+            }
+
+            if let Some(reseeding) = self.config.reseeding.clone() {
+                if stagnant_iterations >= reseeding.stagnant_iterations {
+                    self.reseed(&mut states, &reseeding);
+                    stagnant_iterations = 0;
+                    self.reseeds += 1;
+                }
+            }
+        }
+
+        self.successors.recycle(states);
+        let (state, score) = best.unwrap();
+        Outcome::new(state, score, &self.budget, self.rngs.master_seed)
+    }
+}
@@ -0,0 +1,270 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Local search over any problem, rather than just n-queens.
+//!
+//! A `Problem` says what a state looks like, how good it is, and which
+//! states are next to it, and the generic solvers here search it without
+//! knowing anything else. `NQueens` is the first implementation, and
+//! `WeightedNQueens` adds soft constraints to it, and `NQueensCompletion`
+//! pinned queens and blocked squares. There's also `Sudoku`, and
+//! `InstanceGenerator` makes random instances of the variants.
+//! For a one-off search, `anneal` takes the neighbors and the score as
+//! closures instead.
+//!
+//! The n-queens hill climbing, simulated annealing, local beam search and
+//! genetic algorithm in `solvers` are these ones over `NQueens`, plus what's
+//! specific to the board, like checkpoints and solution archives. They get
+//! their speed from the `Moves` and `Successors` hooks: by default, the
+//! solvers move to whole neighbor states and score them from scratch, but
+//! hooks can move in place and undo what isn't taken, score moves
+//! incrementally, like n-queens does with `ConflictCounters`, and break
+//! ties. Likewise, `Breeding` lets n-queens cross over and mutate
+//! placements its own way, and keep their genealogy.
+
+use *;
+
+pub mod constrained;
+pub mod genetic_algorithm;
+pub mod hill_climbing;
+pub mod instances;
+pub mod local_beam_search;
+pub mod moves;
+pub mod queens;
+pub mod simulated_annealing;
+pub mod sudoku;
+
+pub use self::constrained::{ConstraintHandling, PenaltyAdaptation, SoftConstrainedProblem};
+pub use self::genetic_algorithm::{
+    Breeding, GenericGeneticAlgorithm, GenericGeneticAlgorithmConfig, ProblemBreeding,
+};
+pub use self::hill_climbing::{GenericHillClimbing, GenericHillClimbingConfig};
+pub use self::instances::{Instance, InstanceGenerator};
+pub use self::local_beam_search::{GenericLocalBeamSearch, GenericLocalBeamSearchConfig};
+pub use self::moves::{MoveContext, Moves, Successors, WholeStates};
+pub use self::queens::{NQueens, NQueensCompletion, WeightedNQueens};
+pub use self::simulated_annealing::{
+    anneal, GenericSimulatedAnnealing, GenericSimulatedAnnealingConfig,
+};
+pub use self::sudoku::Sudoku;
+
+/// Something to search for a state that scores zero.
+pub trait Problem {
+    type State: Clone;
+
+    /// Returns a state to start searching from.
+    fn random_state(&self, rng: &mut StreamRng) -> Self::State;
+
+    /// Returns how far `state` is from being a solution, where zero means
+    /// it is one.
+    fn score(&self, state: &Self::State) -> usize;
+
+    /// Returns one of the neighbors of `state`, at random.
+    fn random_neighbor(&self, state: &Self::State, rng: &mut StreamRng) -> Self::State;
+
+    /// Returns all the neighbors of `state`.
+    fn neighbors(&self, state: &Self::State) -> Vec<Self::State>;
+}
+
+impl<P: Problem> Problem for &P {
+    type State = P::State;
+
+    fn random_state(&self, rng: &mut StreamRng) -> P::State {
+        (**self).random_state(rng)
+    }
+
+    fn score(&self, state: &P::State) -> usize {
+        (**self).score(state)
+    }
+
+    fn random_neighbor(&self, state: &P::State, rng: &mut StreamRng) -> P::State {
+        (**self).random_neighbor(state, rng)
+    }
+
+    fn neighbors(&self, state: &P::State) -> Vec<P::State> {
+        (**self).neighbors(state)
+    }
+}
+
+/// A problem whose states can be bred, for the genetic algorithm.
+pub trait EvolvableProblem: Problem {
+    /// Returns a child of `one` and `other`.
+    fn crossover(&self, one: &Self::State, other: &Self::State, rng: &mut StreamRng)
+        -> Self::State;
+
+    /// Changes `state` a bit, at random.
+    fn mutate(&self, state: &mut Self::State, rng: &mut StreamRng);
+}
+
+impl<P: EvolvableProblem> EvolvableProblem for &P {
+    fn crossover(&self, one: &P::State, other: &P::State, rng: &mut StreamRng) -> P::State {
+        (**self).crossover(one, other, rng)
+    }
+
+    fn mutate(&self, state: &mut P::State, rng: &mut StreamRng) {
+        (**self).mutate(state, rng)
+    }
+}
+
+/// How a generic solve ended.
+#[derive(Clone, Debug)]
+pub struct Outcome<S> {
+    /// The best state found.
+    pub state: S,
+    pub score: usize,
+    pub status: SolveStatus,
+    /// The iterations of the main loop of the solver.
+    pub iterations: usize,
+    /// The master seed of the random streams, to replay the run.
+    pub master_seed: u64,
+    /// The solutions found, in order, which is more than `state` when the
+    /// search was configured to keep restarting after the first one.
+    pub solutions: Vec<S>,
+}
+
+impl<S: Clone> Outcome<S> {
+    fn new(state: S, score: usize, budget: &BudgetTracker, master_seed: u64) -> Self {
+        Outcome {
+            solutions: if score == 0 {
+                vec![state.clone()]
+            } else {
+                vec![]
+            },
+            state,
+            score,
+            status: budget.status(score == 0),
+            iterations: budget.iterations,
+            master_seed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generic_solvers_solve_queens() {
+        let problem = NQueens::new(8, Objective::AttackingPairs);
+        let budget = || SolveBudget {
+            max_iterations: Some(100_000),
+            ..SolveBudget::default()
+        };
+
+        let hc = GenericHillClimbingConfig {
+            seed: Some(1),
+            budget: budget(),
+            ..GenericHillClimbingConfig::default()
+        };
+        let outcome = GenericHillClimbing::new(&problem, hc).solve();
+        assert_eq!(outcome.status, SolveStatus::Solved);
+        assert_eq!(problem.score(&outcome.state), 0);
+
+        let sa = GenericSimulatedAnnealingConfig {
+            starting_temperature: 20.,
+            freezing_temperature: 1.,
+            cooling_schedule: Box::new(solvers::simulated_annealing::GeometricCooling {
+                factor: 0.001,
+            }),
+            restarts: 20,
+            seed: Some(2),
+            budget: budget(),
+            initial_acceptance: None,
+            ..GenericSimulatedAnnealingConfig::default()
+        };
+        let outcome = GenericSimulatedAnnealing::new(&problem, sa).solve();
+        assert_eq!((outcome.score, outcome.status), (0, SolveStatus::Solved));
+
+        let lbs = GenericLocalBeamSearchConfig {
+            seed: Some(3),
+            budget: budget(),
+            ..GenericLocalBeamSearchConfig::default()
+        };
+        let outcome = GenericLocalBeamSearch::new(&problem, lbs).solve();
+        assert_eq!(problem.score(&outcome.state), outcome.score);
+        assert_eq!(outcome.status, SolveStatus::Solved);
+
+        let ga = GenericGeneticAlgorithmConfig {
+            generation_size: 50,
+            mutation_probability: 0.3,
+            generation_count: 2000,
+            seed: Some(4),
+            budget: budget(),
+            ..GenericGeneticAlgorithmConfig::default()
+        };
+        let outcome = GenericGeneticAlgorithm::new(&problem, ga).solve();
+        assert_eq!(problem.score(&outcome.state), outcome.score);
+        assert!(outcome.iterations > 0);
+    }
+
+    #[test]
+    fn scores_queen_moves_incrementally() {
+        let problem = || NQueens::new(12, Objective::AttackingPairs);
+        let moves = queens::QueenMoves::new(
+            12,
+            MoveSelection::RandomSwap,
+            None,
+            solvers::simulated_annealing::Neighborhood::Swaps,
+            Some(TieBreaking::Random),
+        );
+        let config = GenericHillClimbingConfig {
+            random_walk_probability: 0.3,
+            seed: Some(6),
+            ..GenericHillClimbingConfig::default()
+        };
+        let (scorer, mut steps) = (problem(), 0);
+        GenericHillClimbing::with_moves(problem(), moves, config).solve_with_callback(
+            |state, score| {
+                assert_eq!(scorer.score(state), score);
+                steps += 1;
+            },
+        );
+        assert!(steps > 1);
+    }
+
+    #[test]
+    fn anneals_closures() {
+        use rand::Rng;
+
+        // Sorting by random swaps, with the inversions as the score.
+        let inversions = |v: &Vec<usize>| {
+            (0..v.len())
+                .map(|i| (i + 1..v.len()).filter(|&j| v[i] > v[j]).count())
+                .sum()
+        };
+        let swap = |v: &Vec<usize>, rng: &mut StreamRng| {
+            let mut v = v.clone();
+            let (i, j) = (rng.gen_range(0, v.len()), rng.gen_range(0, v.len()));
+            v.swap(i, j);
+            v
+        };
+        let config = GenericSimulatedAnnealingConfig {
+            starting_temperature: 5.,
+            freezing_temperature: 0.05,
+            cooling_schedule: Box::new(solvers::simulated_annealing::GeometricCooling {
+                factor: 0.001,
+            }),
+            restarts: 5,
+            seed: Some(5),
+            initial_acceptance: None,
+            ..GenericSimulatedAnnealingConfig::default()
+        };
+        let outcome = anneal(vec![5, 3, 8, 0, 9, 1, 7, 2, 6, 4], swap, inversions, config);
+        assert_eq!(outcome.status, SolveStatus::Solved);
+        assert_eq!(outcome.state, (0..10).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,192 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! How hill climbing, simulated annealing and local beam search move
+//! between states.
+//!
+//! A `Problem` hands out whole neighbor states, which the solvers can take
+//! or leave as they are, but problems that can do better, like n-queens
+//! with its `ConflictCounters`, can instead apply moves in place, score
+//! them from the score before them, and undo the ones that aren't taken.
+//! Likewise, local beam search scores every move out of the beam through
+//! `Successors`, and only builds the states it keeps.
+
+use problem::Problem;
+use solvers::simulated_annealing::CoolingSchedule;
+use std::mem;
+use *;
+
+/// How the search is going when it draws a move.
+pub struct MoveContext<'a> {
+    /// Moves tried since the climb or the annealing (re)started.
+    pub iteration: usize,
+    /// The score of the state to move from.
+    pub score: usize,
+    /// The current temperature, when annealing.
+    pub temperature: f32,
+    /// The temperature the annealing started from.
+    pub starting_temperature: f32,
+    /// The cooling schedule when annealing, whose visiting distribution can
+    /// size the moves.
+    pub cooling_schedule: Option<&'a dyn CoolingSchedule>,
+}
+
+/// Draws, scores and undoes moves between states of `P`.
+pub trait Moves<P: Problem> {
+    /// What's needed to undo a move.
+    type Move: Clone;
+
+    /// Called when the search goes to `state` other than by a move, like
+    /// when starting or restarting, so that whatever is kept up to date
+    /// with the current state can be recomputed.
+    fn reset(&mut self, _problem: &P, _state: &P::State) {}
+
+    /// Called with each state the search starts from or moves to.
+    fn visit(&mut self, _problem: &P, _state: &P::State) {}
+
+    /// Moves `state` to one of its neighbors, at random, and returns the
+    /// move.
+    fn apply(
+        &mut self,
+        problem: &P,
+        state: &mut P::State,
+        context: &MoveContext,
+        rng: &mut StreamRng,
+    ) -> Self::Move;
+
+    /// Returns the score of `state`, which `applied` moved to from a state
+    /// with score `score`. Scores it from scratch unless overridden to do
+    /// it incrementally.
+    fn score_after(
+        &mut self,
+        problem: &P,
+        state: &P::State,
+        _applied: &Self::Move,
+        _score: usize,
+    ) -> usize {
+        problem.score(state)
+    }
+
+    /// Takes `state` back to where it was before `applied`.
+    fn undo(&mut self, problem: &P, state: &mut P::State, applied: Self::Move);
+
+    /// Called when the search takes `applied`, which moved to `state`.
+    fn accept(&mut self, _problem: &P, _state: &P::State, _applied: &Self::Move) {}
+
+    /// Returns whether to take `applied`, which moved to `state` and left
+    /// the score as it was, or `None` to leave it to the search.
+    fn breaks_tie(
+        &mut self,
+        _problem: &P,
+        _state: &mut P::State,
+        _applied: &Self::Move,
+        _rng: &mut StreamRng,
+    ) -> Option<bool> {
+        None
+    }
+}
+
+/// Moves to the random neighbors of the problem, keeping the previous state
+/// around to undo them, or to all of them in turn for local beam search.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WholeStates;
+
+impl<P: Problem> Moves<P> for WholeStates {
+    type Move = P::State;
+
+    fn apply(
+        &mut self,
+        problem: &P,
+        state: &mut P::State,
+        _context: &MoveContext,
+        rng: &mut StreamRng,
+    ) -> P::State {
+        let neighbor = problem.random_neighbor(state, rng);
+        mem::replace(state, neighbor)
+    }
+
+    fn undo(&mut self, _problem: &P, state: &mut P::State, previous: P::State) {
+        *state = previous;
+    }
+}
+
+/// Scores every move out of the states of `P`, and builds the successors
+/// they lead to, for local beam search.
+pub trait Successors<P: Problem> {
+    /// What's needed to build a successor from the state it moves from.
+    type Move;
+
+    /// Calls `visit` with each move out of `state`, whose score is `score`,
+    /// and the score of the successor it leads to, always in the same
+    /// order. `state` can be changed meanwhile, as long as it's left as it
+    /// was.
+    fn scored_moves<F>(&mut self, problem: &P, state: &mut P::State, score: usize, visit: F)
+    where
+        F: FnMut(Self::Move, usize);
+
+    /// Builds the successor that `chosen` leads to from `state`.
+    fn successor(&mut self, problem: &P, state: &P::State, chosen: Self::Move) -> P::State;
+
+    /// How many of the best successors to build for a beam `width` states
+    /// wide.
+    fn candidates(&self, width: usize) -> usize {
+        width
+    }
+
+    /// Picks the `width` states of `candidates`, which are sorted by score
+    /// and come with it, that make up the next beam, best first.
+    fn select(
+        &mut self,
+        _problem: &P,
+        candidates: Vec<(P::State, usize)>,
+        width: usize,
+    ) -> Vec<P::State> {
+        let mut candidates = candidates.into_iter().map(|(state, _)| state);
+        let selected = candidates.by_ref().take(width).collect();
+        self.recycle(candidates);
+        selected
+    }
+
+    /// Called with each beam, best first, and the scores of its states.
+    fn record_beam(&mut self, _iteration: usize, _states: &[P::State], _scores: &[usize]) {}
+
+    /// Takes the states the search is done with, so that they can be
+    /// reused for the next successors.
+    fn recycle<I>(&mut self, _states: I)
+    where
+        I: IntoIterator<Item = P::State>,
+    {
+    }
+}
+
+impl<P: Problem> Successors<P> for WholeStates {
+    type Move = P::State;
+
+    fn scored_moves<F>(&mut self, problem: &P, state: &mut P::State, _score: usize, mut visit: F)
+    where
+        F: FnMut(P::State, usize),
+    {
+        for neighbor in problem.neighbors(state) {
+            let score = problem.score(&neighbor);
+            visit(neighbor, score);
+        }
+    }
+
+    fn successor(&mut self, _problem: &P, _state: &P::State, neighbor: P::State) -> P::State {
+        neighbor
+    }
+}
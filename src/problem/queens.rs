@@ -0,0 +1,650 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! N-queens as a generic problem, with a queen per column.
+
+use problem::{EvolvableProblem, MoveContext, Moves, Problem, SoftConstrainedProblem, Successors};
+use solvers::simulated_annealing::Neighborhood;
+use std::mem;
+use *;
+
+/// Placing `size` queens on a board so that none attacks another.
+///
+/// States are the row of the queen of each column. Random states and
+/// neighbors are permutations, so that only diagonals can conflict, but
+/// crossover can repeat rows.
+pub struct NQueens {
+    size: usize,
+    objective: Objective,
+}
+
+impl NQueens {
+    pub fn new(size: usize, objective: Objective) -> Self {
+        NQueens { size, objective }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn objective(&self) -> Objective {
+        self.objective
+    }
+}
+
+impl Problem for NQueens {
+    type State = Vec<usize>;
+
+    fn random_state(&self, rng: &mut StreamRng) -> Vec<usize> {
+        GenericChallengeState::new(self.size, rng).queen_rows
+    }
+
+    fn score(&self, state: &Vec<usize>) -> usize {
+        GenericChallengeState::evaluate_rows(self.size, state, self.objective)
+    }
+
+    /// Swaps the rows of two random queens.
+    fn random_neighbor(&self, state: &Vec<usize>, rng: &mut StreamRng) -> Vec<usize> {
+        let mut neighbor = GenericChallengeState {
+            size: self.size,
+            queen_rows: state.clone(),
+        };
+        if self.size > 1 {
+            let (one, other) = neighbor.get_two_random_queens(rng);
+            neighbor.queen_rows.swap(one, other);
+        }
+        neighbor.queen_rows
+    }
+
+    fn neighbors(&self, state: &Vec<usize>) -> Vec<Vec<usize>> {
+        let mut neighbors = Vec::with_capacity(self.size * self.size.saturating_sub(1) / 2);
+        for one in 0..self.size {
+            for other in one + 1..self.size {
+                let mut neighbor = state.clone();
+                neighbor.swap(one, other);
+                neighbors.push(neighbor);
+            }
+        }
+        neighbors
+    }
+}
+
+impl EvolvableProblem for NQueens {
+    /// Takes the rows of `one` up to a random column, and the rest from
+    /// `other`, like the genetic algorithm does.
+    fn crossover(&self, one: &Vec<usize>, other: &Vec<usize>, rng: &mut StreamRng) -> Vec<usize> {
+        use rand::Rng;
+
+        let split = rng.gen_range(0, self.size + 1);
+        one[..split]
+            .iter()
+            .chain(&other[split..])
+            .cloned()
+            .collect()
+    }
+
+    fn mutate(&self, state: &mut Vec<usize>, rng: &mut StreamRng) {
+        *state = self.random_neighbor(state, rng);
+    }
+}
+
+/// Moves queens around in place for `NQueens`, like the n-queens hill
+/// climbing and simulated annealing do, scoring attacking pairs
+/// incrementally and breaking ties by the queens moved.
+pub(crate) struct QueenMoves {
+    selector: MoveSelector,
+    /// If set, moves are drawn from it instead.
+    operator: Option<Box<dyn NeighborhoodOp>>,
+    /// Where moves are drawn from otherwise, which is just swaps for hill
+    /// climbing.
+    neighborhood: Neighborhood,
+    ties: Option<TieBreaker>,
+    /// Kept up to date with the current state when minimizing attacking
+    /// pairs, so that moves can be scored incrementally.
+    pub(crate) counters: Option<ConflictCounters>,
+}
+
+impl QueenMoves {
+    pub(crate) fn new(
+        size: usize,
+        selection: MoveSelection,
+        operator: Option<Box<dyn NeighborhoodOp>>,
+        neighborhood: Neighborhood,
+        tie_breaking: Option<TieBreaking>,
+    ) -> Self {
+        QueenMoves {
+            selector: MoveSelector::new(selection),
+            operator,
+            neighborhood,
+            ties: tie_breaking.map(|policy| TieBreaker::new(policy, size)),
+            counters: None,
+        }
+    }
+}
+
+/// Runs `f` on a board with the queens of `queen_rows`, which are moved
+/// into it rather than copied, and back after.
+fn on_board<T, F>(queen_rows: &mut Vec<usize>, f: F) -> T
+where
+    F: FnOnce(&mut GenericChallengeState) -> T,
+{
+    let mut board = GenericChallengeState {
+        size: queen_rows.len(),
+        queen_rows: mem::take(queen_rows),
+    };
+    let result = f(&mut board);
+    *queen_rows = board.queen_rows;
+    result
+}
+
+impl Moves<NQueens> for QueenMoves {
+    type Move = QueenMove;
+
+    fn reset(&mut self, problem: &NQueens, state: &Vec<usize>) {
+        self.counters = match problem.objective {
+            Objective::AttackingPairs => Some(match self.counters.take() {
+                Some(mut counters) => {
+                    counters.recount(problem.size, state);
+                    counters
+                }
+                None => ConflictCounters::for_rows(problem.size, state),
+            }),
+            Objective::ConflictedQueens => None,
+        };
+    }
+
+    fn visit(&mut self, _: &NQueens, state: &Vec<usize>) {
+        self.selector.record_visit(state);
+    }
+
+    fn apply(
+        &mut self,
+        _: &NQueens,
+        state: &mut Vec<usize>,
+        context: &MoveContext,
+        rng: &mut StreamRng,
+    ) -> QueenMove {
+        use rand::Rng;
+
+        let (selector, operator, neighborhood) =
+            (&mut self.selector, &self.operator, &self.neighborhood);
+        on_board(state, |board| {
+            if let Some(ref operator) = *operator {
+                return operator.apply(board, rng);
+            }
+
+            let neighborhood = neighborhood.choose(context.iteration, context.score, rng);
+            if let Neighborhood::SingleQueenMoves = *neighborhood {
+                let column = board.random_queen_index(rng);
+                let from = board.queen_rows[column];
+                let to = (from + 1 + rng.next_u32() as usize % (board.size - 1)) % board.size;
+                board.queen_rows[column] = to;
+                return QueenMove::new(vec![(column, from, to)]);
+            }
+
+            let visiting_swaps = context.cooling_schedule.and_then(|schedule| {
+                let sample = rng.next_f32();
+                schedule.visiting_swaps(
+                    context.temperature,
+                    context.starting_temperature,
+                    board.size,
+                    sample,
+                )
+            });
+            let swaps = match visiting_swaps {
+                Some(count) => MoveSelector::apply_random_swaps(board, rng, count),
+                None => selector.apply_next(board, rng),
+            };
+            QueenMove::from_swaps(board, &swaps)
+        })
+    }
+
+    fn score_after(
+        &mut self,
+        problem: &NQueens,
+        state: &Vec<usize>,
+        applied: &QueenMove,
+        score: usize,
+    ) -> usize {
+        match self.counters {
+            Some(ref mut counters) => {
+                (score as isize + counters.score_delta_for_move(applied)) as usize
+            }
+            None => problem.score(state),
+        }
+    }
+
+    fn undo(&mut self, _: &NQueens, state: &mut Vec<usize>, applied: QueenMove) {
+        on_board(state, |board| applied.undo(board))
+    }
+
+    fn accept(&mut self, _: &NQueens, _: &Vec<usize>, applied: &QueenMove) {
+        if let Some(ref mut counters) = self.counters {
+            counters.record_move(applied);
+        }
+        if let Some(ref mut ties) = self.ties {
+            ties.record_move(applied);
+        }
+    }
+
+    fn breaks_tie(
+        &mut self,
+        _: &NQueens,
+        state: &mut Vec<usize>,
+        applied: &QueenMove,
+        rng: &mut StreamRng,
+    ) -> Option<bool> {
+        let (ties, counters) = (self.ties.as_ref()?, self.counters.as_ref());
+        Some(on_board(state, |board| {
+            ties.prefers_move(board, applied, counters, rng)
+        }))
+    }
+}
+
+/// Scores the swaps out of the beam for `NQueens`, like the n-queens local
+/// beam search does, incrementally when minimizing attacking pairs, and
+/// keeps the beam diverse and its snapshots.
+pub(crate) struct QueenSuccessors {
+    size: usize,
+    /// If non-zero, successors that differ from an already selected state
+    /// in fewer than this many queens are only selected if there aren't
+    /// enough different ones.
+    min_distance: usize,
+    /// If non-zero, keep a snapshot of the whole beam every this many
+    /// iterations.
+    snapshot_interval: usize,
+    pub(crate) snapshots: Vec<PopulationSnapshot>,
+    /// Spare placements, to build successors into.
+    pub(crate) spare: Vec<Vec<usize>>,
+}
+
+impl QueenSuccessors {
+    pub(crate) fn new(size: usize, min_distance: usize, snapshot_interval: usize) -> Self {
+        QueenSuccessors {
+            size,
+            min_distance,
+            snapshot_interval,
+            snapshots: vec![],
+            spare: vec![],
+        }
+    }
+}
+
+/// Returns the number of queens placed differently in both placements.
+fn distance(one: &[usize], other: &[usize]) -> usize {
+    one.iter()
+        .zip(other.iter())
+        .filter(|&(a, b)| a != b)
+        .count()
+}
+
+impl Successors<NQueens> for QueenSuccessors {
+    /// The queens to swap.
+    type Move = (usize, usize);
+
+    fn scored_moves<F>(
+        &mut self,
+        problem: &NQueens,
+        state: &mut Vec<usize>,
+        score: usize,
+        mut visit: F,
+    ) where
+        F: FnMut((usize, usize), usize),
+    {
+        let counters = match problem.objective {
+            Objective::AttackingPairs => Some(ConflictCounters::for_rows(problem.size, state)),
+            Objective::ConflictedQueens => None,
+        };
+        for one in 0..problem.size {
+            for other in one + 1..problem.size {
+                let score = match counters {
+                    Some(ref counters) => {
+                        let delta = counters.score_delta_for_swap(state, one, other);
+                        (score as isize + delta) as usize
+                    }
+                    None => {
+                        state.swap(one, other);
+                        let score = problem.score(state);
+                        state.swap(one, other);
+                        score
+                    }
+                };
+                visit((one, other), score);
+            }
+        }
+    }
+
+    fn successor(
+        &mut self,
+        _problem: &NQueens,
+        state: &Vec<usize>,
+        swap: (usize, usize),
+    ) -> Vec<usize> {
+        let mut successor = match self.spare.pop() {
+            Some(mut copy) => {
+                copy.clone_from(state);
+                copy
+            }
+            None => state.clone(),
+        };
+        successor.swap(swap.0, swap.1);
+        successor
+    }
+
+    /// Only the best successors, up to the beam width times the board
+    /// size, are considered when keeping the beam diverse.
+    fn candidates(&self, width: usize) -> usize {
+        if self.min_distance == 0 {
+            width
+        } else {
+            width.saturating_mul(self.size)
+        }
+    }
+
+    fn select(
+        &mut self,
+        _problem: &NQueens,
+        candidates: Vec<(Vec<usize>, usize)>,
+        width: usize,
+    ) -> Vec<Vec<usize>> {
+        if self.min_distance == 0 {
+            let mut candidates = candidates.into_iter().map(|(state, _)| state);
+            let selected = candidates.by_ref().take(width).collect();
+            self.recycle(candidates);
+            return selected;
+        }
+
+        let mut selected: Vec<(Vec<usize>, usize)> = Vec::with_capacity(width);
+        let mut too_similar = vec![];
+        let mut candidates = candidates.into_iter();
+        while selected.len() < width {
+            let candidate = match candidates.next() {
+                Some(candidate) => candidate,
+                None => break,
+            };
+
+            if selected
+                .iter()
+                .any(|s| distance(&s.0, &candidate.0) < self.min_distance)
+            {
+                too_similar.push(candidate);
+            } else {
+                selected.push(candidate);
+            }
+        }
+
+        // Fill the gaps, if any, with the best of the similar ones.
+        let missing = width.saturating_sub(selected.len());
+        let mut too_similar = too_similar.into_iter();
+        selected.extend(too_similar.by_ref().take(missing));
+        self.recycle(too_similar.chain(candidates).map(|(state, _)| state));
+        selected.sort_by_key(|&(_, score)| score);
+        selected.into_iter().map(|(state, _)| state).collect()
+    }
+
+    fn record_beam(&mut self, iteration: usize, states: &[Vec<usize>], scores: &[usize]) {
+        if PopulationSnapshot::is_due(iteration, self.snapshot_interval) {
+            let members = states
+                .iter()
+                .map(|state| &state[..])
+                .zip(scores.iter().cloned());
+            self.snapshots
+                .push(PopulationSnapshot::new(iteration, members));
+        }
+    }
+
+    fn recycle<I>(&mut self, states: I)
+    where
+        I: IntoIterator<Item = Vec<usize>>,
+    {
+        self.spare.extend(states)
+    }
+}
+
+/// N-queens where each square has a cost, and the placements without
+/// attacks are better the less their squares cost in total.
+pub struct WeightedNQueens {
+    queens: NQueens,
+    /// The cost of each square, by column and then row.
+    costs: Vec<Vec<f64>>,
+}
+
+impl WeightedNQueens {
+    /// Panics unless `costs` is square.
+    pub fn new(costs: Vec<Vec<f64>>) -> Self {
+        let size = costs.len();
+        assert!(
+            costs.iter().all(|column| column.len() == size),
+            "the costs must be a square"
+        );
+        WeightedNQueens {
+            queens: NQueens::new(size, Objective::AttackingPairs),
+            costs,
+        }
+    }
+}
+
+impl Problem for WeightedNQueens {
+    type State = Vec<usize>;
+
+    fn random_state(&self, rng: &mut StreamRng) -> Vec<usize> {
+        self.queens.random_state(rng)
+    }
+
+    fn score(&self, state: &Vec<usize>) -> usize {
+        self.queens.score(state)
+    }
+
+    fn random_neighbor(&self, state: &Vec<usize>, rng: &mut StreamRng) -> Vec<usize> {
+        self.queens.random_neighbor(state, rng)
+    }
+
+    fn neighbors(&self, state: &Vec<usize>) -> Vec<Vec<usize>> {
+        self.queens.neighbors(state)
+    }
+}
+
+impl EvolvableProblem for WeightedNQueens {
+    fn crossover(&self, one: &Vec<usize>, other: &Vec<usize>, rng: &mut StreamRng) -> Vec<usize> {
+        self.queens.crossover(one, other, rng)
+    }
+
+    fn mutate(&self, state: &mut Vec<usize>, rng: &mut StreamRng) {
+        self.queens.mutate(state, rng)
+    }
+}
+
+impl SoftConstrainedProblem for WeightedNQueens {
+    fn soft_cost(&self, state: &Vec<usize>) -> f64 {
+        state
+            .iter()
+            .enumerate()
+            .map(|(column, &row)| self.costs[column][row])
+            .sum()
+    }
+}
+
+/// N-queens where some queens are pinned to a square, and some squares
+/// can't have a queen, which is NP-complete unlike plain n-queens.
+///
+/// Random states and neighbors keep the pinned queens in place and stay
+/// off the blocked squares, so only attacks make the score of those
+/// nonzero. The queens of other states that break the restrictions add one
+/// each to the score.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NQueensCompletion {
+    size: usize,
+    /// The row of the queen pinned to each column, if any.
+    pinned: Vec<Option<usize>>,
+    /// Whether each square is blocked, by column and then row.
+    blocked: Vec<Vec<bool>>,
+}
+
+impl NQueensCompletion {
+    /// Returns a board of `size` without any restriction.
+    pub fn new(size: usize) -> Self {
+        NQueensCompletion {
+            size,
+            pinned: vec![None; size],
+            blocked: vec![vec![false; size]; size],
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Pins the queen of `column` to `row`.
+    pub fn pin(&mut self, column: usize, row: usize) {
+        self.pinned[column] = Some(row);
+    }
+
+    /// Forbids placing a queen at `column` and `row`.
+    pub fn block(&mut self, column: usize, row: usize) {
+        self.blocked[column][row] = true;
+    }
+
+    pub fn pinned(&self, column: usize) -> Option<usize> {
+        self.pinned[column]
+    }
+
+    pub fn is_blocked(&self, column: usize, row: usize) -> bool {
+        self.blocked[column][row]
+    }
+
+    /// Returns whether the queen of `column` can be at `row`.
+    pub fn allows(&self, column: usize, row: usize) -> bool {
+        !self.blocked[column][row] && self.pinned[column].is_none_or(|pinned| pinned == row)
+    }
+
+    /// Returns the rows the queen of `column` can be at.
+    pub fn allowed_rows(&self, column: usize) -> Vec<usize> {
+        (0..self.size)
+            .filter(|&row| self.allows(column, row))
+            .collect()
+    }
+
+    /// Returns whether swapping the rows of the queens of `one` and `other`
+    /// in `state` leaves both of them on squares they can be at.
+    pub fn allows_swap(&self, state: &[usize], one: usize, other: usize) -> bool {
+        self.allows(one, state[other]) && self.allows(other, state[one])
+    }
+
+    /// Moves the queens of `state` that break the restrictions to squares
+    /// they can be at. Each of them swaps rows with another queen if it can,
+    /// so that permutations stay permutations, and otherwise goes to one of
+    /// its allowed rows at random.
+    pub fn repair(&self, state: &mut [usize], rng: &mut StreamRng) {
+        use rand::Rng;
+
+        for column in 0..self.size {
+            if self.allows(column, state[column]) {
+                continue;
+            }
+            let other = (0..self.size)
+                .find(|&other| other != column && self.allows_swap(state, column, other));
+            match other {
+                Some(other) => state.swap(column, other),
+                None => {
+                    if let Some(&row) = rng.choose(&self.allowed_rows(column)) {
+                        state[column] = row;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the columns whose queen can move.
+    fn free_columns(&self) -> Vec<usize> {
+        (0..self.size)
+            .filter(|&column| self.pinned[column].is_none() && self.allowed_rows(column).len() > 1)
+            .collect()
+    }
+}
+
+impl Problem for NQueensCompletion {
+    type State = Vec<usize>;
+
+    /// Puts each queen at one of its allowed rows at random, or at the top
+    /// if there's none.
+    fn random_state(&self, rng: &mut StreamRng) -> Vec<usize> {
+        use rand::Rng;
+
+        (0..self.size)
+            .map(|column| *rng.choose(&self.allowed_rows(column)).unwrap_or(&0))
+            .collect()
+    }
+
+    fn score(&self, state: &Vec<usize>) -> usize {
+        let broken = state
+            .iter()
+            .enumerate()
+            .filter(|&(column, &row)| !self.allows(column, row))
+            .count();
+        GenericChallengeState::evaluate_rows(self.size, state, Objective::AttackingPairs) + broken
+    }
+
+    /// Moves a queen that isn't pinned to another of its allowed rows.
+    fn random_neighbor(&self, state: &Vec<usize>, rng: &mut StreamRng) -> Vec<usize> {
+        use rand::Rng;
+
+        let mut neighbor = state.clone();
+        if let Some(&column) = rng.choose(&self.free_columns()) {
+            let rows = self
+                .allowed_rows(column)
+                .into_iter()
+                .filter(|&row| row != state[column])
+                .collect::<Vec<_>>();
+            neighbor[column] = *rng.choose(&rows).unwrap();
+        }
+        neighbor
+    }
+
+    fn neighbors(&self, state: &Vec<usize>) -> Vec<Vec<usize>> {
+        let mut neighbors = vec![];
+        for column in self.free_columns() {
+            for row in self.allowed_rows(column) {
+                if row != state[column] {
+                    let mut neighbor = state.clone();
+                    neighbor[column] = row;
+                    neighbors.push(neighbor);
+                }
+            }
+        }
+        neighbors
+    }
+}
+
+impl EvolvableProblem for NQueensCompletion {
+    /// Takes the rows of `one` up to a random column, and the rest from
+    /// `other`, which keeps the restrictions if both parents do.
+    fn crossover(&self, one: &Vec<usize>, other: &Vec<usize>, rng: &mut StreamRng) -> Vec<usize> {
+        use rand::Rng;
+
+        let split = rng.gen_range(0, self.size + 1);
+        one[..split]
+            .iter()
+            .chain(&other[split..])
+            .cloned()
+            .collect()
+    }
+
+    fn mutate(&self, state: &mut Vec<usize>, rng: &mut StreamRng) {
+        *state = self.random_neighbor(state, rng);
+    }
+}
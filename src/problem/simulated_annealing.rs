@@ -0,0 +1,701 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Simulated annealing over any problem: take random neighbors, accepting
+//! the ones that make the score worse with a probability that decreases as
+//! the temperature cools down.
+//!
+//! The n-queens simulated annealing in `solvers` is this one over
+//! `NQueens`, moving queens in place through a `Moves` hook. The annealing
+//! freezes once it's below `freezing_temperature` and stops moving, where
+//! the freezing temperature depends on how much the scores of neighbors
+//! tend to differ: one for n-queens, less for finer scores. The
+//! constrained variant stops as soon as it's that cold, since it accepts
+//! by differences of penalized energies that aren't integers, and so
+//! doesn't use an `AcceptanceCriterion`.
+
+use problem::constrained::{self, AdaptivePenalty};
+use problem::moves::{MoveContext, Moves, WholeStates};
+use problem::{ConstraintHandling, Outcome, Problem, SoftConstrainedProblem};
+use solvers::simulated_annealing::{
+    AcceptanceCriterion, CoolingSchedule, GeometricCooling, Metropolis, Reheating, RestartPolicy,
+    MAX_ITERATIONS_WITHOUT_IMPROVEMENT,
+};
+use std::mem;
+use *;
+
+pub struct GenericSimulatedAnnealingConfig {
+    pub starting_temperature: f32,
+    /// The temperature below which the annealing can freeze, which depends
+    /// on how much the scores of neighbors tend to differ.
+    pub freezing_temperature: f32,
+    pub cooling_schedule: Box<dyn CoolingSchedule>,
+    /// Which moves that don't improve the score to take, or `Metropolis`
+    /// at `freezing_temperature` if unset.
+    pub acceptance: Option<Box<dyn AcceptanceCriterion>>,
+    /// If set, the annealing is reheated when it stops improving after
+    /// cooling down, rather than ending there.
+    pub reheating: Option<Reheating>,
+    /// How many times the search can be restarted from a fresh random
+    /// state and the starting temperature after freezing.
+    pub restarts: usize,
+    /// Whether to keep restarting after a solution is found, in order to
+    /// collect more of them in `Outcome::solutions`.
+    pub exhaust_restarts: bool,
+    /// Where reheats and restarts go on from.
+    pub restart_policy: RestartPolicy,
+    /// If present, `starting_temperature` is ignored, and instead
+    /// calibrated so that moves that worsen the initial state are
+    /// accepted with roughly this probability.
+    pub initial_acceptance: Option<f32>,
+    /// Stop once this many states have been scored across the whole run,
+    /// counting incremental evaluations too, if set.
+    pub max_evaluations: Option<usize>,
+    /// The master seed of the random streams, so that runs can be
+    /// reproduced, or a random one if unset.
+    pub seed: Option<u64>,
+    /// Limits on the whole solve, and a way to cancel it.
+    pub budget: SolveBudget,
+}
+
+impl Default for GenericSimulatedAnnealingConfig {
+    fn default() -> Self {
+        GenericSimulatedAnnealingConfig {
+            // Calibrated to the problem from `initial_acceptance` instead.
+            starting_temperature: 1.,
+            freezing_temperature: 1.,
+            cooling_schedule: Box::new(GeometricCooling { factor: 0.01 }),
+            acceptance: None,
+            reheating: None,
+            restarts: 2,
+            exhaust_restarts: false,
+            restart_policy: RestartPolicy::Fresh,
+            initial_acceptance: Some(0.5),
+            max_evaluations: None,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+}
+
+pub struct GenericSimulatedAnnealing<P: Problem, M: Moves<P> = WholeStates> {
+    problem: P,
+    pub(crate) moves: M,
+    pub(crate) rngs: ComponentRngs,
+    pub(crate) effort: SearchEffort,
+    pub(crate) budget: BudgetTracker,
+    /// The state the search is at, which annealings start from.
+    pub(crate) state: P::State,
+    /// The score of the current state, while annealing.
+    score: usize,
+    pub(crate) starting_temperature: f32,
+    /// The temperature the cooling started from, which is lower than the
+    /// starting one after reheating.
+    pub(crate) cooling_start: f32,
+    pub(crate) temperature: f32,
+    /// How many times the annealing was reheated, in all.
+    pub(crate) reheats: usize,
+    /// Iterations since the annealing last (re)started or was reheated.
+    pub(crate) iteration: usize,
+    /// Iterations since the annealing last took a move.
+    pub(crate) stagnant_iterations: usize,
+    /// How many more times the current annealing can be reheated.
+    pub(crate) reheats_left: usize,
+    /// How many times the search was restarted.
+    pub(crate) restarts: usize,
+    pub(crate) restarts_left: usize,
+    /// The current annealing, or `None` before starting the next one.
+    annealing: Option<Annealing<P::State>>,
+    /// The best state any annealing ended on, and its score.
+    pub(crate) best: Option<(P::State, usize)>,
+    /// The state the last annealing ended on, before going back to the
+    /// best one it saw.
+    final_state: Option<(P::State, usize)>,
+    /// The solutions annealings ended on.
+    solutions: Vec<P::State>,
+    config: GenericSimulatedAnnealingConfig,
+}
+
+/// How an annealing, from the start or a restart, is going.
+struct Annealing<S> {
+    /// Worsening moves can be accepted, so this is the best state seen
+    /// during the annealing, or `None` while it's the current one.
+    best: Option<(S, usize)>,
+    best_score: usize,
+    /// Iterations since the best score of the annealing last improved.
+    iterations_since_best: usize,
+}
+
+impl<P: Problem> GenericSimulatedAnnealing<P> {
+    pub fn new(problem: P, config: GenericSimulatedAnnealingConfig) -> Self {
+        Self::with_moves(problem, WholeStates, config)
+    }
+}
+
+impl<P: Problem, M: Moves<P>> GenericSimulatedAnnealing<P, M> {
+    /// Like `new`, but drawing moves from `moves` rather than taking whole
+    /// neighbor states.
+    pub fn with_moves(problem: P, moves: M, config: GenericSimulatedAnnealingConfig) -> Self {
+        let mut rngs = ComponentRngs::for_seed(config.seed);
+        let state = problem.random_state(&mut rngs.initializer);
+        let mut annealing = Self::with_state(problem, moves, rngs, state, config);
+
+        if let Some(acceptance) = annealing.config.initial_acceptance {
+            if let Some(temperature) = annealing.calibrate_temperature(acceptance) {
+                annealing.starting_temperature = temperature;
+                annealing.cooling_start = temperature;
+                annealing.temperature = temperature;
+            }
+        }
+
+        annealing
+    }
+
+    /// Returns an annealing from `state`, with the random streams `rngs`,
+    /// at the starting temperature of `config` as it is.
+    pub(crate) fn with_state(
+        problem: P,
+        moves: M,
+        rngs: ComponentRngs,
+        state: P::State,
+        config: GenericSimulatedAnnealingConfig,
+    ) -> Self {
+        GenericSimulatedAnnealing {
+            problem,
+            moves,
+            rngs,
+            effort: SearchEffort::default(),
+            budget: BudgetTracker::new(config.budget.clone()),
+            state,
+            score: 0,
+            starting_temperature: config.starting_temperature,
+            cooling_start: config.starting_temperature,
+            temperature: config.starting_temperature,
+            reheats: 0,
+            iteration: 0,
+            stagnant_iterations: 0,
+            reheats_left: config
+                .reheating
+                .as_ref()
+                .map_or(0, |reheating| reheating.max_reheats),
+            restarts: 0,
+            restarts_left: config.restarts,
+            annealing: None,
+            best: None,
+            final_state: None,
+            solutions: vec![],
+            config,
+        }
+    }
+
+    pub fn problem(&self) -> &P {
+        &self.problem
+    }
+
+    /// Returns the state the search is at, and its score, which is only
+    /// meaningful once annealing.
+    pub fn current(&self) -> (&P::State, usize) {
+        (&self.state, self.score)
+    }
+
+    pub fn temperature(&self) -> f32 {
+        self.temperature
+    }
+
+    /// Returns the best state seen so far, and its score, if any.
+    pub fn best(&self) -> Option<(P::State, usize)> {
+        let mut best = self.best.clone();
+        if let Some(ref annealing) = self.annealing {
+            let annealed = annealing
+                .best
+                .clone()
+                .unwrap_or_else(|| (self.state.clone(), self.score));
+            if best
+                .as_ref()
+                .is_none_or(|&(_, best_score)| annealed.1 < best_score)
+            {
+                best = Some(annealed);
+            }
+        }
+        best
+    }
+
+    /// Returns the state the last annealing ended on, before going back to
+    /// the best one it saw, once an annealing ended.
+    pub fn final_state(&self) -> Option<&(P::State, usize)> {
+        self.final_state.as_ref()
+    }
+
+    fn should_accept(&mut self, old_score: usize, new_score: usize) -> bool {
+        let rng = &mut self.rngs.acceptance;
+        match self.config.acceptance {
+            Some(ref mut acceptance) => {
+                acceptance.accepts(old_score, new_score, self.temperature, rng)
+            }
+            None => Metropolis {
+                freezing_temperature: self.config.freezing_temperature,
+            }
+            .accepts(old_score, new_score, self.temperature, rng),
+        }
+    }
+
+    /// Samples random moves from the current state, and returns the
+    /// temperature at which the average move that worsens it would be
+    /// accepted with probability `acceptance`, if any of them does.
+    fn calibrate_temperature(&mut self, acceptance: f32) -> Option<f32> {
+        const CALIBRATION_SAMPLES: usize = 100;
+
+        let score = self.problem.score(&self.state);
+        let mut worsening_moves = 0;
+        let mut total_worsening = 0;
+        for _ in 0..CALIBRATION_SAMPLES {
+            let applied = self.apply_next_move(score);
+            let new_score = self.problem.score(&self.state);
+            self.effort.evaluations += 1;
+            self.moves.undo(&self.problem, &mut self.state, applied);
+
+            if new_score > score {
+                worsening_moves += 1;
+                total_worsening += new_score - score;
+            }
+        }
+
+        if worsening_moves == 0 {
+            return None;
+        }
+
+        // exp(-delta / t) = acceptance <=> t = -delta / ln(acceptance).
+        let mean_worsening = total_worsening as f32 / worsening_moves as f32;
+        Some(-mean_worsening / acceptance.clamp(f32::EPSILON, 1. - f32::EPSILON).ln())
+    }
+
+    /// Applies the next move to try from a state with score `score`, and
+    /// returns it.
+    fn apply_next_move(&mut self, score: usize) -> M::Move {
+        let context = MoveContext {
+            iteration: self.iteration,
+            score,
+            temperature: self.temperature,
+            starting_temperature: self.starting_temperature,
+            cooling_schedule: Some(&*self.config.cooling_schedule),
+        };
+        self.moves.apply(
+            &self.problem,
+            &mut self.state,
+            &context,
+            &mut self.rngs.neighborhood,
+        )
+    }
+
+    pub fn solve(self) -> Outcome<P::State> {
+        self.solve_with_callback(|_, _| {})
+    }
+
+    /// Solves the problem, calling `callback` with each state moved to and
+    /// its score.
+    pub fn solve_with_callback<F>(self, mut callback: F) -> Outcome<P::State>
+    where
+        F: FnMut(&P::State, usize),
+    {
+        self.solve_with_temperatures(|state, score, _| callback(state, score))
+    }
+
+    /// Like `solve_with_callback`, but also passes `callback` the
+    /// temperature each step was taken at.
+    pub fn solve_with_temperatures<F>(mut self, mut callback: F) -> Outcome<P::State>
+    where
+        F: FnMut(&P::State, usize, f32),
+    {
+        loop {
+            if let Some(outcome) = self.advance(&mut callback) {
+                return outcome;
+            }
+        }
+    }
+
+    /// Runs the next iteration of the search, which either starts annealing
+    /// from the current state, tries a move, reheats, or ends the annealing
+    /// and restarts, and returns how the search ended if it's over.
+    pub(crate) fn advance<F>(&mut self, callback: &mut F) -> Option<Outcome<P::State>>
+    where
+        F: FnMut(&P::State, usize, f32),
+    {
+        let mut annealing = match self.annealing.take() {
+            Some(annealing) => annealing,
+            None => {
+                self.start_annealing(callback);
+                return None;
+            }
+        };
+
+        let frozen = match self.config.acceptance {
+            Some(ref acceptance) if !acceptance.freezes() => {
+                annealing.iterations_since_best > MAX_ITERATIONS_WITHOUT_IMPROVEMENT
+            }
+            _ => {
+                self.temperature < self.config.freezing_temperature
+                    && self.stagnant_iterations > MAX_ITERATIONS_WITHOUT_IMPROVEMENT
+            }
+        };
+        if self.score != 0
+            && !frozen
+            && !self.effort.exhausts(self.config.max_evaluations)
+            && !self.budget.should_stop()
+        {
+            self.try_move(&mut annealing, callback);
+            self.annealing = Some(annealing);
+            return None;
+        }
+
+        // Stagnant after cooling down, rather than solved or out of
+        // budget.
+        let stagnant = self.score != 0
+            && !self.effort.exhausts(self.config.max_evaluations)
+            && !self.budget.stopped();
+        let reheating = match self.config.reheating {
+            Some(ref reheating) if stagnant && self.reheats_left > 0 => Some(reheating.fraction),
+            _ => None,
+        };
+        if let Some(fraction) = reheating {
+            if self.config.restart_policy == RestartPolicy::FromBest {
+                if let Some((state, best_score)) = annealing.best.take() {
+                    self.state = state;
+                    self.score = best_score;
+                    self.moves.reset(&self.problem, &self.state);
+                    callback(&self.state, self.score, self.temperature);
+                }
+            }
+            self.reheats_left -= 1;
+            self.reheats += 1;
+            self.cooling_start = self.starting_temperature * fraction;
+            self.temperature = self.cooling_start;
+            self.iteration = 0;
+            self.stagnant_iterations = 0;
+            annealing.iterations_since_best = 0;
+            self.annealing = Some(annealing);
+            return None;
+        }
+
+        // Go back to the best state the annealing saw.
+        self.final_state = Some((self.state.clone(), self.score));
+        if let Some((state, best_score)) = annealing.best {
+            if best_score < self.score {
+                self.state = state;
+                self.score = best_score;
+            }
+        }
+
+        let score = self.score;
+        if score == 0 {
+            self.solutions.push(self.state.clone());
+        }
+        if self
+            .best
+            .as_ref()
+            .is_none_or(|&(_, best_score)| score < best_score)
+        {
+            self.best = Some((self.state.clone(), score));
+        }
+
+        if self.restarts_left == 0
+            || (score == 0 && !self.config.exhaust_restarts)
+            || self.effort.exhausts(self.config.max_evaluations)
+            || self.budget.stopped()
+        {
+            let (state, score) = self.best.take().unwrap();
+            let mut outcome = Outcome::new(state, score, &self.budget, self.rngs.master_seed);
+            outcome.solutions = mem::take(&mut self.solutions);
+            return Some(outcome);
+        }
+
+        self.restarts_left -= 1;
+        self.restarts += 1;
+        self.state = match self.config.restart_policy {
+            RestartPolicy::Fresh => self.problem.random_state(&mut self.rngs.initializer),
+            RestartPolicy::FromBest => self.best.as_ref().unwrap().0.clone(),
+        };
+        self.temperature = self.starting_temperature;
+        self.cooling_start = self.starting_temperature;
+        self.iteration = 0;
+        self.stagnant_iterations = 0;
+        self.reheats_left = self
+            .config
+            .reheating
+            .as_ref()
+            .map_or(0, |reheating| reheating.max_reheats);
+        None
+    }
+
+    /// Starts annealing from the current state, at the current
+    /// temperature.
+    fn start_annealing<F>(&mut self, callback: &mut F)
+    where
+        F: FnMut(&P::State, usize, f32),
+    {
+        self.score = self.problem.score(&self.state);
+        self.effort.evaluations += 1;
+        callback(&self.state, self.score, self.temperature);
+        if let Some(ref mut acceptance) = self.config.acceptance {
+            acceptance.start(self.score);
+        }
+        self.moves.reset(&self.problem, &self.state);
+        self.moves.visit(&self.problem, &self.state);
+        self.annealing = Some(Annealing {
+            best: None,
+            best_score: self.score,
+            iterations_since_best: 0,
+        });
+    }
+
+    /// Tries the next move of `annealing`, takes it if accepted, and cools
+    /// the system down.
+    fn try_move<F>(&mut self, annealing: &mut Annealing<P::State>, callback: &mut F)
+    where
+        F: FnMut(&P::State, usize, f32),
+    {
+        let score = self.score;
+        let applied = self.apply_next_move(score);
+
+        let new_score = self
+            .moves
+            .score_after(&self.problem, &self.state, &applied, score);
+        self.effort.evaluations += 1;
+        let tie = if new_score == score {
+            self.moves.breaks_tie(
+                &self.problem,
+                &mut self.state,
+                &applied,
+                &mut self.rngs.acceptance,
+            )
+        } else {
+            None
+        };
+        let accepted = match tie {
+            Some(accepted) => accepted,
+            None => new_score < score || self.should_accept(score, new_score),
+        };
+        annealing.iterations_since_best += 1;
+        if accepted {
+            if new_score < annealing.best_score {
+                annealing.best_score = new_score;
+                annealing.best = None;
+                annealing.iterations_since_best = 0;
+            } else if annealing.best.is_none() && new_score > annealing.best_score {
+                let mut previous = self.state.clone();
+                self.moves
+                    .undo(&self.problem, &mut previous, applied.clone());
+                annealing.best = Some((previous, annealing.best_score));
+            }
+            self.score = new_score;
+            self.moves.accept(&self.problem, &self.state, &applied);
+            self.effort.accepted_moves += 1;
+            // This is fiddly, but this only really matters when the
+            // system is already cooled down, so it's fine.
+            self.stagnant_iterations = 0;
+            self.moves.visit(&self.problem, &self.state);
+            callback(&self.state, new_score, self.temperature);
+        } else {
+            self.stagnant_iterations += 1;
+            // Back to where we were.
+            self.moves.undo(&self.problem, &mut self.state, applied);
+        }
+
+        if let Some(ref mut acceptance) = self.config.acceptance {
+            acceptance.record(self.score);
+        }
+
+        // Cool the system down.
+        self.iteration += 1;
+        self.temperature =
+            self.config
+                .cooling_schedule
+                .cool(self.cooling_start, self.temperature, self.iteration);
+    }
+}
+
+impl<P: SoftConstrainedProblem> GenericSimulatedAnnealing<P> {
+    /// Anneals from the current state until freezing, weighing the hard
+    /// constraints against the soft ones as `handling` says, and returns
+    /// the best state seen.
+    fn anneal_constrained<F>(
+        &mut self,
+        handling: &ConstraintHandling,
+        penalty: &mut Option<AdaptivePenalty>,
+        callback: &mut F,
+    ) -> (P::State, usize, f64)
+    where
+        F: FnMut(&P::State, usize),
+    {
+        use rand::Rng;
+
+        let mut state = self.state.clone();
+        let mut current = (self.problem.score(&state), self.problem.soft_cost(&state));
+        let mut best = (state.clone(), current.0, current.1);
+        callback(&state, current.0);
+
+        let starting = self.starting_temperature;
+        let mut temperature = starting;
+        let mut iteration = 0;
+        while temperature >= self.config.freezing_temperature && !self.budget.should_stop() {
+            let neighbor = self
+                .problem
+                .random_neighbor(&state, &mut self.rngs.neighborhood);
+            let candidate = (
+                self.problem.score(&neighbor),
+                self.problem.soft_cost(&neighbor),
+            );
+            let delta = match (handling, penalty.as_ref()) {
+                (_, Some(penalty)) => {
+                    penalty.energy(candidate.0, candidate.1) - penalty.energy(current.0, current.1)
+                }
+                (&ConstraintHandling::StochasticRanking { cost_probability }, None) => {
+                    let by_cost = constrained::compares_by_cost(
+                        current,
+                        candidate,
+                        cost_probability,
+                        &mut self.rngs.acceptance,
+                    );
+                    if by_cost {
+                        candidate.1 - current.1
+                    } else {
+                        candidate.0 as f64 - current.0 as f64
+                    }
+                }
+                (&ConstraintHandling::AdaptivePenalty(..), None) => unreachable!(),
+            };
+            if delta <= 0. || (-delta / temperature as f64).exp() > self.rngs.acceptance.next_f64()
+            {
+                state = neighbor;
+                current = candidate;
+                callback(&state, current.0);
+                if constrained::is_better(current, (best.1, best.2)) {
+                    best = (state.clone(), current.0, current.1);
+                }
+            }
+            if let Some(ref mut penalty) = *penalty {
+                penalty.record(current.0 == 0);
+            }
+
+            iteration += 1;
+            temperature = self
+                .config
+                .cooling_schedule
+                .cool(starting, temperature, iteration);
+        }
+
+        best
+    }
+
+    /// Like `solve_with_callback`, but minimizing the soft cost too: the
+    /// annealing doesn't stop at the first state that breaks no hard
+    /// constraints, and returns the cheapest of those it sees, if any.
+    pub fn solve_constrained<F>(
+        mut self,
+        handling: &ConstraintHandling,
+        mut callback: F,
+    ) -> Outcome<P::State>
+    where
+        F: FnMut(&P::State, usize),
+    {
+        let mut penalty = match *handling {
+            ConstraintHandling::AdaptivePenalty(ref adaptation) => {
+                Some(AdaptivePenalty::new(adaptation.clone()))
+            }
+            ConstraintHandling::StochasticRanking { .. } => None,
+        };
+
+        let mut best: Option<(P::State, usize, f64)> = None;
+        let mut restarts_left = self.config.restarts;
+        loop {
+            let (state, score, cost) =
+                self.anneal_constrained(handling, &mut penalty, &mut callback);
+            if best
+                .as_ref()
+                .is_none_or(|best| constrained::is_better((score, cost), (best.1, best.2)))
+            {
+                best = Some((state, score, cost));
+            }
+
+            if restarts_left == 0 || self.budget.stopped() {
+                break;
+            }
+            restarts_left -= 1;
+            self.state = self.problem.random_state(&mut self.rngs.initializer);
+        }
+
+        let (state, score, _) = best.unwrap();
+        Outcome::new(state, score, &self.budget, self.rngs.master_seed)
+    }
+}
+
+/// A problem made of the closures passed to `anneal`.
+struct ClosureProblem<S, N, C> {
+    initial: S,
+    neighbor: N,
+    score: C,
+}
+
+impl<S, N, C> Problem for ClosureProblem<S, N, C>
+where
+    S: Clone,
+    N: Fn(&S, &mut StreamRng) -> S,
+    C: Fn(&S) -> usize,
+{
+    type State = S;
+
+    fn random_state(&self, _: &mut StreamRng) -> S {
+        self.initial.clone()
+    }
+
+    fn score(&self, state: &S) -> usize {
+        (self.score)(state)
+    }
+
+    fn random_neighbor(&self, state: &S, rng: &mut StreamRng) -> S {
+        (self.neighbor)(state, rng)
+    }
+
+    /// The annealing only ever takes random neighbors.
+    fn neighbors(&self, _: &S) -> Vec<S> {
+        vec![]
+    }
+}
+
+/// Anneals from `initial`, moving to the states that `neighbor` returns
+/// and scoring them with `score`, where zero means a solution, without
+/// having to implement `Problem`.
+///
+/// Each restart starts again from `initial`, so it's only worth having some
+/// if `neighbor` is random.
+pub fn anneal<S, N, C>(
+    initial: S,
+    neighbor: N,
+    score: C,
+    config: GenericSimulatedAnnealingConfig,
+) -> Outcome<S>
+where
+    S: Clone,
+    N: Fn(&S, &mut StreamRng) -> S,
+    C: Fn(&S) -> usize,
+{
+    let problem = ClosureProblem {
+        initial,
+        neighbor,
+        score,
+    };
+    GenericSimulatedAnnealing::new(problem, config).solve()
+}
@@ -0,0 +1,390 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Sudoku as a generic problem, for boards made of `order` × `order` blocks
+//! of `order` × `order` cells, like the usual 9 × 9 one of order 3.
+//!
+//! States are complete grids where every block has each value once, so only
+//! rows and columns can have duplicates, and neighbors swap two cells of a
+//! block that aren't givens. Sudokus can also be solved exactly by
+//! backtracking with constraint propagation.
+//!
+//! The strategies in `solvers` only know about n-queens, so sudokus are
+//! searched with the generic solvers of `problem`.
+
+use problem::{Outcome, Problem};
+use *;
+
+/// A sudoku to solve, with its givens.
+#[derive(Clone, Debug)]
+pub struct Sudoku {
+    order: usize,
+    /// The value of each cell, by row and then column, from 1 to the size of
+    /// the board, or 0 if empty.
+    givens: Vec<usize>,
+    /// The cells of each block that aren't givens.
+    free_cells: Vec<Vec<usize>>,
+}
+
+impl Sudoku {
+    /// Creates a sudoku of blocks of `order` × `order` cells from its
+    /// `givens`, in the format of `Sudoku::givens`.
+    ///
+    /// Fails with `Error::InvalidConfig` unless there's a value for each
+    /// cell, all of them fit, and no two givens conflict. Orders above 7
+    /// are not supported.
+    pub fn new(order: usize, givens: Vec<usize>) -> Result<Self, Error> {
+        if order == 0 || order > 7 {
+            return Err(Error::UnsupportedDimension(order));
+        }
+        let size = order * order;
+        if givens.len() != size * size {
+            return Err(Error::InvalidConfig("there must be a value for each cell"));
+        }
+        if givens.iter().any(|&value| value > size) {
+            return Err(Error::InvalidConfig(
+                "values can't exceed the size of the board",
+            ));
+        }
+
+        let mut sudoku = Sudoku {
+            order,
+            givens,
+            free_cells: vec![],
+        };
+        sudoku.free_cells = sudoku
+            .blocks()
+            .into_iter()
+            .map(|cells| {
+                cells
+                    .into_iter()
+                    .filter(|&c| sudoku.givens[c] == 0)
+                    .collect()
+            })
+            .collect();
+        if sudoku.duplicates(&sudoku.givens, true) != 0 {
+            return Err(Error::InvalidConfig("givens can't conflict"));
+        }
+        Ok(sudoku)
+    }
+
+    /// Parses a sudoku of order 3, given as its 81 cells by rows, with
+    /// digits for the givens and `.` or `0` for the empty cells. Whitespace
+    /// is ignored.
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let givens = text
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| match c {
+                '.' => Ok(0),
+                c => c
+                    .to_digit(10)
+                    .map(|digit| digit as usize)
+                    .ok_or(Error::InvalidConfig("cells must be digits or dots")),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(3, givens)
+    }
+
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// Returns the number of rows, columns, blocks, and values.
+    pub fn size(&self) -> usize {
+        self.order * self.order
+    }
+
+    /// The value of each cell, by row and then column, from 1 to the size of
+    /// the board, or 0 if empty.
+    pub fn givens(&self) -> &[usize] {
+        &self.givens
+    }
+
+    /// Returns the cells of each block, by row and then column.
+    fn blocks(&self) -> Vec<Vec<usize>> {
+        let (order, size) = (self.order, self.size());
+        (0..size)
+            .map(|block| {
+                let (top, left) = (block / order * order, block % order * order);
+                (0..size)
+                    .map(|i| (top + i / order) * size + left + i % order)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Returns how many values repeat in the rows and columns of `grid`, and
+    /// in its blocks too if `blocks`, ignoring empty cells.
+    fn duplicates(&self, grid: &[usize], blocks: bool) -> usize {
+        let size = self.size();
+        let mut units = (0..size)
+            .map(|row| (0..size).map(|column| row * size + column).collect())
+            .chain((0..size).map(|column| (0..size).map(|row| row * size + column).collect()))
+            .collect::<Vec<Vec<usize>>>();
+        if blocks {
+            units.extend(self.blocks());
+        }
+
+        units
+            .iter()
+            .map(|unit| {
+                let mut seen = 0u64;
+                unit.iter()
+                    .filter(|&&cell| {
+                        let value = grid[cell];
+                        let repeated = value != 0 && seen & (1 << value) != 0;
+                        seen |= 1 << value;
+                        repeated
+                    })
+                    .count()
+            })
+            .sum()
+    }
+
+    /// Returns the values that fit in the empty `cell` of `grid`, as a mask
+    /// with a bit per value.
+    fn candidates(&self, grid: &[usize], cell: usize) -> u64 {
+        let (order, size) = (self.order, self.size());
+        let (row, column) = (cell / size, cell % size);
+        let (top, left) = (row / order * order, column / order * order);
+
+        let mut taken = 0u64;
+        for i in 0..size {
+            taken |= 1 << grid[row * size + i];
+            taken |= 1 << grid[i * size + column];
+            taken |= 1 << grid[(top + i / order) * size + left + i % order];
+        }
+        let all = ((1u64 << size) - 1) << 1;
+        all & !taken
+    }
+
+    /// Fills the empty cells of `grid` that have a single candidate until
+    /// there are none, then tries each candidate of the cell with the
+    /// fewest, returning a solution if there's any, or `Err(())` if
+    /// `budget` ran out.
+    fn backtrack(
+        &self,
+        mut grid: Vec<usize>,
+        budget: &mut BudgetTracker,
+    ) -> Result<Option<Vec<usize>>, ()> {
+        if budget.should_stop() {
+            return Err(());
+        }
+
+        let fewest = loop {
+            let mut fewest: Option<(usize, u64)> = None;
+            let mut filled = false;
+            for cell in 0..grid.len() {
+                if grid[cell] != 0 {
+                    continue;
+                }
+                let candidates = self.candidates(&grid, cell);
+                match candidates.count_ones() {
+                    0 => return Ok(None),
+                    1 => {
+                        grid[cell] = candidates.trailing_zeros() as usize;
+                        filled = true;
+                    }
+                    count => {
+                        if fewest.is_none_or(|(_, c)| count < c.count_ones()) {
+                            fewest = Some((cell, candidates));
+                        }
+                    }
+                }
+            }
+            if !filled {
+                break fewest;
+            }
+        };
+
+        let (cell, mut candidates) = match fewest {
+            Some(fewest) => fewest,
+            None => return Ok(Some(grid)),
+        };
+        while candidates != 0 {
+            let value = candidates.trailing_zeros() as usize;
+            candidates &= candidates - 1;
+            let mut next = grid.clone();
+            next[cell] = value;
+            if let Some(solution) = self.backtrack(next, budget)? {
+                return Ok(Some(solution));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Solves the sudoku exactly by backtracking with constraint
+    /// propagation, where each iteration of `budget` is a node of the
+    /// search.
+    ///
+    /// If there's no solution, or the budget runs out, the givens are
+    /// returned, with the status saying which.
+    pub fn solve_by_propagation(&self, budget: SolveBudget) -> Outcome<Vec<usize>> {
+        let mut budget = BudgetTracker::new(budget);
+        let solution = self.backtrack(self.givens.clone(), &mut budget);
+        match solution {
+            Ok(Some(grid)) => Outcome::new(grid, 0, &budget, 0),
+            Ok(None) | Err(()) => {
+                let score = self.duplicates(&self.givens, false);
+                let mut outcome = Outcome::new(self.givens.clone(), score, &budget, 0);
+                // The givens never have duplicates, but aren't a solution.
+                outcome.status = budget.status(false);
+                outcome
+            }
+        }
+    }
+}
+
+impl Problem for Sudoku {
+    type State = Vec<usize>;
+
+    /// Fills the empty cells of each block with its missing values, in a
+    /// random order.
+    fn random_state(&self, rng: &mut StreamRng) -> Vec<usize> {
+        use rand::Rng;
+
+        let mut grid = self.givens.clone();
+        for cells in self.blocks() {
+            let mut missing = (1..=self.size())
+                .filter(|value| !cells.iter().any(|&c| grid[c] == *value))
+                .collect::<Vec<_>>();
+            rng.shuffle(&mut missing);
+            for (&cell, value) in cells.iter().filter(|&&c| self.givens[c] == 0).zip(missing) {
+                grid[cell] = value;
+            }
+        }
+        grid
+    }
+
+    /// Counts the repeated values in each row and column.
+    fn score(&self, state: &Vec<usize>) -> usize {
+        self.duplicates(state, false)
+    }
+
+    /// Swaps two cells of a random block that aren't givens.
+    fn random_neighbor(&self, state: &Vec<usize>, rng: &mut StreamRng) -> Vec<usize> {
+        use rand::Rng;
+
+        let mut neighbor = state.clone();
+        let blocks = self
+            .free_cells
+            .iter()
+            .filter(|cells| cells.len() > 1)
+            .collect::<Vec<_>>();
+        if let Some(cells) = rng.choose(&blocks) {
+            let one = rng.gen_range(0, cells.len());
+            let other = (one + rng.gen_range(1, cells.len())) % cells.len();
+            neighbor.swap(cells[one], cells[other]);
+        }
+        neighbor
+    }
+
+    fn neighbors(&self, state: &Vec<usize>) -> Vec<Vec<usize>> {
+        let mut neighbors = vec![];
+        for cells in &self.free_cells {
+            for (i, &one) in cells.iter().enumerate() {
+                for &other in &cells[i + 1..] {
+                    let mut neighbor = state.clone();
+                    neighbor.swap(one, other);
+                    neighbors.push(neighbor);
+                }
+            }
+        }
+        neighbors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use problem::{GenericSimulatedAnnealing, GenericSimulatedAnnealingConfig};
+
+    const PUZZLE: &str = "
+        53..7....
+        6..195...
+        .98....6.
+        8...6...3
+        4..8.3..1
+        7...2...6
+        .6....28.
+        ...419..5
+        ....8..79
+    ";
+
+    fn is_solution(sudoku: &Sudoku, grid: &[usize]) -> bool {
+        grid.iter().all(|&value| value != 0)
+            && sudoku.duplicates(grid, true) == 0
+            && sudoku
+                .givens()
+                .iter()
+                .zip(grid)
+                .all(|(&given, &value)| given == 0 || given == value)
+    }
+
+    #[test]
+    fn solves_by_propagation_and_annealing() {
+        let sudoku = Sudoku::parse(PUZZLE).unwrap();
+
+        let outcome = sudoku.solve_by_propagation(SolveBudget::default());
+        assert_eq!(outcome.status, SolveStatus::Solved);
+        assert!(is_solution(&sudoku, &outcome.state));
+
+        let config = GenericSimulatedAnnealingConfig {
+            starting_temperature: 2.,
+            freezing_temperature: 0.05,
+            cooling_schedule: Box::new(solvers::simulated_annealing::GeometricCooling {
+                factor: 0.0001,
+            }),
+            restarts: 20,
+            seed: Some(3),
+            initial_acceptance: None,
+            ..GenericSimulatedAnnealingConfig::default()
+        };
+        let outcome = GenericSimulatedAnnealing::new(&sudoku, config).solve();
+        assert_eq!(outcome.status, SolveStatus::Solved);
+        assert!(is_solution(&sudoku, &outcome.state));
+    }
+
+    #[test]
+    fn rejects_invalid_givens() {
+        assert!(Sudoku::parse("12").is_err());
+        let conflicting = PUZZLE.replacen('.', "5", 1);
+        assert_eq!(
+            Sudoku::parse(&conflicting).err(),
+            Some(Error::InvalidConfig("givens can't conflict"))
+        );
+
+        // The states keep the givens, and each block a permutation.
+        let sudoku = Sudoku::parse(PUZZLE).unwrap();
+        let mut rng = stream_rng(0, RngStream::Initializer);
+        let state = sudoku.random_state(&mut rng);
+        let neighbor = sudoku.random_neighbor(&state, &mut rng);
+        for grid in &[state, neighbor] {
+            assert!(sudoku
+                .givens()
+                .iter()
+                .zip(grid)
+                .all(|(&given, &value)| given == 0 || given == value));
+            assert!(sudoku.blocks().iter().all(|cells| {
+                let mut values = cells.iter().map(|&c| grid[c]).collect::<Vec<_>>();
+                values.sort();
+                values == (1..=9).collect::<Vec<_>>()
+            }));
+        }
+    }
+}
@@ -0,0 +1,334 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! How much processor time and memory a run used, to compare strategies by
+//! what they cost and not only by how many iterations they took.
+//!
+//! The processor time and resident memory are read from the operating
+//! system, on Linux only so far. Allocations are only counted in programs
+//! that install the `CountingAllocator`:
+//!
+//! ```
+//! use local_search_algorithms::prelude::*;
+//! use local_search_algorithms::resources::CountingAllocator;
+//!
+//! #[global_allocator]
+//! static ALLOCATOR: CountingAllocator = CountingAllocator;
+//!
+//! fn main() {
+//!     let solution = GeneticAlgorithm::new(12, GeneticAlgorithmConfig::default()).solve();
+//!     assert!(solution.statistics().resources.allocations.unwrap() > 0);
+//! }
+//! ```
+//!
+//! The counting allocator can also cancel solves that take too much memory,
+//! through `CountingAllocator::limit_memory`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicUsize};
+use *;
+
+/// Whether the `CountingAllocator` is the global allocator, which it finds
+/// out on the first allocation.
+static COUNTING: AtomicBool = AtomicBool::new(false);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// How many runs can keep track of their peak heap memory at once.
+const PEAK_SLOTS: usize = 32;
+/// The most heap memory in use since each `ResourceSnapshot` was taken, in
+/// the slot it claimed, so that nested and concurrent runs don't reset each
+/// other's peak.
+static PEAKS: [AtomicUsize; PEAK_SLOTS] = [const { AtomicUsize::new(0) }; PEAK_SLOTS];
+/// The slots of `PEAKS` claimed by a snapshot, as a bit each.
+static OPEN_PEAKS: AtomicUsize = AtomicUsize::new(0);
+
+/// The heap memory in use past which `MEMORY_CANCELLATION` is set.
+static MEMORY_THRESHOLD: AtomicUsize = AtomicUsize::new(usize::MAX);
+static MEMORY_CANCELLATION: AtomicPtr<AtomicBool> = AtomicPtr::new(ptr::null_mut());
+
+/// A global allocator that forwards to the system one, counting the
+/// allocations and the bytes in use for `ResourceUsage`.
+pub struct CountingAllocator;
+
+impl CountingAllocator {
+    /// Sets `cancellation` as soon as the heap memory in use goes more than
+    /// `max_bytes` over what is in use now, so that a solve given it in its
+    /// budget stops before it takes all the memory there is.
+    ///
+    /// There's a single limit at a time, in place until
+    /// `remove_memory_limit`. It only works if the counting allocator is the
+    /// global allocator.
+    pub fn limit_memory(max_bytes: usize, cancellation: &'static AtomicBool) {
+        let cancellation = cancellation as *const AtomicBool as *mut AtomicBool;
+        MEMORY_CANCELLATION.store(cancellation, Ordering::Relaxed);
+        let threshold = LIVE_BYTES.load(Ordering::Relaxed).saturating_add(max_bytes);
+        MEMORY_THRESHOLD.store(threshold, Ordering::Relaxed);
+    }
+
+    pub fn remove_memory_limit() {
+        MEMORY_THRESHOLD.store(usize::MAX, Ordering::Relaxed);
+    }
+
+    fn allocated(size: usize) {
+        COUNTING.store(true, Ordering::Relaxed);
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(size, Ordering::Relaxed);
+        let live = LIVE_BYTES.fetch_add(size, Ordering::Relaxed) + size;
+        record_peak(live);
+        if live > MEMORY_THRESHOLD.load(Ordering::Relaxed) {
+            let cancellation = MEMORY_CANCELLATION.load(Ordering::Relaxed);
+            // Only ever set from a `&'static AtomicBool`.
+            if let Some(cancellation) = unsafe { cancellation.as_ref() } {
+                cancellation.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn freed(size: usize) {
+        LIVE_BYTES.fetch_sub(size, Ordering::Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            Self::allocated(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        Self::freed(layout.size());
+    }
+
+    /// Counts as an allocation of the new size and the release of the old
+    /// one, even if the memory doesn't move.
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            Self::allocated(new_size);
+            Self::freed(layout.size());
+        }
+        new_ptr
+    }
+}
+
+/// The resources a run used, where known.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ResourceUsage {
+    /// The processor time the process spent during the run, across all its
+    /// threads, which can be more than the wall time if the strategy uses
+    /// several threads. It has a resolution of 10 ms.
+    pub cpu_time: Option<Duration>,
+    /// The peak resident memory of the process by the end of the run, in
+    /// bytes, which also covers whatever ran before it.
+    pub peak_resident_bytes: Option<u64>,
+    /// The heap allocations made during the run, including reallocations.
+    pub allocations: Option<usize>,
+    pub allocated_bytes: Option<usize>,
+    /// The most heap memory in use at once during the run, over what was
+    /// in use when it started.
+    pub peak_heap_bytes: Option<usize>,
+}
+
+/// Raises the peak of every open snapshot to `live` bytes, if below.
+fn record_peak(live: usize) {
+    let mut open = OPEN_PEAKS.load(Ordering::Relaxed);
+    while open != 0 {
+        PEAKS[open.trailing_zeros() as usize].fetch_max(live, Ordering::Relaxed);
+        open &= open - 1;
+    }
+}
+
+/// Claims a free slot of `PEAKS`, starting at `live` bytes, if there's any.
+fn claim_peak_slot(live: usize) -> Option<usize> {
+    let mut open = OPEN_PEAKS.load(Ordering::Relaxed);
+    loop {
+        let slot = (!open).trailing_zeros() as usize;
+        if slot >= PEAK_SLOTS {
+            return None;
+        }
+        match OPEN_PEAKS.compare_exchange_weak(
+            open,
+            open | 1 << slot,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => {
+                PEAKS[slot].store(live, Ordering::Relaxed);
+                return Some(slot);
+            }
+            Err(current) => open = current,
+        }
+    }
+}
+
+/// The counters when a run started, to tell what it used by the end.
+///
+/// The counters are the process', so runs at the same time on other
+/// threads add to each other's usage. Each snapshot keeps its own peak of
+/// the heap memory in use, though, as long as there aren't more than
+/// `PEAK_SLOTS` of them at once.
+#[derive(Debug)]
+pub(crate) struct ResourceSnapshot {
+    cpu_time: Option<Duration>,
+    allocations: usize,
+    allocated_bytes: usize,
+    live_bytes: usize,
+    peak_slot: Option<usize>,
+}
+
+impl ResourceSnapshot {
+    pub(crate) fn take() -> Self {
+        let live_bytes = LIVE_BYTES.load(Ordering::Relaxed);
+        ResourceSnapshot {
+            cpu_time: process_cpu_time(),
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+            allocated_bytes: ALLOCATED_BYTES.load(Ordering::Relaxed),
+            live_bytes,
+            peak_slot: claim_peak_slot(live_bytes),
+        }
+    }
+
+    /// The most heap memory in use at once since the snapshot was taken,
+    /// over what was in use then.
+    fn peak_heap_bytes(&self) -> Option<usize> {
+        let slot = self.peak_slot?;
+        Some(
+            PEAKS[slot]
+                .load(Ordering::Relaxed)
+                .saturating_sub(self.live_bytes),
+        )
+    }
+
+    /// Returns the resources used since the snapshot was taken.
+    pub(crate) fn usage(&self) -> ResourceUsage {
+        let counting = COUNTING.load(Ordering::Relaxed);
+        let count = |counter: &AtomicUsize, start: usize| {
+            if counting {
+                Some(counter.load(Ordering::Relaxed).saturating_sub(start))
+            } else {
+                None
+            }
+        };
+        let peak_heap_bytes = if counting {
+            self.peak_heap_bytes()
+        } else {
+            None
+        };
+        let cpu_time = match (self.cpu_time, process_cpu_time()) {
+            (Some(start), Some(end)) => Some(end.saturating_sub(start)),
+            _ => None,
+        };
+        ResourceUsage {
+            cpu_time,
+            peak_resident_bytes: peak_resident_bytes(),
+            allocations: count(&ALLOCATIONS, self.allocations),
+            allocated_bytes: count(&ALLOCATED_BYTES, self.allocated_bytes),
+            peak_heap_bytes,
+        }
+    }
+}
+
+impl Drop for ResourceSnapshot {
+    fn drop(&mut self) {
+        if let Some(slot) = self.peak_slot {
+            OPEN_PEAKS.fetch_and(!(1 << slot), Ordering::Relaxed);
+        }
+    }
+}
+
+/// Returns the user and system time of the process so far, from
+/// `/proc/self/stat`, which counts it in ticks of 10 ms.
+#[cfg(target_os = "linux")]
+fn process_cpu_time() -> Option<Duration> {
+    let stat = std::fs::read_to_string("/proc/self/stat").ok()?;
+    // The fields after the name of the command, which can have spaces,
+    // start with the third one.
+    let fields = stat[stat.rfind(')')? + 1..]
+        .split_whitespace()
+        .collect::<Vec<_>>();
+    let user = fields.get(11)?.parse::<u64>().ok()?;
+    let system = fields.get(12)?.parse::<u64>().ok()?;
+    Some(Duration::from_millis((user + system) * 10))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn process_cpu_time() -> Option<Duration> {
+    None
+}
+
+/// Returns the peak resident memory of the process, from
+/// `/proc/self/status`.
+#[cfg(target_os = "linux")]
+fn peak_resident_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kilobytes = line["VmHWM:".len()..]
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kilobytes * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn peak_resident_bytes() -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_process_usage() {
+        let usage = ResourceSnapshot::take().usage();
+        if cfg!(target_os = "linux") {
+            assert!(usage.cpu_time.is_some());
+            assert!(usage.peak_resident_bytes.unwrap() > 0);
+        }
+        // The tests don't install the counting allocator.
+        assert_eq!(usage.allocations, None);
+    }
+
+    #[test]
+    fn nested_runs_keep_their_own_peak() {
+        let outer = ResourceSnapshot::take();
+        record_peak(outer.live_bytes + 1000);
+        let inner = ResourceSnapshot::take();
+        record_peak(inner.live_bytes + 10);
+        assert_eq!(inner.peak_heap_bytes(), Some(10));
+        drop(inner);
+        assert_eq!(outer.peak_heap_bytes(), Some(1000));
+    }
+}
@@ -0,0 +1,164 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Runs several searches on a single thread, a batch of steps of each in
+//! turn, so that a frontend without threads can show them progressing at
+//! the same time.
+
+use stepper::{StepResult, Stepper};
+use Solution;
+
+struct Task {
+    stepper: Box<dyn Stepper>,
+    /// The result of the search once it's over, until taken.
+    result: Option<Solution>,
+    done: bool,
+}
+
+/// Interleaves searches round-robin, in batches of a fixed number of steps.
+pub struct Scheduler {
+    steps_per_turn: usize,
+    tasks: Vec<Task>,
+}
+
+impl Scheduler {
+    /// Creates a scheduler that runs up to `steps_per_turn` steps of each
+    /// search on each turn.
+    pub fn new(steps_per_turn: usize) -> Self {
+        Scheduler {
+            steps_per_turn: steps_per_turn.max(1),
+            tasks: vec![],
+        }
+    }
+
+    /// Adds a search to the ones run on each turn, returning its id, which
+    /// counts up from zero.
+    pub fn add(&mut self, stepper: Box<dyn Stepper>) -> usize {
+        self.tasks.push(Task {
+            stepper,
+            result: None,
+            done: false,
+        });
+        self.tasks.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Runs a batch of steps of each search that isn't over, in the order
+    /// they were added, and returns whether any of them is still running.
+    pub fn run_turn(&mut self) -> bool {
+        for task in self.tasks.iter_mut().filter(|task| !task.done) {
+            for _ in 0..self.steps_per_turn {
+                if let StepResult::Done(solution) = task.stepper.step() {
+                    task.result = Some(*solution);
+                    task.done = true;
+                    break;
+                }
+            }
+        }
+        !self.is_done()
+    }
+
+    /// Returns whether all the searches are over.
+    pub fn is_done(&self) -> bool {
+        self.tasks.iter().all(|task| task.done)
+    }
+
+    /// Returns whether the search `id` is over, or `None` if there's no
+    /// such search.
+    pub fn is_finished(&self, id: usize) -> Option<bool> {
+        self.tasks.get(id).map(|task| task.done)
+    }
+
+    /// Returns the placement the search `id` is on, and its score, or
+    /// `None` if there's no such search.
+    pub fn current(&self, id: usize) -> Option<(&[usize], usize)> {
+        self.tasks.get(id).map(|task| task.stepper.current())
+    }
+
+    /// Takes the result of the search `id`, if there's such a search, it's
+    /// over and its result hasn't been taken yet.
+    pub fn take_result(&mut self, id: usize) -> Option<Solution> {
+        self.tasks.get_mut(id).and_then(|task| task.result.take())
+    }
+
+    /// Runs turns until all the searches are over, and returns their
+    /// results in the order they were added, or `None` for the ones
+    /// already taken.
+    pub fn run_to_completion(mut self) -> Vec<Option<Solution>> {
+        while self.run_turn() {}
+        self.tasks.into_iter().map(|task| task.result).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use presets::Preset;
+    use *;
+
+    #[test]
+    fn interleaves_searches() {
+        let mut scheduler = Scheduler::new(10);
+        for &(strategy, seed) in &[
+            ("min_conflicts", 1),
+            ("tabu_search", 2),
+            ("min_conflicts", 3),
+        ] {
+            let stepper = Preset::Fast
+                .stepper(strategy, 20, Some(seed), SolveBudget::default())
+                .unwrap();
+            scheduler.add(stepper);
+        }
+        assert!(Preset::Fast
+            .stepper("pbil", 20, None, SolveBudget::default())
+            .is_err());
+
+        assert!(scheduler.run_turn());
+        for id in 0..scheduler.len() {
+            assert_eq!(scheduler.current(id).unwrap().0.len(), 20);
+        }
+        assert!(scheduler.current(3).is_none());
+        assert_eq!(scheduler.is_finished(3), None);
+        assert!(scheduler.take_result(3).is_none());
+
+        // The same runs give the same results interleaved or not.
+        let solo = Preset::Fast
+            .solve(
+                "tabu_search",
+                20,
+                Some(2),
+                SolveBudget::default(),
+                |_, _| {},
+            )
+            .unwrap();
+        let results = scheduler.run_to_completion();
+        assert_eq!(results.len(), 3);
+        let interleaved = results[1].as_ref().unwrap();
+        assert_eq!(interleaved.queen_rows(), solo.queen_rows());
+        assert_eq!(
+            interleaved.statistics().iterations,
+            solo.statistics().iterations
+        );
+    }
+}
@@ -0,0 +1,201 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Picks a strategy for a board on its own, from its size, the budget to
+//! solve it in, and whether the answer has to be exact, for callers that
+//! don't want to know which strategy suits what:
+//!
+//! ```
+//! use local_search_algorithms::prelude::*;
+//!
+//! let budget = SolveBudget::default();
+//! let selection = select(200, &budget, false);
+//! assert_eq!(selection.strategy.name, "min_conflicts");
+//!
+//! let solution = selection.solve(200, Some(1), budget, |_, _| {}).unwrap();
+//! assert_eq!(solution.status(), SolveStatus::Solved);
+//! ```
+
+use presets::Preset;
+use std::fmt;
+use *;
+
+/// Boards up to this size are solved by backtracking, which is immediate
+/// there.
+const SMALL_BOARD: usize = 16;
+
+/// Boards from this size on are solved by decomposition, which starts to
+/// beat min-conflicts on the whole board around here: about as fast at 5000
+/// queens, and a quarter faster at 20000.
+const LARGE_BOARD: usize = 5_000;
+
+/// Backtracking takes about a second to place this many queens, so exact
+/// answers for bigger boards may not fit in a budget.
+const EXACT_WITHIN_A_SECOND: usize = 4_000;
+
+/// The strategy picked by `select`, and why.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Selection {
+    pub strategy: &'static StrategyInfo,
+    /// The preset to configure the strategy with.
+    pub preset: Preset,
+    /// Why the strategy was picked, to show to the user.
+    pub reason: String,
+}
+
+impl Selection {
+    /// Solves a board of `n` queens with the strategy picked, configured by
+    /// the preset with `seed` and `budget`, like `Preset::solve`.
+    pub fn solve<F>(
+        &self,
+        n: usize,
+        seed: Option<u64>,
+        budget: SolveBudget,
+        callback: F,
+    ) -> Result<Solution, Error>
+    where
+        F: FnMut(&[usize], usize),
+    {
+        self.preset
+            .solve(self.strategy.name, n, seed, budget, callback)
+    }
+}
+
+impl fmt::Display for Selection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}): {}",
+            self.strategy.display_name,
+            self.preset.name(),
+            self.reason
+        )
+    }
+}
+
+/// Picks a strategy and preset for a board of `n` queens to be solved
+/// within `budget`, where `exact` means that only a strategy that's sure to
+/// find a solution will do.
+///
+/// Backtracking goes to the exact answers and the small boards, the
+/// decomposition to the very large ones, and min-conflicts to everything
+/// in between. There's no explicit construction that places the queens of
+/// a large board directly, so those are still searched for: the
+/// decomposition is a local search too, repairing the board a block of
+/// columns at a time, and may give up on a board it doesn't repair within
+/// the budget. The local searches are configured to keep trying as long as
+/// the budget lets them, if it has a time limit, and to give up early
+/// otherwise.
+pub fn select(n: usize, budget: &SolveBudget, exact: bool) -> Selection {
+    let local_search_preset = if budget.max_wall_time.is_some() {
+        Preset::Thorough
+    } else {
+        Preset::Fast
+    };
+
+    if exact {
+        let mut reason =
+            String::from("an exact answer was asked for, which only backtracking is sure to find");
+        if n > EXACT_WITHIN_A_SECOND && budget.max_wall_time.is_some() {
+            reason.push_str(", though it may not finish within the budget on a board this big");
+        }
+        return Selection {
+            strategy: &info::CONSTRAINT_PROPAGATION,
+            preset: Preset::Fast,
+            reason,
+        };
+    }
+
+    if n <= SMALL_BOARD {
+        return Selection {
+            strategy: &info::CONSTRAINT_PROPAGATION,
+            preset: Preset::Fast,
+            reason: format!(
+                "backtracking solves boards of up to {} queens at once, and never misses a solution",
+                SMALL_BOARD
+            ),
+        };
+    }
+
+    if n >= LARGE_BOARD {
+        return Selection {
+            strategy: &info::DECOMPOSITION,
+            preset: local_search_preset,
+            reason: format!(
+                "boards of {} queens or more are repaired faster a block of columns at a time than all at once",
+                LARGE_BOARD
+            ),
+        };
+    }
+
+    Selection {
+        strategy: &info::MIN_CONFLICTS,
+        preset: local_search_preset,
+        reason: String::from("min-conflicts repairs boards of this size in a few steps per queen"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_by_size_and_exactness() {
+        let budget = SolveBudget::default();
+        assert_eq!(
+            select(8, &budget, false).strategy.name,
+            "constraint_propagation"
+        );
+        assert_eq!(select(100, &budget, false).strategy.name, "min_conflicts");
+        assert_eq!(
+            select(100, &budget, true).strategy.name,
+            "constraint_propagation"
+        );
+        assert_eq!(
+            select(LARGE_BOARD, &budget, false).strategy.name,
+            "decomposition"
+        );
+        assert!(select(LARGE_BOARD, &budget, false)
+            .reason
+            .contains("a block of columns at a time"));
+    }
+
+    #[test]
+    fn keeps_trying_within_a_time_limit() {
+        let unlimited = select(100, &SolveBudget::default(), false);
+        assert_eq!(unlimited.preset, Preset::Fast);
+
+        let budget = SolveBudget {
+            max_wall_time: Some(Duration::from_secs(1)),
+            ..SolveBudget::default()
+        };
+        assert_eq!(select(100, &budget, false).preset, Preset::Thorough);
+        let exact = select(EXACT_WITHIN_A_SECOND + 1, &budget, true);
+        assert!(exact.reason.contains("budget"));
+    }
+
+    #[test]
+    fn solves_what_it_picks() {
+        for &(n, exact) in &[(6, false), (30, false), (30, true)] {
+            let selection = select(n, &SolveBudget::default(), exact);
+            let solution = selection
+                .solve(n, Some(7), SolveBudget::default(), |_, _| {})
+                .unwrap();
+            assert_eq!(solution.status(), SolveStatus::Solved, "{}", selection);
+        }
+    }
+}
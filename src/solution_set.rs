@@ -0,0 +1,219 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! A compact file format for sets of solutions to boards of the same size,
+//! like all the fundamental ones, so that other tools can use them without
+//! enumerating them again.
+//!
+//! A file starts with the magic bytes `NQSS`, a version byte, the board
+//! size as a little-endian `u32`, and the number of solutions as a
+//! little-endian `u64`. Each solution follows, as the row of each queen
+//! packed in as few bits as the rows need, least significant bits first,
+//! and padded to a whole byte. All the fundamental solutions for n = 14 take
+//! 7 bytes each.
+
+use solvers::constraint_propagation::{
+    ColumnOrder, ConstraintPropagation, ConstraintPropagationConfig, SymmetryBreaking,
+};
+use std::io::{self, Read, Write};
+use *;
+
+const MAGIC: &[u8; 4] = b"NQSS";
+const VERSION: u8 = 1;
+
+/// Solutions to a board of a given size, deduplicated up to the symmetries
+/// of the board.
+#[derive(Clone, Debug)]
+pub struct SolutionSet {
+    dimension: usize,
+    archive: SolutionArchive,
+}
+
+/// Returns how many bits the rows of a board of `dimension` need.
+fn bits_per_row(dimension: usize) -> usize {
+    match dimension {
+        0 | 1 => 0,
+        n => (usize::BITS - (n - 1).leading_zeros()) as usize,
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl SolutionSet {
+    pub fn new(dimension: usize) -> Self {
+        SolutionSet {
+            dimension,
+            archive: SolutionArchive::default(),
+        }
+    }
+
+    /// Returns all the solutions to a board of `dimension`, one for each
+    /// class of symmetric ones, found with the exact solver.
+    pub fn fundamental(dimension: usize) -> Self {
+        let config = ConstraintPropagationConfig {
+            randomize_rows: false,
+            column_order: ColumnOrder::LeftToRight,
+            symmetry_breaking: SymmetryBreaking::Fundamental,
+            restarts: 0,
+            restart_backtracks: 0,
+            max_nogood_size: 0,
+            seed: None,
+            budget: SolveBudget::default(),
+        };
+        let mut set = Self::new(dimension);
+        ConstraintPropagation::new(dimension, config).for_each_solution(|queen_rows| {
+            set.insert(queen_rows);
+        });
+        set
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Adds a solution to the set, returning false if it, or a symmetric
+    /// one, was already there.
+    ///
+    /// Panics if `queen_rows` isn't a solution to a board of the size of the
+    /// set.
+    pub fn insert(&mut self, queen_rows: &[usize]) -> bool {
+        assert!(
+            Solution::partial(queen_rows.to_vec(), 0, self.dimension).is_valid(),
+            "not a solution for n = {}",
+            self.dimension
+        );
+        self.archive.insert(queen_rows)
+    }
+
+    pub fn len(&self) -> usize {
+        self.archive.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.archive.is_empty()
+    }
+
+    /// The solutions in the set, in the order they were added.
+    pub fn solutions(&self) -> &[Box<[usize]>] {
+        self.archive.solutions()
+    }
+
+    /// Writes the set to `writer` in the format of this module.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        writer.write_all(&(self.dimension as u32).to_le_bytes())?;
+        writer.write_all(&(self.len() as u64).to_le_bytes())?;
+
+        let bits = bits_per_row(self.dimension);
+        let mut record = vec![0u8; (self.dimension * bits).div_ceil(8)];
+        for queen_rows in self.solutions() {
+            record.iter_mut().for_each(|byte| *byte = 0);
+            for (column, &row) in queen_rows.iter().enumerate() {
+                for bit in 0..bits {
+                    if row & (1 << bit) != 0 {
+                        let offset = column * bits + bit;
+                        record[offset / 8] |= 1 << (offset % 8);
+                    }
+                }
+            }
+            writer.write_all(&record)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a set written by `write_to`, keeping a single solution of each
+    /// class of symmetric ones.
+    ///
+    /// Fails with `io::ErrorKind::InvalidData` if the data isn't in the
+    /// format of this module, or contains anything but solutions.
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut header = [0u8; 17];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid_data("not a solution set"));
+        }
+        if header[4] != VERSION {
+            return Err(invalid_data("unsupported solution set version"));
+        }
+        let mut dimension = [0u8; 4];
+        dimension.copy_from_slice(&header[5..9]);
+        let dimension = u32::from_le_bytes(dimension) as usize;
+        let mut count = [0u8; 8];
+        count.copy_from_slice(&header[9..]);
+        let count = u64::from_le_bytes(count);
+
+        let bits = bits_per_row(dimension);
+        let mut record = vec![0u8; (dimension * bits).div_ceil(8)];
+        let mut queen_rows = vec![0; dimension];
+        let mut set = Self::new(dimension);
+        for _ in 0..count {
+            reader.read_exact(&mut record)?;
+            for (column, row) in queen_rows.iter_mut().enumerate() {
+                *row = (0..bits)
+                    .filter(|bit| {
+                        let offset = column * bits + bit;
+                        record[offset / 8] & (1 << (offset % 8)) != 0
+                    })
+                    .map(|bit| 1 << bit)
+                    .sum();
+            }
+            if !Solution::partial(queen_rows.clone(), 0, dimension).is_valid() {
+                return Err(invalid_data("not a solution"));
+            }
+            set.archive.insert(&queen_rows);
+        }
+        Ok(set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_and_deduplicates() {
+        let set = SolutionSet::fundamental(8);
+        assert_eq!(
+            set.len() as u64,
+            validation::known_fundamental_solution_count(8).unwrap()
+        );
+
+        let mut bytes = vec![];
+        set.write_to(&mut bytes).unwrap();
+        // Three bits per queen, so three bytes per solution.
+        assert_eq!(bytes.len(), 17 + 3 * set.len());
+        let read = SolutionSet::read_from(&mut &bytes[..]).unwrap();
+        assert_eq!(read.dimension(), 8);
+        assert_eq!(read.solutions(), set.solutions());
+
+        // A mirrored copy of a solution is dropped on import.
+        let first = set.solutions()[0].clone();
+        let mirrored = first.iter().rev().cloned().collect::<Vec<_>>();
+        let mut both = SolutionSet::new(8);
+        both.archive.solutions = vec![first, mirrored.into_boxed_slice()];
+        let mut bytes = vec![];
+        both.write_to(&mut bytes).unwrap();
+        assert_eq!(SolutionSet::read_from(&mut &bytes[..]).unwrap().len(), 1);
+
+        bytes[0] = b'X';
+        let error = SolutionSet::read_from(&mut &bytes[..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
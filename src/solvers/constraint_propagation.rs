@@ -0,0 +1,871 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::mem;
+use *;
+
+/// The order in which the columns get their queens.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ColumnOrder {
+    /// From the first column to the last.
+    LeftToRight,
+    /// The column with the fewest rows left that no queen attacks goes
+    /// next, breaking ties to the left. A column with none left is a dead
+    /// end as soon as it happens, rather than once it's reached.
+    MostConstrained,
+}
+
+/// Which solutions the search skips because they're symmetric to others.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SymmetryBreaking {
+    /// None of them.
+    None,
+    /// Those with the first queen on the bottom half of its column. When
+    /// enumerating, each solution found is reported along with its mirror
+    /// image from top to bottom, so all of them are still reported with
+    /// half the search.
+    Reflection,
+    /// Like `Reflection`, but only the solutions that are the smallest of
+    /// those equivalent to them under rotations and reflections are
+    /// reported when enumerating, as given by `canonical_placement`.
+    Fundamental,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct ConstraintPropagationConfig {
+    /// Whether to try the rows of each column in a random order, instead
+    /// of from top to bottom. The order is shuffled again on each
+    /// restart.
+    pub randomize_rows: bool,
+    /// Which column gets the next queen.
+    pub column_order: ColumnOrder,
+    pub symmetry_breaking: SymmetryBreaking,
+    /// How many times the search can be restarted from scratch.
+    pub restarts: usize,
+    /// How many backtracks to allow before the first restart. The limit
+    /// doubles after each restart, and the last attempt has no limit, so
+    /// the search remains complete. Zero disables restarts.
+    pub restart_backtracks: usize,
+    /// Partial placements of up to this many queens proven not to lead to
+    /// a solution are remembered across restarts, and never tried again.
+    /// Only when filling the columns left to right.
+    pub max_nogood_size: usize,
+    /// The master seed of the random streams, so that runs can be
+    /// reproduced, or a random one if unset.
+    pub seed: Option<u64>,
+    /// Limits on the whole solve, and a way to cancel it.
+    pub budget: SolveBudget,
+}
+
+impl Default for ConstraintPropagationConfig {
+    fn default() -> Self {
+        ConstraintPropagationConfig {
+            randomize_rows: true,
+            column_order: ColumnOrder::LeftToRight,
+            symmetry_breaking: SymmetryBreaking::None,
+            restarts: 10,
+            restart_backtracks: 100,
+            max_nogood_size: 0,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+}
+
+/// A constraint-propagation solution to the n-queens challenge.
+pub struct ConstraintPropagation {
+    base: GenericChallengeState,
+    config: ConstraintPropagationConfig,
+    rngs: ComponentRngs,
+    /// The order in which the rows of each column are tried, if not
+    /// ascending.
+    row_order: Option<Vec<Vec<usize>>>,
+    /// The partial placements known not to lead to a solution.
+    nogoods: HashSet<Vec<usize>>,
+    effort: SearchEffort,
+    budget: BudgetTracker,
+}
+
+impl ConstraintPropagation {
+    /// Shuffles the order in which rows are tried, if requested.
+    fn shuffle_rows(&mut self) {
+        use rand::Rng;
+
+        let size = self.base.size;
+        if self.config.randomize_rows {
+            let rng = &mut self.rngs.value_ordering;
+            let order = (0..size)
+                .map(|_| {
+                    let mut rows = (0..size).collect::<Vec<_>>();
+                    rng.shuffle(&mut rows);
+                    rows
+                })
+                .collect();
+            self.row_order = Some(order);
+        }
+    }
+
+    /// Returns whether placing the next queen at `row` is known not to
+    /// lead to a solution.
+    fn is_nogood(&mut self, row: usize) -> bool {
+        if self.base.queen_rows.len() >= self.config.max_nogood_size {
+            return false;
+        }
+        self.base.queen_rows.push(row);
+        let is_nogood = self.nogoods.contains(&self.base.queen_rows);
+        self.base.queen_rows.pop();
+        is_nogood
+    }
+
+    /// Returns whether the queen of `column` can go on `row` without
+    /// leaving out symmetric solutions the search must find.
+    fn breaks_symmetry(&self, column: usize, row: usize) -> bool {
+        self.config.symmetry_breaking != SymmetryBreaking::None
+            && column == 0
+            && 2 * row + 1 > self.base.size
+    }
+
+    /// Returns the row that is tried in `index`-th place for `column`.
+    fn row_at(&self, column: usize, index: usize) -> usize {
+        match self.row_order {
+            Some(ref order) => order[column][index],
+            None => index,
+        }
+    }
+
+    /// Returns the place in which `row` is tried for `column`.
+    fn index_of(&self, column: usize, row: usize) -> usize {
+        match self.row_order {
+            Some(ref order) => order[column].iter().position(|&r| r == row).unwrap(),
+            None => row,
+        }
+    }
+
+    /// Tries to position the next queen at the row tried in `index`-th
+    /// place, or any of the following ones, returning the place of the
+    /// row it fits in.
+    fn position_next_queen_from(&mut self, mut index: usize) -> Result<usize, ()> {
+        let column = self.base.queen_rows.len();
+        while index < self.base.size {
+            let row = self.row_at(column, index);
+            if !self.breaks_symmetry(column, row)
+                && self.base.queen_can_be_positioned_at((column, row))
+                && !self.is_nogood(row)
+            {
+                return Ok(index);
+            }
+            index += 1;
+        }
+
+        Err(())
+    }
+
+    /// Places queens on the first `count` columns, none of them attacking
+    /// another, like `solve_with_callback` does with the whole board,
+    /// always filling the columns left to right. Unless `count` is the size
+    /// of the board, the solution is partial and unsolved.
+    pub fn place_queens<F>(mut self, count: usize, mut callback: F) -> Solution
+    where
+        F: FnMut(&[usize], usize),
+    {
+        let mut restarts = 0;
+        let mut backtracks = 0;
+        let mut backtrack_limit = self.config.restart_backtracks;
+
+        let mut start_search_at = 0;
+        while self.base.queen_rows.len() != count && !self.budget.should_stop() {
+            match self.position_next_queen_from(start_search_at) {
+                Ok(index) => {
+                    let column = self.base.queen_rows.len();
+                    let row = self.row_at(column, index);
+                    self.base.queen_rows.push(row);
+                    self.effort.nodes_expanded += 1;
+                    callback(&self.base.queen_rows, 0);
+                    start_search_at = 0;
+                }
+                Err(()) => {
+                    let placed = self.base.queen_rows.len();
+                    if placed > 0 && placed <= self.config.max_nogood_size {
+                        self.nogoods.insert(self.base.queen_rows.clone());
+                    }
+
+                    match self.base.queen_rows.pop() {
+                        Some(row) => {
+                            backtracks += 1;
+                            self.effort.backtracks += 1;
+                            callback(&self.base.queen_rows, 0);
+                            let column = self.base.queen_rows.len();
+                            start_search_at = self.index_of(column, row) + 1;
+                        }
+                        // Not a single solution.
+                        None => break,
+                    }
+                }
+            }
+
+            if backtrack_limit != 0
+                && restarts < self.config.restarts
+                && backtracks >= backtrack_limit
+            {
+                restarts += 1;
+                backtracks = 0;
+                backtrack_limit *= 2;
+                start_search_at = 0;
+                self.base.queen_rows.clear();
+                self.shuffle_rows();
+                callback(&self.base.queen_rows, 0);
+            }
+        }
+
+        self.finish(restarts)
+    }
+
+    /// Solves like `solve_with_callback`, filling the most constrained
+    /// column first. The callback gets the board after each step.
+    fn solve_most_constrained_first<F>(mut self, mut callback: F) -> Solution
+    where
+        F: FnMut(&ConstrainedBoard),
+    {
+        let size = self.base.size;
+        let mut board = ConstrainedBoard::new(size);
+        let mut restarts = 0;
+        let mut backtracks = 0;
+        let mut backtrack_limit = self.config.restart_backtracks;
+
+        // The column to try next and the place of the row to start at, when
+        // backtracking.
+        let mut next = None;
+        while board.placed.len() != size && !self.budget.should_stop() {
+            let (column, start_search_at) = match next.take() {
+                Some(next) => next,
+                None => (board.most_constrained_column().unwrap(), 0),
+            };
+
+            let found = (start_search_at..size).find(|&index| {
+                let row = self.row_at(column, index);
+                !board.is_attacked((column, row)) && !self.breaks_symmetry(column, row)
+            });
+            match found {
+                Some(index) => {
+                    board.place((column, self.row_at(column, index)), index);
+                    self.effort.nodes_expanded += 1;
+                    callback(&board);
+                }
+                None => match board.pop() {
+                    Some((column, index)) => {
+                        backtracks += 1;
+                        self.effort.backtracks += 1;
+                        callback(&board);
+                        next = Some((column, index + 1));
+                    }
+                    // Not a single solution.
+                    None => break,
+                },
+            }
+
+            if backtrack_limit != 0
+                && restarts < self.config.restarts
+                && backtracks >= backtrack_limit
+            {
+                restarts += 1;
+                backtracks = 0;
+                backtrack_limit *= 2;
+                next = None;
+                board.clear();
+                self.shuffle_rows();
+                callback(&board);
+            }
+        }
+
+        self.base.queen_rows = board.leading_rows();
+        self.finish(restarts)
+    }
+
+    fn finish(mut self, restarts: usize) -> Solution {
+        let score = self.base.score();
+        self.effort.evaluations += 1;
+        let solved = score == 0 && self.base.queen_rows.len() == self.base.size;
+        let mut solution = Solution::partial(self.base.queen_rows, score, self.base.size);
+        self.budget.finish(Self::info(), &mut solution, solved);
+        solution.statistics.master_seed = self.rngs.master_seed;
+        solution.statistics.effort = self.effort;
+        solution.statistics.restarts = restarts;
+        solution.statistics.nogoods = self.nogoods.len();
+        solution
+    }
+
+    /// Counts all the solutions by exhausting the search space, ignoring
+    /// restarts and nogoods.
+    pub fn count_solutions(self) -> u64 {
+        let mut count = 0;
+        self.for_each_solution(|_| count += 1);
+        count
+    }
+
+    /// Solves like `solve_with_callback`, but also passes the callback the
+    /// rows each column can still take given the queens placed so far, so
+    /// that the squares pruned by them can be shown. Those of the placed
+    /// queens are just their own rows.
+    ///
+    /// When filling the most constrained column first, the domains are
+    /// those of the whole board, so they also show the queens that aren't
+    /// on the leading columns.
+    pub fn solve_with_domains<F>(self, mut callback: F) -> Solution
+    where
+        F: FnMut(&[usize], &[Vec<usize>]),
+    {
+        if self.config.column_order == ColumnOrder::MostConstrained {
+            return self.solve_most_constrained_first(|board| {
+                callback(&board.leading_rows(), &board.domains())
+            });
+        }
+        let size = self.base.size;
+        self.solve_with_callback(|queen_rows, _| callback(queen_rows, &domains(queen_rows, size)))
+    }
+
+    /// Calls `callback` with each of the solutions, in the order the rows
+    /// are tried, by exhausting the search space like `count_solutions`.
+    pub fn for_each_solution<F>(self, mut callback: F)
+    where
+        F: FnMut(&[usize]),
+    {
+        for solution in self.solve_all() {
+            callback(solution.queen_rows());
+        }
+    }
+
+    /// Returns all the solutions, in the order the rows are tried, ignoring
+    /// restarts and nogoods. The search goes on from where it found the
+    /// last solution each time the next one is asked for, and stops early
+    /// if the budget runs out.
+    ///
+    /// With symmetry breaking, the mirror image of each solution found
+    /// comes right after it, and only the fundamental solutions are
+    /// returned if so configured.
+    pub fn solve_all(self) -> AllSolutions {
+        AllSolutions {
+            search: self,
+            start_search_at: 0,
+            started: false,
+            pending: vec![],
+        }
+    }
+}
+
+/// The solutions of a board, found one by one. See
+/// `ConstraintPropagation::solve_all`.
+pub struct AllSolutions {
+    search: ConstraintPropagation,
+    /// The place of the row to try next for the next column.
+    start_search_at: usize,
+    /// Whether any solution was asked for yet.
+    started: bool,
+    /// The solutions to return before searching again, last first.
+    pending: Vec<Vec<usize>>,
+}
+
+impl AllSolutions {
+    /// Places and removes queens until a complete placement is found,
+    /// returning false if there are no more.
+    fn advance(&mut self) -> bool {
+        let search = &mut self.search;
+        let size = search.base.size;
+        // The empty board is the only solution for no queens.
+        if !mem::replace(&mut self.started, true) && size == 0 {
+            return true;
+        }
+
+        while !search.budget.should_stop() {
+            let next = if search.base.queen_rows.len() == size {
+                Err(())
+            } else {
+                search.position_next_queen_from(self.start_search_at)
+            };
+
+            match next {
+                Ok(index) => {
+                    let column = search.base.queen_rows.len();
+                    let row = search.row_at(column, index);
+                    search.base.queen_rows.push(row);
+                    search.effort.nodes_expanded += 1;
+                    self.start_search_at = 0;
+                    if search.base.queen_rows.len() == size {
+                        return true;
+                    }
+                }
+                Err(()) => match search.base.queen_rows.pop() {
+                    Some(row) => {
+                        search.effort.backtracks += 1;
+                        let column = search.base.queen_rows.len();
+                        self.start_search_at = search.index_of(column, row) + 1;
+                    }
+                    None => return false,
+                },
+            }
+        }
+        false
+    }
+}
+
+impl Iterator for AllSolutions {
+    type Item = Solution;
+
+    fn next(&mut self) -> Option<Solution> {
+        while self.pending.is_empty() {
+            if !self.advance() {
+                return None;
+            }
+
+            let found = self.search.base.queen_rows.clone();
+            let size = found.len();
+            let symmetry_breaking = self.search.config.symmetry_breaking;
+            // Mirroring a solution with the first queen on the middle row
+            // leaves it there, so the search finds the mirror image too.
+            if symmetry_breaking != SymmetryBreaking::None && size > 0 && 2 * found[0] + 1 != size {
+                self.pending
+                    .push(found.iter().map(|row| size - 1 - row).collect());
+            }
+            self.pending.push(found);
+            if symmetry_breaking == SymmetryBreaking::Fundamental {
+                self.pending
+                    .retain(|queen_rows| canonical_placement(queen_rows) == *queen_rows);
+            }
+        }
+
+        let search = &self.search;
+        let mut solution = Solution::new(self.pending.pop().unwrap(), 0);
+        search
+            .budget
+            .finish(ConstraintPropagation::info(), &mut solution, true);
+        solution.statistics.master_seed = search.rngs.master_seed;
+        solution.statistics.effort = search.effort.clone();
+        Some(solution)
+    }
+}
+
+/// Returns the rows each column of a board of `size` can take without
+/// being attacked by the queens in `queen_rows`, placed in the first
+/// columns.
+fn domains(queen_rows: &[usize], size: usize) -> Vec<Vec<usize>> {
+    let mut rows = vec![false; size];
+    let mut diagonals = vec![false; 2 * size];
+    let mut anti_diagonals = vec![false; 2 * size];
+    for (column, &row) in queen_rows.iter().enumerate() {
+        rows[row] = true;
+        diagonals[size + column - row] = true;
+        anti_diagonals[column + row] = true;
+    }
+
+    (0..size)
+        .map(|column| match queen_rows.get(column) {
+            Some(&row) => vec![row],
+            None => (0..size)
+                .filter(|&row| {
+                    !rows[row] && !diagonals[size + column - row] && !anti_diagonals[column + row]
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// The queens placed by a search that fills the most constrained column
+/// first, and how many of them attack each square, kept up to date as they
+/// come and go.
+struct ConstrainedBoard {
+    size: usize,
+    /// The row of the queen on each column, if any.
+    rows: Vec<Option<usize>>,
+    /// How many queens attack each square, by column.
+    attacks: Vec<usize>,
+    /// How many rows of each column no queen attacks.
+    free_rows: Vec<usize>,
+    /// The columns with a queen, in the order they got it, with the place
+    /// in which its row was tried.
+    placed: Vec<(usize, usize)>,
+}
+
+impl ConstrainedBoard {
+    fn new(size: usize) -> Self {
+        ConstrainedBoard {
+            size,
+            rows: vec![None; size],
+            attacks: vec![0; size * size],
+            free_rows: vec![size; size],
+            placed: vec![],
+        }
+    }
+
+    fn is_attacked(&self, (column, row): (usize, usize)) -> bool {
+        self.attacks[column * self.size + row] != 0
+    }
+
+    /// Returns the column without a queen with the fewest free rows, if
+    /// any.
+    fn most_constrained_column(&self) -> Option<usize> {
+        (0..self.size)
+            .filter(|&column| self.rows[column].is_none())
+            .min_by_key(|&column| self.free_rows[column])
+    }
+
+    /// Adds or removes the attacks of a queen at `(column, row)` on the
+    /// other columns.
+    fn update_attacks(&mut self, (column, row): (usize, usize), add: bool) {
+        let size = self.size;
+        for other in (0..size).filter(|&other| other != column) {
+            let distance = other.abs_diff(column);
+            let targets = [
+                Some(row),
+                row.checked_add(distance),
+                row.checked_sub(distance),
+            ];
+            for target in targets.iter().filter_map(|&target| target) {
+                if target >= size {
+                    continue;
+                }
+                let attacks = &mut self.attacks[other * size + target];
+                if add {
+                    *attacks += 1;
+                    if *attacks == 1 {
+                        self.free_rows[other] -= 1;
+                    }
+                } else {
+                    *attacks -= 1;
+                    if *attacks == 0 {
+                        self.free_rows[other] += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    fn place(&mut self, (column, row): (usize, usize), index: usize) {
+        self.rows[column] = Some(row);
+        self.placed.push((column, index));
+        self.update_attacks((column, row), true);
+    }
+
+    /// Removes the last queen placed, returning its column and the place
+    /// in which its row was tried.
+    fn pop(&mut self) -> Option<(usize, usize)> {
+        let (column, index) = self.placed.pop()?;
+        let row = self.rows[column].take().unwrap();
+        self.update_attacks((column, row), false);
+        Some((column, index))
+    }
+
+    fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    /// Returns the rows of the queens on the leading columns that have
+    /// one.
+    fn leading_rows(&self) -> Vec<usize> {
+        self.rows.iter().map_while(|&row| row).collect()
+    }
+
+    /// Returns the rows each column can take, like `domains` does, but
+    /// counting the queens on every column.
+    fn domains(&self) -> Vec<Vec<usize>> {
+        (0..self.size)
+            .map(|column| match self.rows[column] {
+                Some(row) => vec![row],
+                None => (0..self.size)
+                    .filter(|&row| !self.is_attacked((column, row)))
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+impl NQueensStrategy for ConstraintPropagation {
+    type Config = ConstraintPropagationConfig;
+
+    fn new(size: usize, config: Self::Config) -> Self {
+        let mut challenge = ConstraintPropagation {
+            base: GenericChallengeState::unpositioned(size),
+            rngs: ComponentRngs::for_seed(config.seed),
+            budget: BudgetTracker::new(config.budget.clone()),
+            config,
+            row_order: None,
+            nogoods: HashSet::new(),
+            effort: SearchEffort::default(),
+        };
+        challenge.shuffle_rows();
+        challenge
+    }
+
+    fn lint(_dimension: usize, config: &Self::Config) -> Vec<ConfigWarning> {
+        let mut warnings = vec![];
+        if config.restarts != 0 && config.restart_backtracks == 0 {
+            warnings.push(ConfigWarning::new(
+                "restarts",
+                "restarts never happen without a backtrack limit".to_owned(),
+            ));
+        }
+        if config.max_nogood_size != 0 && config.column_order != ColumnOrder::LeftToRight {
+            warnings.push(ConfigWarning::new(
+                "max_nogood_size",
+                "nogoods are only remembered when filling the columns left to right".to_owned(),
+            ));
+        }
+        warnings
+    }
+
+    fn info() -> &'static StrategyInfo {
+        &info::CONSTRAINT_PROPAGATION
+    }
+
+    fn dimension(&self) -> usize {
+        self.base.size
+    }
+
+    fn solve_with_callback<F>(self, mut callback: F) -> Solution
+    where
+        F: FnMut(&[usize], usize),
+    {
+        if self.config.column_order == ColumnOrder::MostConstrained {
+            // The queens on the columns after the first one without a queen
+            // can't be told apart from missing ones.
+            return self.solve_most_constrained_first(|board| callback(&board.leading_rows(), 0));
+        }
+        let size = self.base.size;
+        self.place_queens(size, callback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIM: usize = 8;
+    fn pos(x: usize, y: usize) -> (usize, usize) {
+        (x, y)
+    }
+
+    fn config() -> ConstraintPropagationConfig {
+        ConstraintPropagationConfig {
+            randomize_rows: false,
+            restarts: 0,
+            restart_backtracks: 0,
+            seed: Some(1),
+            ..ConstraintPropagationConfig::default()
+        }
+    }
+
+    #[test]
+    fn are_reachable_test() {
+        let challenge = ConstraintPropagation::new(DIM, config());
+
+        assert!(challenge.base.can_position(pos(0, 0), pos(0, 0)).is_err());
+        assert!(challenge.base.can_position(pos(0, 1), pos(0, 0)).is_err());
+        assert!(challenge.base.can_position(pos(1, 0), pos(0, 0)).is_err());
+        assert!(challenge.base.can_position(pos(1, 1), pos(5, 5)).is_err());
+        assert!(challenge.base.can_position(pos(3, 2), pos(2, 3)).is_err());
+    }
+
+    #[test]
+    fn reports_remaining_rows() {
+        let mut steps = 0;
+        let solution =
+            ConstraintPropagation::new(DIM, config()).solve_with_domains(|queens, domains| {
+                steps += 1;
+                assert_eq!(domains.len(), DIM);
+                for (column, domain) in domains.iter().enumerate() {
+                    if let Some(&row) = queens.get(column) {
+                        assert_eq!(*domain, vec![row]);
+                        continue;
+                    }
+                    for row in 0..DIM {
+                        let free = queens.iter().enumerate().all(|(x, &y)| {
+                            y != row && x + row != column + y && x + y != column + row
+                        });
+                        assert_eq!(domain.contains(&row), free);
+                    }
+                }
+            });
+        assert_eq!(solution.score, 0);
+        assert!(steps > 0);
+    }
+
+    #[test]
+    fn finds_eight_queens_solution() {
+        let challenge = ConstraintPropagation::new(DIM, config());
+        assert_eq!(challenge.solve().score, 0);
+    }
+
+    #[test]
+    fn finds_twelve_queens_solution() {
+        let challenge = ConstraintPropagation::new(12, config());
+        assert_eq!(challenge.solve().score, 0);
+    }
+
+    #[test]
+    fn finds_solution_with_randomized_rows() {
+        let config = ConstraintPropagationConfig {
+            randomize_rows: true,
+            ..config()
+        };
+        let challenge = ConstraintPropagation::new(12, config);
+        assert_eq!(challenge.solve().score, 0);
+    }
+
+    #[test]
+    fn finds_solution_with_restarts_and_nogoods() {
+        let config = ConstraintPropagationConfig {
+            restart_backtracks: 50,
+            max_nogood_size: 3,
+            seed: Some(2),
+            ..ConstraintPropagationConfig::default()
+        };
+        let solution = ConstraintPropagation::new(16, config).solve();
+        assert_eq!(solution.score, 0);
+        assert!(solution.statistics.restarts <= 10);
+    }
+
+    #[test]
+    fn enumerates_all_solutions() {
+        let solutions = ConstraintPropagation::new(DIM, config())
+            .solve_all()
+            .collect::<Vec<_>>();
+        assert_eq!(solutions.len(), 92);
+        assert!(solutions.iter().all(|s| s.is_valid()));
+        assert!(solutions
+            .windows(2)
+            .all(|w| w[0].queen_rows() < w[1].queen_rows()));
+
+        let config = ConstraintPropagationConfig {
+            randomize_rows: true,
+            budget: SolveBudget {
+                max_iterations: Some(200),
+                ..SolveBudget::default()
+            },
+            ..config()
+        };
+        let mut solutions = ConstraintPropagation::new(DIM, config).solve_all();
+        let first = solutions.next().unwrap();
+        assert_eq!(first.status(), SolveStatus::Solved);
+        assert!(solutions.count() < 91);
+    }
+
+    #[test]
+    fn skips_symmetric_solutions() {
+        let search = |n, symmetry_breaking| {
+            let config = ConstraintPropagationConfig {
+                symmetry_breaking,
+                ..config()
+            };
+            let solutions = ConstraintPropagation::new(n, config)
+                .solve_all()
+                .collect::<Vec<_>>();
+            let nodes = solutions
+                .last()
+                .map_or(0, |s| s.statistics.effort.nodes_expanded);
+            let mut queen_rows = solutions
+                .iter()
+                .map(|s| s.queen_rows().to_vec())
+                .collect::<Vec<_>>();
+            queen_rows.sort();
+            (queen_rows, nodes)
+        };
+
+        for &n in &[1, 4, 5, 8, 9] {
+            let (all, nodes) = search(n, SymmetryBreaking::None);
+            let (reflected, fewer_nodes) = search(n, SymmetryBreaking::Reflection);
+            assert_eq!(reflected, all);
+            assert!(fewer_nodes <= nodes);
+
+            let (fundamental, _) = search(n, SymmetryBreaking::Fundamental);
+            assert_eq!(
+                fundamental.len() as u64,
+                validation::known_fundamental_solution_count(n).unwrap()
+            );
+        }
+
+        let config = ConstraintPropagationConfig {
+            symmetry_breaking: SymmetryBreaking::Reflection,
+            column_order: ColumnOrder::MostConstrained,
+            ..config()
+        };
+        let solution = ConstraintPropagation::new(12, config).solve();
+        assert!(solution.is_valid());
+        assert!(2 * solution.queen_rows()[0] < 12);
+    }
+
+    #[test]
+    fn fills_the_most_constrained_column_first() {
+        let config = || ConstraintPropagationConfig {
+            column_order: ColumnOrder::MostConstrained,
+            ..config()
+        };
+        let mut steps = 0;
+        let solution =
+            ConstraintPropagation::new(DIM, config()).solve_with_callback(|_, _| steps += 1);
+        assert_eq!(solution.status(), SolveStatus::Solved);
+        assert!(solution.is_valid());
+        assert!(steps >= DIM);
+
+        // Far fewer queens are tried than going left to right.
+        let most_constrained = ConstraintPropagation::new(20, config()).solve();
+        assert!(most_constrained.is_valid());
+        let left_to_right = ConstraintPropagation::new(
+            20,
+            ConstraintPropagationConfig {
+                column_order: ColumnOrder::LeftToRight,
+                ..config()
+            },
+        )
+        .solve();
+        assert!(
+            most_constrained.statistics.effort.nodes_expanded * 10
+                < left_to_right.statistics.effort.nodes_expanded
+        );
+
+        // No board of three queens has a solution.
+        let solution = ConstraintPropagation::new(3, config()).solve();
+        assert_eq!(solution.status(), SolveStatus::Unsolved);
+
+        // The domains show the queens past the leading columns too.
+        let mut beyond_leading = 0;
+        let mut last = vec![];
+        let solution =
+            ConstraintPropagation::new(DIM, config()).solve_with_domains(|queen_rows, board| {
+                let leading = domains(queen_rows, DIM);
+                for column in queen_rows.len()..DIM {
+                    assert!(board[column]
+                        .iter()
+                        .all(|row| leading[column].contains(row)));
+                    if board[column] != leading[column] {
+                        beyond_leading += 1;
+                    }
+                }
+                last = board.to_vec();
+            });
+        assert!(solution.is_valid());
+        assert!(beyond_leading > 0);
+        let rows = solution.queen_rows().iter().map(|&row| vec![row]);
+        assert_eq!(last, rows.collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn finds_fifteen_queens_solution() {
+        let challenge = ConstraintPropagation::new(15, config());
+        assert_eq!(challenge.solve().score, 0);
+    }
+}
@@ -0,0 +1,356 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Constructive beam search: build placements column by column, keeping
+//! only the most promising partial placements at each depth. Unlike the
+//! exact solver it never backtracks, and unlike the local beam search it
+//! only holds complete placements at the very end.
+
+use std::cmp::Reverse;
+use *;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct ConstructiveBeamSearchConfig {
+    /// How many partial placements to keep at each depth.
+    pub beam_width: usize,
+    /// Whether to drop partial placements with attacks altogether, instead
+    /// of keeping the least attacked ones. When the beam then runs out of
+    /// placements, the search starts over with a beam twice as wide, until
+    /// it finds a solution or no placement was ever dropped, so it's exact.
+    pub prune_conflicts: bool,
+    /// Stop once this many partial placements have been scored, if set.
+    pub max_evaluations: Option<usize>,
+    /// The master seed of the random streams, so that runs can be
+    /// reproduced, or a random one if unset.
+    pub seed: Option<u64>,
+    /// Limits on the whole solve, and a way to cancel it.
+    pub budget: SolveBudget,
+}
+
+impl Default for ConstructiveBeamSearchConfig {
+    fn default() -> Self {
+        ConstructiveBeamSearchConfig {
+            beam_width: 16,
+            prune_conflicts: true,
+            max_evaluations: None,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+}
+
+/// A placement of the queens of the first columns.
+#[derive(Clone)]
+struct PartialPlacement {
+    queen_rows: Vec<usize>,
+    /// Whether each cell of the board, indexed by `column * size + row`,
+    /// can be hit by one of the queens. Only kept up to date for the
+    /// columns without a queen yet.
+    attacked: Vec<bool>,
+    /// The cells that can't be hit in the columns without a queen yet.
+    free_cells: usize,
+    /// How many cells of each column can't be hit.
+    free_in_column: Vec<usize>,
+    /// The number of pairs of placed queens that can hit each other.
+    conflicts: usize,
+}
+
+impl PartialPlacement {
+    fn empty(size: usize) -> Self {
+        PartialPlacement {
+            queen_rows: vec![],
+            attacked: vec![false; size * size],
+            free_cells: size * size,
+            free_in_column: vec![size; size],
+            conflicts: 0,
+        }
+    }
+
+    /// Calls `f` with each cell of the columns after `column` that a queen
+    /// at `row` in `column` can hit.
+    fn for_each_attacked_cell<F>(size: usize, column: usize, row: usize, mut f: F)
+    where
+        F: FnMut(usize, usize),
+    {
+        for other in (column + 1)..size {
+            let distance = other - column;
+            f(other, row);
+            if row + distance < size {
+                f(other, row + distance);
+            }
+            if row >= distance {
+                f(other, row - distance);
+            }
+        }
+    }
+
+    /// Returns how many placed queens can hit a queen at `row` in the next
+    /// column.
+    fn conflicts_at(&self, row: usize) -> usize {
+        let column = self.queen_rows.len();
+        self.queen_rows
+            .iter()
+            .enumerate()
+            .filter(|&(other, &other_row)| {
+                let distance = column - other;
+                other_row == row || other_row + distance == row || row + distance == other_row
+            })
+            .count()
+    }
+
+    /// Returns the partial placement with a queen at `row` in the next
+    /// column, given its precomputed score.
+    fn extend(&self, size: usize, row: usize, conflicts: usize, free_cells: usize) -> Self {
+        let column = self.queen_rows.len();
+        let mut child = self.clone();
+        child.queen_rows.push(row);
+        child.conflicts = conflicts;
+        child.free_cells = free_cells;
+        Self::for_each_attacked_cell(size, column, row, |c, r| {
+            if !child.attacked[c * size + r] {
+                child.attacked[c * size + r] = true;
+                child.free_in_column[c] -= 1;
+            }
+        });
+        child
+    }
+}
+
+/// A candidate extension of a partial placement in the beam.
+struct Candidate {
+    parent: usize,
+    row: usize,
+    conflicts: usize,
+    free_cells: usize,
+}
+
+pub struct ConstructiveBeamSearch {
+    size: usize,
+    rngs: ComponentRngs,
+    effort: SearchEffort,
+    budget: BudgetTracker,
+    config: ConstructiveBeamSearchConfig,
+}
+
+impl ConstructiveBeamSearch {
+    /// Scores every way of placing a queen in the next column of each
+    /// partial placement of `beam`.
+    fn candidates(&mut self, beam: &[PartialPlacement]) -> Vec<Candidate> {
+        let size = self.size;
+        let mut candidates = vec![];
+        let mut newly_attacked_in = vec![0; size];
+        for (parent, placement) in beam.iter().enumerate() {
+            let column = placement.queen_rows.len();
+            for row in 0..size {
+                if self.config.prune_conflicts && placement.attacked[column * size + row] {
+                    continue;
+                }
+
+                let conflicts = placement.conflicts + placement.conflicts_at(row);
+                let mut newly_attacked = 0;
+                PartialPlacement::for_each_attacked_cell(size, column, row, |c, r| {
+                    if !placement.attacked[c * size + r] {
+                        newly_attacked += 1;
+                        newly_attacked_in[c] += 1;
+                    }
+                });
+                self.effort.evaluations += 1;
+                // When pruning, a placement that leaves a later column
+                // without free cells can't be completed, so it's dropped
+                // right away rather than taking up room in the beam.
+                let dead_end = ((column + 1)..size)
+                    .any(|c| placement.free_in_column[c] == newly_attacked_in[c]);
+                for count in &mut newly_attacked_in[(column + 1)..] {
+                    *count = 0;
+                }
+                if self.config.prune_conflicts && dead_end {
+                    continue;
+                }
+                candidates.push(Candidate {
+                    parent,
+                    row,
+                    conflicts,
+                    free_cells: placement.free_cells
+                        - placement.free_in_column[column]
+                        - newly_attacked,
+                });
+            }
+        }
+        candidates
+    }
+
+    /// Builds placements column by column with a beam of `width`, and
+    /// returns the best one, and whether any candidate was left out of the
+    /// beam.
+    fn build<F>(&mut self, width: usize, callback: &mut F) -> (PartialPlacement, bool)
+    where
+        F: FnMut(&[usize], usize),
+    {
+        use rand::Rng;
+
+        let mut beam = vec![PartialPlacement::empty(self.size)];
+        let mut truncated = false;
+        for _ in 0..self.size {
+            if self.effort.exhausts(self.config.max_evaluations) || self.budget.should_stop() {
+                break;
+            }
+
+            let mut candidates = self.candidates(&beam);
+            if candidates.is_empty() {
+                break;
+            }
+
+            // Shuffle before the stable sort so that ties are broken at
+            // random, rather than always favoring the top rows.
+            self.rngs.selection.shuffle(&mut candidates);
+            candidates.sort_by_key(|c| (c.conflicts, Reverse(c.free_cells)));
+            truncated |= candidates.len() > width;
+            candidates.truncate(width);
+
+            beam = candidates
+                .iter()
+                .map(|c| beam[c.parent].extend(self.size, c.row, c.conflicts, c.free_cells))
+                .collect();
+            self.effort.nodes_expanded += beam.len();
+            self.effort.generations += 1;
+            callback(&beam[0].queen_rows, beam[0].conflicts);
+        }
+
+        // The beam is sorted, so the first one is the best, even if the
+        // search stopped early.
+        (beam.swap_remove(0), truncated)
+    }
+}
+
+impl NQueensStrategy for ConstructiveBeamSearch {
+    type Config = ConstructiveBeamSearchConfig;
+
+    fn new(size: usize, config: Self::Config) -> Self {
+        Self {
+            size,
+            rngs: ComponentRngs::for_seed(config.seed),
+            effort: SearchEffort::default(),
+            budget: BudgetTracker::new(config.budget.clone()),
+            config,
+        }
+    }
+
+    fn validate(_dimension: usize, config: &Self::Config) -> Result<(), Error> {
+        if config.beam_width == 0 {
+            return Err(Error::InvalidConfig("beam width must be positive"));
+        }
+        Ok(())
+    }
+
+    fn lint(_dimension: usize, config: &Self::Config) -> Vec<ConfigWarning> {
+        if config.beam_width != 1 {
+            return vec![];
+        }
+        vec![ConfigWarning::new(
+            "beam_width",
+            "a beam of width 1 is a greedy construction, which rarely finds a solution".to_owned(),
+        )]
+    }
+
+    fn info() -> &'static StrategyInfo {
+        &info::CONSTRUCTIVE_BEAM_SEARCH
+    }
+
+    fn dimension(&self) -> usize {
+        self.size
+    }
+
+    fn solve_with_callback<F>(mut self, mut callback: F) -> Solution
+    where
+        F: FnMut(&[usize], usize),
+    {
+        let mut width = self.config.beam_width;
+        let (mut best, mut truncated) = self.build(width, &mut callback);
+        let complete = |p: &PartialPlacement, size| p.queen_rows.len() == size;
+        // Without conflicts pruned the beam never runs out, so there's
+        // nothing to retry.
+        while self.config.prune_conflicts && !complete(&best, self.size) && truncated {
+            if self.effort.exhausts(self.config.max_evaluations) || self.budget.should_stop() {
+                break;
+            }
+            width = width.saturating_mul(2);
+            let (attempt, dropped) = self.build(width, &mut callback);
+            if attempt.queen_rows.len() > best.queen_rows.len() {
+                best = attempt;
+            }
+            truncated = dropped;
+        }
+
+        let solved = best.conflicts == 0 && complete(&best, self.size);
+        let mut solution = Solution::partial(best.queen_rows, best.conflicts, self.size);
+        self.budget.finish(Self::info(), &mut solution, solved);
+        solution.statistics.master_seed = self.rngs.master_seed;
+        solution.statistics.effort = self.effort;
+        solution
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_complete_placements() {
+        // Wide enough to keep every conflict-free partial placement of an
+        // 8x8 board, so it must find a solution.
+        let exact = ConstructiveBeamSearchConfig {
+            beam_width: 1000,
+            seed: Some(1),
+            ..ConstructiveBeamSearchConfig::default()
+        };
+        let solution = ConstructiveBeamSearch::new(8, exact).solve();
+        assert_eq!(solution.queen_rows.len(), 8);
+        assert_eq!(solution.score, 0);
+
+        let narrow = ConstructiveBeamSearchConfig {
+            beam_width: 2,
+            prune_conflicts: false,
+            seed: Some(2),
+            ..ConstructiveBeamSearchConfig::default()
+        };
+        let solution = ConstructiveBeamSearch::new(20, narrow).solve();
+        assert_eq!(solution.queen_rows.len(), 20);
+        let state = GenericChallengeState {
+            size: 20,
+            queen_rows: solution.queen_rows.to_vec(),
+        };
+        assert_eq!(state.score(), solution.score);
+    }
+
+    #[test]
+    fn widens_the_beam_when_it_runs_out() {
+        let greedy = ConstructiveBeamSearchConfig {
+            beam_width: 1,
+            seed: Some(1),
+            ..ConstructiveBeamSearchConfig::default()
+        };
+        let solution = ConstructiveBeamSearch::new(8, greedy.clone()).solve();
+        assert!(solution.is_valid());
+
+        // Once nothing is dropped from the beam, there's no solution to find.
+        let solution = ConstructiveBeamSearch::new(3, greedy).solve();
+        assert!(!solution.is_valid());
+        assert!(solution.queen_rows.len() < 3);
+    }
+}
@@ -0,0 +1,199 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Cuckoo search: each nest holds a placement, cuckoos lay new placements
+//! obtained by Lévy flights from existing ones, and a fraction of the worst
+//! nests is abandoned on each generation.
+
+use std::cmp;
+use *;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct CuckooSearchConfig {
+    pub nests: usize,
+    /// The fraction of the worst nests that is abandoned and replaced by
+    /// random placements on each generation.
+    pub discovery_probability: f32,
+    /// The exponent of the distribution of Lévy flight lengths, in
+    /// number of swaps. See `MoveSelection::HeavyTailed`.
+    pub levy_exponent: f32,
+    pub generation_count: usize,
+    /// What to minimize.
+    pub objective: Objective,
+    /// Stop once this many placements have been scored across the whole
+    /// run, counting incremental evaluations too, if set.
+    pub max_evaluations: Option<usize>,
+    /// The master seed of the random streams, so that runs can be
+    /// reproduced, or a random one if unset.
+    pub seed: Option<u64>,
+    /// Limits on the whole solve, and a way to cancel it.
+    pub budget: SolveBudget,
+}
+
+impl Default for CuckooSearchConfig {
+    fn default() -> Self {
+        CuckooSearchConfig {
+            nests: 15,
+            discovery_probability: 0.25,
+            levy_exponent: 1.5,
+            generation_count: 10_000,
+            objective: Objective::AttackingPairs,
+            max_evaluations: None,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+}
+
+pub struct CuckooSearch {
+    size: usize,
+    rngs: ComponentRngs,
+    flights: MoveSelector,
+    effort: SearchEffort,
+    budget: BudgetTracker,
+    config: CuckooSearchConfig,
+}
+
+impl NQueensStrategy for CuckooSearch {
+    type Config = CuckooSearchConfig;
+
+    fn new(size: usize, config: Self::Config) -> Self {
+        Self {
+            size,
+            rngs: ComponentRngs::for_seed(config.seed),
+            flights: MoveSelector::new(MoveSelection::HeavyTailed {
+                exponent: config.levy_exponent,
+            }),
+            effort: SearchEffort::default(),
+            budget: BudgetTracker::new(config.budget.clone()),
+            config,
+        }
+    }
+
+    fn validate(_dimension: usize, config: &Self::Config) -> Result<(), Error> {
+        if config.nests == 0 {
+            return Err(Error::InvalidConfig("nest count must be positive"));
+        }
+        check_probability(
+            config.discovery_probability,
+            "discovery probability must be between 0 and 1",
+        )
+    }
+
+    fn lint(_dimension: usize, config: &Self::Config) -> Vec<ConfigWarning> {
+        if config.discovery_probability < 1. {
+            return vec![];
+        }
+        vec![ConfigWarning::new(
+            "discovery_probability",
+            "every nest but the best one is abandoned on each generation".to_owned(),
+        )]
+    }
+
+    fn info() -> &'static StrategyInfo {
+        &info::CUCKOO_SEARCH
+    }
+
+    fn dimension(&self) -> usize {
+        self.size
+    }
+
+    fn solve_with_callback<F>(mut self, mut callback: F) -> Solution
+    where
+        F: FnMut(&[usize], usize),
+    {
+        use rand::Rng;
+
+        if self.config.nests == 0 {
+            return Solution::partial(vec![], 0, self.size);
+        }
+
+        let mut nests = Vec::with_capacity(self.config.nests);
+        for _ in 0..self.config.nests {
+            let state = GenericChallengeState::new(self.size, &mut self.rngs.initializer);
+            let score = state.evaluate(self.config.objective);
+            nests.push((state, score));
+        }
+        self.effort.evaluations += nests.len();
+
+        for _ in 0..self.config.generation_count {
+            nests.sort_by_key(|&(_, score)| score);
+            callback(&nests[0].0.queen_rows, nests[0].1);
+            if nests[0].1 == 0
+                || self.size < 2
+                || self.effort.exhausts(self.config.max_evaluations)
+                || self.budget.should_stop()
+            {
+                break;
+            }
+
+            // Lay a cuckoo egg from each nest, and drop it in a random one
+            // if it's better than what's there.
+            for i in 0..nests.len() {
+                let mut cuckoo = nests[i].0.clone();
+                self.flights
+                    .apply_next(&mut cuckoo, &mut self.rngs.neighborhood);
+                let score = cuckoo.evaluate(self.config.objective);
+                self.effort.evaluations += 1;
+
+                let j = self.rngs.selection.next_u32() as usize % nests.len();
+                if score < nests[j].1 {
+                    nests[j] = (cuckoo, score);
+                    self.effort.accepted_moves += 1;
+                }
+            }
+
+            // Abandon the worst nests, but never the best one.
+            nests.sort_by_key(|&(_, score)| score);
+            let abandoned = (nests.len() as f32 * self.config.discovery_probability) as usize;
+            let abandoned = cmp::min(abandoned, nests.len() - 1);
+            let first_abandoned = nests.len() - abandoned;
+            for nest in &mut nests[first_abandoned..] {
+                let state = GenericChallengeState::new(self.size, &mut self.rngs.initializer);
+                let score = state.evaluate(self.config.objective);
+                *nest = (state, score);
+            }
+            self.effort.evaluations += abandoned;
+            self.effort.generations += 1;
+        }
+
+        let (state, score) = nests.into_iter().min_by_key(|&(_, score)| score).unwrap();
+        let mut solution = Solution::new(state.queen_rows, score);
+        self.budget.finish(Self::info(), &mut solution, score == 0);
+        solution.statistics.master_seed = self.rngs.master_seed;
+        solution.statistics.effort = self.effort;
+        solution
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solves_small_boards() {
+        let config = CuckooSearchConfig {
+            generation_count: 2000,
+            seed: Some(1),
+            ..CuckooSearchConfig::default()
+        };
+        let solution = CuckooSearch::new(6, config).solve();
+        assert_eq!(solution.score, 0);
+    }
+}
@@ -0,0 +1,220 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Solves very large boards by splitting their columns into blocks, solving
+//! each block on its own range of rows, and then repairing the attacks
+//! between blocks on the whole board.
+//!
+//! The row ranges are given to the blocks in a random order, rather than
+//! along the main diagonal, so that queens of different blocks rarely share
+//! a diagonal, and the repair only has a few of them to move.
+
+use solvers::min_conflicts::MinConflictsConfig;
+use std::cmp;
+use *;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct DecompositionConfig {
+    /// How many columns each block has. The last one has fewer if it
+    /// doesn't divide the board.
+    pub block_size: usize,
+    /// How to solve each block. Its seed and budget are ignored.
+    pub blocks: MinConflictsConfig,
+    /// How to repair the board once the blocks are put together. Its seed
+    /// and budget are ignored, and restarts start from a random placement,
+    /// so they're rarely useful.
+    pub repair: MinConflictsConfig,
+    /// The master seed of the random streams, so that runs can be
+    /// reproduced, or a random one if unset.
+    pub seed: Option<u64>,
+    /// Limits on the whole solve, and a way to cancel it.
+    pub budget: SolveBudget,
+}
+
+impl Default for DecompositionConfig {
+    fn default() -> Self {
+        DecompositionConfig {
+            block_size: 64,
+            blocks: MinConflictsConfig::default(),
+            repair: MinConflictsConfig {
+                max_steps: 100_000,
+                restarts: 0,
+                ..MinConflictsConfig::default()
+            },
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+}
+
+pub struct Decomposition {
+    size: usize,
+    rngs: ComponentRngs,
+    effort: SearchEffort,
+    budget: BudgetTracker,
+    config: DecompositionConfig,
+}
+
+impl Decomposition {
+    fn add_effort(&mut self, solution: &Solution) {
+        let effort = &solution.statistics.effort;
+        self.effort.evaluations += effort.evaluations;
+        self.effort.accepted_moves += effort.accepted_moves;
+    }
+
+    /// Solves each block, and returns the placement of the whole board they
+    /// make up, or `None` if the budget ran out first.
+    fn solve_blocks(&mut self) -> Option<Vec<usize>> {
+        use rand::Rng;
+
+        let block_size = cmp::max(1, cmp::min(self.config.block_size, self.size));
+        let full_blocks = self.size / block_size;
+
+        // The row range of each block, by index. Only the full ranges can be
+        // shuffled, the last one may be shorter.
+        let mut ranges = (0..full_blocks).collect::<Vec<_>>();
+        self.rngs.initializer.shuffle(&mut ranges);
+        if !self.size.is_multiple_of(block_size) {
+            ranges.push(full_blocks);
+        }
+
+        let mut queen_rows = vec![0; self.size];
+        for (block, range) in ranges.into_iter().enumerate() {
+            if self.budget.should_stop() {
+                return None;
+            }
+
+            let start = block * block_size;
+            let width = cmp::min(block_size, self.size - start);
+            let config = MinConflictsConfig {
+                seed: Some(self.rngs.initializer.next_u64()),
+                budget: self.budget.remaining(),
+                ..self.config.blocks.clone()
+            };
+            let solution = MinConflicts::new(width, config).solve();
+            self.add_effort(&solution);
+            for (column, &row) in solution.queen_rows().iter().enumerate() {
+                queen_rows[start + column] = range * block_size + row;
+            }
+        }
+        Some(queen_rows)
+    }
+}
+
+impl NQueensStrategy for Decomposition {
+    type Config = DecompositionConfig;
+
+    fn new(size: usize, config: Self::Config) -> Self {
+        Self {
+            size,
+            rngs: ComponentRngs::for_seed(config.seed),
+            effort: SearchEffort::default(),
+            budget: BudgetTracker::new(config.budget.clone()),
+            config,
+        }
+    }
+
+    fn validate(dimension: usize, config: &Self::Config) -> Result<(), Error> {
+        if config.block_size == 0 {
+            return Err(Error::InvalidConfig("block size must be positive"));
+        }
+        MinConflicts::validate(dimension, &config.blocks)?;
+        MinConflicts::validate(dimension, &config.repair)
+    }
+
+    fn lint(dimension: usize, config: &Self::Config) -> Vec<ConfigWarning> {
+        if config.block_size >= cmp::min(4, dimension) {
+            return vec![];
+        }
+        vec![ConfigWarning::new(
+            "block_size",
+            "blocks of less than 4 queens can't be solved on their own".to_owned(),
+        )]
+    }
+
+    fn info() -> &'static StrategyInfo {
+        &info::DECOMPOSITION
+    }
+
+    fn dimension(&self) -> usize {
+        self.size
+    }
+
+    fn solve_with_callback<F>(mut self, mut callback: F) -> Solution
+    where
+        F: FnMut(&[usize], usize),
+    {
+        use rand::Rng;
+
+        let stitched = self.solve_blocks();
+        let mut solution = match stitched {
+            Some(ref queen_rows) if !self.budget.should_stop() => {
+                let config = MinConflictsConfig {
+                    seed: Some(self.rngs.initializer.next_u64()),
+                    budget: self.budget.remaining(),
+                    ..self.config.repair.clone()
+                };
+                let repaired = MinConflicts::from_placement(queen_rows.clone(), config)
+                    .solve_with_callback(&mut callback);
+                self.add_effort(&repaired);
+                Solution::new(repaired.queen_rows().to_vec(), repaired.score())
+            }
+            Some(queen_rows) => {
+                let score = GenericChallengeState {
+                    size: self.size,
+                    queen_rows: queen_rows.clone(),
+                }
+                .score();
+                Solution::new(queen_rows, score)
+            }
+            None => Solution::partial(vec![], 0, self.size),
+        };
+
+        let solved = solution.queen_rows().len() == self.size && solution.score() == 0;
+        self.budget.finish(Self::info(), &mut solution, solved);
+        solution.statistics.master_seed = self.rngs.master_seed;
+        solution.statistics.effort = self.effort;
+        solution
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solves_blocks_and_repairs_them() {
+        let config = DecompositionConfig {
+            block_size: 50,
+            seed: Some(5),
+            ..DecompositionConfig::default()
+        };
+        let solution = Decomposition::new(1000, config).solve();
+        assert!(solution.is_valid());
+        assert_eq!(solution.status(), SolveStatus::Solved);
+
+        // Blocks that don't divide the board, and too small to be solved.
+        let config = DecompositionConfig {
+            block_size: 3,
+            seed: Some(5),
+            ..DecompositionConfig::default()
+        };
+        assert!(Decomposition::new(20, config).solve().is_valid());
+    }
+}
@@ -0,0 +1,1506 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use checkpoint::SearchState;
+use problem::genetic_algorithm::{
+    Breeding, GenericGeneticAlgorithm, GenericGeneticAlgorithmConfig,
+};
+use problem::{NQueens, NQueensCompletion, Outcome};
+use std::cmp;
+use std::fmt;
+use std::mem;
+use stepper::{StepResult, Stepper};
+use *;
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct GeneticAlgorithmConfig {
+    pub generation_size: usize,
+    pub elitism: f32,
+    /// The fraction of the individuals other than the elite that offspring
+    /// replace each generation, worst first. One replaces all of them, and
+    /// anything close to zero is steady-state reproduction, where the two
+    /// children of a single crossover replace the worst two. The rest are
+    /// carried over as they are, and aren't scored again.
+    pub generation_gap: f32,
+    /// How the parents of the rest of each generation are picked.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub selection: Box<dyn SelectionStrategy>,
+    /// How two individuals are combined.
+    pub crossover: Crossover,
+    pub crossover_probability: f32,
+    pub mutation_probability: f32,
+    /// If set, the mutation probability goes up while the population is
+    /// too uniform, and back down to `mutation_probability` once it isn't.
+    pub adaptive_mutation: Option<AdaptiveMutation>,
+    pub generation_count: usize,
+    /// The fraction of each generation that is replaced by fresh random
+    /// individuals, to prevent premature convergence.
+    pub immigrant_fraction: f32,
+    /// If set, the best individuals ever seen are kept outside of the
+    /// population, and returned in the solution's alternatives.
+    pub hall_of_fame: Option<HallOfFame>,
+    /// If set, part of the population is replaced by fresh random
+    /// individuals whenever the best score stops improving, counted as
+    /// restarts.
+    pub reseed: Option<Reseed>,
+    /// Stop before `generation_count` if the fraction of distinct
+    /// individuals in a generation falls below this. Zero disables it.
+    pub min_diversity: f32,
+    /// Stop before `generation_count` if the best score hasn't improved
+    /// for this many generations. Zero disables it.
+    pub max_stagnant_generations: usize,
+    /// Whether to record where each individual came from, and return it
+    /// in the solution's genealogy.
+    pub track_genealogy: bool,
+    /// Whether to report how diverse each generation is, in the solution's
+    /// diversity.
+    pub track_diversity: bool,
+    /// The pinned queens and blocked squares every individual keeps to, if
+    /// any. Mutation never moves a queen onto a square it can't be at, and
+    /// the individuals that crossover breaks them in are repaired.
+    pub constraints: Option<NQueensCompletion>,
+    /// What to minimize.
+    pub objective: Objective,
+    /// If non-zero, keep a snapshot of the whole generation every this
+    /// many generations, in the solution's snapshots.
+    pub snapshot_interval: usize,
+    /// Stop once this many placements have been scored across the whole
+    /// run, counting incremental evaluations too, if set.
+    pub max_evaluations: Option<usize>,
+    /// The master seed of the random streams, so that runs can be
+    /// reproduced, or a random one if unset.
+    pub seed: Option<u64>,
+    /// Limits on the whole solve, and a way to cancel it.
+    pub budget: SolveBudget,
+}
+
+impl Default for GeneticAlgorithmConfig {
+    fn default() -> Self {
+        GeneticAlgorithmConfig {
+            generation_size: 100,
+            elitism: 0.1,
+            generation_gap: 1.,
+            selection: Box::new(TournamentSelection { size: 3 }),
+            crossover: Crossover::PartiallyMapped,
+            crossover_probability: 0.8,
+            mutation_probability: 0.1,
+            adaptive_mutation: None,
+            generation_count: 1000,
+            immigrant_fraction: 0.,
+            hall_of_fame: None,
+            reseed: None,
+            min_diversity: 0.,
+            max_stagnant_generations: 0,
+            track_genealogy: false,
+            track_diversity: false,
+            constraints: None,
+            objective: Objective::AttackingPairs,
+            snapshot_interval: 0,
+            max_evaluations: None,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+}
+
+/// Picks the individuals that make it to the next generation, besides the
+/// elite.
+pub trait SelectionStrategy: fmt::Debug {
+    /// Returns the indices of `count` individuals of a generation with
+    /// `scores`, sorted from best to worst. The same individual can be
+    /// picked more than once.
+    fn select(&mut self, scores: &[usize], count: usize, rng: &mut StreamRng) -> Vec<usize>;
+
+    /// Checks the parameters of the strategy.
+    fn validate(&self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// How much better than the worst individual each one is, which is what
+/// fitness-proportional selection goes by, and their sum.
+fn inverse_scores(scores: &[usize]) -> (Vec<usize>, usize) {
+    let worst = scores.iter().cloned().max().unwrap_or(0);
+    let inverse = scores.iter().map(|score| worst - score).collect::<Vec<_>>();
+    let total = inverse.iter().sum();
+    (inverse, total)
+}
+
+/// Picks individuals with a probability proportional to how much better
+/// than the worst individual they are. Nearly uniform when all the scores
+/// are close.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RouletteSelection;
+
+impl SelectionStrategy for RouletteSelection {
+    fn select(&mut self, scores: &[usize], count: usize, rng: &mut StreamRng) -> Vec<usize> {
+        use rand::Rng;
+
+        let (inverse, total) = inverse_scores(scores);
+        (0..count)
+            .map(|_| {
+                let p = rng.next_f32();
+                let mut previous = 0.;
+                for (i, inverse) in inverse.iter().enumerate().rev() {
+                    let probability = if total == 0 {
+                        previous + 1. / scores.len() as f32
+                    } else {
+                        previous + *inverse as f32 / total as f32
+                    };
+                    if p < probability {
+                        return i;
+                    }
+                    previous = probability;
+                }
+                // Rounding can leave the probabilities a bit short of one.
+                0
+            })
+            .collect()
+    }
+}
+
+/// Picks individuals with a probability proportional to their rank, from
+/// `n` for the best to one for the worst, so that how far apart the scores
+/// are doesn't matter.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RankSelection;
+
+impl SelectionStrategy for RankSelection {
+    fn select(&mut self, scores: &[usize], count: usize, rng: &mut StreamRng) -> Vec<usize> {
+        use rand::Rng;
+
+        let n = scores.len();
+        let total = n * (n + 1) / 2;
+        (0..count)
+            .map(|_| {
+                let mut ticket = rng.gen_range(0, total);
+                let mut i = 0;
+                while ticket >= n - i {
+                    ticket -= n - i;
+                    i += 1;
+                }
+                i
+            })
+            .collect()
+    }
+}
+
+/// Picks the best of `size` individuals picked at random, with replacement,
+/// each time. Larger tournaments favor the best individuals more.
+#[derive(Clone, Copy, Debug)]
+pub struct TournamentSelection {
+    pub size: usize,
+}
+
+impl SelectionStrategy for TournamentSelection {
+    fn select(&mut self, scores: &[usize], count: usize, rng: &mut StreamRng) -> Vec<usize> {
+        use rand::Rng;
+
+        // The generation is sorted by score, so the best of the tournament
+        // is the one that comes first.
+        (0..count)
+            .map(|_| {
+                (0..self.size)
+                    .map(|_| rng.gen_range(0, scores.len()))
+                    .min()
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if self.size == 0 {
+            return Err(Error::InvalidConfig("tournament size must be positive"));
+        }
+        Ok(())
+    }
+}
+
+/// Picks individuals with the same probabilities as `RouletteSelection`,
+/// but with evenly spaced pointers from a single spin, so that each one is
+/// picked about as many times as expected, even in small generations.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StochasticUniversalSampling;
+
+impl SelectionStrategy for StochasticUniversalSampling {
+    fn select(&mut self, scores: &[usize], count: usize, rng: &mut StreamRng) -> Vec<usize> {
+        use rand::Rng;
+
+        let (mut inverse, mut total) = inverse_scores(scores);
+        if total == 0 {
+            inverse = vec![1; scores.len()];
+            total = scores.len();
+        }
+        let spacing = total as f64 / count as f64;
+        let start = rng.next_f64() * spacing;
+
+        let mut selected = Vec::with_capacity(count);
+        let mut i = 0;
+        let mut cumulative = inverse[0] as f64;
+        for k in 0..count {
+            let pointer = start + k as f64 * spacing;
+            while pointer >= cumulative && i + 1 < inverse.len() {
+                i += 1;
+                cumulative += inverse[i] as f64;
+            }
+            selected.push(i);
+        }
+        selected
+    }
+}
+
+/// How a crossover combines two parents into two children, each of which
+/// takes a segment of queens from one parent, and the rest from the other.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Crossover {
+    /// The parents swap the queens of a prefix. Children often have several
+    /// queens on the same row.
+    PrefixSwap,
+    /// PMX: each child takes a segment from one parent, and the rows of the
+    /// other parent elsewhere, except for the rows already in the segment,
+    /// which are mapped to the rows they replaced. Children of permutations
+    /// are permutations.
+    PartiallyMapped,
+    /// OX: each child takes a segment from one parent, and the rest of the
+    /// rows in the order they appear in the other parent, starting after
+    /// the segment. Children of permutations are permutations.
+    Ordered,
+}
+
+impl Crossover {
+    /// Returns the child of `base` that takes the queens in `start..end`
+    /// from `donor`.
+    fn child(self, base: &[usize], donor: &[usize], start: usize, end: usize) -> Vec<usize> {
+        let size = base.len();
+        let mut child = base.to_vec();
+        child[start..end].copy_from_slice(&donor[start..end]);
+        match self {
+            Crossover::PrefixSwap => {}
+            Crossover::PartiallyMapped => {
+                // Where each row is in the segment of the donor, if it is.
+                let mut donated = vec![None; size];
+                for column in start..end {
+                    donated[donor[column]] = Some(column);
+                }
+                for column in (0..start).chain(end..size) {
+                    let mut row = base[column];
+                    // Bounded, in case the parents aren't permutations.
+                    for _ in start..end {
+                        match donated[row] {
+                            Some(index) => row = base[index],
+                            None => break,
+                        }
+                    }
+                    child[column] = row;
+                }
+            }
+            Crossover::Ordered => {
+                let mut pending = vec![0; size];
+                for &row in &donor[start..end] {
+                    pending[row] += 1;
+                }
+                let mut rows = base[end..].iter().chain(&base[..end]).filter(|&&row| {
+                    if pending[row] == 0 {
+                        return true;
+                    }
+                    pending[row] -= 1;
+                    false
+                });
+                for column in (end..size).chain(0..start) {
+                    child[column] = *rows.next().unwrap();
+                }
+            }
+        }
+        child
+    }
+}
+
+/// How the mutation probability adapts to the diversity of the population,
+/// as estimated by `Breeding::gene_diversity`, which for n-queens is the
+/// average fraction of queens of each individual that aren't on the same
+/// row as in the best one.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AdaptiveMutation {
+    /// The diversity below which the population is too uniform.
+    pub min_diversity: f32,
+    /// How much the mutation probability changes each generation.
+    pub step: f32,
+    /// How high the mutation probability can go.
+    pub max_probability: f32,
+}
+
+/// An archive of the best distinct individuals seen in a run, which
+/// crossover and mutation can't destroy.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HallOfFame {
+    /// How many individuals to keep.
+    pub size: usize,
+    /// If non-zero, the archived individuals replace part of the population
+    /// every time the best score hasn't improved for this many generations.
+    pub stagnant_generations: usize,
+}
+
+/// When and how much of the population to start over.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Reseed {
+    /// Reseed every time the best score hasn't improved for this many
+    /// generations.
+    pub stagnant_generations: usize,
+    /// The fraction of the individuals other than the elite to replace.
+    pub fraction: f32,
+}
+
+/// How an individual came to be.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Origin {
+    /// Randomly generated, either initially or as an immigrant.
+    Random,
+    /// Crossover of its two parents, where the first parent's queens in
+    /// `start..end` were replaced by the second's.
+    Crossover { start: usize, end: usize },
+    /// Mutation of its parent.
+    Mutation,
+}
+
+/// A node of the genealogy graph.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Individual {
+    pub id: usize,
+    /// The generation the individual was born into.
+    pub generation: usize,
+    pub origin: Origin,
+    pub parents: Vec<usize>,
+    /// The swaps applied when mutating the individual.
+    pub mutations: Vec<(usize, usize)>,
+}
+
+/// The parent/child relationships of all the individuals that took part
+/// in a run. Individuals are identified by their index.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Genealogy {
+    individuals: Vec<Individual>,
+    solution: Option<usize>,
+}
+
+impl Genealogy {
+    fn add(&mut self, generation: usize, origin: Origin, parents: Vec<usize>) -> usize {
+        let id = self.individuals.len();
+        self.individuals.push(Individual {
+            id,
+            generation,
+            origin,
+            parents,
+            mutations: vec![],
+        });
+        id
+    }
+
+    /// Records the mutation of the individual `id` into the generation
+    /// `generation`, returning the id of the mutated individual.
+    fn mutate(&mut self, id: usize, generation: usize, swaps: Vec<(usize, usize)>) -> usize {
+        // Individuals born in this same generation are mutated in place.
+        let id = if self.individuals[id].generation == generation {
+            id
+        } else {
+            self.add(generation, Origin::Mutation, vec![id])
+        };
+        self.individuals[id].mutations.extend(swaps);
+        id
+    }
+
+    pub fn individuals(&self) -> &[Individual] {
+        &self.individuals
+    }
+
+    /// The id of the individual returned as the solution.
+    pub fn solution(&self) -> Option<usize> {
+        self.solution
+    }
+
+    /// Returns the individual `id` and all its ancestors, sorted by id.
+    pub fn ancestry(&self, id: usize) -> Vec<&Individual> {
+        let mut visited = HashSet::new();
+        let mut pending = vec![id];
+        while let Some(id) = pending.pop() {
+            if visited.insert(id) {
+                pending.extend(self.individuals[id].parents.iter().cloned());
+            }
+        }
+
+        let mut ancestry = visited
+            .into_iter()
+            .map(|id| &self.individuals[id])
+            .collect::<Vec<_>>();
+        ancestry.sort_by_key(|i| i.id);
+        ancestry
+    }
+
+    /// Returns the lineage graph of the individual `id` in the DOT
+    /// format.
+    pub fn lineage_to_dot(&self, id: usize) -> String {
+        use std::fmt::Write;
+
+        let mut dot = String::from("digraph lineage {\n");
+        for individual in self.ancestry(id) {
+            let origin = match individual.origin {
+                Origin::Random => "random".to_owned(),
+                Origin::Crossover { start, end } => format!("crossover of {}..{}", start, end),
+                Origin::Mutation => "mutation".to_owned(),
+            };
+            let _ = writeln!(
+                dot,
+                "  {} [label=\"#{} (generation {}, {}, {} mutations)\"];",
+                individual.id,
+                individual.id,
+                individual.generation,
+                origin,
+                individual.mutations.len()
+            );
+            for parent in &individual.parents {
+                let _ = writeln!(dot, "  {} -> {};", parent, individual.id);
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Records a new individual in `genealogy` if we're tracking it,
+/// returning its id.
+fn record(
+    genealogy: &mut Option<Genealogy>,
+    generation: usize,
+    origin: Origin,
+    parents: Vec<usize>,
+) -> usize {
+    match *genealogy {
+        Some(ref mut genealogy) => genealogy.add(generation, origin, parents),
+        None => 0,
+    }
+}
+
+/// Breeds placements with the crossovers and swap mutations of the n-queens
+/// genetic algorithm, keeping to its constraints, and keeps the genealogy,
+/// the snapshots and the diversity reports of the run. Individuals are
+/// tagged with their id in the genealogy, or zero if it isn't tracked.
+struct QueenBreeding {
+    size: usize,
+    crossover: Crossover,
+    constraints: Option<NQueensCompletion>,
+    snapshot_interval: usize,
+    track_diversity: bool,
+    genealogy: Option<Genealogy>,
+    snapshots: Vec<PopulationSnapshot>,
+    diversity: Vec<DiversityReport>,
+    /// Spare placements, to copy individuals into.
+    spare: Vec<Vec<usize>>,
+}
+
+impl Breeding<NQueens> for QueenBreeding {
+    type Tag = usize;
+
+    fn newcomer(&mut self, generation: usize) -> usize {
+        record(&mut self.genealogy, generation, Origin::Random, vec![])
+    }
+
+    /// Moves the queens of `queen_rows` that break the constraints, if any,
+    /// to squares they can be at.
+    fn repair(&mut self, _problem: &NQueens, queen_rows: &mut Vec<usize>, rng: &mut StreamRng) {
+        if let Some(ref constraints) = self.constraints {
+            constraints.repair(queen_rows, rng);
+        }
+    }
+
+    fn cross_over(
+        &mut self,
+        _problem: &NQueens,
+        (first, one_id): (&mut Vec<usize>, &mut usize),
+        (second, other_id): (&mut Vec<usize>, &mut usize),
+        generation: usize,
+        rng: &mut StreamRng,
+    ) {
+        use rand::Rng;
+
+        let (start, end) = match self.crossover {
+            Crossover::PrefixSwap => (0, rng.next_u32() as usize % self.size),
+            Crossover::PartiallyMapped | Crossover::Ordered => {
+                let a = rng.next_u32() as usize % self.size;
+                let b = rng.next_u32() as usize % self.size;
+                (cmp::min(a, b), cmp::max(a, b) + 1)
+            }
+        };
+
+        if self.crossover == Crossover::PrefixSwap {
+            first[start..end].swap_with_slice(&mut second[start..end]);
+        } else {
+            let first_child = self.crossover.child(first, second, start, end);
+            let second_child = self.crossover.child(second, first, start, end);
+            *first = first_child;
+            *second = second_child;
+        }
+        if let Some(ref constraints) = self.constraints {
+            constraints.repair(first, rng);
+            constraints.repair(second, rng);
+        }
+
+        if self.genealogy.is_none() {
+            return;
+        }
+        let (one, other) = (*one_id, *other_id);
+        let origin = Origin::Crossover { start, end };
+        *one_id = record(
+            &mut self.genealogy,
+            generation,
+            origin.clone(),
+            vec![one, other],
+        );
+        *other_id = record(&mut self.genealogy, generation, origin, vec![other, one]);
+    }
+
+    /// Swaps each queen with a random one with `probability`.
+    fn mutate(
+        &mut self,
+        _problem: &NQueens,
+        queen_rows: &mut Vec<usize>,
+        id: &mut usize,
+        generation: usize,
+        probability: f32,
+        rng: &mut StreamRng,
+    ) -> bool {
+        use rand::Rng;
+
+        let mut state = GenericChallengeState {
+            size: self.size,
+            queen_rows: mem::take(queen_rows),
+        };
+        let mut swaps = vec![];
+        for _ in 0..self.size {
+            if rng.next_f32() < probability {
+                let (one, other) = state.get_two_random_queens(rng);
+                let allowed = self.constraints.as_ref().is_none_or(|constraints| {
+                    constraints.allows_swap(&state.queen_rows, one, other)
+                });
+                if !allowed {
+                    continue;
+                }
+                state.queen_rows.swap(one, other);
+                swaps.push((one, other));
+            }
+        }
+        *queen_rows = state.queen_rows;
+
+        if swaps.is_empty() {
+            return false;
+        }
+        if let Some(ref mut genealogy) = self.genealogy {
+            *id = genealogy.mutate(*id, generation, swaps);
+        }
+        true
+    }
+
+    /// Returns the average fraction of queens of the individuals in
+    /// `generation` that are on a different row than in the first one,
+    /// which is much cheaper than comparing every pair.
+    fn gene_diversity(&self, generation: &[Vec<usize>]) -> f32 {
+        let first = match generation.first() {
+            Some(first) if !first.is_empty() => first,
+            _ => return 0.,
+        };
+
+        let different = generation[1..]
+            .iter()
+            .map(|queen_rows| {
+                queen_rows
+                    .iter()
+                    .zip(first)
+                    .filter(|&(a, b)| a != b)
+                    .count()
+            })
+            .sum::<usize>();
+        different as f32 / (generation.len() * first.len()) as f32
+    }
+
+    fn record_generation(&mut self, generation: usize, individuals: &[(usize, Vec<usize>, usize)]) {
+        if PopulationSnapshot::is_due(generation, self.snapshot_interval) {
+            let members = individuals
+                .iter()
+                .map(|(score, queen_rows, _)| (&queen_rows[..], *score));
+            self.snapshots
+                .push(PopulationSnapshot::new(generation, members));
+        }
+        if self.track_diversity {
+            let members = individuals.iter().map(|(_, queen_rows, _)| &queen_rows[..]);
+            self.diversity
+                .push(DiversityReport::new(generation, members));
+        }
+    }
+
+    fn copy(&mut self, queen_rows: &Vec<usize>) -> Vec<usize> {
+        match self.spare.pop() {
+            Some(mut copy) => {
+                copy.clone_from(queen_rows);
+                copy
+            }
+            None => queen_rows.clone(),
+        }
+    }
+
+    fn recycle<I>(&mut self, states: I)
+    where
+        I: IntoIterator<Item = Vec<usize>>,
+    {
+        self.spare.extend(states)
+    }
+}
+
+/// The genetic algorithm over `NQueens`.
+pub struct GeneticAlgorithm {
+    search: GenericGeneticAlgorithm<NQueens, QueenBreeding>,
+    /// The iterations of the runs this one resumes.
+    resumed_iterations: usize,
+    context: SolverContext,
+}
+
+impl GeneticAlgorithm {
+    /// Creates an instance for boards of `size` queens whose initial
+    /// population starts with `population`, and is filled up with random
+    /// individuals.
+    pub fn from_population(
+        size: usize,
+        population: Vec<Vec<usize>>,
+        config: GeneticAlgorithmConfig,
+    ) -> Self {
+        let mut genetic = Self::new(size, config);
+        genetic.search.population = population;
+        genetic
+    }
+
+    /// Carries on the search stopped at `checkpoint`, within the budget and
+    /// limits of `config`, which should otherwise be the one it ran with.
+    ///
+    /// The genealogy, the snapshots and the diversity reports only cover the
+    /// resumed run.
+    pub fn resume(checkpoint: Checkpoint, config: GeneticAlgorithmConfig) -> Result<Self, Error> {
+        Self::validate(checkpoint.dimension, &config)?;
+        checkpoint.validate()?;
+        match checkpoint.state {
+            SearchState::GeneticAlgorithm {
+                population,
+                generation,
+                pending_generations,
+                stagnant_generations,
+                mutation_probability,
+                hall_of_fame,
+            } => {
+                let mut genetic = Self::new(checkpoint.dimension, config);
+                let search = &mut genetic.search;
+                search.rngs = checkpoint.rngs;
+                search.effort = checkpoint.effort;
+                search.restarts = checkpoint.restarts;
+                search.population = population;
+                search.generation = generation;
+                search.pending_generations = pending_generations;
+                search.stagnant_generations = stagnant_generations;
+                search.mutation_probability = mutation_probability;
+                search.resumed_best = checkpoint.best;
+                search.resumed_hall_of_fame = hall_of_fame;
+                genetic.resumed_iterations = checkpoint.iterations;
+                Ok(genetic)
+            }
+            _ => Err(Error::InvalidConfig(
+                "the checkpoint is of another strategy",
+            )),
+        }
+    }
+
+    /// Solves like `solve_with_callback`, and also returns the last
+    /// generation, best first, to carry on evolving it. The generation is
+    /// empty if a solution was found.
+    pub fn evolve<F>(self, callback: F) -> (Solution, Vec<Vec<usize>>)
+    where
+        F: FnMut(&[usize], usize),
+    {
+        let (solution, _, population) = self.run(callback);
+        (solution, population)
+    }
+
+    fn run<F>(mut self, mut callback: F) -> (Solution, SolverContext, Vec<Vec<usize>>)
+    where
+        F: FnMut(&[usize], usize),
+    {
+        loop {
+            if let Some(solution) = self.advance(&mut callback) {
+                return (solution, self.context, self.search.population);
+            }
+        }
+    }
+
+    /// Runs the next iteration of the search, which either creates the
+    /// initial population, or scores a generation and breeds the next one,
+    /// and returns the solution if the search is over.
+    fn advance<F>(&mut self, callback: &mut F) -> Option<Solution>
+    where
+        F: FnMut(&[usize], usize),
+    {
+        if self.search.config.generation_size == 0 {
+            self.search.population.clear();
+            return Some(Solution::partial(vec![], 0, self.dimension()));
+        }
+
+        let outcome = self
+            .search
+            .advance(&mut |queen_rows: &Vec<usize>, score| callback(queen_rows, score))?;
+        Some(self.finish(outcome))
+    }
+
+    /// Returns where the search is at, to resume it later even if it's not
+    /// over yet, like between two of its steps. It carries on from the
+    /// generation about to be scored.
+    pub fn snapshot(&self) -> Checkpoint {
+        let (population, best, hall_of_fame) = self.search.resumable();
+        self.checkpoint(population, best, hall_of_fame)
+    }
+
+    /// Returns a checkpoint of the run, with `population` to evolve next.
+    fn checkpoint(
+        &self,
+        population: Vec<Vec<usize>>,
+        best: Option<(Vec<usize>, usize)>,
+        hall_of_fame: Vec<(Vec<usize>, usize)>,
+    ) -> Checkpoint {
+        let search = &self.search;
+        Checkpoint {
+            dimension: self.dimension(),
+            rngs: search.rngs.clone(),
+            effort: search.effort.clone(),
+            iterations: self.resumed_iterations + search.budget.iterations(),
+            restarts: search.restarts,
+            best,
+            state: SearchState::GeneticAlgorithm {
+                population,
+                generation: search.generation,
+                pending_generations: search.pending_generations,
+                stagnant_generations: search.stagnant_generations,
+                mutation_probability: search.mutation_probability,
+                hall_of_fame,
+            },
+        }
+    }
+
+    fn finish(&mut self, outcome: Outcome<Vec<usize>>) -> Solution {
+        let score = outcome.score;
+        let (population, best, hall_of_fame) = self.search.resumable();
+        // The budget stopped the run before it was over. The best score
+        // is the one stagnation is measured against, so it leaves out the
+        // generation that wasn't evolved yet.
+        let checkpoint = if self.search.budget.stopped() && score != 0 {
+            Some(self.checkpoint(population, best, hall_of_fame.clone()))
+        } else {
+            None
+        };
+
+        let mut solution = Solution::new(outcome.state, score);
+        self.search
+            .budget
+            .finish(<Self as NQueensStrategy>::info(), &mut solution, score == 0);
+        solution.statistics.iterations += self.resumed_iterations;
+        let mut genealogy = self.search.breeding.genealogy.take();
+        if let Some(ref mut genealogy) = genealogy {
+            genealogy.solution = self.search.winner;
+        }
+        solution.genealogy = genealogy;
+        solution.alternatives = hall_of_fame
+            .into_iter()
+            .map(|(queen_rows, score)| Solution::new(queen_rows, score))
+            .collect();
+        solution.snapshots = mem::take(&mut self.search.breeding.snapshots);
+        solution.diversity = mem::take(&mut self.search.breeding.diversity);
+        solution.statistics.restarts = self.search.restarts;
+        solution.statistics.master_seed = outcome.master_seed;
+        solution.statistics.effort = self.search.effort.clone();
+        if let Some((ref queen_rows, final_score)) = self.search.final_state().cloned() {
+            solution.set_final_state(queen_rows, final_score);
+        }
+        solution.checkpoint = checkpoint;
+
+        let size = self.dimension();
+        self.context.recycle(
+            self.search
+                .breeding
+                .spare
+                .drain(..)
+                .map(|queen_rows| GenericChallengeState { size, queen_rows }),
+        );
+        solution
+    }
+}
+
+impl Stepper for GeneticAlgorithm {
+    fn step(&mut self) -> StepResult {
+        match self.advance(&mut |_, _| {}) {
+            Some(solution) => StepResult::Done(Box::new(solution)),
+            None => StepResult::Running,
+        }
+    }
+
+    /// Returns the best individual of the last generation scored, or the
+    /// first one of the initial population before scoring any.
+    fn current(&self) -> (&[usize], usize) {
+        self.search
+            .current()
+            .map_or((&[], 0), |(queen_rows, score)| (queen_rows, score))
+    }
+}
+
+impl NQueensStrategy for GeneticAlgorithm {
+    type Config = GeneticAlgorithmConfig;
+
+    fn new(size: usize, config: Self::Config) -> Self {
+        Self::new_in_context(SolverContext::new(size), config)
+    }
+
+    fn new_in_context(mut context: SolverContext, config: Self::Config) -> Self {
+        let size = context.dimension();
+        let breeding = QueenBreeding {
+            size,
+            crossover: config.crossover,
+            constraints: config.constraints,
+            snapshot_interval: config.snapshot_interval,
+            track_diversity: config.track_diversity,
+            genealogy: if config.track_genealogy {
+                Some(Genealogy::default())
+            } else {
+                None
+            },
+            snapshots: vec![],
+            diversity: vec![],
+            spare: mem::take(&mut context.states)
+                .into_iter()
+                .map(|state| state.queen_rows)
+                .collect(),
+        };
+        let problem = NQueens::new(size, config.objective);
+        let config = GenericGeneticAlgorithmConfig {
+            generation_size: config.generation_size,
+            elitism: config.elitism,
+            generation_gap: config.generation_gap,
+            selection: config.selection,
+            crossover_probability: config.crossover_probability,
+            mutation_probability: config.mutation_probability,
+            adaptive_mutation: config.adaptive_mutation,
+            generation_count: config.generation_count,
+            immigrant_fraction: config.immigrant_fraction,
+            hall_of_fame: config.hall_of_fame,
+            reseed: config.reseed,
+            min_diversity: config.min_diversity,
+            max_stagnant_generations: config.max_stagnant_generations,
+            max_evaluations: config.max_evaluations,
+            seed: config.seed,
+            budget: config.budget,
+        };
+        Self {
+            search: GenericGeneticAlgorithm::with_breeding(problem, breeding, config),
+            resumed_iterations: 0,
+            context,
+        }
+    }
+
+    fn validate(dimension: usize, config: &Self::Config) -> Result<(), Error> {
+        if config.generation_size == 0 {
+            return Err(Error::InvalidConfig("generation size must be positive"));
+        }
+        check_probability(config.elitism, "elitism must be between 0 and 1")?;
+        if !(config.generation_gap > 0. && config.generation_gap <= 1.) {
+            return Err(Error::InvalidConfig(
+                "generation gap must be positive and at most 1",
+            ));
+        }
+        check_probability(
+            config.crossover_probability,
+            "crossover probability must be between 0 and 1",
+        )?;
+        check_probability(
+            config.mutation_probability,
+            "mutation probability must be between 0 and 1",
+        )?;
+        check_probability(
+            config.immigrant_fraction,
+            "immigrant fraction must be between 0 and 1",
+        )?;
+        config.selection.validate()?;
+        if let Some(ref adaptive) = config.adaptive_mutation {
+            check_probability(
+                adaptive.min_diversity,
+                "adaptive mutation diversity must be between 0 and 1",
+            )?;
+            check_probability(
+                adaptive.step,
+                "adaptive mutation step must be between 0 and 1",
+            )?;
+            check_probability(
+                adaptive.max_probability,
+                "adaptive mutation probability must be between 0 and 1",
+            )?;
+        }
+        if config.hall_of_fame.as_ref().is_some_and(|h| h.size == 0) {
+            return Err(Error::InvalidConfig("hall of fame size must be positive"));
+        }
+        if let Some(ref reseed) = config.reseed {
+            if reseed.stagnant_generations == 0 {
+                return Err(Error::InvalidConfig(
+                    "reseed stagnant generations must be positive",
+                ));
+            }
+            check_probability(reseed.fraction, "reseed fraction must be between 0 and 1")?;
+        }
+        if let Some(ref constraints) = config.constraints {
+            if constraints.size() != dimension {
+                return Err(Error::InvalidConfig(
+                    "the constraints must be for a board of the same size",
+                ));
+            }
+            if (0..dimension).any(|column| constraints.allowed_rows(column).is_empty()) {
+                return Err(Error::InvalidConfig(
+                    "every column needs a square its queen can be at",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn lint(dimension: usize, config: &Self::Config) -> Vec<ConfigWarning> {
+        let mut warnings = vec![];
+        if config.mutation_probability >= 0.5 {
+            warnings.push(ConfigWarning::new(
+                "mutation_probability",
+                format!(
+                    "about {} swaps per individual and generation, which is close to starting over",
+                    (config.mutation_probability * dimension as f32).round()
+                ),
+            ));
+        }
+        if config.elitism >= 1. {
+            warnings.push(ConfigWarning::new(
+                "elitism",
+                "the whole population is kept as is, so nothing evolves".to_owned(),
+            ));
+        }
+        if config.generation_size < 2 {
+            warnings.push(ConfigWarning::new(
+                "generation_size",
+                "a single individual can't cross over with anything".to_owned(),
+            ));
+        }
+        warnings
+    }
+
+    fn info() -> &'static StrategyInfo {
+        &info::GENETIC_ALGORITHM
+    }
+
+    fn dimension(&self) -> usize {
+        self.search.problem().size()
+    }
+
+    fn solve_with_callback<F>(self, callback: F) -> Solution
+    where
+        F: FnMut(&[usize], usize),
+    {
+        self.solve_in_context(callback).0
+    }
+
+    fn solve_in_context<F>(self, callback: F) -> (Solution, SolverContext)
+    where
+        F: FnMut(&[usize], usize),
+    {
+        let (solution, context, _) = self.run(callback);
+        (solution, context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stops_early_once_converged() {
+        let stagnant = GeneticAlgorithmConfig {
+            generation_size: 10,
+            generation_count: 1000,
+            max_stagnant_generations: 5,
+            seed: Some(3),
+            ..GeneticAlgorithmConfig::default()
+        };
+        let homogeneous = GeneticAlgorithmConfig {
+            generation_size: 10,
+            generation_count: 1000,
+            immigrant_fraction: 0.,
+            mutation_probability: 0.,
+            min_diversity: 0.9,
+            seed: Some(3),
+            ..GeneticAlgorithmConfig::default()
+        };
+
+        for config in [stagnant, homogeneous] {
+            let solution = GeneticAlgorithm::new(40, config).solve();
+            assert_eq!(solution.status(), SolveStatus::Unsolved);
+            assert!(solution.score() > 0);
+            let generations = solution.statistics().effort.generations;
+            assert!(generations > 0 && generations < 1000, "{}", generations);
+        }
+    }
+
+    #[test]
+    fn tracks_genealogy() {
+        let config = GeneticAlgorithmConfig {
+            generation_size: 20,
+            selection: Box::new(RouletteSelection),
+            crossover: Crossover::PrefixSwap,
+            crossover_probability: 0.5,
+            generation_count: 10,
+            immigrant_fraction: 0.1,
+            track_genealogy: true,
+            seed: Some(1),
+            ..GeneticAlgorithmConfig::default()
+        };
+        let solution = GeneticAlgorithm::new(8, config).solve();
+
+        let genealogy = solution.genealogy().unwrap();
+        let id = genealogy.solution().unwrap();
+        let ancestry = genealogy.ancestry(id);
+        assert_eq!(ancestry.last().unwrap().id, id);
+        for individual in ancestry {
+            assert!(individual.parents.iter().all(|&p| p < individual.id));
+            if individual.origin == Origin::Random {
+                assert!(individual.parents.is_empty());
+            }
+        }
+        assert!(genealogy.lineage_to_dot(id).starts_with("digraph"));
+    }
+
+    #[test]
+    fn takes_population_snapshots() {
+        let config = GeneticAlgorithmConfig {
+            generation_size: 20,
+            selection: Box::new(RouletteSelection),
+            crossover: Crossover::PrefixSwap,
+            crossover_probability: 0.5,
+            generation_count: 10,
+            track_diversity: true,
+            snapshot_interval: 3,
+            seed: Some(2),
+            ..GeneticAlgorithmConfig::default()
+        };
+        let solution = GeneticAlgorithm::new(12, config).solve();
+
+        assert!(!solution.snapshots().is_empty());
+        for (i, snapshot) in solution.snapshots().iter().enumerate() {
+            assert_eq!(snapshot.generation, 3 * i);
+            assert_eq!(snapshot.members.len(), 20);
+            assert!(snapshot.members.windows(2).all(|w| w[0].1 <= w[1].1));
+
+            let report = &solution.diversity()[snapshot.generation];
+            assert_eq!(report.generation, snapshot.generation);
+            assert_eq!(report.distinct, snapshot.diversity().distinct);
+            assert!((report.mean_distance - snapshot.diversity().mean_distance).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn keeps_a_hall_of_fame() {
+        let config = GeneticAlgorithmConfig {
+            generation_size: 20,
+            generation_count: 50,
+            hall_of_fame: Some(HallOfFame {
+                size: 5,
+                stagnant_generations: 3,
+            }),
+            seed: Some(7),
+            ..GeneticAlgorithmConfig::default()
+        };
+        let solution = GeneticAlgorithm::new(16, config).solve();
+
+        let famous = solution.alternatives();
+        assert_eq!(famous.len(), 5);
+        assert!(famous.windows(2).all(|w| w[0].score() <= w[1].score()));
+        assert_eq!(famous[0].score(), solution.score());
+
+        let invalid = GeneticAlgorithmConfig {
+            hall_of_fame: Some(HallOfFame {
+                size: 0,
+                stagnant_generations: 0,
+            }),
+            ..GeneticAlgorithmConfig::default()
+        };
+        assert!(GeneticAlgorithm::try_new(8, invalid).is_err());
+    }
+
+    #[test]
+    fn selects_by_tournament() {
+        // Tournaments far larger than the generation pick its best
+        // individual almost every time.
+        let config = GeneticAlgorithmConfig {
+            generation_size: 20,
+            elitism: 0.,
+            selection: Box::new(TournamentSelection { size: 1000 }),
+            crossover_probability: 0.,
+            mutation_probability: 0.,
+            generation_count: 2,
+            snapshot_interval: 1,
+            seed: Some(3),
+            ..GeneticAlgorithmConfig::default()
+        };
+        let solution = GeneticAlgorithm::new(12, config).solve();
+
+        let best = &solution.snapshots()[0].members[0];
+        assert!(solution.snapshots()[1].members.iter().all(|m| m == best));
+
+        let invalid = GeneticAlgorithmConfig {
+            selection: Box::new(TournamentSelection { size: 0 }),
+            ..GeneticAlgorithmConfig::default()
+        };
+        assert!(GeneticAlgorithm::validate(8, &invalid).is_err());
+    }
+
+    #[test]
+    fn adapts_the_mutation_rate() {
+        // Without mutation, every generation after the first would be made
+        // of copies of the best individual of the first.
+        let config = |adaptive_mutation| GeneticAlgorithmConfig {
+            generation_size: 20,
+            elitism: 0.,
+            selection: Box::new(TournamentSelection { size: 1000 }),
+            crossover_probability: 0.,
+            mutation_probability: 0.,
+            adaptive_mutation,
+            generation_count: 4,
+            snapshot_interval: 1,
+            seed: Some(3),
+            ..GeneticAlgorithmConfig::default()
+        };
+        let distinct = |solution: &Solution, generation: usize| {
+            let members = &solution.snapshots()[generation].members;
+            members.iter().filter(|m| *m != &members[0]).count()
+        };
+
+        let fixed = GeneticAlgorithm::new(12, config(None)).solve();
+        assert_eq!(distinct(&fixed, 3), 0);
+
+        let adaptive = GeneticAlgorithm::new(
+            12,
+            config(Some(AdaptiveMutation {
+                min_diversity: 0.5,
+                step: 0.1,
+                max_probability: 0.3,
+            })),
+        )
+        .solve();
+        assert_eq!(distinct(&adaptive, 1), 0);
+        assert!(distinct(&adaptive, 2) > 0);
+    }
+
+    #[test]
+    fn keeps_to_pinned_queens_and_blocked_squares() {
+        use problem::InstanceGenerator;
+
+        let mut generator = InstanceGenerator::new(Some(6));
+        let instance = generator.blocked(12, 60).unwrap();
+        let mut constraints = instance.problem;
+        constraints.pin(0, instance.solution[0]);
+        constraints.pin(7, instance.solution[7]);
+        for &crossover in &[Crossover::PrefixSwap, Crossover::PartiallyMapped] {
+            let config = GeneticAlgorithmConfig {
+                generation_size: 30,
+                crossover,
+                generation_count: 20,
+                immigrant_fraction: 0.1,
+                constraints: Some(constraints.clone()),
+                snapshot_interval: 1,
+                seed: Some(6),
+                ..GeneticAlgorithmConfig::default()
+            };
+            assert_eq!(GeneticAlgorithm::validate(12, &config), Ok(()));
+            let solution = GeneticAlgorithm::new(12, config).solve();
+            let members = solution.snapshots().iter().flat_map(|s| &s.members);
+            for queen_rows in members.map(|m| &m.0[..]).chain(Some(solution.queen_rows())) {
+                assert!(queen_rows
+                    .iter()
+                    .enumerate()
+                    .all(|(column, &row)| constraints.allows(column, row)));
+            }
+        }
+
+        let config = GeneticAlgorithmConfig {
+            constraints: Some(constraints),
+            ..GeneticAlgorithmConfig::default()
+        };
+        assert!(GeneticAlgorithm::validate(8, &config).is_err());
+    }
+
+    #[test]
+    fn reseeds_stagnant_populations() {
+        // Without variation, the population is all copies of the best
+        // individual of the first generation from the second on.
+        let config = GeneticAlgorithmConfig {
+            generation_size: 20,
+            elitism: 0.,
+            selection: Box::new(TournamentSelection { size: 1000 }),
+            crossover_probability: 0.,
+            mutation_probability: 0.,
+            generation_count: 4,
+            reseed: Some(Reseed {
+                stagnant_generations: 2,
+                fraction: 0.5,
+            }),
+            snapshot_interval: 1,
+            seed: Some(8),
+            ..GeneticAlgorithmConfig::default()
+        };
+        let solution = GeneticAlgorithm::new(12, config).solve();
+        assert_eq!(solution.statistics().restarts, 1);
+
+        let distinct = |generation: usize| {
+            let members = &solution.snapshots()[generation].members;
+            members.iter().filter(|m| m.0 != members[0].0).count()
+        };
+        assert_eq!(distinct(2), 0);
+        assert!(distinct(3) > 0);
+    }
+
+    #[test]
+    fn replaces_a_fraction_of_each_generation() {
+        // Every child is mutated, so it's scored again, unlike the
+        // individuals carried over.
+        let config = |generation_gap| GeneticAlgorithmConfig {
+            generation_size: 20,
+            elitism: 0.,
+            generation_gap,
+            mutation_probability: 1.,
+            generation_count: 10,
+            snapshot_interval: 1,
+            seed: Some(4),
+            ..GeneticAlgorithmConfig::default()
+        };
+
+        let generational = GeneticAlgorithm::new(12, config(1.)).solve();
+        assert_eq!(generational.statistics().effort.evaluations, 20 + 20 * 10);
+
+        let steady = GeneticAlgorithm::new(12, config(0.01)).solve();
+        assert_eq!(steady.statistics().effort.evaluations, 20 + 2 * 10);
+        for pair in steady.snapshots().windows(2) {
+            let carried = pair[1]
+                .members
+                .iter()
+                .filter(|m| pair[0].members.contains(m))
+                .count();
+            assert!(carried >= 18);
+        }
+
+        assert!(GeneticAlgorithm::validate(8, &config(0.)).is_err());
+    }
+
+    #[test]
+    fn resumes_from_checkpoints() {
+        let config = |max_iterations| GeneticAlgorithmConfig {
+            generation_size: 30,
+            generation_count: 40,
+            adaptive_mutation: Some(AdaptiveMutation {
+                min_diversity: 0.5,
+                step: 0.05,
+                max_probability: 0.5,
+            }),
+            hall_of_fame: Some(HallOfFame {
+                size: 3,
+                stagnant_generations: 4,
+            }),
+            reseed: Some(Reseed {
+                stagnant_generations: 6,
+                fraction: 0.3,
+            }),
+            seed: Some(9),
+            budget: SolveBudget {
+                max_iterations,
+                ..SolveBudget::default()
+            },
+            ..GeneticAlgorithmConfig::default()
+        };
+        let famous = |solution: &Solution| {
+            solution
+                .alternatives()
+                .iter()
+                .map(|s| s.queen_rows().to_vec())
+                .collect::<Vec<_>>()
+        };
+        let whole = GeneticAlgorithm::new(20, config(None)).solve();
+        assert!(whole.checkpoint().is_none());
+        assert!(!whole.alternatives().is_empty());
+
+        let mut chunks = 1;
+        let mut solution = GeneticAlgorithm::new(20, config(Some(7))).solve();
+        while let Some(checkpoint) = solution.checkpoint().cloned() {
+            assert_eq!(checkpoint.strategy(), "genetic");
+            solution = GeneticAlgorithm::resume(checkpoint, config(Some(7)))
+                .unwrap()
+                .solve();
+            chunks += 1;
+        }
+        assert!(chunks > 1);
+        assert_eq!(solution.queen_rows(), whole.queen_rows());
+        assert_eq!(famous(&solution), famous(&whole));
+        assert_eq!(solution.statistics().restarts, whole.statistics().restarts);
+        assert_eq!(
+            solution.statistics().iterations,
+            whole.statistics().iterations
+        );
+        assert_eq!(
+            solution.statistics().effort.generations,
+            whole.statistics().effort.generations
+        );
+
+        // A snapshot between steps carries on the same way.
+        let mut stepped = GeneticAlgorithm::new(20, config(None));
+        for _ in 0..10 {
+            assert!(matches!(stepped.step(), StepResult::Running));
+        }
+        let snapshot = stepped.snapshot();
+        let (population, hall_of_fame) = match snapshot.state {
+            SearchState::GeneticAlgorithm {
+                ref population,
+                ref hall_of_fame,
+                ..
+            } => (population.clone(), hall_of_fame.clone()),
+            _ => unreachable!(),
+        };
+        let resumes = |edited_population, edited_hall_of_fame| {
+            let mut checkpoint = snapshot.clone();
+            if let SearchState::GeneticAlgorithm {
+                ref mut population,
+                ref mut hall_of_fame,
+                ..
+            } = checkpoint.state
+            {
+                *population = edited_population;
+                *hall_of_fame = edited_hall_of_fame;
+            }
+            GeneticAlgorithm::resume(checkpoint, config(None)).is_ok()
+        };
+        let mut off_board = population.clone();
+        off_board[3][5] = 20;
+        assert!(!resumes(off_board, hall_of_fame.clone()));
+        let mut too_long = population.clone();
+        too_long[0].push(0);
+        assert!(!resumes(too_long, hall_of_fame.clone()));
+        let mut truncated = hall_of_fame.clone();
+        truncated[0].0.clear();
+        assert!(!resumes(population, truncated));
+        let solution = GeneticAlgorithm::resume(stepped.snapshot(), config(None))
+            .unwrap()
+            .solve();
+        assert_eq!(solution.queen_rows(), whole.queen_rows());
+        assert_eq!(famous(&solution), famous(&whole));
+        assert_eq!(
+            solution.statistics().iterations,
+            whole.statistics().iterations
+        );
+
+        let annealing = SimulatedAnnealing::new(
+            20,
+            SimulatedAnnealingConfig {
+                budget: SolveBudget {
+                    max_iterations: Some(1),
+                    ..SolveBudget::default()
+                },
+                ..SimulatedAnnealingConfig::default()
+            },
+        )
+        .solve();
+        let checkpoint = annealing.checkpoint().cloned().unwrap();
+        assert!(GeneticAlgorithm::resume(checkpoint, config(None)).is_err());
+    }
+
+    #[test]
+    fn selection_strategies_favor_the_best() {
+        let scores = (0..10).collect::<Vec<_>>();
+        let strategies: Vec<Box<dyn SelectionStrategy>> = vec![
+            Box::new(RouletteSelection),
+            Box::new(RankSelection),
+            Box::new(TournamentSelection { size: 3 }),
+            Box::new(StochasticUniversalSampling),
+        ];
+        let mut rng = stream_rng(5, RngStream::Selection);
+        for mut strategy in strategies {
+            let selected = strategy.select(&scores, 1000, &mut rng);
+            assert_eq!(selected.len(), 1000);
+            let mut counts = vec![0; scores.len()];
+            for i in selected {
+                counts[i] += 1;
+            }
+            assert!(
+                counts.windows(2).all(|w| w[0] + 50 >= w[1]),
+                "{:?}",
+                strategy
+            );
+            assert!(counts[0] > 2 * counts[9], "{:?}", strategy);
+        }
+
+        // Sampling hits each individual as often as expected, rounded.
+        let selected = StochasticUniversalSampling.select(&[0, 2, 4], 3, &mut rng);
+        assert_eq!(selected, vec![0, 0, 1]);
+    }
+
+    #[test]
+    fn crossovers_keep_permutations() {
+        use rand::Rng;
+
+        let mut rng = stream_rng(11, RngStream::Crossover);
+        for _ in 0..100 {
+            let one = GenericChallengeState::new(9, &mut rng).queen_rows;
+            let other = GenericChallengeState::new(9, &mut rng).queen_rows;
+            let (a, b) = (rng.gen_range(0, 9), rng.gen_range(0, 9));
+            let (start, end) = (cmp::min(a, b), cmp::max(a, b) + 1);
+
+            for &crossover in &[Crossover::PartiallyMapped, Crossover::Ordered] {
+                let child = crossover.child(&one, &other, start, end);
+                assert_eq!(&child[start..end], &other[start..end]);
+                let mut rows = child.clone();
+                rows.sort();
+                assert_eq!(rows, (0..9).collect::<Vec<_>>(), "{:?}", crossover);
+            }
+        }
+
+        // The classic examples, with the segment 3..7.
+        let one = [0, 1, 2, 3, 4, 5, 6, 7, 8];
+        let other = [8, 2, 6, 7, 1, 5, 4, 0, 3];
+        assert_eq!(
+            Crossover::PartiallyMapped.child(&one, &other, 3, 7),
+            vec![0, 6, 2, 7, 1, 5, 4, 3, 8]
+        );
+        assert_eq!(
+            Crossover::Ordered.child(&one, &other, 3, 7),
+            vec![2, 3, 6, 7, 1, 5, 4, 8, 0]
+        );
+    }
+}
@@ -0,0 +1,359 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use problem::hill_climbing::{GenericHillClimbing, GenericHillClimbingConfig};
+use problem::queens::QueenMoves;
+use problem::{NQueens, Outcome};
+use solvers::simulated_annealing::Neighborhood;
+use stepper::{StepResult, Stepper};
+use *;
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct HillClimbingConfig {
+    /// How many times the search can be restarted from a fresh random
+    /// state after getting stuck.
+    pub restarts: usize,
+    /// Whether to keep restarting after a solution is found, in order to
+    /// collect more of them in the solution archive.
+    pub exhaust_restarts: bool,
+    /// How to choose the next move to try.
+    pub move_selection: MoveSelection,
+    /// If set, moves are drawn from it instead, and `move_selection` is
+    /// ignored.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub operator: Option<Box<dyn NeighborhoodOp>>,
+    /// Probability of accepting a move that doesn't improve the score
+    /// anyway, WalkSAT-style, to escape local minima.
+    pub random_walk_probability: f32,
+    /// What to do with moves that don't change the score.
+    pub tie_breaking: TieBreaking,
+    /// What to minimize.
+    pub objective: Objective,
+    /// Stop once this many placements have been scored across the whole
+    /// run, counting incremental evaluations too, if set.
+    pub max_evaluations: Option<usize>,
+    /// The master seed of the random streams, so that runs can be
+    /// reproduced, or a random one if unset.
+    pub seed: Option<u64>,
+    /// Limits on the whole solve, and a way to cancel it.
+    pub budget: SolveBudget,
+}
+
+impl Default for HillClimbingConfig {
+    fn default() -> Self {
+        HillClimbingConfig {
+            restarts: 100,
+            exhaust_restarts: false,
+            move_selection: MoveSelection::RandomSwap,
+            operator: None,
+            random_walk_probability: 0.05,
+            tie_breaking: TieBreaking::Random,
+            objective: Objective::AttackingPairs,
+            max_evaluations: None,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+}
+
+/// Hill climbing over `NQueens`, moving queens in place.
+pub struct HillClimbing {
+    search: GenericHillClimbing<NQueens, QueenMoves>,
+}
+
+impl HillClimbing {
+    /// Like `new`, but climbing from `queen_rows` rather than a random
+    /// placement. Restarts still start from random placements.
+    pub fn from_placement(queen_rows: Vec<usize>, config: HillClimbingConfig) -> Self {
+        let mut climbing = Self::new(queen_rows.len(), config);
+        climbing.search.state = queen_rows;
+        climbing
+    }
+
+    /// Runs the next iteration of the search, which either starts climbing
+    /// from a placement, tries a move, or ends the climb and restarts, and
+    /// returns the solution if the search is over.
+    fn advance<F>(&mut self, callback: &mut F) -> Option<Solution>
+    where
+        F: FnMut(&[usize], usize),
+    {
+        let outcome = self
+            .search
+            .advance(&mut |queen_rows: &Vec<usize>, score| callback(queen_rows, score))?;
+        Some(self.finish(outcome))
+    }
+
+    fn finish(&mut self, outcome: Outcome<Vec<usize>>) -> Solution {
+        let score = outcome.score;
+        let mut solution = Solution::new(outcome.state, score);
+        if let Some(&(ref queen_rows, final_score)) = self.search.final_state() {
+            solution.set_final_state(queen_rows, final_score);
+        }
+        for queen_rows in &outcome.solutions {
+            solution.archive.insert(queen_rows);
+        }
+        self.search
+            .budget
+            .finish(<Self as NQueensStrategy>::info(), &mut solution, score == 0);
+        solution.statistics.master_seed = outcome.master_seed;
+        solution.statistics.effort = self.search.effort.clone();
+        solution.statistics.restarts = self.search.restarts();
+        solution
+    }
+}
+
+impl Stepper for HillClimbing {
+    fn step(&mut self) -> StepResult {
+        match self.advance(&mut |_, _| {}) {
+            Some(solution) => StepResult::Done(Box::new(solution)),
+            None => StepResult::Running,
+        }
+    }
+
+    fn current(&self) -> (&[usize], usize) {
+        let (queen_rows, score) = self.search.current();
+        (queen_rows, score)
+    }
+}
+
+impl NQueensStrategy for HillClimbing {
+    type Config = HillClimbingConfig;
+
+    fn new(size: usize, config: Self::Config) -> Self {
+        let problem = NQueens::new(size, config.objective);
+        let moves = QueenMoves::new(
+            size,
+            config.move_selection,
+            config.operator,
+            Neighborhood::Swaps,
+            Some(config.tie_breaking),
+        );
+        let config = GenericHillClimbingConfig {
+            restarts: config.restarts,
+            exhaust_restarts: config.exhaust_restarts,
+            max_iterations_without_improvement: 1000,
+            random_walk_probability: config.random_walk_probability,
+            max_evaluations: config.max_evaluations,
+            seed: config.seed,
+            budget: config.budget,
+        };
+        HillClimbing {
+            search: GenericHillClimbing::with_moves(problem, moves, config),
+        }
+    }
+
+    fn validate(_dimension: usize, config: &Self::Config) -> Result<(), Error> {
+        check_probability(
+            config.random_walk_probability,
+            "random walk probability must be between 0 and 1",
+        )
+    }
+
+    fn lint(_dimension: usize, config: &Self::Config) -> Vec<ConfigWarning> {
+        if config.random_walk_probability < 0.5 {
+            return vec![];
+        }
+        vec![ConfigWarning::new(
+            "random_walk_probability",
+            "most moves are taken regardless of the score, so this is mostly a random walk"
+                .to_owned(),
+        )]
+    }
+
+    fn info() -> &'static StrategyInfo {
+        &info::HILL_CLIMBING
+    }
+
+    fn dimension(&self) -> usize {
+        self.search.problem().size()
+    }
+
+    fn solve_with_callback<F>(mut self, mut callback: F) -> Solution
+    where
+        F: FnMut(&[usize], usize),
+    {
+        loop {
+            if let Some(solution) = self.advance(&mut callback) {
+                return solution;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_best_distinct_placements() {
+        let config = HillClimbingConfig {
+            restarts: 0,
+            seed: Some(1),
+            ..HillClimbingConfig::default()
+        };
+        let solution = HillClimbing::new(8, config).solve_keeping_best(3, |_, _| {});
+
+        let alternatives = solution.alternatives();
+        assert!(!alternatives.is_empty() && alternatives.len() <= 3);
+        assert_eq!(alternatives[0].score(), solution.score());
+        for (i, one) in alternatives.iter().enumerate() {
+            for other in &alternatives[i + 1..] {
+                assert!(one.score() <= other.score());
+                assert!(one.queen_rows() != other.queen_rows());
+            }
+        }
+    }
+
+    #[test]
+    fn heavy_tailed_moves_keep_a_permutation() {
+        let config = HillClimbingConfig {
+            restarts: 0,
+            move_selection: MoveSelection::HeavyTailed { exponent: 1.5 },
+            seed: Some(2),
+            ..HillClimbingConfig::default()
+        };
+        let solution = HillClimbing::new(12, config).solve();
+
+        let mut rows = solution.queen_rows.to_vec();
+        rows.sort();
+        assert_eq!(rows, (0..12).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn archives_solutions_up_to_symmetry() {
+        // All the solutions for n = 6 are symmetric to each other.
+        let config = HillClimbingConfig {
+            restarts: 50,
+            exhaust_restarts: true,
+            seed: Some(1),
+            ..HillClimbingConfig::default()
+        };
+        let solution = HillClimbing::new(6, config).solve();
+
+        assert_eq!(solution.score(), 0);
+        assert_eq!(solution.archive().len(), 1);
+    }
+
+    #[test]
+    fn tie_breaking_policies_keep_a_permutation() {
+        for policy in [
+            TieBreaking::Random,
+            TieBreaking::LeastRecentlyMoved,
+            TieBreaking::MostConflicted,
+        ] {
+            let config = HillClimbingConfig {
+                restarts: 0,
+                tie_breaking: policy,
+                seed: Some(3),
+                ..HillClimbingConfig::default()
+            };
+            let solution = HillClimbing::new(10, config).solve();
+
+            let mut rows = solution.queen_rows.to_vec();
+            rows.sort();
+            assert_eq!(rows, (0..10).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn objectives_count_pairs_or_queens() {
+        let diagonal = GenericChallengeState {
+            size: 4,
+            queen_rows: vec![0, 1, 2, 3],
+        };
+        assert_eq!(diagonal.evaluate(Objective::AttackingPairs), 6);
+        assert_eq!(diagonal.evaluate(Objective::ConflictedQueens), 4);
+
+        let config = HillClimbingConfig {
+            restarts: 20,
+            objective: Objective::ConflictedQueens,
+            seed: Some(4),
+            ..HillClimbingConfig::default()
+        };
+        let solution = HillClimbing::new(8, config).solve();
+        let state = GenericChallengeState {
+            size: 8,
+            queen_rows: solution.queen_rows.to_vec(),
+        };
+        assert_eq!(solution.score, state.evaluate(Objective::ConflictedQueens));
+    }
+
+    #[test]
+    fn trace_levels_bound_what_is_kept() {
+        let config = |restarts| HillClimbingConfig {
+            restarts,
+            seed: Some(5),
+            ..HillClimbingConfig::default()
+        };
+
+        let off = HillClimbing::new(8, config(5)).solve_with_trace(TraceLevel::Off, |_, _| {});
+        let trace = off.trace.unwrap();
+        assert!(trace.entries().is_empty() && trace.steps() > 0);
+
+        let best = HillClimbing::new(8, config(5))
+            .solve_with_trace(TraceLevel::BestImprovementsOnly, |_, _| {});
+        let convergence = best.trace.unwrap().convergence();
+        assert!(convergence.windows(2).all(|w| w[1].1 < w[0].1));
+        assert_eq!(convergence.last().unwrap().1, best.score);
+
+        let sampled = HillClimbing::new(8, config(5))
+            .solve_with_trace(TraceLevel::SampledEveryK(3), |_, _| {});
+        let trace = sampled.trace.unwrap();
+        assert_eq!(trace.entries().len(), trace.steps().div_ceil(3));
+        assert!(trace.entries().iter().all(|e| e.step % 3 == 0));
+    }
+
+    #[test]
+    fn stops_at_evaluation_budget() {
+        let config = HillClimbingConfig {
+            restarts: 1000,
+            exhaust_restarts: true,
+            max_evaluations: Some(500),
+            seed: Some(6),
+            ..HillClimbingConfig::default()
+        };
+        let solution = HillClimbing::new(30, config).solve();
+
+        assert_eq!(solution.statistics.effort.evaluations, 500);
+        assert!(solution.statistics.restarts < 1000);
+    }
+
+    #[test]
+    fn random_walk_returns_best_state_seen() {
+        let config = HillClimbingConfig {
+            restarts: 0,
+            random_walk_probability: 0.3,
+            seed: Some(7),
+            ..HillClimbingConfig::default()
+        };
+        let mut best_seen = usize::MAX;
+        let solution = HillClimbing::new(10, config).solve_with_callback(|_, score| {
+            best_seen = best_seen.min(score);
+        });
+
+        assert_eq!(solution.score, best_seen);
+        assert_eq!(
+            solution.score,
+            GenericChallengeState {
+                size: 10,
+                queen_rows: solution.queen_rows.to_vec(),
+            }
+            .score()
+        );
+    }
+}
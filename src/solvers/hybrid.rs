@@ -0,0 +1,241 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Places the queens of the first few columns by backtracking, so that none
+//! of them attack each other, fills the rest of the columns with the rows
+//! left in a random order, and lets a local search finish the board from
+//! there.
+
+use solvers::constraint_propagation::{ColumnOrder, SymmetryBreaking};
+use std::cmp;
+use *;
+
+/// The local search that finishes the board. Its seed and budget are
+/// ignored.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Finisher {
+    HillClimbing(HillClimbingConfig),
+    SimulatedAnnealing(SimulatedAnnealingConfig),
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct HybridConfig {
+    /// How many queens to place by backtracking, from the first column.
+    /// Boards with fewer columns are placed whole.
+    pub placed_queens: usize,
+    pub finisher: Finisher,
+    /// The master seed of the random streams, so that runs can be
+    /// reproduced, or a random one if unset.
+    pub seed: Option<u64>,
+    /// Limits on the whole solve, and a way to cancel it.
+    pub budget: SolveBudget,
+}
+
+impl Default for HybridConfig {
+    fn default() -> Self {
+        HybridConfig {
+            placed_queens: 8,
+            finisher: Finisher::HillClimbing(HillClimbingConfig::default()),
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+}
+
+pub struct Hybrid {
+    size: usize,
+    rngs: ComponentRngs,
+    effort: SearchEffort,
+    budget: BudgetTracker,
+    config: HybridConfig,
+}
+
+fn add_effort(effort: &mut SearchEffort, solution: &Solution) {
+    let added = &solution.statistics.effort;
+    effort.nodes_expanded += added.nodes_expanded;
+    effort.backtracks += added.backtracks;
+    effort.evaluations += added.evaluations;
+    effort.accepted_moves += added.accepted_moves;
+}
+
+impl Hybrid {
+    /// Places the first queens by backtracking, and returns the placement
+    /// of the whole board with the rest of the rows shuffled.
+    fn place_queens<F>(&mut self, callback: &mut F) -> Vec<usize>
+    where
+        F: FnMut(&[usize], usize),
+    {
+        use rand::Rng;
+
+        let config = ConstraintPropagationConfig {
+            randomize_rows: true,
+            column_order: ColumnOrder::LeftToRight,
+            symmetry_breaking: SymmetryBreaking::None,
+            restarts: 0,
+            restart_backtracks: 0,
+            max_nogood_size: 0,
+            seed: Some(self.rngs.initializer.next_u64()),
+            budget: self.budget.remaining(),
+        };
+        let count = cmp::min(self.config.placed_queens, self.size);
+        let placed =
+            ConstraintPropagation::new(self.size, config).place_queens(count, &mut *callback);
+        add_effort(&mut self.effort, &placed);
+
+        let mut queen_rows = placed.queen_rows().to_vec();
+        let mut used = vec![false; self.size];
+        for &row in &queen_rows {
+            used[row] = true;
+        }
+        let mut rest = (0..self.size).filter(|&row| !used[row]).collect::<Vec<_>>();
+        self.rngs.initializer.shuffle(&mut rest);
+        queen_rows.extend(rest);
+        queen_rows
+    }
+}
+
+impl NQueensStrategy for Hybrid {
+    type Config = HybridConfig;
+
+    fn new(size: usize, config: Self::Config) -> Self {
+        Self {
+            size,
+            rngs: ComponentRngs::for_seed(config.seed),
+            effort: SearchEffort::default(),
+            budget: BudgetTracker::new(config.budget.clone()),
+            config,
+        }
+    }
+
+    fn validate(dimension: usize, config: &Self::Config) -> Result<(), Error> {
+        match config.finisher {
+            Finisher::HillClimbing(ref finisher) => HillClimbing::validate(dimension, finisher),
+            Finisher::SimulatedAnnealing(ref finisher) => {
+                SimulatedAnnealing::validate(dimension, finisher)
+            }
+        }
+    }
+
+    fn lint(dimension: usize, config: &Self::Config) -> Vec<ConfigWarning> {
+        let mut warnings = match config.finisher {
+            Finisher::HillClimbing(ref finisher) => HillClimbing::lint(dimension, finisher),
+            Finisher::SimulatedAnnealing(ref finisher) => {
+                SimulatedAnnealing::lint(dimension, finisher)
+            }
+        };
+        if config.placed_queens > dimension {
+            warnings.push(ConfigWarning::new(
+                "placed_queens",
+                format!(
+                    "the whole board is placed by backtracking, it has only {} queens",
+                    dimension
+                ),
+            ));
+        }
+        warnings
+    }
+
+    fn info() -> &'static StrategyInfo {
+        &info::HYBRID
+    }
+
+    fn dimension(&self) -> usize {
+        self.size
+    }
+
+    fn solve_with_callback<F>(mut self, mut callback: F) -> Solution
+    where
+        F: FnMut(&[usize], usize),
+    {
+        use rand::Rng;
+
+        if self.budget.should_stop() {
+            let mut solution = Solution::partial(vec![], 0, self.size);
+            self.budget.finish(Self::info(), &mut solution, false);
+            return solution;
+        }
+
+        let queen_rows = self.place_queens(&mut callback);
+        let seed = Some(self.rngs.initializer.next_u64());
+        let budget = self.budget.remaining();
+        let finished = match self.config.finisher {
+            Finisher::HillClimbing(config) => {
+                let config = HillClimbingConfig {
+                    seed,
+                    budget,
+                    ..config
+                };
+                HillClimbing::from_placement(queen_rows, config).solve_with_callback(&mut callback)
+            }
+            Finisher::SimulatedAnnealing(config) => {
+                let config = SimulatedAnnealingConfig {
+                    seed,
+                    budget,
+                    ..config
+                };
+                SimulatedAnnealing::from_placement(queen_rows, config)
+                    .solve_with_callback(&mut callback)
+            }
+        };
+        add_effort(&mut self.effort, &finished);
+
+        let mut solution = Solution::new(finished.queen_rows().to_vec(), finished.score());
+        let solved = solution.queen_rows().len() == self.size && solution.score() == 0;
+        // Notices if the budget stopped the local search.
+        if !solved {
+            self.budget.should_stop();
+        }
+        self.budget.finish(Self::info(), &mut solution, solved);
+        solution.statistics.master_seed = self.rngs.master_seed;
+        solution.statistics.effort = self.effort;
+        solution.statistics.restarts = finished.statistics.restarts;
+        solution
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use presets::Preset;
+
+    #[test]
+    fn places_the_first_queens_and_finishes_the_board() {
+        let mut placements = 0;
+        let config = HybridConfig {
+            placed_queens: 12,
+            seed: Some(4),
+            ..HybridConfig::default()
+        };
+        let solution = Hybrid::new(24, config).solve_with_callback(|queen_rows, _| {
+            if queen_rows.len() < 24 {
+                placements += 1;
+            }
+        });
+        assert!(solution.is_valid());
+        assert!(placements >= 12);
+        assert!(solution.statistics.effort.nodes_expanded >= 12);
+
+        let config = HybridConfig {
+            placed_queens: 4,
+            finisher: Finisher::SimulatedAnnealing(Preset::Thorough.simulated_annealing(16)),
+            seed: Some(4),
+            ..HybridConfig::default()
+        };
+        assert!(Hybrid::new(16, config).solve().is_valid());
+    }
+}
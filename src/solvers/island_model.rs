@@ -0,0 +1,350 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Runs several genetic algorithms, the islands, side by side, and every
+//! few generations moves the best individuals of each island to another
+//! one, so that the islands explore different regions of the search space
+//! but still share their progress.
+//!
+//! The islands can evolve on threads of their own, which doesn't change the
+//! outcome of a seeded run: every island evolves from the population and
+//! the seed it's handed before the threads start, and the results are
+//! gathered and migrated in island order once they're all done.
+
+use solvers::genetic_algorithm::GeneticAlgorithmConfig;
+use std::cmp;
+use std::sync::Arc;
+use std::thread;
+use *;
+
+/// Where the migrants of each island go.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MigrationTopology {
+    /// To the next island, and from the last one to the first.
+    Ring,
+    /// To another island picked at random on each migration.
+    Random,
+}
+
+/// Returns the configuration of the genetic algorithm of each island on
+/// boards of the given size.
+pub type IslandFactory = Arc<dyn Fn(usize) -> GeneticAlgorithmConfig + Send + Sync>;
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct IslandModelConfig {
+    /// How each island evolves. Its generation count, seed and budget are
+    /// ignored.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub island: IslandFactory,
+    pub islands: usize,
+    /// How many generations the islands evolve between migrations.
+    pub migration_interval: usize,
+    /// How many of the best individuals of each island migrate, replacing
+    /// the worst ones of their destination.
+    pub migration_size: usize,
+    pub topology: MigrationTopology,
+    /// How many migration intervals to run for.
+    pub epochs: usize,
+    /// Whether to evolve each island on its own thread. Each island gets
+    /// its seed up front, so a seeded run finds the same solution either
+    /// way, unless the budget has a wall time: how far each island gets
+    /// before it runs out then depends on how the threads are scheduled.
+    pub threads: bool,
+    /// The master seed of the random streams, so that runs can be
+    /// reproduced, or a random one if unset.
+    pub seed: Option<u64>,
+    /// Limits on the whole solve, and a way to cancel it.
+    pub budget: SolveBudget,
+}
+
+impl Default for IslandModelConfig {
+    fn default() -> Self {
+        IslandModelConfig {
+            island: Arc::new(|_| GeneticAlgorithmConfig {
+                generation_size: 50,
+                ..GeneticAlgorithmConfig::default()
+            }),
+            islands: 4,
+            migration_interval: 25,
+            migration_size: 2,
+            topology: MigrationTopology::Ring,
+            epochs: 40,
+            threads: false,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+}
+
+impl IslandModelConfig {
+    /// Evolves `population` for a migration interval, with the given seed
+    /// and budget.
+    fn evolve(
+        &self,
+        size: usize,
+        population: Vec<Vec<usize>>,
+        seed: u64,
+        budget: SolveBudget,
+    ) -> (Solution, Vec<Vec<usize>>) {
+        let config = GeneticAlgorithmConfig {
+            generation_count: self.migration_interval,
+            seed: Some(seed),
+            budget,
+            ..(self.island)(size)
+        };
+        GeneticAlgorithm::from_population(size, population, config).evolve(|_, _| {})
+    }
+}
+
+pub struct IslandModel {
+    size: usize,
+    rngs: ComponentRngs,
+    effort: SearchEffort,
+    budget: BudgetTracker,
+    config: IslandModelConfig,
+}
+
+impl IslandModel {
+    /// Evolves every island for a migration interval, and returns their
+    /// results in the same order.
+    fn epoch(&mut self, populations: Vec<Vec<Vec<usize>>>) -> Vec<(Solution, Vec<Vec<usize>>)> {
+        use rand::Rng;
+
+        let seeds = populations
+            .iter()
+            .map(|_| self.rngs.initializer.next_u64())
+            .collect::<Vec<_>>();
+        let budget = self.budget.remaining();
+        let size = self.size;
+        let config = &self.config;
+        if !config.threads {
+            return populations
+                .into_iter()
+                .zip(seeds)
+                .map(|(population, seed)| config.evolve(size, population, seed, budget.clone()))
+                .collect();
+        }
+
+        thread::scope(|scope| {
+            let handles = populations
+                .into_iter()
+                .zip(seeds)
+                .map(|(population, seed)| {
+                    let budget = budget.clone();
+                    scope.spawn(move || config.evolve(size, population, seed, budget))
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        })
+    }
+
+    /// Moves the best individuals of each island to its destination, in
+    /// place of the worst ones there. Each population is sorted best
+    /// first.
+    fn migrate(&mut self, populations: &mut [Vec<Vec<usize>>]) {
+        use rand::Rng;
+
+        let islands = populations.len();
+        if islands < 2 {
+            return;
+        }
+
+        let migrants = populations
+            .iter()
+            .map(|population| {
+                let count = cmp::min(self.config.migration_size, population.len());
+                population[..count].to_vec()
+            })
+            .collect::<Vec<_>>();
+        for (source, migrants) in migrants.into_iter().enumerate() {
+            let destination = match self.config.topology {
+                MigrationTopology::Ring => (source + 1) % islands,
+                MigrationTopology::Random => {
+                    let other = self.rngs.selection.gen_range(0, islands - 1);
+                    if other >= source {
+                        other + 1
+                    } else {
+                        other
+                    }
+                }
+            };
+            let population = &mut populations[destination];
+            let start = population.len().saturating_sub(migrants.len());
+            population.truncate(start);
+            population.extend(migrants);
+        }
+    }
+}
+
+impl NQueensStrategy for IslandModel {
+    type Config = IslandModelConfig;
+
+    fn new(size: usize, config: Self::Config) -> Self {
+        Self {
+            size,
+            rngs: ComponentRngs::for_seed(config.seed),
+            effort: SearchEffort::default(),
+            budget: BudgetTracker::new(config.budget.clone()),
+            config,
+        }
+    }
+
+    fn validate(dimension: usize, config: &Self::Config) -> Result<(), Error> {
+        if config.islands == 0 {
+            return Err(Error::InvalidConfig("island count must be positive"));
+        }
+        if config.migration_interval == 0 {
+            return Err(Error::InvalidConfig("migration interval must be positive"));
+        }
+        GeneticAlgorithm::validate(dimension, &(config.island)(dimension))
+    }
+
+    fn lint(dimension: usize, config: &Self::Config) -> Vec<ConfigWarning> {
+        let mut warnings = vec![];
+        let generation_size = (config.island)(dimension).generation_size;
+        if config.islands > 1 && config.migration_size >= generation_size {
+            warnings.push(ConfigWarning::new(
+                "migration_size",
+                "whole islands are replaced on each migration, so they don't evolve apart"
+                    .to_owned(),
+            ));
+        }
+        warnings
+    }
+
+    fn info() -> &'static StrategyInfo {
+        &info::ISLAND_MODEL
+    }
+
+    fn dimension(&self) -> usize {
+        self.size
+    }
+
+    fn solve_with_callback<F>(mut self, mut callback: F) -> Solution
+    where
+        F: FnMut(&[usize], usize),
+    {
+        let mut populations = vec![vec![]; self.config.islands];
+        let mut best: Option<(Vec<usize>, usize)> = None;
+        for _ in 0..self.config.epochs {
+            if self.budget.should_stop() {
+                break;
+            }
+
+            let results = self.epoch(populations);
+            populations = Vec::with_capacity(results.len());
+            let mut solved = false;
+            for (solution, population) in results {
+                let effort = &solution.statistics.effort;
+                self.effort.evaluations += effort.evaluations;
+                self.effort.accepted_moves += effort.accepted_moves;
+                solved |= solution.score() == 0;
+                if best
+                    .as_ref()
+                    .is_none_or(|&(_, score)| solution.score() < score)
+                {
+                    best = Some((solution.queen_rows().to_vec(), solution.score()));
+                }
+                populations.push(population);
+            }
+
+            if let Some((ref queen_rows, score)) = best {
+                callback(queen_rows, score);
+            }
+            if solved {
+                break;
+            }
+            self.migrate(&mut populations);
+        }
+
+        let mut solution = match best {
+            Some((queen_rows, score)) => Solution::new(queen_rows, score),
+            None => Solution::partial(vec![], 0, self.size),
+        };
+        let solved = solution.queen_rows().len() == self.size && solution.score() == 0;
+        self.budget.finish(Self::info(), &mut solution, solved);
+        solution.statistics.master_seed = self.rngs.master_seed;
+        solution.statistics.effort = self.effort;
+        solution
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evolves_islands_and_migrates() {
+        for &(topology, threads) in &[
+            (MigrationTopology::Ring, false),
+            (MigrationTopology::Random, true),
+        ] {
+            let config = IslandModelConfig {
+                topology,
+                threads,
+                seed: Some(3),
+                ..IslandModelConfig::default()
+            };
+            let solution = IslandModel::new(10, config).solve();
+            assert!(solution.is_valid(), "{:?}", topology);
+            assert_eq!(solution.status(), SolveStatus::Solved);
+        }
+
+        let mut model = IslandModel::new(4, IslandModelConfig::default());
+        let mut populations = (0..3)
+            .map(|island| vec![vec![island; 4]; 3])
+            .collect::<Vec<_>>();
+        model.migrate(&mut populations);
+        assert_eq!(populations[1], vec![vec![1; 4], vec![0; 4], vec![0; 4]]);
+        assert_eq!(populations[0], vec![vec![0; 4], vec![2; 4], vec![2; 4]]);
+    }
+
+    #[test]
+    fn threads_dont_change_seeded_runs() {
+        for &topology in &[MigrationTopology::Ring, MigrationTopology::Random] {
+            let solve = |threads| {
+                let config = IslandModelConfig {
+                    island: Arc::new(|_| GeneticAlgorithmConfig {
+                        generation_size: 20,
+                        ..GeneticAlgorithmConfig::default()
+                    }),
+                    migration_interval: 5,
+                    epochs: 6,
+                    topology,
+                    threads,
+                    seed: Some(11),
+                    ..IslandModelConfig::default()
+                };
+                IslandModel::new(24, config).solve()
+            };
+
+            let (sequential, threaded) = (solve(false), solve(true));
+            assert_eq!(sequential.queen_rows(), threaded.queen_rows());
+            assert_eq!(sequential.score(), threaded.score());
+            assert_eq!(
+                sequential.statistics().effort.evaluations,
+                threaded.statistics().effort.evaluations
+            );
+        }
+    }
+}
@@ -0,0 +1,256 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Limited discrepancy search: a backtracking search that tries the rows of
+//! each column in the order a greedy heuristic prefers, and explores the
+//! placements that deviate from it the least first.
+//!
+//! The heuristic prefers the rows that leave the most cells free in the
+//! following columns. Taking any row but the preferred one costs a
+//! discrepancy, and each iteration allows one more discrepancy than the
+//! previous one.
+
+use *;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct LimitedDiscrepancySearchConfig {
+    /// The most discrepancies allowed in the last iteration. The search is
+    /// exact if it's at least the size of the board.
+    pub max_discrepancies: usize,
+    /// Limits on the whole solve, and a way to cancel it.
+    pub budget: SolveBudget,
+}
+
+impl Default for LimitedDiscrepancySearchConfig {
+    fn default() -> Self {
+        LimitedDiscrepancySearchConfig {
+            max_discrepancies: 3,
+            budget: SolveBudget::default(),
+        }
+    }
+}
+
+pub struct LimitedDiscrepancySearch {
+    size: usize,
+    queen_rows: Vec<usize>,
+    /// Whether each row, diagonal and anti-diagonal holds a queen.
+    rows: Vec<bool>,
+    diagonals: Vec<bool>,
+    anti_diagonals: Vec<bool>,
+    /// The deepest partial placement found so far.
+    deepest: Vec<usize>,
+    /// Whether the current iteration skipped any row for lack of
+    /// discrepancies.
+    pruned: bool,
+    effort: SearchEffort,
+    budget: BudgetTracker,
+    config: LimitedDiscrepancySearchConfig,
+}
+
+impl LimitedDiscrepancySearch {
+    fn is_free(&self, column: usize, row: usize) -> bool {
+        !self.rows[row]
+            && !self.diagonals[column + row]
+            && !self.anti_diagonals[column + self.size - 1 - row]
+    }
+
+    fn set(&mut self, column: usize, row: usize, occupied: bool) {
+        self.rows[row] = occupied;
+        self.diagonals[column + row] = occupied;
+        self.anti_diagonals[column + self.size - 1 - row] = occupied;
+    }
+
+    /// Returns how many of the free cells of the following columns a queen
+    /// at `row` in `column` would attack.
+    fn cells_attacked(&self, column: usize, row: usize) -> usize {
+        ((column + 1)..self.size)
+            .map(|other| {
+                let distance = other - column;
+                let mut attacked = self.is_free(other, row) as usize;
+                if row + distance < self.size {
+                    attacked += self.is_free(other, row + distance) as usize;
+                }
+                if row >= distance {
+                    attacked += self.is_free(other, row - distance) as usize;
+                }
+                attacked
+            })
+            .sum()
+    }
+
+    /// Returns the free rows of the next column, preferred ones first.
+    fn ordered_rows(&self) -> Vec<usize> {
+        let column = self.queen_rows.len();
+        let mut rows = (0..self.size)
+            .filter(|&row| self.is_free(column, row))
+            .collect::<Vec<_>>();
+        rows.sort_by_key(|&row| self.cells_attacked(column, row));
+        rows
+    }
+
+    /// Completes the current partial placement with at most
+    /// `discrepancies` deviations from the heuristic, returning whether it
+    /// succeeded.
+    fn probe<F>(&mut self, discrepancies: usize, callback: &mut F) -> bool
+    where
+        F: FnMut(&[usize], usize),
+    {
+        let column = self.queen_rows.len();
+        if column == self.size {
+            return true;
+        }
+        if self.budget.should_stop() {
+            return false;
+        }
+
+        let mut rows = self.ordered_rows();
+        if discrepancies == 0 && rows.len() > 1 {
+            rows.truncate(1);
+            self.pruned = true;
+        }
+
+        for (rank, row) in rows.into_iter().enumerate() {
+            if self.budget.stopped() {
+                break;
+            }
+            self.queen_rows.push(row);
+            self.set(column, row, true);
+            self.effort.nodes_expanded += 1;
+            callback(&self.queen_rows, 0);
+            if self.queen_rows.len() > self.deepest.len() {
+                self.deepest.clone_from(&self.queen_rows);
+            }
+
+            let left = if rank == 0 {
+                discrepancies
+            } else {
+                discrepancies - 1
+            };
+            if self.probe(left, callback) {
+                return true;
+            }
+
+            self.queen_rows.pop();
+            self.set(column, row, false);
+            self.effort.backtracks += 1;
+            callback(&self.queen_rows, 0);
+        }
+
+        false
+    }
+}
+
+impl NQueensStrategy for LimitedDiscrepancySearch {
+    type Config = LimitedDiscrepancySearchConfig;
+
+    fn new(size: usize, config: Self::Config) -> Self {
+        let lines = 2 * size.max(1) - 1;
+        Self {
+            size,
+            queen_rows: Vec::with_capacity(size),
+            rows: vec![false; size],
+            diagonals: vec![false; lines],
+            anti_diagonals: vec![false; lines],
+            deepest: vec![],
+            pruned: false,
+            effort: SearchEffort::default(),
+            budget: BudgetTracker::new(config.budget.clone()),
+            config,
+        }
+    }
+
+    fn lint(_dimension: usize, config: &Self::Config) -> Vec<ConfigWarning> {
+        if config.max_discrepancies != 0 {
+            return vec![];
+        }
+        vec![ConfigWarning::new(
+            "max_discrepancies",
+            "without discrepancies, only the greedy placement is tried".to_owned(),
+        )]
+    }
+
+    fn info() -> &'static StrategyInfo {
+        &info::LIMITED_DISCREPANCY_SEARCH
+    }
+
+    fn dimension(&self) -> usize {
+        self.size
+    }
+
+    fn solve_with_callback<F>(mut self, mut callback: F) -> Solution
+    where
+        F: FnMut(&[usize], usize),
+    {
+        let mut iterations = 0;
+        for discrepancies in 0..=self.config.max_discrepancies {
+            iterations += 1;
+            self.pruned = false;
+            if self.probe(discrepancies, &mut callback) {
+                break;
+            }
+            // Nothing was left out, so there's no solution.
+            if !self.pruned || self.budget.stopped() {
+                break;
+            }
+        }
+
+        let solved = self.queen_rows.len() == self.size;
+        let queen_rows = if solved {
+            self.queen_rows
+        } else {
+            self.deepest
+        };
+        let mut solution = Solution::partial(queen_rows, 0, self.size);
+        self.budget.finish(Self::info(), &mut solution, solved);
+        solution.statistics.effort = self.effort;
+        // Each iteration starts from scratch, like a restart would.
+        solution.statistics.restarts = iterations - 1;
+        solution
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_solutions_with_few_discrepancies() {
+        for &n in &[1, 4, 8, 20] {
+            let config = LimitedDiscrepancySearchConfig {
+                max_discrepancies: n,
+                ..LimitedDiscrepancySearchConfig::default()
+            };
+            let solution = LimitedDiscrepancySearch::new(n, config).solve();
+            assert_eq!(solution.queen_rows.len(), n);
+            let state = GenericChallengeState {
+                size: n,
+                queen_rows: solution.queen_rows.to_vec(),
+            };
+            assert_eq!(state.score(), 0);
+        }
+
+        let config = LimitedDiscrepancySearchConfig {
+            max_discrepancies: 10,
+            ..LimitedDiscrepancySearchConfig::default()
+        };
+        let solution = LimitedDiscrepancySearch::new(3, config).solve();
+        assert!(solution.queen_rows.len() < 3);
+        assert_eq!(solution.statistics.restarts, 1);
+    }
+}
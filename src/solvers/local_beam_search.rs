@@ -0,0 +1,418 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use problem::local_beam_search::{GenericLocalBeamSearch, GenericLocalBeamSearchConfig};
+use problem::queens::QueenSuccessors;
+use problem::NQueens;
+use std::mem;
+use *;
+
+/// How the number of states kept in the beam changes over time.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BeamWidthSchedule {
+    /// Always keep `state_count` states.
+    Fixed,
+    /// Start with `state_count` states, and multiply the width by `factor`
+    /// after each iteration, without going below `min_state_count`.
+    Shrinking { min_state_count: usize, factor: f32 },
+    /// Start with `state_count` states, and multiply the width by `factor`
+    /// each iteration the best score doesn't improve, without going over
+    /// `max_state_count`. The width goes back to `state_count` as soon as
+    /// the score improves.
+    WidenOnStagnation { max_state_count: usize, factor: f32 },
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct LocalBeamSearchConfig {
+    pub state_count: usize,
+    pub schedule: BeamWidthSchedule,
+    /// If non-zero, successors that differ from an already selected
+    /// state in fewer than this many queens are only selected if there
+    /// aren't enough different ones, so that the beam doesn't collapse
+    /// into copies of the same basin. Only the best successors, up to the
+    /// beam width times the board size, are considered then.
+    pub min_distance: usize,
+    /// What to do when the beam stops improving, if anything.
+    pub reseeding: Option<BeamReseeding>,
+    /// What to minimize.
+    pub objective: Objective,
+    /// If non-zero, keep a snapshot of the whole beam every this many
+    /// iterations, in the solution's snapshots.
+    pub snapshot_interval: usize,
+    /// Stop once this many placements have been scored across the whole
+    /// run, counting incremental evaluations too, if set.
+    pub max_evaluations: Option<usize>,
+    /// The master seed of the random streams, so that runs can be
+    /// reproduced, or a random one if unset.
+    pub seed: Option<u64>,
+    /// Limits on the whole solve, and a way to cancel it.
+    pub budget: SolveBudget,
+}
+
+impl Default for LocalBeamSearchConfig {
+    fn default() -> Self {
+        LocalBeamSearchConfig {
+            state_count: 8,
+            schedule: BeamWidthSchedule::Fixed,
+            min_distance: 0,
+            reseeding: None,
+            objective: Objective::AttackingPairs,
+            snapshot_interval: 0,
+            max_evaluations: None,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+}
+
+/// How to partially reseed the beam when it stagnates.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BeamReseeding {
+    /// How many iterations without improving the best score to wait
+    /// before reseeding.
+    pub stagnant_iterations: usize,
+    /// How many of the best states to keep.
+    pub keep: usize,
+    /// If non-zero, the rest of the beam is refilled with copies of the
+    /// states kept moved to a random neighbor this many times, which for
+    /// n-queens are random swaps, instead of with random states.
+    pub mutation_swaps: usize,
+}
+
+/// Local beam search over `NQueens`, scoring swaps incrementally.
+pub struct LocalBeamSearch {
+    search: GenericLocalBeamSearch<NQueens, QueenSuccessors>,
+    context: SolverContext,
+}
+
+impl NQueensStrategy for LocalBeamSearch {
+    type Config = LocalBeamSearchConfig;
+
+    fn new(size: usize, config: Self::Config) -> Self {
+        Self::new_in_context(SolverContext::new(size), config)
+    }
+
+    fn new_in_context(mut context: SolverContext, config: Self::Config) -> Self {
+        let size = context.dimension();
+        let mut successors =
+            QueenSuccessors::new(size, config.min_distance, config.snapshot_interval);
+        successors.spare = mem::take(&mut context.states)
+            .into_iter()
+            .map(|state| state.queen_rows)
+            .collect();
+        let problem = NQueens::new(size, config.objective);
+        let config = GenericLocalBeamSearchConfig {
+            state_count: config.state_count,
+            schedule: config.schedule,
+            reseeding: config.reseeding,
+            // Search until solving it or running out of budget.
+            max_iterations_without_improvement: None,
+            max_evaluations: config.max_evaluations,
+            seed: config.seed,
+            budget: config.budget,
+        };
+        Self {
+            search: GenericLocalBeamSearch::with_successors(problem, successors, config),
+            context,
+        }
+    }
+
+    fn validate(_dimension: usize, config: &Self::Config) -> Result<(), Error> {
+        if config.state_count == 0 {
+            return Err(Error::InvalidConfig("state count must be positive"));
+        }
+        match config.schedule {
+            BeamWidthSchedule::Shrinking { factor, .. } if !(factor > 0. && factor <= 1.) => Err(
+                Error::InvalidConfig("shrinking factor must be positive and at most 1"),
+            ),
+            BeamWidthSchedule::WidenOnStagnation { factor, .. }
+                if factor.is_nan() || factor < 1. =>
+            {
+                Err(Error::InvalidConfig("widening factor must be at least 1"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn lint(_dimension: usize, config: &Self::Config) -> Vec<ConfigWarning> {
+        if config.state_count != 1 {
+            return vec![];
+        }
+        vec![ConfigWarning::new(
+            "state_count",
+            "a single state makes this steepest-ascent hill climbing".to_owned(),
+        )]
+    }
+
+    fn info() -> &'static StrategyInfo {
+        &info::LOCAL_BEAM_SEARCH
+    }
+
+    fn dimension(&self) -> usize {
+        self.search.problem().size()
+    }
+
+    fn solve_with_callback<F>(self, callback: F) -> Solution
+    where
+        F: FnMut(&[usize], usize),
+    {
+        self.solve_in_context(callback).0
+    }
+
+    fn solve_in_context<F>(mut self, mut callback: F) -> (Solution, SolverContext)
+    where
+        F: FnMut(&[usize], usize),
+    {
+        let outcome = self
+            .search
+            .search(&mut |queen_rows: &Vec<usize>, score| callback(queen_rows, score));
+        let score = outcome.score;
+        let mut solution = Solution::new(outcome.state, score);
+        self.search
+            .budget
+            .finish(Self::info(), &mut solution, score == 0);
+        solution.snapshots = mem::take(&mut self.search.successors.snapshots);
+        solution.statistics.master_seed = outcome.master_seed;
+        solution.statistics.effort = self.search.effort.clone();
+        solution.statistics.restarts = self.search.reseeds;
+        let size = self.dimension();
+        self.context.recycle(
+            self.search
+                .successors
+                .spare
+                .drain(..)
+                .map(|queen_rows| GenericChallengeState { size, queen_rows }),
+        );
+        (solution, self.context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use problem::{Problem, Successors};
+
+    #[test]
+    fn reseeding_finds_solution() {
+        let config = LocalBeamSearchConfig {
+            reseeding: Some(BeamReseeding {
+                stagnant_iterations: 5,
+                keep: 2,
+                mutation_swaps: 0,
+            }),
+            seed: Some(1),
+            ..LocalBeamSearchConfig::default()
+        };
+        let solution = LocalBeamSearch::new(8, config).solve();
+        assert_eq!(solution.score, 0);
+    }
+
+    #[test]
+    fn selects_distant_states_first() {
+        let config = LocalBeamSearchConfig {
+            min_distance: 3,
+            ..LocalBeamSearchConfig::default()
+        };
+        let mut search = LocalBeamSearch::new(6, config);
+        let candidates = || {
+            let rows: [[usize; 6]; 4] = [
+                [0, 1, 2, 3, 4, 5],
+                [1, 0, 2, 3, 4, 5],
+                [5, 4, 3, 2, 1, 0],
+                [0, 1, 2, 3, 5, 4],
+            ];
+            rows.iter()
+                .map(|rows| rows.to_vec())
+                .enumerate()
+                .map(|(score, state)| (state, score))
+                .collect::<Vec<_>>()
+        };
+        let selected = |mut rows: Vec<Vec<usize>>| {
+            rows.sort();
+            rows
+        };
+        let problem = NQueens::new(6, Objective::AttackingPairs);
+        let successors = &mut search.search.successors;
+
+        // The two candidates close to the first one are skipped while
+        // there are enough distant ones.
+        let distant = successors.select(&problem, candidates(), 2);
+        assert_eq!(
+            selected(distant),
+            vec![vec![0, 1, 2, 3, 4, 5], vec![5, 4, 3, 2, 1, 0]]
+        );
+
+        // Otherwise the best of them fill the beam.
+        let filled = successors.select(&problem, candidates(), 3);
+        assert_eq!(
+            selected(filled),
+            vec![
+                vec![0, 1, 2, 3, 4, 5],
+                vec![1, 0, 2, 3, 4, 5],
+                vec![5, 4, 3, 2, 1, 0],
+            ]
+        );
+        assert_eq!(successors.select(&problem, candidates(), 6).len(), 4);
+    }
+
+    #[test]
+    fn schedules_the_beam_width() {
+        let search = |schedule| {
+            LocalBeamSearch::new(
+                8,
+                LocalBeamSearchConfig {
+                    state_count: 8,
+                    schedule,
+                    ..LocalBeamSearchConfig::default()
+                },
+            )
+        };
+
+        let fixed = search(BeamWidthSchedule::Fixed);
+        assert_eq!(fixed.search.next_width(8, false), 8);
+        assert_eq!(fixed.search.next_width(5, true), 5);
+
+        let shrinking = search(BeamWidthSchedule::Shrinking {
+            min_state_count: 3,
+            factor: 0.5,
+        });
+        assert_eq!(shrinking.search.next_width(8, false), 4);
+        assert_eq!(shrinking.search.next_width(8, true), 4);
+        assert_eq!(shrinking.search.next_width(4, false), 3);
+        assert_eq!(shrinking.search.next_width(3, false), 3);
+        // The beam never goes empty, even with no minimum.
+        let unbounded = search(BeamWidthSchedule::Shrinking {
+            min_state_count: 0,
+            factor: 0.1,
+        });
+        assert_eq!(unbounded.search.next_width(2, false), 1);
+
+        let widening = search(BeamWidthSchedule::WidenOnStagnation {
+            max_state_count: 20,
+            factor: 1.5,
+        });
+        assert_eq!(widening.search.next_width(8, false), 12);
+        assert_eq!(widening.search.next_width(9, false), 14);
+        assert_eq!(widening.search.next_width(16, false), 20);
+        assert_eq!(widening.search.next_width(20, false), 20);
+        // An improvement goes back to the initial width.
+        assert_eq!(widening.search.next_width(20, true), 8);
+        assert_eq!(widening.search.next_width(8, true), 8);
+    }
+
+    #[test]
+    fn rejects_factors_that_dont_shrink() {
+        let shrinking = |factor| LocalBeamSearchConfig {
+            schedule: BeamWidthSchedule::Shrinking {
+                min_state_count: 1,
+                factor,
+            },
+            ..LocalBeamSearchConfig::default()
+        };
+        assert_eq!(LocalBeamSearch::validate(8, &shrinking(0.5)), Ok(()));
+        assert_eq!(LocalBeamSearch::validate(8, &shrinking(1.)), Ok(()));
+        for &factor in &[0., -0.5, 1.5, f32::NAN] {
+            assert!(LocalBeamSearch::validate(8, &shrinking(factor)).is_err());
+        }
+    }
+
+    #[test]
+    fn takes_snapshots_of_the_beam() {
+        let config = LocalBeamSearchConfig {
+            state_count: 6,
+            snapshot_interval: 2,
+            seed: Some(5),
+            ..LocalBeamSearchConfig::default()
+        };
+        let solution = LocalBeamSearch::new(12, config).solve();
+
+        let snapshots = solution.snapshots();
+        assert!(!snapshots.is_empty());
+        for (i, snapshot) in snapshots.iter().enumerate() {
+            assert_eq!(snapshot.generation, 2 * i);
+            assert!(!snapshot.members.is_empty() && snapshot.members.len() <= 6);
+            assert!(snapshot.members.windows(2).all(|w| w[0].1 <= w[1].1));
+            assert!(snapshot.members.iter().all(|m| m.0.len() == 12));
+        }
+    }
+
+    #[test]
+    fn reuses_context_across_solves() {
+        let config = || LocalBeamSearchConfig {
+            state_count: 4,
+            min_distance: 2,
+            reseeding: Some(BeamReseeding {
+                stagnant_iterations: 5,
+                keep: 1,
+                mutation_swaps: 2,
+            }),
+            seed: Some(2),
+            ..LocalBeamSearchConfig::default()
+        };
+
+        let mut context = SolverContext::new(8);
+        for _ in 0..3 {
+            let (solution, used) =
+                LocalBeamSearch::new_in_context(context, config()).solve_in_context(|_, _| {});
+            assert_eq!(solution.score, 0);
+            assert_eq!(solution.queen_rows.len(), 8);
+            context = used;
+        }
+        assert!(!context.states.is_empty());
+        assert!(context.states.iter().all(|s| s.queen_rows.len() == 8));
+    }
+
+    #[test]
+    fn keeps_the_best_successors() {
+        for &objective in &[Objective::AttackingPairs, Objective::ConflictedQueens] {
+            let config = LocalBeamSearchConfig {
+                objective,
+                seed: Some(3),
+                ..LocalBeamSearchConfig::default()
+            };
+            let mut search = LocalBeamSearch::new(10, config);
+            let problem = NQueens::new(10, objective);
+            let mut states = (0..4)
+                .map(|_| problem.random_state(&mut search.search.rngs.initializer))
+                .collect::<Vec<_>>();
+            let scores = states.iter().map(|s| problem.score(s)).collect::<Vec<_>>();
+
+            let mut expected = vec![];
+            for state in &states {
+                for i in 0..10 {
+                    for j in i + 1..10 {
+                        let mut successor = state.clone();
+                        successor.swap(i, j);
+                        expected.push(successor);
+                    }
+                }
+            }
+            expected.sort_by_key(|s| problem.score(s));
+            expected.truncate(7);
+
+            let best = search.search.best_successors(&mut states, &scores, 7);
+            assert_eq!(
+                best.iter().map(|s| &s.0).collect::<Vec<_>>(),
+                expected.iter().collect::<Vec<_>>()
+            );
+            assert!(best.iter().all(|&(ref s, score)| problem.score(s) == score));
+        }
+    }
+}
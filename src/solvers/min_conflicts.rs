@@ -0,0 +1,275 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Min-conflicts: repeatedly pick a queen that is attacked, and move it to
+//! the row of its column where it's attacked the least.
+//!
+//! Unlike the other local search strategies, queens may share rows along the
+//! way, since each move only changes one column.
+
+use std::iter;
+use stepper::{StepResult, Stepper};
+use *;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct MinConflictsConfig {
+    /// How many queens to move before giving up on a placement.
+    pub max_steps: usize,
+    /// How many times the search can be restarted from a fresh random
+    /// state after giving up.
+    pub restarts: usize,
+    /// Probability of moving the chosen queen to a random row instead of
+    /// the best one, to escape local minima.
+    pub random_walk_probability: f32,
+    /// How to choose among the best rows for the chosen queen. As they all
+    /// move the same queen, the policies that look at which queens move
+    /// pick at random.
+    pub tie_breaking: TieBreaking,
+    /// The master seed of the random streams, so that runs can be
+    /// reproduced, or a random one if unset.
+    pub seed: Option<u64>,
+    /// Limits on the whole solve, and a way to cancel it.
+    pub budget: SolveBudget,
+}
+
+impl Default for MinConflictsConfig {
+    fn default() -> Self {
+        MinConflictsConfig {
+            max_steps: 1000,
+            restarts: 10,
+            random_walk_probability: 0.02,
+            tie_breaking: TieBreaking::Random,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+}
+
+pub struct MinConflicts {
+    base: GenericChallengeState,
+    rngs: ComponentRngs,
+    effort: SearchEffort,
+    budget: BudgetTracker,
+    /// The counters of the current placement, or `None` before starting to
+    /// repair it.
+    counters: Option<ConflictCounters>,
+    ties: TieBreaker,
+    score: usize,
+    /// The queens moved since the current placement was generated.
+    steps: usize,
+    restarts_left: usize,
+    best: Option<(Vec<usize>, usize)>,
+    config: MinConflictsConfig,
+}
+
+impl MinConflicts {
+    /// Like `new`, but starting by repairing `queen_rows` rather than a
+    /// random placement. Restarts still start from random placements.
+    pub fn from_placement(queen_rows: Vec<usize>, config: MinConflictsConfig) -> Self {
+        let mut search = Self::new(queen_rows.len(), config);
+        search.base.queen_rows = queen_rows;
+        search
+    }
+
+    /// Runs the next iteration of the search, which either starts repairing
+    /// a placement, moves a queen, or gives up on the placement, and
+    /// returns the solution if the search is over.
+    fn advance<F>(&mut self, callback: &mut F) -> Option<Solution>
+    where
+        F: FnMut(&[usize], usize),
+    {
+        use rand::Rng;
+
+        let size = self.base.size;
+        let counters = match self.counters {
+            Some(ref mut counters) => counters,
+            None => {
+                let counters = ConflictCounters::new(&self.base);
+                self.score = counters.attacking_pairs();
+                self.steps = 0;
+                self.effort.evaluations += 1;
+                self.counters = Some(counters);
+                callback(&self.base.queen_rows, self.score);
+                return None;
+            }
+        };
+
+        if self.score == 0 || self.steps == self.config.max_steps || self.budget.should_stop() {
+            let score = self.score;
+            if self
+                .best
+                .as_ref()
+                .is_none_or(|&(_, best_score)| score < best_score)
+            {
+                self.best = Some((self.base.queen_rows.clone(), score));
+            }
+
+            if self.restarts_left == 0 || score == 0 || self.budget.stopped() {
+                return Some(self.finish());
+            }
+
+            self.restarts_left -= 1;
+            self.base = GenericChallengeState::new(size, &mut self.rngs.initializer);
+            self.counters = None;
+            return None;
+        }
+
+        let conflicted = counters.conflicted_queens(&self.base.queen_rows);
+        let column = conflicted[self.rngs.selection.gen_range(0, conflicted.len())];
+        let from = self.base.queen_rows[column];
+
+        let to = if self.rngs.acceptance.next_f32() < self.config.random_walk_probability {
+            self.rngs.neighborhood.gen_range(0, size)
+        } else {
+            let mut best_rows = vec![];
+            let mut best_delta = isize::MAX;
+            for row in 0..size {
+                let delta = counters.move_delta(column, from, row);
+                if delta < best_delta {
+                    best_delta = delta;
+                    best_rows.clear();
+                }
+                if delta == best_delta {
+                    best_rows.push(row);
+                }
+            }
+            self.effort.evaluations += size;
+            let index = self.ties.choose(
+                best_rows.len(),
+                |_| iter::once(column),
+                &self.base.queen_rows,
+                counters,
+                &mut self.rngs.neighborhood,
+            );
+            best_rows[index]
+        };
+
+        self.score = (self.score as isize + counters.move_delta(column, from, to)) as usize;
+        counters.move_queen(column, from, to);
+        self.base.queen_rows[column] = to;
+        self.ties.record_moved(iter::once(column));
+        self.steps += 1;
+        self.effort.accepted_moves += 1;
+        callback(&self.base.queen_rows, self.score);
+        None
+    }
+
+    fn finish(&mut self) -> Solution {
+        let (queen_rows, score) = self.best.take().unwrap();
+        let mut solution = Solution::new(queen_rows, score);
+        self.budget
+            .finish(<Self as NQueensStrategy>::info(), &mut solution, score == 0);
+        solution.statistics.master_seed = self.rngs.master_seed;
+        solution.statistics.effort = self.effort.clone();
+        solution.statistics.restarts = self.config.restarts - self.restarts_left;
+        solution
+    }
+}
+
+impl Stepper for MinConflicts {
+    fn step(&mut self) -> StepResult {
+        match self.advance(&mut |_, _| {}) {
+            Some(solution) => StepResult::Done(Box::new(solution)),
+            None => StepResult::Running,
+        }
+    }
+
+    fn current(&self) -> (&[usize], usize) {
+        (&self.base.queen_rows, self.score)
+    }
+}
+
+impl NQueensStrategy for MinConflicts {
+    type Config = MinConflictsConfig;
+
+    fn new(size: usize, config: Self::Config) -> Self {
+        let mut rngs = ComponentRngs::for_seed(config.seed);
+        let base = GenericChallengeState::new(size, &mut rngs.initializer);
+        Self {
+            base,
+            rngs,
+            effort: SearchEffort::default(),
+            budget: BudgetTracker::new(config.budget.clone()),
+            counters: None,
+            ties: TieBreaker::new(config.tie_breaking.clone(), size),
+            score: 0,
+            steps: 0,
+            restarts_left: config.restarts,
+            best: None,
+            config,
+        }
+    }
+
+    fn validate(_dimension: usize, config: &Self::Config) -> Result<(), Error> {
+        check_probability(
+            config.random_walk_probability,
+            "random walk probability must be between 0 and 1",
+        )
+    }
+
+    fn lint(_dimension: usize, config: &Self::Config) -> Vec<ConfigWarning> {
+        if config.random_walk_probability < 0.5 {
+            return vec![];
+        }
+        vec![ConfigWarning::new(
+            "random_walk_probability",
+            "most queens are moved to a random row, so this is mostly a random walk".to_owned(),
+        )]
+    }
+
+    fn info() -> &'static StrategyInfo {
+        &info::MIN_CONFLICTS
+    }
+
+    fn dimension(&self) -> usize {
+        self.base.size
+    }
+
+    fn solve_with_callback<F>(mut self, mut callback: F) -> Solution
+    where
+        F: FnMut(&[usize], usize),
+    {
+        loop {
+            if let Some(solution) = self.advance(&mut callback) {
+                return solution;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solves_large_boards() {
+        for policy in [TieBreaking::Random, TieBreaking::KeepIncumbent] {
+            let config = MinConflictsConfig {
+                max_steps: 10_000,
+                restarts: 5,
+                tie_breaking: policy,
+                seed: Some(7),
+                ..MinConflictsConfig::default()
+            };
+            let solution = MinConflicts::new(100, config).solve();
+            assert!(solution.is_valid());
+            assert_eq!(solution.status(), SolveStatus::Solved);
+        }
+    }
+}
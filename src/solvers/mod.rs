@@ -0,0 +1,33 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The strategies to solve the n-queens problem.
+
+pub mod constraint_propagation;
+pub mod constructive_beam_search;
+pub mod cuckoo_search;
+pub mod decomposition;
+pub mod genetic_algorithm;
+pub mod hill_climbing;
+pub mod hybrid;
+pub mod island_model;
+pub mod limited_discrepancy_search;
+pub mod local_beam_search;
+pub mod min_conflicts;
+pub mod pbil;
+pub mod simulated_annealing;
+pub mod tabu_search;
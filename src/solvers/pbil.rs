@@ -0,0 +1,271 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Population-Based Incremental Learning: instead of a population, keep a
+//! probability distribution over the row of each column, sample from it,
+//! and nudge it towards the best sample of each iteration.
+
+use *;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct PbilConfig {
+    /// How many placements to sample from the distribution on each
+    /// iteration.
+    pub samples: usize,
+    /// How much the distribution moves towards the best sample on each
+    /// iteration, between zero and one.
+    pub learning_rate: f32,
+    /// The probability of mutating each entry of the distribution.
+    pub mutation_probability: f32,
+    /// How much a mutation moves an entry towards a random value, between
+    /// zero and one.
+    pub mutation_shift: f32,
+    pub iterations: usize,
+    /// What to minimize.
+    pub objective: Objective,
+    /// Stop once this many placements have been scored across the whole
+    /// run, counting incremental evaluations too, if set.
+    pub max_evaluations: Option<usize>,
+    /// The master seed of the random streams, so that runs can be
+    /// reproduced, or a random one if unset.
+    pub seed: Option<u64>,
+    /// Limits on the whole solve, and a way to cancel it.
+    pub budget: SolveBudget,
+}
+
+impl Default for PbilConfig {
+    fn default() -> Self {
+        PbilConfig {
+            samples: 100,
+            learning_rate: 0.1,
+            mutation_probability: 0.02,
+            mutation_shift: 0.05,
+            iterations: 1000,
+            objective: Objective::AttackingPairs,
+            max_evaluations: None,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+}
+
+pub struct Pbil {
+    size: usize,
+    rngs: ComponentRngs,
+    /// The probability of placing the queen of each column in each row,
+    /// indexed by `column * size + row`.
+    probabilities: Vec<f32>,
+    effort: SearchEffort,
+    budget: BudgetTracker,
+    config: PbilConfig,
+}
+
+impl Pbil {
+    fn sample(&mut self) -> GenericChallengeState {
+        use rand::Rng;
+
+        let mut state = GenericChallengeState::unpositioned(self.size);
+        for column in 0..self.size {
+            let row_probabilities =
+                &self.probabilities[column * self.size..(column + 1) * self.size];
+            let p = self.rngs.initializer.next_f32();
+            let mut accumulated = 0.;
+            let mut chosen = self.size - 1;
+            for (row, probability) in row_probabilities.iter().enumerate() {
+                accumulated += probability;
+                if p < accumulated {
+                    chosen = row;
+                    break;
+                }
+            }
+            state.queen_rows.push(chosen);
+        }
+        state
+    }
+
+    /// Moves the distribution towards `best`, and mutates it.
+    fn learn(&mut self, best: &GenericChallengeState) {
+        use rand::Rng;
+
+        let rate = self.config.learning_rate;
+        let shift = self.config.mutation_shift;
+        for column in 0..self.size {
+            let start = column * self.size;
+            for row in 0..self.size {
+                let target = if best.queen_rows[column] == row {
+                    1.
+                } else {
+                    0.
+                };
+                let probability = &mut self.probabilities[start + row];
+                *probability = *probability * (1. - rate) + target * rate;
+
+                if self.rngs.mutation.next_f32() < self.config.mutation_probability {
+                    let target = if self.rngs.mutation.gen() { 1. } else { 0. };
+                    *probability = *probability * (1. - shift) + target * shift;
+                }
+            }
+
+            // Mutations don't preserve the sum, so normalize again.
+            let total: f32 = self.probabilities[start..start + self.size].iter().sum();
+            if total > 0. {
+                for probability in &mut self.probabilities[start..start + self.size] {
+                    *probability /= total;
+                }
+            }
+        }
+    }
+}
+
+impl NQueensStrategy for Pbil {
+    type Config = PbilConfig;
+
+    fn new(size: usize, config: Self::Config) -> Self {
+        let uniform = if size == 0 { 0. } else { 1. / size as f32 };
+        Self {
+            size,
+            rngs: ComponentRngs::for_seed(config.seed),
+            probabilities: vec![uniform; size * size],
+            effort: SearchEffort::default(),
+            budget: BudgetTracker::new(config.budget.clone()),
+            config,
+        }
+    }
+
+    fn validate(_dimension: usize, config: &Self::Config) -> Result<(), Error> {
+        if config.samples == 0 {
+            return Err(Error::InvalidConfig("sample count must be positive"));
+        }
+        check_probability(
+            config.learning_rate,
+            "learning rate must be between 0 and 1",
+        )?;
+        check_probability(
+            config.mutation_probability,
+            "mutation probability must be between 0 and 1",
+        )?;
+        check_probability(
+            config.mutation_shift,
+            "mutation shift must be between 0 and 1",
+        )
+    }
+
+    fn lint(_dimension: usize, config: &Self::Config) -> Vec<ConfigWarning> {
+        if config.learning_rate > 0. {
+            return vec![];
+        }
+        vec![ConfigWarning::new(
+            "learning_rate",
+            "nothing is learned from the samples, so this is random sampling".to_owned(),
+        )]
+    }
+
+    fn info() -> &'static StrategyInfo {
+        &info::PBIL
+    }
+
+    fn dimension(&self) -> usize {
+        self.size
+    }
+
+    fn solve_with_callback<F>(mut self, mut callback: F) -> Solution
+    where
+        F: FnMut(&[usize], usize),
+    {
+        let mut best: Option<(GenericChallengeState, usize)> = None;
+        let mut last: Option<(Vec<usize>, usize)> = None;
+
+        for _ in 0..self.config.iterations {
+            if self.effort.exhausts(self.config.max_evaluations) || self.budget.should_stop() {
+                break;
+            }
+
+            let mut iteration_best: Option<(GenericChallengeState, usize)> = None;
+            for _ in 0..self.config.samples {
+                let sample = self.sample();
+                let score = sample.evaluate(self.config.objective);
+                self.effort.evaluations += 1;
+                if iteration_best.as_ref().is_none_or(|&(_, s)| score < s) {
+                    iteration_best = Some((sample, score));
+                }
+            }
+
+            let (sample, score) = match iteration_best {
+                Some(sample) => sample,
+                None => break,
+            };
+
+            callback(&sample.queen_rows, score);
+            self.learn(&sample);
+            self.effort.generations += 1;
+            last = Some((sample.queen_rows.clone(), score));
+
+            if best.as_ref().is_none_or(|&(_, s)| score < s) {
+                best = Some((sample, score));
+            }
+
+            if score == 0 {
+                break;
+            }
+        }
+
+        let (state, score) = match best {
+            Some(best) => best,
+            None => {
+                let state = self.sample();
+                let score = state.evaluate(self.config.objective);
+                self.effort.evaluations += 1;
+                (state, score)
+            }
+        };
+
+        let mut solution = Solution::new(state.queen_rows, score);
+        self.budget.finish(Self::info(), &mut solution, score == 0);
+        if let Some((queen_rows, score)) = last {
+            solution.set_final_state(&queen_rows, score);
+        }
+        solution.statistics.master_seed = self.rngs.master_seed;
+        solution.statistics.effort = self.effort;
+        solution
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn returns_best_sample() {
+        let config = PbilConfig {
+            samples: 20,
+            iterations: 50,
+            seed: Some(1),
+            ..PbilConfig::default()
+        };
+
+        let mut best_reported = usize::MAX;
+        let solution = Pbil::new(8, config).solve_with_callback(|queens, score| {
+            assert_eq!(queens.len(), 8);
+            best_reported = best_reported.min(score);
+        });
+
+        assert_eq!(solution.score, best_reported);
+        assert_eq!(solution.queen_rows.len(), 8);
+    }
+}
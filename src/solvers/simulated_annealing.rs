@@ -0,0 +1,1016 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use checkpoint::SearchState;
+use problem::queens::QueenMoves;
+use problem::simulated_annealing::{GenericSimulatedAnnealing, GenericSimulatedAnnealingConfig};
+use problem::{NQueens, Outcome};
+use stepper::{StepResult, Stepper};
+use *;
+
+/// How the temperature decreases over time.
+pub trait CoolingSchedule {
+    /// Returns the temperature for the `iteration`-th iteration since the
+    /// annealing (re)started, counting from one, given the starting
+    /// temperature and the one of the previous iteration.
+    fn cool(&self, starting: f32, previous: f32, iteration: usize) -> f32;
+
+    /// Returns how many random swaps a move should consist of, given the
+    /// current and starting temperatures, the board size, and a uniform
+    /// random sample in `[0, 1)`. `None` means using the configured move
+    /// selection.
+    fn visiting_swaps(
+        &self,
+        _temperature: f32,
+        _starting: f32,
+        _size: usize,
+        _sample: f32,
+    ) -> Option<usize> {
+        None
+    }
+}
+
+/// Multiplies the temperature by `1 - factor` on each iteration.
+pub struct GeometricCooling {
+    pub factor: f32,
+}
+
+impl CoolingSchedule for GeometricCooling {
+    fn cool(&self, _: f32, previous: f32, _: usize) -> f32 {
+        previous * (1. - self.factor)
+    }
+}
+
+/// Fast annealing: the temperature decreases as `starting / k`, and moves
+/// follow a Cauchy visiting distribution, so that the number of swaps
+/// applied at once is heavy-tailed and shrinks with the temperature.
+pub struct FastCooling;
+
+impl CoolingSchedule for FastCooling {
+    fn cool(&self, starting: f32, _: f32, iteration: usize) -> f32 {
+        starting / (1 + iteration) as f32
+    }
+
+    fn visiting_swaps(
+        &self,
+        temperature: f32,
+        starting: f32,
+        _size: usize,
+        sample: f32,
+    ) -> Option<usize> {
+        use std::f32::consts::PI;
+
+        let scale = if starting > 0. {
+            temperature / starting
+        } else {
+            0.
+        };
+        let cauchy = (PI * (sample - 0.5)).tan().abs();
+        Some(1 + (cauchy * scale) as usize)
+    }
+}
+
+/// Decides whether to take moves that don't improve the score.
+pub trait AcceptanceCriterion {
+    /// Prepares for an annealing from a state with score `score`.
+    fn start(&mut self, _score: usize) {}
+
+    /// Returns whether to move from a state with score `current` to one
+    /// with score `candidate`, which is no better, at `temperature`.
+    fn accepts(
+        &mut self,
+        current: usize,
+        candidate: usize,
+        temperature: f32,
+        rng: &mut StreamRng,
+    ) -> bool;
+
+    /// Records the score the search is at after an iteration.
+    fn record(&mut self, _score: usize) {}
+
+    /// Whether the criterion stops taking moves that leave the score as it
+    /// is once the system cools down, which is what ends an annealing.
+    /// Annealings with criteria that never do end once they go
+    /// `MAX_ITERATIONS_WITHOUT_IMPROVEMENT` iterations without improving on
+    /// the best score they saw instead, whatever the temperature.
+    fn freezes(&self) -> bool {
+        true
+    }
+}
+
+/// How many iterations an annealing goes on for without progress once it
+/// can't get out of where it is.
+pub(crate) const MAX_ITERATIONS_WITHOUT_IMPROVEMENT: usize = 1000;
+
+/// Accepts a move that worsens the score by `delta` with probability
+/// `exp(-delta / temperature)`, as in the original annealing. At or below
+/// `freezing_temperature`, the system counts as frozen, and nothing worse
+/// is accepted.
+#[derive(Clone, Copy, Debug)]
+pub struct Metropolis {
+    /// One by default, which suits scores that change by whole numbers,
+    /// like the n-queens ones.
+    pub freezing_temperature: f32,
+}
+
+impl Default for Metropolis {
+    fn default() -> Self {
+        Metropolis {
+            freezing_temperature: 1.,
+        }
+    }
+}
+
+impl AcceptanceCriterion for Metropolis {
+    fn accepts(
+        &mut self,
+        current: usize,
+        candidate: usize,
+        temperature: f32,
+        rng: &mut StreamRng,
+    ) -> bool {
+        use rand::Rng;
+
+        debug_assert!(current <= candidate);
+        if temperature <= self.freezing_temperature {
+            return false;
+        }
+
+        (-((candidate - current) as f32) / temperature).exp() > rng.next_f32()
+    }
+}
+
+/// Threshold accepting: takes any move that worsens the score by less than
+/// the temperature, without randomness.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ThresholdAccepting;
+
+impl AcceptanceCriterion for ThresholdAccepting {
+    fn accepts(
+        &mut self,
+        current: usize,
+        candidate: usize,
+        temperature: f32,
+        _: &mut StreamRng,
+    ) -> bool {
+        ((candidate - current) as f32) < temperature
+    }
+}
+
+/// Late acceptance hill climbing: takes a move if it's no worse than the
+/// score the search was at `length` iterations ago, ignoring the
+/// temperature.
+#[derive(Clone, Debug)]
+pub struct LateAcceptance {
+    /// The scores of the last iterations, as a ring.
+    history: Vec<usize>,
+    iteration: usize,
+}
+
+impl LateAcceptance {
+    /// Panics if `length` is zero.
+    pub fn new(length: usize) -> Self {
+        assert!(length > 0, "late acceptance needs some history");
+        LateAcceptance {
+            history: vec![0; length],
+            iteration: 0,
+        }
+    }
+}
+
+impl AcceptanceCriterion for LateAcceptance {
+    fn start(&mut self, score: usize) {
+        for past in &mut self.history {
+            *past = score;
+        }
+        self.iteration = 0;
+    }
+
+    fn accepts(&mut self, current: usize, candidate: usize, _: f32, _: &mut StreamRng) -> bool {
+        candidate == current || candidate <= self.history[self.iteration % self.history.len()]
+    }
+
+    fn record(&mut self, score: usize) {
+        let length = self.history.len();
+        self.history[self.iteration % length] = score;
+        self.iteration += 1;
+    }
+
+    /// Moves that leave the score as it is are always taken.
+    fn freezes(&self) -> bool {
+        false
+    }
+}
+
+/// Which moves the annealing considers.
+///
+/// Besides the basic neighborhoods, there are combinators to switch between
+/// them as the search goes, like `WhileScoreAbove` to make swaps until the
+/// score is low enough, and single queen moves after that.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Neighborhood {
+    /// Swap the rows of queens, as chosen by the move selection or the
+    /// visiting distribution of the cooling schedule. This keeps one
+    /// queen per row.
+    Swaps,
+    /// Move a single queen to another row, which leaves the permutation
+    /// subspace. Attacking pairs are then scored incrementally.
+    SingleQueenMoves,
+    /// Draws each move from one of the neighborhoods, at random, with a
+    /// probability proportional to its weight.
+    Union(Vec<(f32, Neighborhood)>),
+    /// Uses each neighborhood for the given number of iterations since the
+    /// annealing (re)started, in order, and the last one from then on.
+    Phases(Vec<(usize, Neighborhood)>),
+    /// Uses `above` while the score of the current state is above
+    /// `threshold`, and `otherwise` when it isn't.
+    WhileScoreAbove {
+        threshold: usize,
+        above: Box<Neighborhood>,
+        otherwise: Box<Neighborhood>,
+    },
+}
+
+impl Neighborhood {
+    fn validate(&self) -> Result<(), Error> {
+        match *self {
+            Neighborhood::Swaps | Neighborhood::SingleQueenMoves => Ok(()),
+            Neighborhood::Union(ref parts) => {
+                if parts
+                    .iter()
+                    .any(|&(weight, _)| weight < 0. || weight.is_nan())
+                {
+                    return Err(Error::InvalidConfig(
+                        "neighborhood weights can't be negative",
+                    ));
+                }
+                if parts.iter().map(|&(weight, _)| weight).sum::<f32>() <= 0. {
+                    return Err(Error::InvalidConfig(
+                        "a union of neighborhoods needs a positive weight",
+                    ));
+                }
+                parts.iter().try_for_each(|(_, part)| part.validate())
+            }
+            Neighborhood::Phases(ref phases) => {
+                if phases.is_empty() {
+                    return Err(Error::InvalidConfig("neighborhood phases can't be empty"));
+                }
+                phases.iter().try_for_each(|(_, phase)| phase.validate())
+            }
+            Neighborhood::WhileScoreAbove {
+                ref above,
+                ref otherwise,
+                ..
+            } => {
+                above.validate()?;
+                otherwise.validate()
+            }
+        }
+    }
+
+    /// Returns the basic neighborhood to draw a move from, at the given
+    /// iteration since the annealing (re)started, and score of the current
+    /// state.
+    pub(crate) fn choose(
+        &self,
+        iteration: usize,
+        score: usize,
+        rng: &mut StreamRng,
+    ) -> &Neighborhood {
+        use rand::Rng;
+
+        match *self {
+            Neighborhood::Swaps | Neighborhood::SingleQueenMoves => self,
+            Neighborhood::Union(ref parts) => {
+                let total = parts.iter().map(|&(weight, _)| weight).sum::<f32>();
+                let mut sample = rng.next_f32() * total;
+                let mut chosen = &parts[parts.len() - 1].1;
+                for &(weight, ref part) in parts {
+                    if sample < weight {
+                        chosen = part;
+                        break;
+                    }
+                    sample -= weight;
+                }
+                chosen.choose(iteration, score, rng)
+            }
+            Neighborhood::Phases(ref phases) => {
+                let mut since_start = iteration;
+                for (i, &(length, ref phase)) in phases.iter().enumerate() {
+                    if since_start < length || i == phases.len() - 1 {
+                        return phase.choose(since_start, score, rng);
+                    }
+                    since_start -= length;
+                }
+                unreachable!()
+            }
+            Neighborhood::WhileScoreAbove {
+                threshold,
+                ref above,
+                ref otherwise,
+            } => {
+                if score > threshold {
+                    above.choose(iteration, score, rng)
+                } else {
+                    otherwise.choose(iteration, score, rng)
+                }
+            }
+        }
+    }
+}
+
+/// Heats a stagnant annealing back up, instead of ending it.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Reheating {
+    /// The fraction of the starting temperature to go back to.
+    pub fraction: f32,
+    /// How many times each annealing, from the start or a restart, can be
+    /// reheated.
+    pub max_reheats: usize,
+}
+
+/// Where the annealing goes on from after reheating or restarting.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RestartPolicy {
+    /// Reheat from the current state, and restart from a random one.
+    Fresh,
+    /// Go back to the best state seen so far, across restarts too, and
+    /// anneal it again from a fresh temperature, like basin hopping.
+    FromBest,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct SimulatedAnnealingConfig {
+    pub starting_temperature: f32,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub cooling_schedule: Box<dyn CoolingSchedule>,
+    /// Which moves that don't improve the score to take, or `Metropolis`
+    /// if unset.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub acceptance: Option<Box<dyn AcceptanceCriterion>>,
+    /// If set, the annealing is reheated when it stops improving after
+    /// cooling down, rather than ending there.
+    pub reheating: Option<Reheating>,
+    /// How many times the search can be restarted from a fresh random
+    /// state and the starting temperature after freezing.
+    pub restarts: usize,
+    /// Whether to keep restarting after a solution is found, in order to
+    /// collect more of them in the solution archive.
+    pub exhaust_restarts: bool,
+    /// Where reheats and restarts go on from.
+    pub restart_policy: RestartPolicy,
+    /// How to choose the next move to try.
+    pub move_selection: MoveSelection,
+    /// If set, whether to take the moves that leave the score unchanged,
+    /// instead of leaving it to the acceptance criterion, which with
+    /// Metropolis takes them all.
+    pub tie_breaking: Option<TieBreaking>,
+    /// If present, `starting_temperature` is ignored, and instead
+    /// calibrated so that moves that worsen the initial state are
+    /// accepted with roughly this probability.
+    pub initial_acceptance: Option<f32>,
+    /// What to minimize.
+    pub objective: Objective,
+    pub neighborhood: Neighborhood,
+    /// If set, moves are drawn from it instead, and `neighborhood` and
+    /// `move_selection` are ignored.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub operator: Option<Box<dyn NeighborhoodOp>>,
+    /// Stop once this many placements have been scored across the whole
+    /// run, counting incremental evaluations too, if set.
+    pub max_evaluations: Option<usize>,
+    /// The master seed of the random streams, so that runs can be
+    /// reproduced, or a random one if unset.
+    pub seed: Option<u64>,
+    /// Limits on the whole solve, and a way to cancel it.
+    pub budget: SolveBudget,
+}
+
+impl Default for SimulatedAnnealingConfig {
+    fn default() -> Self {
+        SimulatedAnnealingConfig {
+            // Calibrated to the board from `initial_acceptance` instead.
+            starting_temperature: 1.,
+            cooling_schedule: Box::new(GeometricCooling { factor: 0.01 }),
+            acceptance: None,
+            reheating: None,
+            restarts: 2,
+            exhaust_restarts: false,
+            restart_policy: RestartPolicy::Fresh,
+            move_selection: MoveSelection::RandomSwap,
+            tie_breaking: None,
+            initial_acceptance: Some(0.5),
+            objective: Objective::AttackingPairs,
+            neighborhood: Neighborhood::Swaps,
+            operator: None,
+            max_evaluations: None,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+}
+
+/// Simulated annealing over `NQueens`, moving queens in place.
+pub struct SimulatedAnnealing {
+    search: GenericSimulatedAnnealing<NQueens, QueenMoves>,
+    /// The iterations of the runs this one resumes.
+    resumed_iterations: usize,
+    context: SolverContext,
+}
+
+impl SimulatedAnnealing {
+    /// Like `new`, but annealing from `queen_rows` rather than a random
+    /// placement. Fresh restarts still start from random placements, and
+    /// the starting temperature is calibrated on one if so configured.
+    pub fn from_placement(queen_rows: Vec<usize>, config: SimulatedAnnealingConfig) -> Self {
+        let mut annealing = Self::new(queen_rows.len(), config);
+        annealing.search.state = queen_rows;
+        annealing
+    }
+
+    /// Carries on the search stopped at `checkpoint`, within the budget and
+    /// limits of `config`, which should otherwise be the one it ran with.
+    ///
+    /// The move selection and the acceptance criterion start over, so a
+    /// resumed run doesn't take the same path as an uninterrupted one, even
+    /// from the same seed.
+    pub fn resume(checkpoint: Checkpoint, config: SimulatedAnnealingConfig) -> Result<Self, Error> {
+        Self::validate(checkpoint.dimension, &config)?;
+        checkpoint.validate()?;
+        let (queen_rows, temperatures, iteration, stagnant_iterations, reheats, reheats_left) =
+            match checkpoint.state {
+                SearchState::SimulatedAnnealing {
+                    queen_rows,
+                    starting_temperature,
+                    cooling_start,
+                    temperature,
+                    iteration,
+                    stagnant_iterations,
+                    reheats,
+                    reheats_left,
+                } => (
+                    queen_rows,
+                    (starting_temperature, cooling_start, temperature),
+                    iteration,
+                    stagnant_iterations,
+                    reheats,
+                    reheats_left,
+                ),
+                _ => {
+                    return Err(Error::InvalidConfig(
+                        "the checkpoint is of another strategy",
+                    ))
+                }
+            };
+
+        let (problem, moves, config) = Self::split_config(checkpoint.dimension, config);
+        let restarts_left = config.restarts.saturating_sub(checkpoint.restarts);
+        let mut search = GenericSimulatedAnnealing::with_state(
+            problem,
+            moves,
+            checkpoint.rngs,
+            queen_rows,
+            config,
+        );
+        let (starting_temperature, cooling_start, temperature) = temperatures;
+        search.effort = checkpoint.effort;
+        search.starting_temperature = starting_temperature;
+        search.cooling_start = cooling_start;
+        search.temperature = temperature;
+        search.reheats = reheats;
+        search.iteration = iteration;
+        search.stagnant_iterations = stagnant_iterations;
+        search.reheats_left = reheats_left;
+        search.restarts = checkpoint.restarts;
+        search.restarts_left = restarts_left;
+        search.best = checkpoint.best;
+        Ok(SimulatedAnnealing {
+            search,
+            resumed_iterations: checkpoint.iterations,
+            context: SolverContext::new(checkpoint.dimension),
+        })
+    }
+
+    /// Splits `config` into the problem, the moves and the configuration
+    /// of the generic annealing, for a board of `size`.
+    fn split_config(
+        size: usize,
+        config: SimulatedAnnealingConfig,
+    ) -> (NQueens, QueenMoves, GenericSimulatedAnnealingConfig) {
+        let problem = NQueens::new(size, config.objective);
+        let moves = QueenMoves::new(
+            size,
+            config.move_selection,
+            config.operator,
+            config.neighborhood,
+            config.tie_breaking,
+        );
+        let config = GenericSimulatedAnnealingConfig {
+            starting_temperature: config.starting_temperature,
+            // The scores change by whole numbers.
+            freezing_temperature: 1.,
+            cooling_schedule: config.cooling_schedule,
+            acceptance: config.acceptance,
+            reheating: config.reheating,
+            restarts: config.restarts,
+            exhaust_restarts: config.exhaust_restarts,
+            restart_policy: config.restart_policy,
+            // A single queen has no moves to calibrate on.
+            initial_acceptance: config.initial_acceptance.filter(|_| size > 1),
+            max_evaluations: config.max_evaluations,
+            seed: config.seed,
+            budget: config.budget,
+        };
+        (problem, moves, config)
+    }
+
+    /// Like `solve_with_callback`, but also passes `callback` the
+    /// temperature each step was taken at.
+    pub fn solve_with_temperatures<F>(self, callback: F) -> Solution
+    where
+        F: FnMut(&[usize], usize, f32),
+    {
+        self.solve_annealing(callback).0
+    }
+
+    /// Anneals and restarts until done, reporting each step along with its
+    /// temperature.
+    fn solve_annealing<F>(mut self, mut callback: F) -> (Solution, SolverContext)
+    where
+        F: FnMut(&[usize], usize, f32),
+    {
+        loop {
+            if let Some(solution) = self.advance(&mut callback) {
+                return (solution, self.context);
+            }
+        }
+    }
+
+    /// Runs the next iteration of the search, which either starts annealing
+    /// from a placement, tries a move, reheats, or ends the annealing and
+    /// restarts, and returns the solution if the search is over.
+    fn advance<F>(&mut self, callback: &mut F) -> Option<Solution>
+    where
+        F: FnMut(&[usize], usize, f32),
+    {
+        let outcome = self
+            .search
+            .advance(&mut |queen_rows: &Vec<usize>, score, temperature| {
+                callback(queen_rows, score, temperature)
+            })?;
+        Some(self.finish(outcome))
+    }
+
+    /// Returns where the search is at, to resume it later even if it's not
+    /// over yet, like between two of its steps. It anneals again from the
+    /// current state, at the current temperature.
+    pub fn snapshot(&self) -> Checkpoint {
+        self.checkpoint(self.search.state.clone(), self.search.best())
+    }
+
+    /// Returns a checkpoint of the run, annealing from `queen_rows`.
+    fn checkpoint(&self, queen_rows: Vec<usize>, best: Option<(Vec<usize>, usize)>) -> Checkpoint {
+        let search = &self.search;
+        Checkpoint {
+            dimension: search.problem().size(),
+            rngs: search.rngs.clone(),
+            effort: search.effort.clone(),
+            iterations: self.resumed_iterations + search.budget.iterations(),
+            restarts: search.restarts,
+            best,
+            state: SearchState::SimulatedAnnealing {
+                queen_rows,
+                starting_temperature: search.starting_temperature,
+                cooling_start: search.cooling_start,
+                temperature: search.temperature,
+                iteration: search.iteration,
+                stagnant_iterations: search.stagnant_iterations,
+                reheats: search.reheats,
+                reheats_left: search.reheats_left,
+            },
+        }
+    }
+
+    fn finish(&mut self, outcome: Outcome<Vec<usize>>) -> Solution {
+        let score = outcome.score;
+        let mut solution = Solution::new(outcome.state, score);
+        let final_state = self.search.final_state().cloned();
+        if let Some((ref queen_rows, final_score)) = final_state {
+            solution.set_final_state(queen_rows, final_score);
+        }
+        for queen_rows in &outcome.solutions {
+            solution.archive.insert(queen_rows);
+        }
+        self.search
+            .budget
+            .finish(<Self as NQueensStrategy>::info(), &mut solution, score == 0);
+        solution.statistics.iterations += self.resumed_iterations;
+        if let (true, Some((queen_rows, _))) =
+            (self.search.budget.stopped() && score != 0, final_state)
+        {
+            let best = (solution.queen_rows.to_vec(), score);
+            solution.checkpoint = Some(self.checkpoint(queen_rows, Some(best)));
+        }
+        solution.statistics.master_seed = outcome.master_seed;
+        solution.statistics.effort = self.search.effort.clone();
+        solution.statistics.restarts = self.search.restarts;
+        solution.statistics.reheats = self.search.reheats;
+        if let Some(counters) = self.search.moves.counters.take() {
+            self.context.counters = Some(counters);
+        }
+        solution
+    }
+}
+
+impl Stepper for SimulatedAnnealing {
+    fn step(&mut self) -> StepResult {
+        match self.advance(&mut |_, _, _| {}) {
+            Some(solution) => StepResult::Done(Box::new(solution)),
+            None => StepResult::Running,
+        }
+    }
+
+    fn current(&self) -> (&[usize], usize) {
+        let (queen_rows, score) = self.search.current();
+        (queen_rows, score)
+    }
+}
+
+impl NQueensStrategy for SimulatedAnnealing {
+    type Config = SimulatedAnnealingConfig;
+
+    fn new(size: usize, config: Self::Config) -> Self {
+        Self::new_in_context(SolverContext::new(size), config)
+    }
+
+    fn new_in_context(mut context: SolverContext, config: Self::Config) -> Self {
+        let (problem, mut moves, config) = Self::split_config(context.dimension(), config);
+        if let Objective::AttackingPairs = problem.objective() {
+            moves.counters = context.counters.take();
+        }
+        SimulatedAnnealing {
+            search: GenericSimulatedAnnealing::with_moves(problem, moves, config),
+            resumed_iterations: 0,
+            context,
+        }
+    }
+
+    fn validate(_dimension: usize, config: &Self::Config) -> Result<(), Error> {
+        config.neighborhood.validate()?;
+        if let Some(ref reheating) = config.reheating {
+            check_probability(
+                reheating.fraction,
+                "reheating fraction must be between 0 and 1",
+            )?;
+        }
+        match config.initial_acceptance {
+            Some(p) => check_probability(p, "initial acceptance must be between 0 and 1"),
+            None if config.starting_temperature > 0. => Ok(()),
+            None => Err(Error::InvalidConfig(
+                "starting temperature must be positive",
+            )),
+        }
+    }
+
+    fn lint(dimension: usize, config: &Self::Config) -> Vec<ConfigWarning> {
+        let mut warnings = vec![];
+        // A calibrated starting temperature isn't known until the run.
+        if config.initial_acceptance.is_none() && config.starting_temperature >= 1. {
+            let mut temperature = config.starting_temperature;
+            let frozen_at = (1..=dimension).find(|&iteration| {
+                temperature = config.cooling_schedule.cool(
+                    config.starting_temperature,
+                    temperature,
+                    iteration,
+                );
+                temperature < 1.
+            });
+            if let Some(iteration) = frozen_at {
+                warnings.push(ConfigWarning::new(
+                    "cooling_schedule",
+                    format!(
+                        "the temperature drops below 1 after {} iterations, fewer than the {} queens",
+                        iteration, dimension
+                    ),
+                ));
+            }
+        }
+        warnings
+    }
+
+    fn info() -> &'static StrategyInfo {
+        &info::SIMULATED_ANNEALING
+    }
+
+    fn dimension(&self) -> usize {
+        self.search.problem().size()
+    }
+
+    fn solve_with_callback<F>(self, callback: F) -> Solution
+    where
+        F: FnMut(&[usize], usize),
+    {
+        self.solve_in_context(callback).0
+    }
+
+    fn solve_in_context<F>(self, mut callback: F) -> (Solution, SolverContext)
+    where
+        F: FnMut(&[usize], usize),
+    {
+        self.solve_annealing(|queens, score, _| callback(queens, score))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calibrates_starting_temperature() {
+        let config = |initial_acceptance| SimulatedAnnealingConfig {
+            starting_temperature: 0.,
+            restarts: 0,
+            initial_acceptance,
+            seed: Some(1),
+            ..SimulatedAnnealingConfig::default()
+        };
+
+        let hot = SimulatedAnnealing::new(16, config(Some(0.9)));
+        let cold = SimulatedAnnealing::new(16, config(Some(0.1)));
+        assert!(hot.search.starting_temperature > cold.search.starting_temperature);
+        assert!(cold.search.starting_temperature > 0.);
+    }
+
+    #[test]
+    fn single_queen_moves_are_scored_incrementally() {
+        let config = SimulatedAnnealingConfig {
+            starting_temperature: 10.,
+            restarts: 0,
+            initial_acceptance: None,
+            neighborhood: Neighborhood::SingleQueenMoves,
+            seed: Some(2),
+            ..SimulatedAnnealingConfig::default()
+        };
+        let mut scores = vec![];
+        let solution = SimulatedAnnealing::new(12, config).solve_with_callback(|queens, score| {
+            let state = GenericChallengeState {
+                size: 12,
+                queen_rows: queens.to_vec(),
+            };
+            scores.push((score, state.evaluate(Objective::AttackingPairs)));
+        });
+
+        assert!(scores.iter().all(|&(reported, actual)| reported == actual));
+        // The best state seen is returned, which may not be the last one.
+        assert_eq!(scores.iter().map(|s| s.0).min(), Some(solution.score));
+    }
+
+    #[test]
+    fn returns_best_state_seen() {
+        let config = SimulatedAnnealingConfig {
+            starting_temperature: 50.,
+            cooling_schedule: Box::new(GeometricCooling { factor: 0.05 }),
+            restarts: 0,
+            initial_acceptance: None,
+            seed: Some(3),
+            ..SimulatedAnnealingConfig::default()
+        };
+        let mut best_seen = usize::MAX;
+        let solution = SimulatedAnnealing::new(16, config).solve_with_callback(|_, score| {
+            best_seen = best_seen.min(score);
+        });
+
+        assert_eq!(solution.score(), best_seen);
+        if let Some((queen_rows, score)) = solution.final_state() {
+            assert!(score > solution.score());
+            assert!(queen_rows != solution.queen_rows());
+        }
+    }
+
+    #[test]
+    fn tie_breaking_policies_keep_a_permutation() {
+        for policy in [
+            TieBreaking::KeepIncumbent,
+            TieBreaking::Random,
+            TieBreaking::LeastRecentlyMoved,
+            TieBreaking::MostConflicted,
+        ] {
+            let config = SimulatedAnnealingConfig {
+                tie_breaking: Some(policy.clone()),
+                seed: Some(2),
+                ..SimulatedAnnealingConfig::default()
+            };
+            let solution = SimulatedAnnealing::new(12, config).solve();
+
+            let mut rows = solution.queen_rows().to_vec();
+            rows.sort();
+            assert_eq!(rows, (0..12).collect::<Vec<_>>(), "{:?}", policy);
+            assert_eq!(
+                solution.attack_counts().iter().sum::<usize>() / 2,
+                solution.score()
+            );
+        }
+    }
+
+    #[test]
+    fn combines_neighborhoods() {
+        use self::Neighborhood::*;
+
+        let mut rng = stream_rng(1, RngStream::Neighborhood);
+        let phases = Phases(vec![(10, Swaps), (5, SingleQueenMoves)]);
+        assert_eq!(phases.choose(9, 0, &mut rng), &Swaps);
+        assert_eq!(phases.choose(10, 0, &mut rng), &SingleQueenMoves);
+        assert_eq!(phases.choose(100, 0, &mut rng), &SingleQueenMoves);
+
+        let union = Union(vec![(0., Swaps), (1., SingleQueenMoves)]);
+        assert!((0..100).all(|_| union.choose(0, 0, &mut rng) == &SingleQueenMoves));
+
+        let by_score = WhileScoreAbove {
+            threshold: 5,
+            above: Box::new(Swaps),
+            otherwise: Box::new(union),
+        };
+        assert_eq!(by_score.choose(0, 6, &mut rng), &Swaps);
+        assert_eq!(by_score.choose(0, 5, &mut rng), &SingleQueenMoves);
+
+        let config = |neighborhood| SimulatedAnnealingConfig {
+            starting_temperature: 10.,
+            restarts: 0,
+            initial_acceptance: None,
+            neighborhood,
+            seed: Some(3),
+            ..SimulatedAnnealingConfig::default()
+        };
+        assert!(SimulatedAnnealing::try_new(8, config(Union(vec![]))).is_err());
+
+        let mut consistent = true;
+        SimulatedAnnealing::new(12, config(by_score)).solve_with_callback(|queens, score| {
+            let state = GenericChallengeState {
+                size: 12,
+                queen_rows: queens.to_vec(),
+            };
+            consistent &= state.evaluate(Objective::AttackingPairs) == score;
+        });
+        assert!(consistent);
+    }
+
+    #[test]
+    fn reheats_when_stagnant() {
+        let config = SimulatedAnnealingConfig {
+            starting_temperature: 10.,
+            cooling_schedule: Box::new(GeometricCooling { factor: 0.2 }),
+            reheating: Some(Reheating {
+                fraction: 0.5,
+                max_reheats: 3,
+            }),
+            restarts: 0,
+            initial_acceptance: None,
+            seed: Some(6),
+            ..SimulatedAnnealingConfig::default()
+        };
+        let solution = SimulatedAnnealing::new(40, config).solve();
+        let reheats = solution.statistics.reheats;
+        assert!(reheats == 3 || (reheats < 3 && solution.score == 0));
+    }
+
+    #[test]
+    fn restarts_from_the_best_state() {
+        // Frozen from the start, so only restarts can make things worse.
+        let config = |restart_policy| SimulatedAnnealingConfig {
+            starting_temperature: 0.5,
+            restarts: 5,
+            restart_policy,
+            initial_acceptance: None,
+            seed: Some(4),
+            ..SimulatedAnnealingConfig::default()
+        };
+        let scores = |policy| {
+            let mut scores = vec![];
+            SimulatedAnnealing::new(30, config(policy))
+                .solve_with_callback(|_, score| scores.push(score));
+            scores
+        };
+
+        assert!(scores(RestartPolicy::Fresh).windows(2).any(|w| w[1] > w[0]));
+        assert!(scores(RestartPolicy::FromBest)
+            .windows(2)
+            .all(|w| w[1] <= w[0]));
+    }
+
+    #[test]
+    fn resumes_from_snapshots() {
+        let config = || SimulatedAnnealingConfig {
+            seed: Some(6),
+            ..SimulatedAnnealingConfig::default()
+        };
+        let mut annealing = SimulatedAnnealing::new(30, config());
+        for _ in 0..200 {
+            assert!(matches!(annealing.step(), StepResult::Running));
+        }
+
+        let checkpoint = annealing.snapshot();
+        let (queen_rows, score) = annealing.current();
+        let best_score = checkpoint.best.as_ref().unwrap().1;
+        assert!(best_score <= score);
+        match checkpoint.state {
+            SearchState::SimulatedAnnealing {
+                queen_rows: ref snapshot,
+                temperature,
+                ..
+            } => {
+                assert_eq!(&snapshot[..], queen_rows);
+                assert_eq!(temperature, annealing.search.temperature());
+            }
+            _ => unreachable!(),
+        }
+
+        let iterations = checkpoint.iterations;
+        assert!(iterations > 0);
+        let mut off_board = checkpoint.clone();
+        if let SearchState::SimulatedAnnealing {
+            ref mut queen_rows, ..
+        } = off_board.state
+        {
+            queen_rows[0] = 30;
+        }
+        assert!(SimulatedAnnealing::resume(off_board, config()).is_err());
+        let mut truncated = checkpoint.clone();
+        truncated.best.as_mut().unwrap().0.pop();
+        assert!(SimulatedAnnealing::resume(truncated, config()).is_err());
+
+        let solution = SimulatedAnnealing::resume(checkpoint, config())
+            .unwrap()
+            .solve();
+        assert!(solution.score() <= best_score);
+        assert!(solution.statistics().iterations > iterations);
+    }
+
+    #[test]
+    fn acceptance_criteria() {
+        let mut rng = stream_rng(2, RngStream::Acceptance);
+        assert!(!Metropolis::default().accepts(3, 4, 1., &mut rng));
+        assert!(ThresholdAccepting.accepts(3, 5, 2.5, &mut rng));
+        assert!(!ThresholdAccepting.accepts(3, 6, 2.5, &mut rng));
+
+        let mut late = LateAcceptance::new(2);
+        late.start(10);
+        assert!(late.accepts(4, 10, 0., &mut rng));
+        late.record(4);
+        late.record(4);
+        assert!(!late.accepts(4, 5, 0., &mut rng));
+        assert!(late.accepts(4, 4, 0., &mut rng));
+
+        let config = SimulatedAnnealingConfig {
+            starting_temperature: 10.,
+            cooling_schedule: Box::new(GeometricCooling { factor: 0.001 }),
+            acceptance: Some(Box::new(LateAcceptance::new(50))),
+            restarts: 5,
+            initial_acceptance: None,
+            seed: Some(8),
+            ..SimulatedAnnealingConfig::default()
+        };
+        assert!(SimulatedAnnealing::new(16, config).solve().is_valid());
+    }
+
+    #[test]
+    fn late_acceptance_ends_without_a_budget() {
+        // Late acceptance takes every move that leaves the score as it is,
+        // and there's no placement without attacks to stop at.
+        for &n in &[2, 3] {
+            let config = SimulatedAnnealingConfig {
+                acceptance: Some(Box::new(LateAcceptance::new(10))),
+                seed: Some(4),
+                ..Default::default()
+            };
+            let solution = SimulatedAnnealing::new(n, config).solve();
+            assert!(solution.score() > 0);
+            assert_eq!(solution.statistics().restarts, 2);
+        }
+    }
+
+    #[test]
+    fn fast_cooling_shrinks_moves_with_temperature() {
+        let schedule = FastCooling;
+        assert_eq!(schedule.cool(100., 100., 4), 20.);
+        assert_eq!(schedule.visiting_swaps(0., 100., 8, 0.9), Some(1));
+        assert!(schedule.visiting_swaps(100., 100., 8, 0.99).unwrap() > 1);
+    }
+}
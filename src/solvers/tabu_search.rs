@@ -0,0 +1,323 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Tabu search: always take the best swap of two queens, even if it makes
+//! things worse, but don't move the same queens again for a while, so that
+//! the search can't just undo its last moves.
+
+use std::iter;
+use stepper::{StepResult, Stepper};
+use *;
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct TabuSearchConfig {
+    /// For how many steps the queens of a swap can't be moved again,
+    /// unless that leads to the best score seen so far.
+    pub tenure: usize,
+    /// How many swaps to make before giving up.
+    pub max_steps: usize,
+    /// How to choose among the best moves that aren't tabu when several of
+    /// them lead to the same score.
+    pub tie_breaking: TieBreaking,
+    /// If set, the best of its moves is taken at each step instead of the
+    /// best swap, and the queens it moves become tabu.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub operator: Option<Box<dyn NeighborhoodOp>>,
+    /// The master seed of the random streams, so that runs can be
+    /// reproduced, or a random one if unset.
+    pub seed: Option<u64>,
+    /// Limits on the whole solve, and a way to cancel it.
+    pub budget: SolveBudget,
+}
+
+impl Default for TabuSearchConfig {
+    fn default() -> Self {
+        TabuSearchConfig {
+            tenure: 4,
+            max_steps: 2000,
+            tie_breaking: TieBreaking::Random,
+            operator: None,
+            seed: None,
+            budget: SolveBudget::default(),
+        }
+    }
+}
+
+pub struct TabuSearch {
+    base: GenericChallengeState,
+    rngs: ComponentRngs,
+    effort: SearchEffort,
+    budget: BudgetTracker,
+    /// The counters of the current placement, or `None` before starting.
+    counters: Option<ConflictCounters>,
+    score: usize,
+    best: (Vec<usize>, usize),
+    /// The step until which each column can't be moved.
+    tabu_until: Vec<usize>,
+    ties: TieBreaker,
+    /// The swaps tried so far.
+    step: usize,
+    config: TabuSearchConfig,
+}
+
+impl TabuSearch {
+    /// Runs the next iteration of the search, which either scores the
+    /// initial placement, or makes the best swap that isn't tabu, and
+    /// returns the solution if the search is over.
+    fn advance<F>(&mut self, callback: &mut F) -> Option<Solution>
+    where
+        F: FnMut(&[usize], usize),
+    {
+        let size = self.base.size;
+        let counters = match self.counters {
+            Some(ref mut counters) => counters,
+            None => {
+                let counters = ConflictCounters::new(&self.base);
+                self.score = counters.attacking_pairs();
+                self.effort.evaluations += 1;
+                self.best = (self.base.queen_rows.clone(), self.score);
+                self.tabu_until = vec![0; size];
+                self.counters = Some(counters);
+                callback(&self.base.queen_rows, self.score);
+                return None;
+            }
+        };
+
+        let step = self.step;
+        if self.score == 0 || step == self.config.max_steps || self.budget.should_stop() {
+            return Some(self.finish());
+        }
+        self.step += 1;
+
+        let mut chosen_delta = isize::MAX;
+        let chosen = if let Some(ref operator) = self.config.operator {
+            let mut tied = vec![];
+            let moves = operator.moves(&self.base);
+            self.effort.evaluations += moves.len();
+            let tabu_until = &self.tabu_until;
+            for candidate in moves {
+                let delta = counters.score_delta_for_move(&candidate);
+                let is_tabu = candidate.columns().any(|column| tabu_until[column] > step);
+                if is_tabu && self.score as isize + delta >= self.best.1 as isize {
+                    continue;
+                }
+                if delta < chosen_delta {
+                    chosen_delta = delta;
+                    tied.clear();
+                }
+                if delta == chosen_delta {
+                    tied.push(candidate);
+                }
+            }
+
+            // If everything is tabu, the step is wasted.
+            if tied.is_empty() {
+                return None;
+            }
+            let index = self.ties.choose(
+                tied.len(),
+                |i| tied[i].columns(),
+                &self.base.queen_rows,
+                counters,
+                &mut self.rngs.selection,
+            );
+            tied.swap_remove(index)
+        } else {
+            let mut tied_swaps = vec![];
+            for one in 0..size {
+                for other in one + 1..size {
+                    let delta = counters.score_delta_for_swap(&self.base.queen_rows, one, other);
+                    let is_tabu = self.tabu_until[one] > step || self.tabu_until[other] > step;
+                    // Aspiration: a tabu move is fine if it beats the best.
+                    if is_tabu && self.score as isize + delta >= self.best.1 as isize {
+                        continue;
+                    }
+                    if delta < chosen_delta {
+                        chosen_delta = delta;
+                        tied_swaps.clear();
+                    }
+                    if delta == chosen_delta {
+                        tied_swaps.push((one, other));
+                    }
+                }
+            }
+            self.effort.evaluations += size * size.saturating_sub(1) / 2;
+
+            if tied_swaps.is_empty() {
+                return None;
+            }
+            let index = self.ties.choose(
+                tied_swaps.len(),
+                |i| iter::once(tied_swaps[i].0).chain(iter::once(tied_swaps[i].1)),
+                &self.base.queen_rows,
+                counters,
+                &mut self.rngs.selection,
+            );
+            let (one, other) = tied_swaps[index];
+            let rows = &self.base.queen_rows;
+            QueenMove::new(vec![
+                (one, rows[one], rows[other]),
+                (other, rows[other], rows[one]),
+            ])
+        };
+        chosen.apply(&mut self.base);
+        counters.record_move(&chosen);
+        self.score = (self.score as isize + chosen_delta) as usize;
+        for column in chosen.columns() {
+            self.tabu_until[column] = step + 1 + self.config.tenure;
+        }
+        self.ties.record_move(&chosen);
+        self.accepted(callback);
+        None
+    }
+
+    /// Accounts for the move just made.
+    fn accepted<F>(&mut self, callback: &mut F)
+    where
+        F: FnMut(&[usize], usize),
+    {
+        self.effort.accepted_moves += 1;
+        callback(&self.base.queen_rows, self.score);
+
+        if self.score < self.best.1 {
+            self.best = (self.base.queen_rows.clone(), self.score);
+        }
+    }
+
+    fn finish(&mut self) -> Solution {
+        let (queen_rows, best_score) = self.best.clone();
+        let mut solution = Solution::new(queen_rows, best_score);
+        solution.set_final_state(&self.base.queen_rows, self.score);
+        self.budget.finish(
+            <Self as NQueensStrategy>::info(),
+            &mut solution,
+            best_score == 0,
+        );
+        solution.statistics.master_seed = self.rngs.master_seed;
+        solution.statistics.effort = self.effort.clone();
+        solution
+    }
+}
+
+impl Stepper for TabuSearch {
+    fn step(&mut self) -> StepResult {
+        match self.advance(&mut |_, _| {}) {
+            Some(solution) => StepResult::Done(Box::new(solution)),
+            None => StepResult::Running,
+        }
+    }
+
+    fn current(&self) -> (&[usize], usize) {
+        (&self.base.queen_rows, self.score)
+    }
+}
+
+impl NQueensStrategy for TabuSearch {
+    type Config = TabuSearchConfig;
+
+    fn new(size: usize, config: Self::Config) -> Self {
+        let mut rngs = ComponentRngs::for_seed(config.seed);
+        let base = GenericChallengeState::new(size, &mut rngs.initializer);
+        Self {
+            base,
+            rngs,
+            effort: SearchEffort::default(),
+            budget: BudgetTracker::new(config.budget.clone()),
+            counters: None,
+            score: 0,
+            best: (vec![], 0),
+            tabu_until: vec![],
+            ties: TieBreaker::new(config.tie_breaking.clone(), size),
+            step: 0,
+            config,
+        }
+    }
+
+    fn lint(dimension: usize, config: &Self::Config) -> Vec<ConfigWarning> {
+        // Each swap makes two columns tabu.
+        if 2 * config.tenure < dimension.saturating_sub(1) {
+            return vec![];
+        }
+        vec![ConfigWarning::new(
+            "tenure",
+            format!(
+                "with {} queens, every swap is soon tabu, and only new bests are taken",
+                dimension
+            ),
+        )]
+    }
+
+    fn info() -> &'static StrategyInfo {
+        &info::TABU_SEARCH
+    }
+
+    fn dimension(&self) -> usize {
+        self.base.size
+    }
+
+    fn solve_with_callback<F>(mut self, mut callback: F) -> Solution
+    where
+        F: FnMut(&[usize], usize),
+    {
+        loop {
+            if let Some(solution) = self.advance(&mut callback) {
+                return solution;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solves_medium_boards() {
+        for policy in [
+            TieBreaking::KeepIncumbent,
+            TieBreaking::Random,
+            TieBreaking::LeastRecentlyMoved,
+            TieBreaking::MostConflicted,
+        ] {
+            let config = TabuSearchConfig {
+                tenure: 5,
+                max_steps: 5_000,
+                tie_breaking: policy.clone(),
+                seed: Some(3),
+                ..TabuSearchConfig::default()
+            };
+            let solution = TabuSearch::new(30, config).solve();
+            assert!(solution.is_valid(), "{:?}", policy);
+        }
+    }
+
+    #[test]
+    fn takes_moves_from_an_operator() {
+        let config = TabuSearchConfig {
+            tenure: 3,
+            max_steps: 5_000,
+            tie_breaking: TieBreaking::MostConflicted,
+            operator: Some(Box::new(MoveQueenToRow)),
+            seed: Some(4),
+            ..TabuSearchConfig::default()
+        };
+        let solution = TabuSearch::new(16, config).solve();
+        assert!(solution.is_valid());
+    }
+}
@@ -0,0 +1,174 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Searches that can be driven one iteration at a time, rather than run to
+//! completion at once, for frontends that want to animate them, or
+//! interleave several of them.
+
+use Solution;
+
+/// What a step of a `Stepper` led to.
+pub enum StepResult {
+    /// The search can keep going.
+    Running,
+    /// The search is over, and found this.
+    Done(Box<Solution>),
+}
+
+/// A search that can be advanced one iteration at a time.
+pub trait Stepper {
+    /// Runs the next iteration of the search.
+    ///
+    /// Panics if the search is already over.
+    fn step(&mut self) -> StepResult;
+
+    /// Returns the placement the search is on, and its score.
+    fn current(&self) -> (&[usize], usize);
+
+    /// Returns an iterator over the results of the remaining steps, which
+    /// ends after the one that finishes the search.
+    fn steps(self) -> Steps<Self>
+    where
+        Self: Sized,
+    {
+        Steps {
+            stepper: self,
+            done: false,
+        }
+    }
+}
+
+impl<S: Stepper + ?Sized> Stepper for Box<S> {
+    fn step(&mut self) -> StepResult {
+        (**self).step()
+    }
+
+    fn current(&self) -> (&[usize], usize) {
+        (**self).current()
+    }
+}
+
+/// The steps of a search, as returned by `Stepper::steps`. The search can
+/// be inspected between them:
+///
+/// ```
+/// use local_search_algorithms::prelude::*;
+///
+/// let search = Preset::Fast
+///     .stepper("min_conflicts", 8, Some(1), SolveBudget::default())
+///     .unwrap();
+/// let mut steps = search.steps();
+/// while let Some(StepResult::Running) = steps.next() {
+///     assert_eq!(steps.current().0.len(), 8);
+/// }
+/// ```
+pub struct Steps<S> {
+    stepper: S,
+    done: bool,
+}
+
+impl<S: Stepper> Steps<S> {
+    /// Returns the placement the search is on, and its score.
+    pub fn current(&self) -> (&[usize], usize) {
+        self.stepper.current()
+    }
+
+    /// Returns whether the search is over.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    pub fn into_inner(self) -> S {
+        self.stepper
+    }
+}
+
+impl<S: Stepper> Iterator for Steps<S> {
+    type Item = StepResult;
+
+    fn next(&mut self) -> Option<StepResult> {
+        if self.done {
+            return None;
+        }
+        let result = self.stepper.step();
+        self.done = matches!(result, StepResult::Done(..));
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use presets::Preset;
+    use SolveBudget;
+
+    #[test]
+    fn steps_until_the_search_is_over() {
+        let stepper = Preset::Fast
+            .stepper("min_conflicts", 16, Some(4), SolveBudget::default())
+            .unwrap();
+        let mut steps = stepper.steps();
+        let mut count = 0;
+        let mut solution = None;
+        for result in steps.by_ref() {
+            count += 1;
+            if let StepResult::Done(done) = result {
+                solution = Some(done);
+            }
+        }
+        assert!(steps.is_done());
+        assert!(steps.next().is_none());
+
+        let solution = solution.unwrap();
+        let solo = Preset::Fast
+            .solve(
+                "min_conflicts",
+                16,
+                Some(4),
+                SolveBudget::default(),
+                |_, _| {},
+            )
+            .unwrap();
+        assert_eq!(solution.queen_rows(), solo.queen_rows());
+        // Besides moving queens, steps start and give up on placements.
+        assert!(count > solo.statistics().effort.accepted_moves);
+    }
+
+    #[test]
+    fn steps_like_a_whole_solve() {
+        for &strategy in &["hill_climbing", "simulated_annealing", "genetic"] {
+            let stepper = Preset::Fast
+                .stepper(strategy, 10, Some(3), SolveBudget::default())
+                .unwrap();
+            let mut steps = stepper.steps();
+            let mut solution = None;
+            while let Some(result) = steps.next() {
+                assert_eq!(steps.current().0.len(), 10, "{}", strategy);
+                if let StepResult::Done(done) = result {
+                    solution = Some(done);
+                }
+            }
+
+            let solution = solution.unwrap();
+            let solo = Preset::Fast
+                .solve(strategy, 10, Some(3), SolveBudget::default(), |_, _| {})
+                .unwrap();
+            assert_eq!(solution.queen_rows(), solo.queen_rows(), "{}", strategy);
+            assert_eq!(solution.score(), solo.score(), "{}", strategy);
+        }
+    }
+}
@@ -0,0 +1,148 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Short human-readable reports of how a solve went, for the command line
+//! and the teaching frontends.
+
+use *;
+
+/// Formats `value` with a comma every three digits.
+fn with_separators(value: u128) -> String {
+    let digits = value.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+    formatted
+}
+
+fn plural(count: usize, singular: &str) -> String {
+    let suffix = if count == 1 { "" } else { "s" };
+    format!("{} {}{}", with_separators(count as u128), singular, suffix)
+}
+
+/// Describes the run that produced `solution` in a sentence, like
+/// "Simulated annealing solved n=32 in 14,210 iterations and 182 ms after
+/// 2 restarts; best score reached 0."
+pub fn summarize(solution: &Solution, statistics: &Statistics) -> String {
+    let strategy = statistics
+        .strategy
+        .map_or("The search", |info| info.display_name);
+    let outcome = match solution.status() {
+        SolveStatus::Solved => "solved",
+        SolveStatus::Unsolved => "gave up on",
+        SolveStatus::Timeout => "ran out of budget on",
+        SolveStatus::Cancelled => "was cancelled on",
+    };
+
+    let mut summary = format!(
+        "{} {} n={} in {} and {} ms",
+        strategy,
+        outcome,
+        solution.dimension(),
+        plural(statistics.iterations, "iteration"),
+        with_separators(statistics.elapsed.as_millis()),
+    );
+    if statistics.restarts > 0 {
+        summary.push_str(" after ");
+        summary.push_str(&plural(statistics.restarts, "restart"));
+    }
+    summary.push_str(&format!("; best score reached {}.", solution.score()));
+    summary
+}
+
+/// Describes the resources a run used, as far as they're known, like "Used
+/// 180 ms of processor time, 1,204 allocations (96 KiB) and 7 MiB of
+/// resident memory at most."
+pub fn describe_resources(usage: &ResourceUsage) -> Option<String> {
+    let mut parts = vec![];
+    if let Some(cpu_time) = usage.cpu_time {
+        parts.push(format!(
+            "{} ms of processor time",
+            with_separators(cpu_time.as_millis())
+        ));
+    }
+    if let (Some(allocations), Some(bytes)) = (usage.allocations, usage.allocated_bytes) {
+        parts.push(format!(
+            "{} ({} KiB)",
+            plural(allocations, "allocation"),
+            with_separators(bytes as u128 / 1024)
+        ));
+    }
+    if let Some(bytes) = usage.peak_resident_bytes {
+        parts.push(format!(
+            "{} MiB of resident memory at most",
+            with_separators(u128::from(bytes) / (1024 * 1024))
+        ));
+    }
+
+    let last = parts.pop()?;
+    if parts.is_empty() {
+        Some(format!("Used {}.", last))
+    } else {
+        Some(format!("Used {} and {}.", parts.join(", "), last))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_runs() {
+        assert_eq!(with_separators(0), "0");
+        assert_eq!(with_separators(14_210), "14,210");
+        assert_eq!(with_separators(1_000_000), "1,000,000");
+
+        let mut solution = Solution::new(vec![1, 3, 0, 2], 0);
+        solution.status = SolveStatus::Solved;
+        solution.statistics.strategy = Some(SimulatedAnnealing::info());
+        solution.statistics.iterations = 14_210;
+        solution.statistics.elapsed = Duration::from_millis(182);
+        solution.statistics.restarts = 2;
+        assert_eq!(
+            summarize(&solution, solution.statistics()),
+            "Simulated Annealing solved n=4 in 14,210 iterations and 182 ms after 2 \
+             restarts; best score reached 0."
+        );
+
+        let solution = Solution::new(vec![0, 1], 1);
+        assert_eq!(
+            summarize(&solution, solution.statistics()),
+            "The search gave up on n=2 in 0 iterations and 0 ms; best score reached 1."
+        );
+
+        let mut usage = ResourceUsage::default();
+        assert_eq!(describe_resources(&usage), None);
+        usage.cpu_time = Some(Duration::from_millis(180));
+        assert_eq!(
+            describe_resources(&usage).unwrap(),
+            "Used 180 ms of processor time."
+        );
+        usage.allocations = Some(1_204);
+        usage.allocated_bytes = Some(96 * 1024);
+        usage.peak_resident_bytes = Some(7 * 1024 * 1024);
+        assert_eq!(
+            describe_resources(&usage).unwrap(),
+            "Used 180 ms of processor time, 1,204 allocations (96 KiB) and 7 MiB of resident \
+             memory at most."
+        );
+    }
+}
@@ -0,0 +1,127 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Standalone SVG drawings of boards, to embed results in reports and web
+//! pages.
+
+use *;
+
+/// How to draw a board as SVG. Colors are anything SVG takes, like
+/// `#b58863` or `teal`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct SvgOptions {
+    /// The side of each square, in pixels.
+    pub cell_size: usize,
+    pub light_color: String,
+    pub dark_color: String,
+    pub queen_color: String,
+    /// The color of the queens that another queen can hit, or the same as
+    /// the others if unset.
+    pub attacked_queen_color: Option<String>,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions {
+            cell_size: 40,
+            light_color: "#f0d9b5".to_owned(),
+            dark_color: "#b58863".to_owned(),
+            queen_color: "#202020".to_owned(),
+            attacked_queen_color: Some("#d62728".to_owned()),
+        }
+    }
+}
+
+/// Escapes `value` to go in an attribute between double quotes.
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+}
+
+/// Draws the board of `solution`, with the top left square light, and a
+/// circle for each queen.
+///
+/// The squares are filled with a pattern, so that the size of the drawing
+/// only grows with the number of queens, not of squares.
+pub fn render(solution: &Solution, options: &SvgOptions) -> String {
+    let cell = options.cell_size;
+    let side = cell * solution.dimension();
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{side}\" height=\"{side}\" \
+         viewBox=\"0 0 {side} {side}\">\n",
+        side = side
+    );
+    svg.push_str(&format!(
+        "  <defs>\n    <pattern id=\"squares\" width=\"{double}\" height=\"{double}\" \
+         patternUnits=\"userSpaceOnUse\">\n      <rect width=\"{double}\" height=\"{double}\" \
+         fill=\"{light}\"/>\n      <rect x=\"{cell}\" width=\"{cell}\" height=\"{cell}\" \
+         fill=\"{dark}\"/>\n      <rect y=\"{cell}\" width=\"{cell}\" height=\"{cell}\" \
+         fill=\"{dark}\"/>\n    </pattern>\n  </defs>\n",
+        double = 2 * cell,
+        cell = cell,
+        light = escape(&options.light_color),
+        dark = escape(&options.dark_color),
+    ));
+    svg.push_str(&format!(
+        "  <rect width=\"{side}\" height=\"{side}\" fill=\"url(#squares)\"/>\n",
+        side = side
+    ));
+
+    let attack_counts = solution.attack_counts();
+    let radius = cell as f64 * 0.35;
+    for (column, row) in solution.positions() {
+        let color = match options.attacked_queen_color {
+            Some(ref attacked) if attack_counts[column] > 0 => attacked,
+            _ => &options.queen_color,
+        };
+        svg.push_str(&format!(
+            "  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>\n",
+            column as f64 * cell as f64 + cell as f64 / 2.,
+            row as f64 * cell as f64 + cell as f64 / 2.,
+            radius,
+            escape(color)
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_a_circle_per_queen() {
+        let options = SvgOptions {
+            cell_size: 10,
+            attacked_queen_color: Some("red".to_owned()),
+            ..SvgOptions::default()
+        };
+        let svg = render(&Solution::new(vec![1, 3, 0, 2], 0), &options);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"40\""));
+        assert!(svg.contains("<circle cx=\"5\" cy=\"15\" r=\"3.5\" fill=\"#202020\"/>"));
+        assert_eq!(svg.matches("<circle").count(), 4);
+        assert!(!svg.contains("red"));
+
+        let svg = render(&Solution::new(vec![0, 1, 3, 2], 4), &options);
+        assert_eq!(svg.matches("fill=\"red\"").count(), 4);
+    }
+}
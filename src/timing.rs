@@ -0,0 +1,137 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Histograms of how long the steps of a search take, to tell steady work
+//! apart from occasional spikes, like allocations or slow callbacks.
+
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Counts durations in buckets that double in size: bucket `i` holds the
+/// ones of at least `2^i` and less than `2^(i + 1)` nanoseconds, except for
+/// the first one, which also holds those under a nanosecond.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TimingHistogram {
+    counts: Vec<usize>,
+    total: Duration,
+    max: Duration,
+}
+
+impl TimingHistogram {
+    fn bucket_of(duration: Duration) -> usize {
+        let nanos = duration.as_nanos().max(1);
+        (127 - nanos.leading_zeros()) as usize
+    }
+
+    /// Returns the smallest duration bucket `index` holds.
+    fn lower_bound(index: usize) -> Duration {
+        if index == 0 {
+            return Duration::from_nanos(0);
+        }
+        Duration::from_nanos(1 << index)
+    }
+
+    pub fn record(&mut self, duration: Duration) {
+        let bucket = Self::bucket_of(duration);
+        if self.counts.len() <= bucket {
+            self.counts.resize(bucket + 1, 0);
+        }
+        self.counts[bucket] += 1;
+        self.total += duration;
+        self.max = self.max.max(duration);
+    }
+
+    /// The number of durations recorded.
+    pub fn count(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        match self.count() {
+            0 => None,
+            count => Some(self.total / count as u32),
+        }
+    }
+
+    /// Returns the lower bound of each non-empty bucket, and how many
+    /// durations it holds, shortest first.
+    pub fn buckets<'a>(&'a self) -> impl Iterator<Item = (Duration, usize)> + 'a {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|&(_, &count)| count > 0)
+            .map(|(index, &count)| (Self::lower_bound(index), count))
+    }
+
+    /// Returns an upper bound of the duration under which a `fraction` of
+    /// them fall, accurate to a factor of two.
+    pub fn quantile(&self, fraction: f64) -> Option<Duration> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+
+        let wanted = ((count as f64 * fraction).ceil() as usize).clamp(1, count);
+        let mut seen = 0;
+        for (index, &bucket_count) in self.counts.iter().enumerate() {
+            seen += bucket_count;
+            if seen >= wanted {
+                return Some(Self::lower_bound(index + 1).min(self.max));
+            }
+        }
+        Some(self.max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_durations_by_powers_of_two() {
+        let mut histogram = TimingHistogram::default();
+        assert_eq!(histogram.quantile(0.5), None);
+
+        for _ in 0..9 {
+            histogram.record(Duration::from_nanos(100));
+        }
+        histogram.record(Duration::from_millis(5));
+
+        assert_eq!(histogram.count(), 10);
+        assert_eq!(histogram.max(), Duration::from_millis(5));
+        assert_eq!(
+            histogram.buckets().collect::<Vec<_>>(),
+            vec![
+                (Duration::from_nanos(64), 9),
+                (Duration::from_nanos(1 << 22), 1),
+            ]
+        );
+        assert_eq!(histogram.quantile(0.9), Some(Duration::from_nanos(128)));
+        assert_eq!(histogram.quantile(1.), Some(Duration::from_millis(5)));
+    }
+}
@@ -0,0 +1,213 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Tuning the parameters of a strategy by racing configurations against
+//! each other, rather than by running a full grid of them to completion.
+//!
+//! Successive halving runs every configuration with a small budget, keeps
+//! the best fraction of them, and runs those again with a larger budget,
+//! until a single one is left. Bad configurations are usually obvious
+//! early, so most of the time goes to the promising ones.
+
+use std::cmp;
+use *;
+
+/// How a successive halving race is run.
+#[derive(Clone, Debug)]
+pub struct SuccessiveHalving {
+    /// The board to tune on.
+    pub dimension: usize,
+    /// The iterations each run gets on the first round, in the units of
+    /// `SolveBudget::max_iterations`.
+    pub initial_iterations: usize,
+    /// The fraction of the configurations kept after each round. The
+    /// budget of each run grows by its inverse, so that every round takes
+    /// about the same time.
+    pub keep_fraction: f64,
+    /// How many runs each configuration gets on each round, with the same
+    /// seeds for all of them.
+    pub runs: usize,
+    /// The seed of the first run, which the others follow.
+    pub first_seed: u64,
+}
+
+impl Default for SuccessiveHalving {
+    fn default() -> Self {
+        SuccessiveHalving {
+            dimension: 20,
+            initial_iterations: 10,
+            keep_fraction: 0.5,
+            runs: 3,
+            first_seed: 0,
+        }
+    }
+}
+
+/// How a configuration did on the last round it ran.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RaceEntry<P> {
+    pub parameters: P,
+    /// How many rounds the configuration ran, where the winner ran them
+    /// all.
+    pub rounds: usize,
+    /// The iterations each run got on the last round.
+    pub iterations: usize,
+    /// How many of the runs found a solution.
+    pub solved: usize,
+    /// The average score the runs ended with.
+    pub mean_score: f64,
+    /// The average iterations the runs took.
+    pub mean_iterations: f64,
+}
+
+impl<P> RaceEntry<P> {
+    /// Orders entries from best to worst: those that solved more boards
+    /// first, then those that got closer, then the quickest.
+    fn rank(&self, other: &Self) -> cmp::Ordering {
+        other
+            .solved
+            .cmp(&self.solved)
+            .then(self.mean_score.partial_cmp(&other.mean_score).unwrap())
+            .then(
+                self.mean_iterations
+                    .partial_cmp(&other.mean_iterations)
+                    .unwrap(),
+            )
+    }
+}
+
+impl SuccessiveHalving {
+    /// Races the configurations of `T` made by `configure` from each of
+    /// `candidates`, with a seed and a budget to use as they are. Returns
+    /// every candidate from best to worst, where the ones that lasted more
+    /// rounds come first.
+    ///
+    /// Fails with `Error::InvalidConfig` unless the fraction kept is
+    /// between zero and one, or if any configuration is invalid.
+    pub fn race<T, P, F>(
+        &self,
+        candidates: Vec<P>,
+        configure: F,
+    ) -> Result<Vec<RaceEntry<P>>, Error>
+    where
+        T: NQueensStrategy,
+        F: Fn(&P, u64, SolveBudget) -> T::Config,
+    {
+        if !(self.keep_fraction > 0. && self.keep_fraction < 1.) {
+            return Err(Error::InvalidConfig(
+                "the fraction kept must be between 0 and 1",
+            ));
+        }
+
+        let mut racing = candidates;
+        let mut eliminated = vec![];
+        let mut iterations = self.initial_iterations.max(1);
+        let mut round = 0;
+        loop {
+            round += 1;
+            let mut entries = vec![];
+            for parameters in racing {
+                let mut solved = 0;
+                let mut total_score = 0;
+                let mut total_iterations = 0;
+                for run in 0..self.runs.max(1) {
+                    let seed = self
+                        .first_seed
+                        .wrapping_add((round * self.runs.max(1) + run) as u64);
+                    let budget = SolveBudget {
+                        max_iterations: Some(iterations),
+                        ..SolveBudget::default()
+                    };
+                    let config = configure(&parameters, seed, budget);
+                    let solution = T::try_new(self.dimension, config)?.solve();
+                    if solution.status() == SolveStatus::Solved {
+                        solved += 1;
+                    }
+                    total_score += solution.score();
+                    total_iterations += solution.statistics().iterations;
+                }
+
+                let runs = self.runs.max(1) as f64;
+                entries.push(RaceEntry {
+                    parameters,
+                    rounds: round,
+                    iterations,
+                    solved,
+                    mean_score: total_score as f64 / runs,
+                    mean_iterations: total_iterations as f64 / runs,
+                });
+            }
+            entries.sort_by(|a, b| a.rank(b));
+
+            if entries.len() <= 1 {
+                eliminated.extend(entries.into_iter().rev());
+                break;
+            }
+            let kept = cmp::max(1, (entries.len() as f64 * self.keep_fraction) as usize);
+            eliminated.extend(entries.drain(kept..).rev());
+            racing = entries.into_iter().map(|entry| entry.parameters).collect();
+            iterations = (iterations as f64 / self.keep_fraction).ceil() as usize;
+        }
+
+        eliminated.reverse();
+        Ok(eliminated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solvers::genetic_algorithm::GeneticAlgorithmConfig;
+
+    #[test]
+    fn races_genetic_algorithm_configurations() {
+        // Generation size and mutation probability.
+        let candidates = vec![(2, 0.), (10, 0.1), (50, 0.1), (50, 0.3), (100, 0.2)];
+        let halving = SuccessiveHalving {
+            dimension: 8,
+            runs: 2,
+            ..SuccessiveHalving::default()
+        };
+        let ranking = halving
+            .race::<GeneticAlgorithm, _, _>(candidates, |&(size, mutation), seed, budget| {
+                GeneticAlgorithmConfig {
+                    generation_size: size,
+                    mutation_probability: mutation,
+                    seed: Some(seed),
+                    budget,
+                    ..GeneticAlgorithmConfig::default()
+                }
+            })
+            .unwrap();
+
+        assert_eq!(ranking.len(), 5);
+        // 5 candidates, then 2, then 1.
+        assert_eq!(ranking[0].rounds, 3);
+        assert_eq!(ranking[0].iterations, 40);
+        assert_eq!(ranking[1].rounds, 2);
+        assert!(ranking[2..].iter().all(|entry| entry.rounds == 1));
+        assert_ne!(ranking[0].parameters, (2, 0.));
+
+        let halving = SuccessiveHalving {
+            keep_fraction: 1.,
+            ..halving
+        };
+        let result = halving
+            .race::<GeneticAlgorithm, _, _>(vec![()], |_, _, _| GeneticAlgorithmConfig::default());
+        assert!(result.is_err());
+    }
+}
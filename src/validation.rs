@@ -0,0 +1,123 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Known results to check the exact algorithms against.
+
+use solvers::constraint_propagation::{
+    ColumnOrder, ConstraintPropagation, ConstraintPropagationConfig, SymmetryBreaking,
+};
+use {NQueensStrategy, SolveBudget};
+
+/// The number of solutions for each board size (OEIS A000170).
+pub const KNOWN_SOLUTION_COUNTS: &[u64] = &[
+    1,
+    1,
+    0,
+    0,
+    2,
+    10,
+    4,
+    40,
+    92,
+    352,
+    724,
+    2_680,
+    14_200,
+    73_712,
+    365_596,
+    2_279_184,
+    14_772_512,
+    95_815_104,
+    666_090_624,
+    4_968_057_848,
+    39_029_188_884,
+    314_666_222_712,
+    2_691_008_701_644,
+    24_233_937_684_440,
+    227_514_171_973_736,
+    2_207_893_435_808_352,
+    22_317_699_616_364_044,
+    234_907_967_154_122_528,
+];
+
+/// The number of solutions for each board size that are distinct up to
+/// rotations and reflections (OEIS A002562).
+pub const KNOWN_FUNDAMENTAL_SOLUTION_COUNTS: &[u64] = &[
+    1, 1, 0, 0, 1, 2, 1, 6, 12, 46, 92, 341, 1_787, 9_233, 45_752, 285_053, 1_846_955, 11_977_939,
+    83_263_591,
+];
+
+pub fn known_solution_count(n: usize) -> Option<u64> {
+    KNOWN_SOLUTION_COUNTS.get(n).cloned()
+}
+
+pub fn known_fundamental_solution_count(n: usize) -> Option<u64> {
+    KNOWN_FUNDAMENTAL_SOLUTION_COUNTS.get(n).cloned()
+}
+
+/// The number of solutions found for a board size didn't match the known
+/// one, or there's no known count to compare against.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CountMismatch {
+    pub n: usize,
+    pub expected: Option<u64>,
+    pub found: u64,
+}
+
+/// Checks the number of solutions found by `found` against the known one
+/// for `n`.
+pub fn check_count(n: usize, found: u64) -> Result<(), CountMismatch> {
+    let expected = known_solution_count(n);
+    if expected == Some(found) {
+        return Ok(());
+    }
+    Err(CountMismatch { n, expected, found })
+}
+
+/// Counts the solutions for `n` with the exact solver, and checks the
+/// result against the known one.
+pub fn validate_against_known(n: usize) -> Result<(), CountMismatch> {
+    let config = ConstraintPropagationConfig {
+        randomize_rows: false,
+        column_order: ColumnOrder::LeftToRight,
+        symmetry_breaking: SymmetryBreaking::Reflection,
+        restarts: 0,
+        restart_backtracks: 0,
+        max_nogood_size: 0,
+        seed: None,
+        budget: SolveBudget::default(),
+    };
+    check_count(n, ConstraintPropagation::new(n, config).count_solutions())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_solver_matches_known_counts() {
+        for n in 0..10 {
+            assert_eq!(validate_against_known(n), Ok(()));
+        }
+    }
+
+    #[test]
+    fn reports_mismatches() {
+        assert!(check_count(8, 91).is_err());
+        assert!(check_count(KNOWN_SOLUTION_COUNTS.len(), 0).is_err());
+    }
+}
@@ -0,0 +1,288 @@
+/*
+ * Copyright (C) 2017 Emilio Cobos Álvarez <emilio@crisal.io>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! A JavaScript API through `wasm-bindgen`, so that the frontend doesn't need
+//! to manage the memory of the results itself like with the C entry points.
+
+use js_sys::{Float64Array, Function, Uint32Array};
+use wasm_bindgen::prelude::*;
+
+use presets::Preset;
+use scheduler::Scheduler;
+use *;
+
+/// The result of a solve, as seen from JavaScript.
+#[wasm_bindgen]
+pub struct JsSolution {
+    queen_rows: Vec<u32>,
+    attack_counts: Vec<u32>,
+    score: usize,
+    status: SolveStatus,
+    board: String,
+    summary: String,
+    conflict_graph: String,
+    svg: String,
+    score_bins: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl JsSolution {
+    /// The row of the queen in each column.
+    #[wasm_bindgen(getter, js_name = queenRows)]
+    pub fn queen_rows(&self) -> Uint32Array {
+        Uint32Array::from(&self.queen_rows[..])
+    }
+
+    /// How many queens can hit the queen in each column, to highlight the
+    /// ones under attack.
+    #[wasm_bindgen(getter, js_name = attackCounts)]
+    pub fn attack_counts(&self) -> Uint32Array {
+        Uint32Array::from(&self.attack_counts[..])
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn score(&self) -> usize {
+        self.score
+    }
+
+    /// Whether the queens are a solution.
+    #[wasm_bindgen(getter)]
+    pub fn solved(&self) -> bool {
+        self.status == SolveStatus::Solved
+    }
+
+    /// The board rendered as text.
+    #[wasm_bindgen(getter)]
+    pub fn board(&self) -> String {
+        self.board.clone()
+    }
+
+    /// A sentence describing how the solve went.
+    #[wasm_bindgen(getter)]
+    pub fn summary(&self) -> String {
+        self.summary.clone()
+    }
+
+    /// The graph of the queens that can hit each other, in the DOT format.
+    #[wasm_bindgen(getter, js_name = conflictGraph)]
+    pub fn conflict_graph(&self) -> String {
+        self.conflict_graph.clone()
+    }
+
+    /// The board drawn as a standalone SVG document.
+    #[wasm_bindgen(getter)]
+    pub fn svg(&self) -> String {
+        self.svg.clone()
+    }
+
+    /// The binned scores of the search if it was traced, five numbers per
+    /// bin: its first step, its number of steps, and its minimum, mean and
+    /// maximum scores.
+    #[wasm_bindgen(getter, js_name = scoreBins)]
+    pub fn score_bins(&self) -> Float64Array {
+        Float64Array::from(&self.score_bins[..])
+    }
+}
+
+impl From<Solution> for JsSolution {
+    fn from(solution: Solution) -> Self {
+        JsSolution {
+            queen_rows: solution
+                .queen_rows()
+                .iter()
+                .map(|&row| row as u32)
+                .collect(),
+            attack_counts: solution
+                .attack_counts()
+                .iter()
+                .map(|&count| count as u32)
+                .collect(),
+            score: solution.score(),
+            status: solution.status(),
+            board: solution.to_string(),
+            summary: summary::summarize(&solution, solution.statistics()),
+            conflict_graph: solution.conflict_graph().to_dot(),
+            svg: solution.to_svg(&SvgOptions::default()),
+            score_bins: solution
+                .trace()
+                .map_or(&[][..], |trace| trace.bins())
+                .iter()
+                .flat_map(|bin| {
+                    vec![
+                        bin.first_step as f64,
+                        bin.steps as f64,
+                        bin.min as f64,
+                        bin.mean,
+                        bin.max as f64,
+                    ]
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Solves a board of `n` queens with the preset named `preset` of the
+/// strategy named `strategy`, as in `StrategyInfo::name`.
+///
+/// If `seed` is a non-negative integer, the run is seeded with it. If
+/// `progress` is given, it's called with the queen rows and the score on
+/// each step, or at most once every `report_every` steps and once per
+/// `report_interval_ms` if given, plus where the search ended, as in
+/// `ReportConfig`. If `trace_points` is given, the scores of every step are
+/// kept in at most that many bins, to plot them afterwards.
+#[wasm_bindgen(js_name = solveNQueens)]
+#[allow(clippy::too_many_arguments)]
+pub fn solve_n_queens(
+    n: usize,
+    strategy: &str,
+    preset: &str,
+    seed: Option<f64>,
+    progress: Option<Function>,
+    trace_points: Option<usize>,
+    report_every: Option<usize>,
+    report_interval_ms: Option<f64>,
+) -> Result<JsSolution, JsValue> {
+    let preset = Preset::from_name(preset)
+        .ok_or_else(|| js_sys::Error::new(&format!("unknown preset {:?}", preset)))?;
+    let seed = seed
+        .filter(|seed| *seed >= 0. && seed.fract() == 0.)
+        .map(|seed| seed as u64);
+
+    let report = ReportConfig {
+        report_every: report_every.unwrap_or(0),
+        report_interval: Duration::from_millis(
+            report_interval_ms.map_or(0, |ms| ms.max(0.) as u64),
+        ),
+    };
+    let mut progress = ThrottledCallback::new(report, |queens: &[usize], score: usize| {
+        if let Some(ref progress) = progress {
+            let queens = queens.iter().map(|&row| row as u32).collect::<Vec<_>>();
+            let queens = Uint32Array::from(&queens[..]);
+            // There's nothing sensible to do if the callback throws.
+            let _ = progress.call2(&JsValue::NULL, &queens, &JsValue::from(score as u32));
+        }
+    });
+
+    let mut recorder = trace_points.map(|points| TraceRecorder::new(TraceLevel::Binned(points)));
+    let callback = |queens: &[usize], score: usize| {
+        if let Some(ref mut recorder) = recorder {
+            recorder.record(queens, score);
+        }
+        progress.step(queens, score);
+    };
+
+    let mut solution = preset
+        .solve(strategy, n, seed, SolveBudget::default(), callback)
+        .map_err(|error| JsValue::from(js_sys::Error::new(&error.to_string())))?;
+    progress.finish(&solution);
+    solution.trace = recorder;
+    Ok(JsSolution::from(solution))
+}
+
+/// Returns the warnings about how `preset` configures the strategy named
+/// `strategy` for boards of `n` queens, to show before solving.
+#[wasm_bindgen(js_name = lintNQueens)]
+pub fn lint_n_queens(n: usize, strategy: &str, preset: &str) -> Result<Vec<String>, JsValue> {
+    let preset = Preset::from_name(preset)
+        .ok_or_else(|| js_sys::Error::new(&format!("unknown preset {:?}", preset)))?;
+    preset
+        .lint(strategy, n)
+        .map(|warnings| warnings.iter().map(|w| w.to_string()).collect())
+        .map_err(|error| js_sys::Error::new(&error.to_string()).into())
+}
+
+/// Runs several solves a few steps at a time each, so that they can be
+/// animated side by side without workers.
+#[wasm_bindgen(js_name = Scheduler)]
+pub struct JsScheduler {
+    scheduler: Scheduler,
+}
+
+#[wasm_bindgen(js_class = Scheduler)]
+impl JsScheduler {
+    /// Creates a scheduler that runs up to `steps_per_turn` steps of each
+    /// solve on each turn.
+    #[wasm_bindgen(constructor)]
+    pub fn new(steps_per_turn: usize) -> JsScheduler {
+        JsScheduler {
+            scheduler: Scheduler::new(steps_per_turn),
+        }
+    }
+
+    /// Adds a solve like `solveNQueens` does, returning its id.
+    pub fn add(
+        &mut self,
+        n: usize,
+        strategy: &str,
+        preset: &str,
+        seed: Option<f64>,
+    ) -> Result<usize, JsValue> {
+        let preset = Preset::from_name(preset)
+            .ok_or_else(|| js_sys::Error::new(&format!("unknown preset {:?}", preset)))?;
+        let seed = seed
+            .filter(|seed| *seed >= 0. && seed.fract() == 0.)
+            .map(|seed| seed as u64);
+        let stepper = preset
+            .stepper(strategy, n, seed, SolveBudget::default())
+            .map_err(|error| JsValue::from(js_sys::Error::new(&error.to_string())))?;
+        Ok(self.scheduler.add(stepper))
+    }
+
+    /// Runs a turn of every solve, returning whether any is still running.
+    #[wasm_bindgen(js_name = runTurn)]
+    pub fn run_turn(&mut self) -> bool {
+        self.scheduler.run_turn()
+    }
+
+    /// The row of the queen in each column for the solve `id`, as it is
+    /// now.
+    #[wasm_bindgen(js_name = queenRows)]
+    pub fn queen_rows(&self, id: usize) -> Result<Uint32Array, JsValue> {
+        let queens = self
+            .current(id)?
+            .0
+            .iter()
+            .map(|&row| row as u32)
+            .collect::<Vec<_>>();
+        Ok(Uint32Array::from(&queens[..]))
+    }
+
+    pub fn score(&self, id: usize) -> Result<usize, JsValue> {
+        Ok(self.current(id)?.1)
+    }
+
+    /// Takes the result of the solve `id`, once it's over.
+    #[wasm_bindgen(js_name = takeResult)]
+    pub fn take_result(&mut self, id: usize) -> Result<Option<JsSolution>, JsValue> {
+        if id >= self.scheduler.len() {
+            return Err(unknown_solve(id));
+        }
+        Ok(self.scheduler.take_result(id).map(JsSolution::from))
+    }
+}
+
+impl JsScheduler {
+    /// The placement the solve `id` is on and its score, or an error if
+    /// there's no such solve.
+    fn current(&self, id: usize) -> Result<(&[usize], usize), JsValue> {
+        self.scheduler.current(id).ok_or_else(|| unknown_solve(id))
+    }
+}
+
+fn unknown_solve(id: usize) -> JsValue {
+    js_sys::Error::new(&format!("no solve has the id {}", id)).into()
+}